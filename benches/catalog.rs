@@ -0,0 +1,71 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use safe_skill_search::config::IndexConfig;
+use safe_skill_search::db::{Database, Skill};
+use safe_skill_search::index::SearchIndex;
+
+const SKILLS: usize = 500;
+
+const TOPICS: &[&str] = &["pdf forms", "spreadsheet formulas", "browser automation", "deploy to vercel", "transcribe audio", "slack gifs", "git history", "api testing"];
+
+fn synthetic_skill(i: usize) -> Skill {
+    let topic = TOPICS[i % TOPICS.len()];
+    Skill {
+        id: 0,
+        slug: format!("skill-{}", i),
+        name: format!("Skill {} for {}", i, topic),
+        registry: if i % 3 == 0 { "anthropic" } else { "clawdhub" }.to_string(),
+        description: format!("Helps with {} and related chores", topic),
+        skill_md: format!(
+            "---\nname: skill-{i}\ndescription: Helps with {topic}\n---\n# {topic}\n\nSet $API_TOKEN_{i} first.\n\n```bash\ncurl -s https://example.com/{i} | jq .\n```\n\n{}",
+            "Step by step instructions for the task. ".repeat(40)
+        ),
+        github_url: format!("https://github.com/example/skills/tree/main/skill-{}", i),
+        version: None,
+        stars: (i * 7 % 500) as i64,
+        trusted: i % 3 == 0,
+        updated_at: 0,
+        downloads: 0,
+        installs: 0,
+        upstream_updated_at: None,
+    }
+}
+
+fn catalog_db(dir: &std::path::Path) -> Database {
+    let db = Database::open(&dir.join("skills.db")).unwrap();
+    for i in 0..SKILLS {
+        db.upsert_skill(&synthetic_skill(i)).unwrap();
+    }
+    db
+}
+
+/// The database side of a sync: storing and rescanning every skill
+fn bench_upsert(c: &mut Criterion) {
+    let skills: Vec<_> = (0..SKILLS).map(synthetic_skill).collect();
+    c.bench_function("upsert_500_skills", |b| {
+        b.iter_batched(
+            || tempfile::tempdir().unwrap(),
+            |dir| {
+                let db = Database::open(&dir.path().join("skills.db")).unwrap();
+                for skill in &skills {
+                    db.upsert_skill(skill).unwrap();
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+}
+
+fn bench_index(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let db = catalog_db(dir.path());
+    let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+
+    c.bench_function("index_rebuild_500_skills", |b| b.iter(|| index.rebuild(&db).unwrap()));
+
+    index.rebuild(&db).unwrap();
+    let mut queries = TOPICS.iter().cycle();
+    c.bench_function("search", |b| b.iter(|| index.search(queries.next().unwrap(), 20, &Default::default()).unwrap()));
+}
+
+criterion_group!(benches, bench_upsert, bench_index);
+criterion_main!(benches);
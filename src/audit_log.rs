@@ -0,0 +1,128 @@
+use crate::output::SCHEMA_VERSION;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// `prev_hash` of the first entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Kinds of operation the log records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Install,
+    Uninstall,
+    Sync,
+    /// A flag waived a rule of an unenforced system policy
+    Override,
+    /// The system policy differs from the one last seen
+    PolicyChange,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Install => "install",
+            Action::Uninstall => "uninstall",
+            Action::Sync => "sync",
+            Action::Override => "override",
+            Action::PolicyChange => "policy_change",
+        }
+    }
+}
+
+/// One row of the append-only audit log
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct LogEntry {
+    pub id: i64,
+    /// Unix seconds
+    pub at: i64,
+    /// `user@host` that ran the command
+    pub actor: String,
+    pub action: String,
+    /// `registry:slug` for skill operations, else what was acted on
+    pub target: String,
+    pub detail: String,
+    /// Hash of the entry before, [`GENESIS_HASH`] for the first
+    pub prev_hash: String,
+    /// SHA-256 over `prev_hash` and this entry's fields
+    pub hash: String,
+}
+
+/// `audit-log export --format json`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LogExport {
+    pub schema_version: u32,
+    pub entries: Vec<LogEntry>,
+}
+
+impl LogExport {
+    pub fn new(entries: Vec<LogEntry>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            entries,
+        }
+    }
+}
+
+impl LogEntry {
+    pub fn compute_hash(&self) -> String {
+        entry_hash(&self.prev_hash, self.at, &self.actor, &self.action, &self.target, &self.detail)
+    }
+}
+
+pub fn entry_hash(prev_hash: &str, at: i64, actor: &str, action: &str, target: &str, detail: &str) -> String {
+    let mut hasher = Sha256::new();
+    for field in [prev_hash, &at.to_string(), actor, action, target, detail] {
+        hasher.update(field.as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Id of the first entry that doesn't chain onto the one before it (edited,
+/// deleted, or reordered), if any
+pub fn find_break(entries: &[LogEntry]) -> Option<i64> {
+    let mut prev = GENESIS_HASH;
+    for entry in entries {
+        if entry.prev_hash != prev || entry.hash != entry.compute_hash() {
+            return Some(entry.id);
+        }
+        prev = &entry.hash;
+    }
+    None
+}
+
+/// Who is running this: `$USER@hostname`, as far as the environment says
+pub fn current_actor() -> String {
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "unknown".to_string());
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty());
+    match host {
+        Some(host) => format!("{}@{}", user, host),
+        None => user,
+    }
+}
+
+/// Hash of a system policy file's content, or "none" without one; logged
+/// to notice when the policy changes
+pub fn policy_fingerprint(content: Option<&[u8]>) -> String {
+    match content {
+        Some(bytes) => hex::encode(Sha256::digest(bytes)),
+        None => "none".to_string(),
+    }
+}
+
+/// RFC 4180 CSV, one line per entry after a header
+pub fn to_csv(entries: &[LogEntry]) -> String {
+    let quote = crate::export::csv_field;
+    let mut out = String::from("id,at,actor,action,target,detail,prev_hash,hash\n");
+    for e in entries {
+        let fields = [e.id.to_string(), e.at.to_string(), quote(&e.actor), quote(&e.action), quote(&e.target), quote(&e.detail), e.prev_hash.clone(), e.hash.clone()];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,153 @@
+use crate::index::{IndexFilters, SearchIndex};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// File in the data directory holding the last run's timings
+pub const BASELINE_FILE: &str = "bench.json";
+
+/// Times each search query runs
+pub const DEFAULT_ITERATIONS: usize = 5;
+
+/// Changes smaller than this are noise, not regressions
+const NOISE_PCT: f64 = 10.0;
+
+/// Timings of one `bench` run, in milliseconds by measurement name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Unix seconds
+    pub recorded_at: i64,
+    /// Catalog size the timings were taken at
+    pub skills: i64,
+    pub millis: BTreeMap<String, f64>,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content).with_context(|| format!("Invalid benchmark baseline {:?}", path))?))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, name: &str, elapsed: Duration) {
+        self.millis.insert(name.to_string(), elapsed.as_secs_f64() * 1000.0);
+    }
+
+    /// Keep earlier timings this run didn't take (e.g. sync without `--sync`)
+    pub fn carry_over(&mut self, previous: &Baseline) {
+        for (name, ms) in &previous.millis {
+            self.millis.entry(name.clone()).or_insert(*ms);
+        }
+    }
+}
+
+/// One line of the comparison against the baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub name: String,
+    pub millis: f64,
+    pub baseline: Option<f64>,
+}
+
+impl Comparison {
+    /// Percent change against the baseline; positive is slower
+    pub fn change_pct(&self) -> Option<f64> {
+        self.baseline.filter(|b| *b > 0.0).map(|b| (self.millis - b) / b * 100.0)
+    }
+
+    pub fn verdict(&self) -> &'static str {
+        match self.change_pct() {
+            None => "new",
+            Some(pct) if pct > NOISE_PCT => "slower",
+            Some(pct) if pct < -NOISE_PCT => "faster",
+            Some(_) => "same",
+        }
+    }
+}
+
+/// Compare the timings taken this run, in order, with the baseline's
+pub fn compare(current: &Baseline, names: &[&str], baseline: Option<&Baseline>) -> Vec<Comparison> {
+    names
+        .iter()
+        .filter_map(|name| {
+            current.millis.get(*name).map(|ms| Comparison {
+                name: name.to_string(),
+                millis: *ms,
+                baseline: baseline.and_then(|b| b.millis.get(*name).copied()),
+            })
+        })
+        .collect()
+}
+
+/// Run each query `iterations` times against the index; returns every
+/// latency, sorted
+pub fn search_latencies(index: &SearchIndex, queries: &[String], iterations: usize, limit: usize) -> Result<Vec<Duration>> {
+    let mut latencies = Vec::with_capacity(queries.len() * iterations);
+    for _ in 0..iterations {
+        for query in queries {
+            let started = Instant::now();
+            index.search(query, limit, &IndexFilters::default())?;
+            latencies.push(started.elapsed());
+        }
+    }
+    latencies.sort();
+    Ok(latencies)
+}
+
+/// Nearest-rank percentile of sorted values
+pub fn percentile(sorted: &[Duration], pct: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let values: Vec<_> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&values, 50.0), Duration::from_millis(50));
+        assert_eq!(percentile(&values, 99.0), Duration::from_millis(99));
+        assert_eq!(percentile(&values[..1], 99.0), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_compare_with_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BASELINE_FILE);
+        assert!(Baseline::load(&path).unwrap().is_none());
+
+        let mut previous = Baseline::default();
+        previous.record("index_rebuild", Duration::from_millis(100));
+        previous.record("sync_full", Duration::from_secs(30));
+        previous.save(&path).unwrap();
+        let previous = Baseline::load(&path).unwrap().unwrap();
+
+        let mut current = Baseline::default();
+        current.record("index_rebuild", Duration::from_millis(150));
+        current.record("search_p50", Duration::from_millis(2));
+        let rows = compare(&current, &["sync_full", "index_rebuild", "search_p50"], Some(&previous));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].verdict(), "slower");
+        assert_eq!(rows[0].change_pct(), Some(50.0));
+        assert_eq!(rows[1].verdict(), "new");
+
+        current.carry_over(&previous);
+        assert_eq!(current.millis["sync_full"], 30_000.0);
+        assert_eq!(current.millis["index_rebuild"], 150.0);
+    }
+}
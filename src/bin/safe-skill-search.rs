@@ -1,29 +1,20 @@
-mod db {
-    include!("../db.rs");
-}
-mod github {
-    include!("../github.rs");
-}
-mod index {
-    include!("../index.rs");
-}
-mod quality {
-    include!("../quality.rs");
-}
-mod skillssh {
-    include!("../skillssh.rs");
-}
-
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use quality::QualityScores;
-use std::path::PathBuf;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::env::Shells;
+use clap_complete::{ArgValueCandidates, CompleteEnv, CompletionCandidate};
+use safe_skill_search::github::Channel;
+use safe_skill_search::quality::{self, QualityScores};
+use safe_skill_search::resolve::{self, Resolution};
+use safe_skill_search::audit_log::{self, Action};
+use safe_skill_search::{audit, bench, catalog, config, db, deps, diff, digest, doctor, embeddings, env_vars, eval, export, failures, features, github, harness, index, injection, install, launch, links, mcp, migrations, mirror, output, overlay, pack, policy, project, query, rank, risk, sandbox, search, security, server, sync, text, unicode, workspace};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use tracing_subscriber::EnvFilter;
 
 #[derive(Parser)]
 #[command(name = "safe-skill-search")]
 #[command(about = "Search skills with quality filtering - only returns high-quality skills (score >= 80) by default")]
-#[command(version)]
+#[command(version, long_version = long_version())]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
@@ -32,6 +23,17 @@ struct Cli {
     #[arg(long, global = true)]
     data_dir: Option<PathBuf>,
 
+    /// Layer the system catalog (skills.db, index/, repos/, policy.toml) in
+    /// this directory, read-only, under the user catalog in the data directory
+    /// [env: SKILL_SEARCH_CATALOG_DIR]
+    #[arg(long, global = true)]
+    catalog_dir: Option<PathBuf>,
+
+    /// Fail on a corrupt database instead of moving it aside and resyncing
+    /// into a new one
+    #[arg(long, global = true)]
+    no_auto_recover: bool,
+
     /// Verbose output
     #[arg(short, long, global = true)]
     verbose: bool,
@@ -41,14 +43,33 @@ struct Cli {
 enum Commands {
     /// Sync skills from all registries
     Sync {
-        /// Force full resync (ignore cache)
+        /// Force full resync (ignore cache and the disk space check)
         #[arg(long)]
         force: bool,
+
+        /// Registry repos to clone or pull at once
+        #[arg(long, default_value_t = github::DEFAULT_SYNC_JOBS as u64, value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: u64,
+
+        /// Keep running and sync again every --interval, re-indexing only the
+        /// skills that changed and logging what did
+        #[arg(long)]
+        watch: bool,
+
+        /// Time between syncs with --watch, e.g. 30m, 6h, or 1d
+        #[arg(long, default_value = "6h", value_parser = parse_interval, requires = "watch")]
+        interval: std::time::Duration,
     },
     /// Search for skills
     Search {
         /// Search query
-        query: String,
+        #[arg(required_unless_present_any = ["schema", "queries_file"])]
+        query: Option<String>,
+
+        /// Run every query in this file, one per line, and print a JSON map
+        /// of query to results. Much cheaper than one process per query.
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["query", "compact", "ndjson"])]
+        queries_file: Option<PathBuf>,
 
         /// Number of results (default: 10)
         #[arg(short, long, default_value = "10")]
@@ -62,25 +83,470 @@ enum Commands {
         #[arg(long)]
         trusted: bool,
 
-        /// Minimum quality score (default: 80, set to 0 to show all)
-        #[arg(long, default_value = "80")]
-        min_score: i64,
+        /// Only show skills with at least this many GitHub stars
+        #[arg(long)]
+        min_stars: Option<i64>,
+
+        /// Only show skills that declare a version
+        #[arg(long)]
+        has_version: bool,
+
+        /// Only show skills with this tag or category in their frontmatter;
+        /// repeat to require several (see `tags`)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Only show skills from one channel: curated, experimental, or community
+        #[arg(long, value_parser = parse_channel)]
+        channel: Option<Channel>,
+
+        /// Only show skills with a fenced code block in this language
+        /// (python, bash, javascript, ...)
+        #[arg(long, value_name = "LANG")]
+        has_code: Option<String>,
+
+        /// Minimum quality score (default: 80, or the system policy's, or 0 with
+        /// --installed; set to 0 to show all)
+        #[arg(long)]
+        min_score: Option<i64>,
+
+        /// Only search installed skills, using the content of the local copies
+        #[arg(long)]
+        installed: bool,
+
+        /// Hide skills whose prompt-injection risk (0-100) is above this
+        #[arg(long)]
+        max_injection_risk: Option<i64>,
+
+        /// Hide skills last updated upstream more than this many days ago
+        #[arg(long)]
+        max_age_days: Option<i64>,
+
+        /// Also show skills whose URL was found gone (404) in a recent check
+        #[arg(long)]
+        include_unreachable: bool,
+
+        /// Also show skills a sync found gone from their registry upstream
+        /// (see `prune`)
+        #[arg(long)]
+        include_removed: bool,
+
+        /// Blend similarity between the query and skill descriptions into the
+        /// ranking, from the embedding provider in config.toml
+        #[arg(long, conflicts_with = "installed")]
+        semantic: bool,
+
+        /// Let each word also match words a typo or two away (`fuzzy_distance`
+        /// in config.toml). A search with no exact matches does this anyway.
+        #[arg(long)]
+        fuzzy: bool,
+
+        /// Search for the query as written, without dropping filler words
+        /// ("find me a skill that ...") or adding synonyms from config.toml
+        #[arg(long)]
+        verbatim: bool,
+
+        /// Order by relevance (blended with stars, quality, trust, and
+        /// recency by the [ranking] weights in config.toml), stars, quality,
+        /// or recency
+        #[arg(long, value_parser = parse_sort, default_value = "relevance")]
+        sort: rank::Sort,
+
+        /// Apply a ranking profile from config.toml (built-in: strict,
+        /// explore); flags given alongside take precedence
+        #[arg(long, value_name = "PROFILE")]
+        profile_rank: Option<String>,
 
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// One short line per skill, `registry:slug: summary`, for pasting
+        /// into agent prompts
+        #[arg(long, conflicts_with = "json")]
+        compact: bool,
+
+        /// One JSON result per line, each written as soon as it's ready, for
+        /// large --limit exports
+        #[arg(long, conflicts_with_all = ["json", "compact"])]
+        ndjson: bool,
+
+        /// Only these fields of each result, comma-separated (e.g.
+        /// `slug,github_url,quality_score`); with --json, --ndjson, or
+        /// --queries-file
+        #[arg(long, value_name = "FIELDS", conflicts_with = "compact")]
+        fields: Option<String>,
+
+        /// Print the JSON Schema of the `--json` output and exit
+        #[arg(long)]
+        schema: bool,
     },
     /// Show skill details
     Show {
-        /// Skill slug
+        /// Skill slug, optionally as registry:slug (case-insensitive; partial slugs offer suggestions)
+        #[arg(add = ArgValueCandidates::new(complete_slugs))]
         slug: String,
+
+        /// List every link in the skill with its reputation flags
+        #[arg(long)]
+        links: bool,
+
+        /// Print the whole SKILL.md instead of a summary of it
+        #[arg(long)]
+        full: bool,
+
+        /// Print only the SKILL.md heading outline
+        #[arg(long, conflicts_with_all = ["full", "links"])]
+        outline: bool,
+
+        /// Print only the SKILL.md code blocks that name a language, under
+        /// the heading each one is in
+        #[arg(long, conflicts_with_all = ["full", "links", "outline"])]
+        code: bool,
+
+        /// Output as JSON: the skill's search result fields and its SKILL.md
+        #[arg(long, conflicts_with_all = ["full", "links", "outline", "code"])]
+        json: bool,
+
+        /// Only these fields, comma-separated, as one flat JSON object: any
+        /// `search --json` result field, or `skill_md`
+        #[arg(long, value_name = "FIELDS", requires = "json")]
+        fields: Option<String>,
     },
     /// Get install URL for a skill
     Url {
-        /// Skill slug
+        /// Skill slug, optionally as registry:slug (case-insensitive; partial slugs offer suggestions)
+        #[arg(add = ArgValueCandidates::new(complete_slugs))]
+        slug: String,
+        /// Also copy the URL to the clipboard
+        #[arg(long)]
+        copy: bool,
+    },
+    /// Open a skill's GitHub page in the browser
+    Open {
+        /// Skill slug, optionally as registry:slug (case-insensitive; partial slugs offer suggestions)
+        slug: String,
+        /// Open the synced SKILL.md in $EDITOR instead (the next sync overwrites edits there;
+        /// use the overlay for changes that should last)
+        #[arg(long)]
+        editor: bool,
+    },
+    /// Show the skills a skill requires or extends, transitively
+    Deps {
+        /// Skill slug, optionally as registry:slug (case-insensitive; partial slugs offer suggestions)
+        slug: String,
+        /// Print the graph in Graphviz DOT format
+        #[arg(long)]
+        dot: bool,
+    },
+    /// Audit the shell code blocks in a skill's SKILL.md
+    Audit {
+        /// Skill slug, optionally as registry:slug
+        #[arg(required_unless_present = "schema")]
+        slug: Option<String>,
+
+        /// Also run shellcheck (if installed) and record its findings
+        #[arg(long)]
+        shellcheck: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Print the JSON Schema of the `--json` output and exit
+        #[arg(long)]
+        schema: bool,
+    },
+    /// Scan a skill's SKILL.md and bundled scripts for dangerous patterns
+    /// (piping downloads into a shell, sending credentials out, base64
+    /// payloads, network calls) and record its security risk
+    Scan {
+        /// Skill slug, optionally as registry:slug
+        #[arg(required_unless_present = "schema")]
+        slug: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Print the JSON Schema of the `--json` output and exit
+        #[arg(long)]
+        schema: bool,
+    },
+    /// Run the tests a skill declares in evals/ or tests/ and record the result
+    Test {
+        /// Skill slug, optionally as registry:slug
+        slug: String,
+
+        /// Run the declared commands without asking first
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Check catalog integrity and write a signed report (run it on a schedule)
+    AuditCatalog {
+        /// Number of skill URLs to check for reachability
+        #[arg(long, default_value_t = catalog::DEFAULT_URL_SAMPLE)]
+        sample: usize,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Check the signature of a previously written report instead
+        #[arg(long, value_name = "REPORT")]
+        verify: Option<PathBuf>,
+
+        /// Print the JSON Schema of the `--json` output and exit
+        #[arg(long)]
+        schema: bool,
+    },
+    /// Measure search ranking against query -> expected-skill judgments
+    /// (bundled, plus relevance.toml in the data directory)
+    Eval {
+        /// Number of top results each query is judged on
+        #[arg(short, long, default_value_t = eval::DEFAULT_K)]
+        k: usize,
+
+        /// Additional judgments file(s) in the relevance.toml format
+        #[arg(long, value_name = "FILE")]
+        judgments: Vec<PathBuf>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Print the JSON Schema of the `--json` output and exit
+        #[arg(long)]
+        schema: bool,
+    },
+    /// Time index rebuilds and search latency (and optionally syncs) on the
+    /// current catalog, compared with the previous run
+    Bench {
+        /// Also time a full and an incremental sync (uses the network and
+        /// refreshes the catalog)
+        #[arg(long)]
+        sync: bool,
+
+        /// Times each search query runs
+        #[arg(long, default_value_t = bench::DEFAULT_ITERATIONS)]
+        iterations: usize,
+
+        /// Keep the stored baseline instead of replacing it with this run
+        #[arg(long)]
+        no_save: bool,
+    },
+    /// Maintenance of the curated quality scores (skills.json)
+    Quality {
+        #[command(subcommand)]
+        command: QualityCommand,
+    },
+    /// Inspect or sign the system catalog's policy (policy.toml)
+    Policy {
+        #[command(subcommand)]
+        command: PolicyCommand,
+    },
+    /// Install a skill into the local skills directory
+    Install {
+        /// Skill slug, optionally as registry:slug
+        #[arg(required_unless_present = "from_file", add = ArgValueCandidates::new(complete_slugs))]
+        slug: Option<String>,
+
+        /// Install from a .skillpack made with `pack` instead of the catalog
+        #[arg(long, conflicts_with_all = ["slug", "version", "rev", "unpin", "with_deps"])]
+        from_file: Option<PathBuf>,
+
+        /// Skills directory to install into (default: ~/.claude/skills)
+        #[arg(long)]
+        dest: Option<PathBuf>,
+
+        /// Print the files that would be written without installing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Overwrite an existing installation
+        #[arg(long)]
+        force: bool,
+
+        /// Install (and pin) the commit that carried this skill version
+        #[arg(long, conflicts_with = "rev")]
+        version: Option<String>,
+
+        /// Install (and pin) a specific commit SHA of the registry repo
+        #[arg(long)]
+        rev: Option<String>,
+
+        /// Drop an existing pin and install the latest synced copy
+        #[arg(long, conflicts_with_all = ["version", "rev"])]
+        unpin: bool,
+
+        /// Also install the skills it requires (`requires_skills:`), transitively,
+        /// next to it in the skills directory
+        #[arg(long)]
+        with_deps: bool,
+
+        /// Install an untrusted skill even though the system policy only
+        /// installs trusted ones (refused when the policy is enforced)
+        #[arg(long)]
+        allow_untrusted: bool,
+    },
+    /// Copy a skill into a throwaway directory, print how to start an agent
+    /// on it, and remove it when you're done
+    Try {
+        /// Skill slug, optionally as registry:slug
+        #[arg(add = ArgValueCandidates::new(complete_slugs))]
+        slug: String,
+
+        /// Agent platform to lay the skill out for (default: [sandbox] platform
+        /// in config.toml, or claude)
+        #[arg(long)]
+        platform: Option<String>,
+
+        /// Leave the directory in place instead of waiting to remove it
+        #[arg(long)]
+        keep: bool,
+
+        /// Try an untrusted skill even though the system policy only installs
+        /// trusted ones (refused when the policy is enforced)
+        #[arg(long)]
+        allow_untrusted: bool,
+    },
+    /// Write a skill's files, manifest, and content hash to a single
+    /// .skillpack file for sharing offline
+    Pack {
+        /// Skill slug, optionally as registry:slug
+        slug: String,
+
+        /// Where to write it (default: <slug>.skillpack in the current directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Sign the pack with the ed25519 key in this file, creating it on first use
+        #[arg(long)]
+        sign_key: Option<PathBuf>,
+    },
+    /// Copy a collection from config.toml into a git repo, with an index,
+    /// to seed a registry of your own
+    Mirror {
+        /// Collection name, as in [collections.<name>]
+        #[arg(long)]
+        collection: String,
+
+        /// Git work tree to copy into
+        #[arg(long)]
+        dest: PathBuf,
+
+        /// Print the generated index as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show what updating an installed skill would change: a diff from the
+    /// installed copy to the latest catalog version
+    Diff {
+        /// Skill slug, optionally as registry:slug
+        slug: String,
+
+        /// Skills directory it was installed into (default: the most recent install)
+        #[arg(long)]
+        dest: Option<PathBuf>,
+
+        /// Only list changed files with line counts
+        #[arg(long)]
+        stat: bool,
+    },
+    /// List installed skills that are behind the synced registry content,
+    /// and optionally reinstall them
+    Update {
+        /// Only this skill, optionally as registry:slug (default: every install)
+        slug: Option<String>,
+
+        /// Reinstall stale skills from the synced copy
+        #[arg(long)]
+        apply: bool,
+
+        /// With --apply, also replace installs whose files were changed locally
+        #[arg(long, requires = "apply")]
+        force: bool,
+    },
+    /// Remove a skill installed with `install`
+    Uninstall {
+        /// Skill slug, optionally as registry:slug
         slug: String,
+
+        /// Skills directory it was installed into (default: ~/.claude/skills)
+        #[arg(long)]
+        dest: Option<PathBuf>,
+    },
+    /// The hash-chained log of installs, uninstalls, syncs, overrides, and
+    /// policy changes
+    AuditLog {
+        #[command(subcommand)]
+        command: AuditLogCommand,
+    },
+    /// Inspect or upgrade the database schema
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Inspect the search index
+    Index {
+        #[command(subcommand)]
+        command: IndexCommand,
+    },
+    /// Install or update the skills listed in the project's skills.toml
+    SyncProject {
+        /// Directory to search for skills.toml (default: current directory and its parents)
+        #[arg(long)]
+        project: Option<PathBuf>,
+
+        /// Show what would change without installing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Install exactly what skills.lock records, failing if upstream content changed
+        #[arg(long)]
+        locked: bool,
+    },
+    /// Report drift between skills.toml and the installed skills
+    CheckProject {
+        /// Directory to search for skills.toml (default: current directory and its parents)
+        #[arg(long)]
+        project: Option<PathBuf>,
+    },
+    /// List registries with their sync status and recent failures
+    Registries {
+        /// Failures to show per registry (default: 3)
+        #[arg(long, default_value = "3")]
+        failures: usize,
+    },
+    /// List the tags and categories skills declare in their frontmatter, with
+    /// how many skills have each
+    Tags {
+        /// Only skills from this registry
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Number of tags (default: 50)
+        #[arg(short, long, default_value = "50")]
+        limit: usize,
     },
-    /// List top skills by stars
+    /// Check the environment, the database and index, and each registry's
+    /// last sync and reachability, with a hint for each problem
+    Doctor {
+        /// Skip the network checks
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Serve search_skills, get_skill, and get_skill_content as MCP tools over stdio
+    ServeMcp,
+    /// Serve the catalog over HTTP: GET /skills/search?q=..., GET
+    /// /skills/{registry}/{slug}, and POST /sync, answering with the JSON of
+    /// `search --json`
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// List top skills by popularity (stars, installs, and downloads)
     Top {
         /// Number of results (default: 20)
         #[arg(short, long, default_value = "20")]
@@ -90,197 +556,2058 @@ enum Commands {
         #[arg(long)]
         trusted: bool,
 
-        /// Minimum quality score (default: 80, set to 0 to show all)
-        #[arg(long, default_value = "80")]
-        min_score: i64,
+        /// Minimum quality score (default: 80, or the system policy's; set to 0 to show all)
+        #[arg(long)]
+        min_score: Option<i64>,
+
+        /// Hide skills whose prompt-injection risk (0-100) is above this
+        #[arg(long)]
+        max_injection_risk: Option<i64>,
+
+        /// Ranking signal
+        #[arg(long, value_parser = parse_top_sort, default_value = "popularity")]
+        sort: rank::TopSort,
+
+        /// Only show skills with this tag; repeat to require several
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Also show skills whose URL was found gone (404) in a recent check
+        #[arg(long)]
+        include_unreachable: bool,
+
+        /// Apply a ranking profile from config.toml (built-in: strict,
+        /// explore); flags given alongside take precedence
+        #[arg(long, value_name = "PROFILE")]
+        profile_rank: Option<String>,
+    },
+    /// Print a shell completion script; source it from your shell's startup
+    /// file. Skill slugs complete from the local catalog.
+    Completions {
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+    /// Summarize catalog changes for people: new high-quality skills, new
+    /// versions, and skills flagged or deprecated
+    Digest {
+        /// How far back to look, e.g. 7d, 2w, or 12h
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        #[arg(long, value_enum, default_value = "md")]
+        format: DigestFormat,
+
+        /// Minimum quality score of new skills (default: 80, or the system policy's)
+        #[arg(long)]
+        min_score: Option<i64>,
+    },
+    /// Add skills from a local directory (`<dir>/<skill>/SKILL.md`, or one
+    /// level deeper) to the catalog under their own registry. Importing
+    /// again picks up changes and drops skills that are gone.
+    Import {
+        /// Directory to scan
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Registry to list the skills under
+        #[arg(long, default_value = "local")]
+        registry: String,
+    },
+    /// Delete the skills a sync found gone from their registry upstream,
+    /// which search already hides
+    Prune {
+        /// List what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write every skill in the catalog, with its quality score and stars,
+    /// for spreadsheets and dashboards
+    Export {
+        #[arg(long, value_enum, default_value = "jsonl")]
+        format: ExportFormat,
+
+        /// Only skills from this registry
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Only trusted skills
+        #[arg(long)]
+        trusted: bool,
+
+        /// Minimum quality score (default: 0, or the system policy's)
+        #[arg(long)]
+        min_score: Option<i64>,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 }
 
-fn get_data_dir(cli_path: Option<PathBuf>) -> Result<PathBuf> {
-    if let Some(p) = cli_path {
-        return Ok(p);
-    }
-    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
-    let data_dir = PathBuf::from(home)
-        .join(".local")
-        .join("share")
-        .join("skill-search");
-    std::fs::create_dir_all(&data_dir)?;
-    Ok(data_dir)
+#[derive(Subcommand)]
+enum QualityCommand {
+    /// Compare curated scores with the scanners, stars, and ratings, and list
+    /// the scores they contradict
+    Report {
+        /// Only skills from this registry
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Print the JSON Schema of the `--json` output and exit
+        #[arg(long)]
+        schema: bool,
+    },
+    /// Score skills outside the catalog with the heuristic rubric, writing
+    /// one `skills.json` entry per line
+    BatchScore {
+        /// JSONL of candidates: name, registry, optional url and stars, and
+        /// either `skill_md` or a local `path`
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Add or update curated scores from a file in skills.json form (a JSON
+    /// array, or the lines `batch-score` writes)
+    Import {
+        file: PathBuf,
+
+        /// Drop the scores the file doesn't have
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Replace the curated scores with the latest published ones
+    Refresh {
+        /// Where to fetch skills.json from
+        #[arg(long, default_value = quality::DEFAULT_REFRESH_URL)]
+        url: String,
+    },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+#[derive(Subcommand)]
+enum AuditLogCommand {
+    /// Write the log, after checking its hash chain
+    Export {
+        #[arg(long, value_enum, default_value = "jsonl")]
+        format: LogFormat,
 
-    let filter = if cli.verbose {
-        EnvFilter::new("skill_search=debug,info")
-    } else {
-        EnvFilter::new("skill_search=info,warn")
-    };
-    tracing_subscriber::fmt().with_env_filter(filter).init();
-
-    let data_dir = get_data_dir(cli.data_dir)?;
-    let db_path = data_dir.join("skills.db");
-    let index_path = data_dir.join("index");
-    let repos_dir = data_dir.join("repos");
-
-    let mut db = db::Database::open(&db_path)?;
-    let search_index = index::SearchIndex::open_or_create(&index_path)?;
-    let quality_scores = QualityScores::load();
-
-    // Auto-sync on first launch
-    if db.needs_initial_sync()? {
-        tracing::info!("First launch detected, syncing skills...");
-        github::sync_all_registries(&mut db, &repos_dir).await?;
-        skillssh::sync_skillssh(&mut db).await?;
-        search_index.rebuild(&db)?;
-    }
+        /// Only entries from the last N days
+        #[arg(long)]
+        since_days: Option<i64>,
 
-    match cli.command {
-        Commands::Sync { force } => {
-            if force {
-                db.clear_sync_state()?;
-            }
-            github::sync_all_registries(&mut db, &repos_dir).await?;
-            skillssh::sync_skillssh(&mut db).await?;
-            search_index.rebuild(&db)?;
-            tracing::info!("Sync complete");
-        }
-        Commands::Search {
-            query,
-            limit,
-            registry,
-            trusted,
-            min_score,
-            json,
-        } => {
-            let results = search_index.search(&query, limit * 4, registry.as_deref())?;
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Check that no entry was edited, removed, or reordered
+    Verify,
+}
 
-            let enriched: Vec<_> = results
-                .into_iter()
-                .filter_map(|r| {
-                    db.get_skill(&r.registry, &r.slug).ok().flatten().map(|s| {
-                        let quality_score = quality_scores
-                            .get_score(&s.registry, &s.slug)
-                            .or_else(|| quality_scores.get_score(&s.registry, &s.name))
-                            .unwrap_or(0);
-                        
-                        serde_json::json!({
-                            "slug": s.slug,
-                            "name": s.name,
-                            "registry": s.registry,
-                            "description": s.description,
-                            "github_url": s.github_url,
-                            "stars": s.stars,
-                            "trusted": s.trusted,
-                            "search_score": r.score,
-                            "quality_score": quality_score,
-                        })
-                    })
-                })
-                .filter(|r| !trusted || r["trusted"].as_bool().unwrap_or(false))
-                .filter(|r| r["quality_score"].as_i64().unwrap_or(0) >= min_score)
-                .take(limit)
-                .collect();
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum LogFormat {
+    /// One JSON object per line
+    Jsonl,
+    Json,
+    Csv,
+}
 
-            if json {
-                println!("{}", serde_json::to_string_pretty(&enriched)?);
-            } else {
-                if enriched.is_empty() {
-                    println!("No skills found with score >= {}. Try --min-score 0 to see all.", min_score);
-                } else {
-                    for (i, r) in enriched.iter().enumerate() {
-                        let trusted = r["trusted"].as_bool().unwrap_or(false);
-                        let trust_icon = if trusted { "✓" } else { "⚠" };
-                        let stars = r["stars"].as_i64().unwrap_or(0);
-                        let quality = r["quality_score"].as_i64().unwrap_or(0);
-                        let stars_str = if stars > 0 { format!(" ★{}", stars) } else { String::new() };
-                        
-                        println!(
-                            "{}. [{}] {}{} ({}) [Q:{}] - {}",
-                            i + 1,
-                            trust_icon,
-                            r["name"].as_str().unwrap_or(""),
-                            stars_str,
-                            r["registry"].as_str().unwrap_or(""),
-                            quality,
-                            r["description"].as_str().unwrap_or("")
-                        );
-                        println!("   {}", r["github_url"].as_str().unwrap_or(""));
-                        println!();
-                    }
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Apply pending schema migrations (any command does this on opening the
+    /// database; this shows what changes)
+    Migrate {
+        /// List the pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexCommand {
+    /// Segments, documents, size, terms per field, and the largest
+    /// documents, without changing anything
+    Stats {
+        /// How many of the largest documents to list
+        #[arg(long, default_value = "10")]
+        largest: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Merge the index's segments, for faster cold searches after many
+    /// updates. Safe to run from cron while `serve` is running.
+    Optimize {
+        /// Segments to leave
+        #[arg(long, default_value = "1")]
+        segments: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum PolicyCommand {
+    /// Print the policy in effect
+    Show,
+    /// Sign policy.toml with policy.key (created on first use), after which
+    /// an unsigned or edited policy is refused
+    Sign {
+        /// Catalog directory holding policy.toml (default: --catalog-dir)
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum DigestFormat {
+    /// Markdown, for chat channels and wikis
+    Md,
+    /// A standalone HTML page, for email or publishing
+    Html,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum ExportFormat {
+    /// One JSON object per line
+    Jsonl,
+    Csv,
+    /// A Markdown table
+    Md,
+}
+
+fn parse_channel(s: &str) -> Result<Channel, String> {
+    Channel::parse(s).ok_or_else(|| format!("unknown channel '{}' (expected curated, experimental, or community)", s))
+}
+
+fn parse_interval(s: &str) -> Result<std::time::Duration, String> {
+    sync::parse_interval(s).map_err(|e| e.to_string())
+}
+
+fn parse_top_sort(s: &str) -> Result<rank::TopSort, String> {
+    rank::TopSort::parse(s).ok_or_else(|| format!("unknown sort '{}' (expected popularity, stars, downloads, installs, or rating)", s))
+}
+
+fn parse_sort(s: &str) -> Result<rank::Sort, String> {
+    rank::Sort::parse(s).ok_or_else(|| format!("unknown sort '{}' (expected relevance, stars, quality, or recency)", s))
+}
+
+/// Non-zero usage counts, e.g. " ★12 · 340 downloads · 56 installs"
+fn format_usage(stars: i64, downloads: i64, installs: i64) -> String {
+    let mut out = String::new();
+    if stars > 0 {
+        out.push_str(&format!(" ★{}", stars));
+    }
+    for (count, label) in [(downloads, "downloads"), (installs, "installs")] {
+        if count > 0 {
+            out.push_str(&format!("{} {} {}", if out.is_empty() { "" } else { " ·" }, count, label));
+        }
+    }
+    out
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+
+fn print_failure(failure: &db::SyncFailure, now: i64) {
+    println!("    {}", doctor::describe_failure(failure, now));
+}
+
+fn long_version() -> &'static str {
+    Box::leak(format!("{}\nfeatures: {}", env!("CARGO_PKG_VERSION"), features::summary()).into_boxed_str())
+}
+
+/// The `--profile-rank` profile, or one that changes nothing
+fn rank_profile(config: &config::Config, name: Option<&str>) -> Result<config::RankProfile> {
+    let Some(name) = name else {
+        return Ok(config::RankProfile::default());
+    };
+    config
+        .rank_profile(name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown ranking profile {} (known: {})", name, config.rank_profile_names().join(", ")))
+}
+
+/// `sync` exit status when some registries failed even after a retry
+const EXIT_SYNC_PARTIAL: i32 = 3;
+
+/// One line per registry: skills afterwards, skills over its limits, time
+/// taken, and any failure
+fn print_sync_summary(summary: &github::SyncSummary) {
+    println!("{:<20} {:>7} {:>8} {:>8}  status", "registry", "skills", "skipped", "time");
+    for r in &summary.registries {
+        let retried = if r.retried { " on retry" } else { "" };
+        let status = match &r.error {
+            Some((stage, error)) => format!("failed ({}){}: {}", stage, retried, error),
+            None => format!("ok{}", retried),
+        };
+        println!("{:<20} {:>7} {:>8} {:>7.1}s  {}", r.registry, r.skills, r.skipped, r.seconds, status);
+    }
+    if summary.skipped() > 0 {
+        println!("{} skills left out by the registry limits in config.toml", summary.skipped());
+    }
+    if summary.failed() > 0 {
+        println!("{} of {} registries failed; see `registries` for details", summary.failed(), summary.registries.len());
+    }
+}
+
+/// Slugs in the catalog, and `registry:slug` for each, as completion
+/// candidates. Uses the default data directory and `SKILL_SEARCH_CATALOG_DIR`,
+/// since options on the line being completed aren't parsed yet.
+fn complete_slugs() -> Vec<CompletionCandidate> {
+    let mut dirs: Vec<PathBuf> = workspace::data_dir(None).into_iter().collect();
+    dirs.extend(std::env::var_os(workspace::CATALOG_DIR_ENV).map(PathBuf::from));
+    let mut slugs = std::collections::BTreeSet::new();
+    for dir in dirs {
+        let Ok(db) = db::Database::open_read_only(&dir.join("skills.db")) else {
+            continue;
+        };
+        for (registry, slug) in db.get_all_slugs().unwrap_or_default() {
+            slugs.insert(format!("{}:{}", registry, slug));
+            slugs.insert(slug);
+        }
+    }
+    slugs.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// Frontmatter, heading outline, and first paragraph of a SKILL.md, for
+/// `show` without `--full`
+fn print_skill_md_summary(skill_md: &str) {
+    let summary = text::summarize(skill_md);
+    println!("\n--- SKILL.md: {} lines, ~{} tokens (--full to print it) ---", summary.lines, summary.tokens);
+    if let Some(frontmatter) = summary.frontmatter {
+        println!("Frontmatter:");
+        for line in frontmatter.lines().filter(|l| !l.trim().is_empty()) {
+            println!("  {}", line.trim_end());
+        }
+    }
+    if !summary.headings.is_empty() {
+        println!("Outline:");
+        for h in &summary.headings {
+            println!("  {}{}", "  ".repeat(h.level as usize - 1), h.title);
+        }
+    }
+    if let Some(paragraph) = summary.first_paragraph {
+        println!("Summary: {}", paragraph);
+    }
+}
+
+/// Resolve a user-supplied slug to a single skill. On a TTY, ambiguous or
+/// partial matches open a selection prompt; otherwise a JSON error listing
+/// the candidates is written to stderr and the process exits.
+fn resolve_slug_or_exit(db: &db::Database, slug: &str, priority: &[String]) -> Result<db::Skill> {
+    let (error, prompt, candidates) = match resolve::resolve_skill(db, slug, priority)? {
+        Resolution::Exact(s) => return Ok(s),
+        Resolution::Preferred(s, others) => {
+            // On stderr, so JSON output stays parseable
+            let others: Vec<&str> = others.iter().map(|o| o.registry.as_str()).collect();
+            eprintln!(
+                "Using {}:{} by registry priority ({}); also in {}. Write registry:slug for another.",
+                s.registry,
+                s.slug,
+                priority.join(" > "),
+                others.join(", ")
+            );
+            return Ok(s);
+        }
+        Resolution::Ambiguous(c) => ("ambiguous", format!("'{}' exists in several registries. Pick one", slug), c),
+        Resolution::Candidates(c) => ("not_found", format!("No exact match for '{}'. Did you mean", slug), c),
+    };
+
+    if !interactive() || candidates.is_empty() {
+        let err = resolve::ResolutionError::new(error, slug, &candidates);
+        eprintln!("{}", serde_json::to_string_pretty(&err)?);
+        std::process::exit(1);
+    }
+
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|s| format!("{}:{} - {}", s.registry, s.slug, s.name))
+        .collect();
+    match select(&prompt, &items)? {
+        Some(i) => Ok(candidates.into_iter().nth(i).expect("selection in range")),
+        None => std::process::exit(1),
+    }
+}
+
+/// Whether prompts can be shown: a `tui` build on a terminal
+fn interactive() -> bool {
+    features::enabled("tui") && std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Index of the item picked, or None when the prompt is cancelled
+#[cfg(feature = "tui")]
+fn select(prompt: &str, items: &[String]) -> Result<Option<usize>> {
+    Ok(dialoguer::Select::new().with_prompt(prompt).items(items).default(0).interact_opt()?)
+}
+
+#[cfg(not(feature = "tui"))]
+fn select(_prompt: &str, _items: &[String]) -> Result<Option<usize>> {
+    features::require("tui", "interactive prompts").map(|_| None)
+}
+
+#[cfg(feature = "tui")]
+fn confirm(prompt: &str) -> Result<bool> {
+    Ok(dialoguer::Confirm::new().with_prompt(prompt).default(false).interact()?)
+}
+
+#[cfg(not(feature = "tui"))]
+fn confirm(_prompt: &str) -> Result<bool> {
+    features::require("tui", "interactive prompts").map(|_| false)
+}
+
+fn print_hook_runs(runs: &[db::HookRun]) {
+    for run in runs {
+        if run.success() {
+            println!("Hook {}: ok", run.hook);
+        } else {
+            let code = run.exit_code.map(|c| format!(" (exit {})", c)).unwrap_or_default();
+            eprintln!("Hook {}: failed{}\n{}", run.hook, code, run.output.trim_end());
+        }
+    }
+}
+
+/// List the environment variables a skill references, marking the ones
+/// missing from the current environment. Returns the unset names.
+fn print_env_vars(db: &db::Database, skill: &db::Skill) -> Result<Vec<String>> {
+    let vars = db.get_env_vars(&skill.registry, &skill.slug)?;
+    if vars.is_empty() {
+        println!("Environment: none referenced");
+        return Ok(Vec::new());
+    }
+    let unset = env_vars::unset_vars(vars.iter().map(|(name, _)| name.as_str()), |v| std::env::var(v).ok());
+    println!("Environment:");
+    for (name, source) in &vars {
+        let state = if unset.contains(&name.as_str()) { "unset" } else { "set" };
+        println!("  {}  {}  ({})", name, state, source.as_str());
+    }
+    Ok(unset.into_iter().map(String::from).collect())
+}
+
+/// Warn on stderr about disguised characters in a skill's slug or name
+fn print_unicode_warnings(db: &db::Database, skill: &db::Skill) -> Result<()> {
+    for issue in db.get_unicode_flags(&skill.registry, &skill.slug)? {
+        eprintln!("WARNING [{}]: {}", issue.flag.as_str(), issue.detail);
+    }
+    Ok(())
+}
+
+/// One-line summary of the recorded shell audit findings for a skill
+fn print_audit_summary(db: &db::Database, skill: &db::Skill) -> Result<()> {
+    let findings = db.get_audit_findings(&skill.registry, &skill.slug)?;
+    if findings.is_empty() {
+        println!("Shell audit: no findings");
+        return Ok(());
+    }
+    let high = findings.iter().filter(|f| f.severity == audit::Severity::High).count();
+    println!(
+        "Shell audit: {} finding{} ({} high) - run `safe-skill-search audit {}:{}` for details",
+        findings.len(),
+        if findings.len() == 1 { "" } else { "s" },
+        high,
+        skill.registry,
+        skill.slug
+    );
+    Ok(())
+}
+
+fn print_security_summary(skill: &db::Skill, report: &security::SecurityReport) {
+    println!(
+        "Security scan: risk {} ({}), {} finding{} in {} file{}{}",
+        report.score,
+        report.level(),
+        report.findings.len(),
+        if report.findings.len() == 1 { "" } else { "s" },
+        report.files + 1,
+        if report.files == 0 { "" } else { "s" },
+        if report.findings.is_empty() { String::new() } else { format!(" - run `safe-skill-search scan {}:{}` for details", skill.registry, skill.slug) }
+    );
+}
+
+fn print_security_findings(report: &security::SecurityReport) {
+    for f in &report.findings {
+        println!("{}:{}  [{}] {}: {}", f.file, f.finding.line, f.finding.severity.as_str(), f.finding.rule, f.finding.message);
+        println!("    {}", f.finding.snippet);
+    }
+}
+
+/// Injection risk score and the rules behind it
+fn print_injection_risk<S: AsRef<str>>(score: i64, rules: &[S]) {
+    if rules.is_empty() {
+        println!("Injection Risk: {}", score);
+    } else {
+        println!("Injection Risk: {} ({})", score, rules.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", "));
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Answers the completion scripts' requests, then exits
+    CompleteEnv::with_factory(Cli::command).complete();
+    let cli = Cli::parse();
+
+    let filter = if cli.verbose {
+        EnvFilter::new("safe_skill_search=debug,info")
+    } else {
+        EnvFilter::new("safe_skill_search=info,warn")
+    };
+    // Over MCP, stdout carries the protocol
+    if matches!(cli.command, Commands::ServeMcp) {
+        tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr).init();
+    } else {
+        tracing_subscriber::fmt().with_env_filter(filter).init();
+    }
+
+    let data_dir = cli.data_dir.clone();
+    let no_auto_recover = cli.no_auto_recover;
+    let layered = cli.catalog_dir.is_some() || std::env::var_os(workspace::CATALOG_DIR_ENV).is_some();
+    let err = match run(cli).await {
+        Err(e) if db::is_corruption(&e) => e,
+        other => return other,
+    };
+    // Only the data directory's database is ours to replace
+    let db_path = workspace::data_dir(data_dir)?.join("skills.db");
+    if !db_path.exists() || db::Database::is_intact(&db_path) {
+        if layered {
+            return Err(err.context("The system catalog's database is corrupt; rebuild it where it is synced"));
+        }
+        return Err(err);
+    }
+    if no_auto_recover {
+        return Err(err.context(format!(
+            "The database {} is corrupt; move it aside and the catalog will be resynced, or run without --no-auto-recover",
+            db_path.display()
+        )));
+    }
+    let backup = db::Database::back_up_corrupt(&db_path, unix_now())?;
+    tracing::warn!(
+        "The database was corrupt ({:#}); moved it to {} and starting over with a fresh sync. Installed skills are still on disk, but their install records, pins, and the audit log stay in the backup.",
+        err,
+        backup.display()
+    );
+    // A new empty database syncs on open, then the command runs again
+    run(Cli::parse()).await
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    // Schemas are static; don't open (or sync) the database for them
+    match &cli.command {
+        Commands::Search { schema: true, queries_file: Some(_), .. } => {
+            println!("{}", output::schema_for::<output::BatchSearchOutput>());
+            return Ok(());
+        }
+        Commands::Search { schema: true, .. } => {
+            println!("{}", output::schema_for::<output::SearchOutput>());
+            return Ok(());
+        }
+        Commands::Audit { schema: true, .. } => {
+            println!("{}", output::schema_for::<output::AuditOutput>());
+            return Ok(());
+        }
+        Commands::Scan { schema: true, .. } => {
+            println!("{}", output::schema_for::<output::ScanOutput>());
+            return Ok(());
+        }
+        Commands::AuditCatalog { schema: true, .. } => {
+            println!("{}", output::schema_for::<catalog::SignedReport>());
+            return Ok(());
+        }
+        Commands::Eval { schema: true, .. } => {
+            println!("{}", output::schema_for::<eval::EvalReport>());
+            return Ok(());
+        }
+        Commands::Quality { command: QualityCommand::Report { schema: true, .. } } => {
+            println!("{}", output::schema_for::<quality::CalibrationReport>());
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // The script calls back into the binary (see `main`) for completions
+    if let Commands::Completions { shell } = &cli.command {
+        let name = match shell {
+            CompletionShell::Bash => "bash",
+            CompletionShell::Zsh => "zsh",
+            CompletionShell::Fish => "fish",
+            CompletionShell::Powershell => "powershell",
+        };
+        let shells = Shells::builtins();
+        let completer = shells.completer(name).ok_or_else(|| anyhow::anyhow!("No completions for {}", name))?;
+        let bin = Cli::command().get_name().to_string();
+        completer.write_registration("COMPLETE", &bin, &bin, &bin, &mut std::io::stdout())?;
+        return Ok(());
+    }
+
+    // Candidates aren't in the catalog, so this doesn't need the database either
+    if let Commands::Quality { command: QualityCommand::BatchScore { input, output } } = &cli.command {
+        let entries = quality::score_candidates_file(input)?;
+        let mut text = String::new();
+        for entry in &entries {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        match output {
+            Some(path) => {
+                std::fs::write(path, text)?;
+                eprintln!("Scored {} candidates to {}", entries.len(), path.display());
+            }
+            None => print!("{}", text),
+        }
+        return Ok(());
+    }
+
+    let paths = workspace::Paths::new(cli.data_dir, cli.catalog_dir)?;
+    let (data_dir, catalog_dir) = (paths.data_dir.clone(), paths.catalog_dir.clone());
+    let (db_path, index_path, repos_dir) = (paths.db_path(), paths.index_path(), paths.repos_dir());
+
+    // Before the policy is loaded: signing is how a refused one is fixed
+    if let Commands::Policy { command: PolicyCommand::Sign { dir } } = &cli.command {
+        let Some(dir) = dir.as_ref().or(catalog_dir.as_ref()) else {
+            anyhow::bail!("No catalog directory to sign the policy in; pass one or use --catalog-dir");
+        };
+        println!("Signed {:?}", policy::sign_in(dir)?);
+        return Ok(());
+    }
+
+    // Before opening the database, which would apply the migrations. Only
+    // the data directory's database is ours to migrate.
+    if let Commands::Db { command: DbCommand::Migrate { dry_run } } = &cli.command {
+        let path = data_dir.join("skills.db");
+        let pending = migrations::pending_at(&path)?;
+        if pending.is_empty() {
+            println!("{} is up to date (schema version {})", path.display(), migrations::latest_version());
+            return Ok(());
+        }
+        println!("Pending migrations for {}:", path.display());
+        for m in &pending {
+            println!("  {:>3}  {}", m.version, m.description);
+        }
+        if *dry_run {
+            println!("Dry run; nothing was changed.");
+        } else {
+            db::Database::open(&path)?;
+            println!("Migrated to schema version {}", migrations::latest_version());
+        }
+        return Ok(());
+    }
+
+    // Read-only, so neither a sync nor a rebuild runs first
+    if let Commands::Index { command: IndexCommand::Stats { largest, json } } = &cli.command {
+        let config = config::Config::load(&data_dir.join("config.toml"))?;
+        let stats = index::SearchIndex::open_read_only(&index_path, &config.index)?.stats(*largest)?;
+        if *json {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            return Ok(());
+        }
+        println!("Index: {}", index_path.display());
+        println!("Segments: {}", stats.segments);
+        println!("Documents: {} ({} deleted)", stats.docs, stats.deleted_docs);
+        println!("Size: {} ({} stored text)", install::format_size(stats.bytes), install::format_size(stats.store_bytes));
+        println!("\nFields:");
+        for f in &stats.fields {
+            println!("  {:<12} {:>9} terms  {:>9}", f.field, f.terms, install::format_size(f.bytes));
+        }
+        if !stats.largest.is_empty() {
+            println!("\nLargest documents:");
+            for d in &stats.largest {
+                println!("  {:>9}  {}:{}", install::format_size(d.bytes), d.registry, d.slug);
+            }
+        }
+        return Ok(());
+    }
+    if let Commands::Index { command: IndexCommand::Optimize { segments } } = &cli.command {
+        // A system catalog's index is read-only; the user layer's is ours
+        let path = data_dir.join("index");
+        if !path.join("meta.json").exists() {
+            anyhow::bail!("No search index at {:?}", path);
+        }
+        let config = config::Config::load(&data_dir.join("config.toml"))?;
+        let search_index = index::SearchIndex::open_or_create(&path, &config.index)?;
+        if search_index.recreated() {
+            println!("The index was built by an older version and has been cleared; the next search rebuilds it.");
+            return Ok(());
+        }
+        let started = std::time::Instant::now();
+        match search_index.optimize(*segments)? {
+            Some((before, after)) => println!("Merged {} segments into {} in {:.1}s", before, after, started.elapsed().as_secs_f64()),
+            None => println!("Nothing to merge in {}", path.display()),
+        }
+        return Ok(());
+    }
+
+    let mut workspace = workspace::Workspace::open(paths)?;
+    workspace
+        .prepare(workspace::Needs {
+            writes_catalog: matches!(cli.command, Commands::AuditCatalog { .. } | Commands::Bench { .. } | Commands::Audit { shellcheck: true, .. } | Commands::Serve { .. } | Commands::Prune { .. })
+                || matches!(cli.command, Commands::Quality { command: QualityCommand::Import { .. } | QualityCommand::Refresh { .. } }),
+            fills_catalog: matches!(cli.command, Commands::Import { .. }),
+            search: matches!(cli.command, Commands::Search { installed: false, .. }),
+            skip_space_checks: matches!(cli.command, Commands::Sync { force: true, .. }),
+        })
+        .await?;
+    let workspace::Workspace { mut db, config, rules, decisions, search_index, user_index, quality_scores, mut rebuild_after, .. } = workspace;
+
+    match cli.command {
+        Commands::Sync { watch: true, .. } if catalog_dir.is_some() => {
+            anyhow::bail!("sync --watch keeps a catalog of its own up to date; run it without --catalog-dir");
+        }
+        Commands::Sync { force, jobs, watch: true, interval } => {
+            tracing::info!("Syncing every {}s; Ctrl-C to stop", interval.as_secs());
+            let mut force = force;
+            loop {
+                let target = sync::SyncTarget { db: &mut db, repos_dir: &repos_dir, index: &search_index, index_path: &index_path };
+                match sync::watch_round(target, &config.registries, force, jobs as usize).await {
+                    Ok((summary, changes)) => {
+                        print_sync_summary(&summary);
+                        for (verb, keys) in [("Added", &changes.added), ("Updated", &changes.updated), ("Removed", &changes.removed)] {
+                            for (registry, slug) in keys {
+                                tracing::info!("{} {}:{}", verb, registry, slug);
+                            }
+                        }
+                        tracing::info!("Sync complete: {}", changes.summary());
+                    }
+                    // The next round may well work; a service shouldn't stop for one
+                    Err(e) => tracing::error!("Sync failed: {:#}", e),
+                }
+                force = false;
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+            }
+        }
+        Commands::Sync { force, jobs, .. } if catalog_dir.is_some() => {
+            // Only the user layer is ours to sync, and only for registries
+            // the system catalog doesn't already provide
+            let mut user_db = db::Database::open(&data_dir.join("skills.db"))?;
+            let (user_repos, user_index_path) = (data_dir.join("repos"), data_dir.join("index"));
+            let user_index = user_index.as_ref().expect("a system catalog comes with a user index");
+            let target = sync::SyncTarget { db: &mut user_db, repos_dir: &user_repos, index: user_index, index_path: &user_index_path };
+            let summary = sync::sync_user_layer(&db, target, &config.registries, force, jobs as usize).await?;
+            print_sync_summary(&summary);
+            tracing::info!("Sync of the user catalog complete");
+            if summary.failed() > 0 {
+                std::process::exit(EXIT_SYNC_PARTIAL);
+            }
+        }
+        Commands::Sync { force, jobs, .. } => {
+            let target = sync::SyncTarget { db: &mut db, repos_dir: &repos_dir, index: &search_index, index_path: &index_path };
+            let summary = sync::sync(target, &config.registries, force, jobs as usize).await?;
+            print_sync_summary(&summary);
+            tracing::info!("Sync complete");
+            if summary.failed() > 0 {
+                std::process::exit(EXIT_SYNC_PARTIAL);
+            }
+        }
+        Commands::Search {
+            query,
+            queries_file,
+            limit,
+            registry,
+            trusted,
+            min_stars,
+            has_version,
+            tags,
+            channel,
+            has_code,
+            min_score,
+            installed,
+            max_injection_risk,
+            max_age_days,
+            include_unreachable,
+            include_removed,
+            semantic,
+            fuzzy,
+            verbatim,
+            sort,
+            profile_rank,
+            json,
+            compact,
+            ndjson,
+            fields,
+            schema: _,
+        } => {
+            if fields.is_some() && !(json || ndjson || queries_file.is_some()) {
+                anyhow::bail!("--fields applies to --json, --ndjson, and --queries-file output");
+            }
+            let fields = fields.map(|spec| output::parse_fields(&spec, &output::field_names::<output::SearchResult>())).transpose()?;
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
+            let profile = rank_profile(&config, profile_rank.as_deref())?;
+            let (trusted, include_unreachable) = (trusted || profile.trusted, include_unreachable || profile.include_unreachable);
+            let min_score = min_score.or(profile.min_score);
+            let min_stars = min_stars.or(profile.min_stars);
+            let max_injection_risk = max_injection_risk.or(profile.max_injection_risk);
+            let max_age_days = max_age_days.or(profile.max_age_days);
+            rules.check_registry_flag(registry.as_deref())?;
+            // Whatever was installed is worth finding, whatever its score
+            let min_score = if installed { min_score.unwrap_or(0) } else { rules.min_score(min_score, 80)? };
+            let installed_copies = if installed { Some(install::installed_skills(&db)?) } else { None };
+            let tags: Vec<String> = tags.iter().filter_map(|t| github::normalize_tag(t)).collect();
+            let index_filters = index::IndexFilters {
+                registry: registry.as_deref(),
+                min_stars,
+                // The index only knows the registry's trust, which a decision can add to
+                trusted: trusted && !decisions.adds_trust(),
+                has_version,
+                tags: &tags,
+            };
+            // Built once, however many queries there are
+            let local_index = match &installed_copies {
+                Some(skills) => {
+                    let local_index = index::SearchIndex::in_memory(&config.index)?;
+                    local_index.index_skills(skills)?;
+                    Some(local_index)
+                }
+                None => None,
+            };
+            let service = search::SearchService {
+                enricher: output::Enricher {
+                    db: &db,
+                    quality: &quality_scores,
+                    decisions: &decisions,
+                    overlay_dir: config.index.overlay_dir.as_deref(),
+                    // Local copies are there whatever happened upstream
+                    unreachable: if installed { Default::default() } else { db.get_unreachable()? },
+                    now,
+                },
+                rules: &rules,
+                ranking: &config.ranking,
+                sort,
+                filters: output::SearchFilters {
+                    trusted,
+                    channel,
+                    include_unreachable,
+                    // Local copies are there whatever happened upstream
+                    include_removed: include_removed || installed,
+                    min_score,
+                    max_injection_risk,
+                    max_age_days,
+                },
+                registry: registry.as_deref(),
+                has_code,
+                // Pinned installs can stay on a superseded entry, so keep those
+                superseded: if installed { Default::default() } else { db.get_superseded()? },
+                installed: installed_copies.as_deref(),
+                limit,
+            };
+            let queries = match &queries_file {
+                Some(path) => search::read_queries(path)?,
+                None => vec![query.unwrap_or_default()],
+            };
+            let mut batch: Option<std::collections::BTreeMap<String, Vec<output::SearchResult>>> = queries_file.is_some().then(std::collections::BTreeMap::new);
+            let mut indexes = search::Indexes {
+                db: &db,
+                catalog: &search_index,
+                user: user_index.as_ref(),
+                local: local_index.as_ref(),
+                weights: &config.index.weights,
+                direct: rebuild_after,
+            };
+
+            for query in queries {
+                // The database fallback requires every word, so it goes without synonyms
+                let keywords = if verbatim { query.clone() } else { query::key_terms(&query, &config.query) };
+                let terms = if verbatim { query.clone() } else { query::expand(&keywords, &config.query) };
+                if terms != query {
+                    tracing::debug!("Searching for {:?}", terms);
+                }
+                let mut results = service.fetch(|limit| indexes.search(&terms, &keywords, fuzzy, limit, &index_filters))?;
+                // Typos are the usual reason for finding nothing at all
+                let retry_fuzzy = results.is_empty() && !fuzzy && config.index.fuzzy_distance > 0 && !query.trim().is_empty();
+                if retry_fuzzy {
+                    results = service.fetch(|limit| indexes.search(&terms, &keywords, true, limit, &index_filters))?;
+                }
+                let (mut results, scale) = if semantic {
+                    (embeddings::semantic_search(&db, &config.embeddings, &query, results, service.fetch_limit(), &index_filters).await?, index::ScoreScale::Unit)
+                } else {
+                    (results, index::ScoreScale::keyword(&terms))
+                };
+                search::boost_recent(&db, &mut results, &profile, now);
+                let enriched = service.results(results, scale);
+
+                if let Some(batch) = &mut batch {
+                    batch.insert(query, enriched.collect());
+                } else if ndjson {
+                    // Each line goes out as soon as its skill is enriched, so
+                    // large exports start at once and don't pile up in memory
+                    for r in enriched {
+                        match &fields {
+                            Some(fields) => println!("{}", serde_json::to_string(&output::Projection::new(&r, fields)?)?),
+                            None => println!("{}", serde_json::to_string(&r)?),
+                        }
+                    }
+                } else if let (true, Some(fields)) = (json, &fields) {
+                    let out = output::ProjectedSearchOutput {
+                        schema_version: output::SCHEMA_VERSION,
+                        query,
+                        results: enriched.map(|r| output::Projection::new(&r, fields)).collect::<Result<_, _>>()?,
+                    };
+                    println!("{}", serde_json::to_string_pretty(&out)?);
+                } else if json {
+                    println!("{}", serde_json::to_string_pretty(&output::SearchOutput::new(query, enriched.collect()))?);
+                } else if compact {
+                    for r in enriched {
+                        println!("- {}:{}: {}{}", r.registry, r.slug, r.summary, if r.fuzzy { " [fuzzy]" } else { "" });
+                    }
+                } else {
+                    let enriched: Vec<_> = enriched.collect();
+                    if enriched.is_empty() {
+                        if installed {
+                            println!("No installed skills match.");
+                        } else {
+                            println!("No skills found with score >= {}. Try --min-score 0 to see all.", min_score);
+                        }
+                    } else {
+                        let color = std::io::stdout().is_terminal();
+                        if retry_fuzzy {
+                            println!("No exact matches for \"{}\"; showing close spellings.\n", query);
+                        }
+                        for (i, r) in enriched.iter().enumerate() {
+                            let trust_icon = if r.trusted { "✓" } else { "⚠" };
+                            let mut stars_str = format_usage(r.stars, r.downloads, r.installs);
+                            let rank = db::PopularityRank {
+                                stars_top_pct: r.stars_top_pct,
+                                downloads_top_pct: r.downloads_top_pct,
+                            };
+                            if let Some(rank) = rank.describe() {
+                                stars_str.push_str(&format!(" ({})", rank));
+                            }
+                        
+                            println!(
+                                "{}. [{}] {}{} ({}{}{}) [Q:{}]{}{}{} - {}",
+                                i + 1,
+                                trust_icon,
+                                r.name,
+                                stars_str,
+                                r.registry,
+                                if catalog_dir.is_some() && r.layer == db::Layer::User { ", user" } else { "" },
+                                if r.overlay { ", overlay" } else { "" },
+                                r.quality_score,
+                                if r.tested { " [tested]" } else { "" },
+                                if r.unreachable { " [unreachable]" } else { "" },
+                                if r.fuzzy { " [fuzzy]" } else { "" },
+                                r.description
+                            );
+                            if let Some(snippet) = &r.snippet {
+                                println!("   …{}…", snippet.render(color));
+                            }
+                            if r.security_risk >= 25 {
+                                println!("   ⚠ SECURITY RISK {} ({}) - check with `scan {}:{}` before installing", r.security_risk, risk::level(r.security_risk), r.registry, r.slug);
+                            }
+                            if !r.unicode_flags.is_empty() {
+                                let flags: Vec<_> = r.unicode_flags.iter().map(|f| f.as_str()).collect();
+                                println!("   ⚠ SUSPICIOUS UNICODE ({}) - check with `show` before installing", flags.join(", "));
+                            }
+                            match r.age_days {
+                                Some(age) => println!("   {} · updated {}", r.github_url, text::format_age(age)),
+                                None => println!("   {}", r.github_url),
+                            }
+                            println!();
+                        }
+                    }
+                }
+            }
+            rebuild_after = indexes.direct;
+
+            match (batch, &fields) {
+                (Some(batch), Some(fields)) => {
+                    let mut results = std::collections::BTreeMap::new();
+                    for (query, found) in batch {
+                        results.insert(query, found.iter().map(|r| output::Projection::new(r, fields)).collect::<Result<_, _>>()?);
+                    }
+                    let out = output::ProjectedBatchOutput { schema_version: output::SCHEMA_VERSION, results };
+                    println!("{}", serde_json::to_string_pretty(&out)?);
+                }
+                (Some(batch), None) => {
+                    let out = output::BatchSearchOutput { schema_version: output::SCHEMA_VERSION, results: batch };
+                    println!("{}", serde_json::to_string_pretty(&out)?);
+                }
+                (None, _) => {}
+            }
+        }
+        Commands::Show { outline: true, slug, .. } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let headings = output::skill_outline(&db, &s);
+            if headings.is_empty() {
+                println!("{}:{} has no headings", s.registry, s.slug);
+            }
+            for h in &headings {
+                println!("{}{}", "  ".repeat(h.level as usize - 1), h.title);
+            }
+        }
+        Commands::Show { code: true, slug, .. } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let samples = db.get_code_samples(&s.registry, &s.slug)?;
+            if samples.is_empty() {
+                println!("{}:{} has no code blocks with a language", s.registry, s.slug);
+            }
+            let mut heading = None;
+            for (i, sample) in samples.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                if sample.heading.is_some() && sample.heading != heading {
+                    println!("## {}\n", sample.heading.as_deref().unwrap_or_default());
+                    heading = sample.heading.clone();
+                }
+                print!("```{}\n{}```\n", sample.language, sample.code);
+            }
+        }
+        Commands::Show { slug, json: true, fields, .. } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let enricher = output::Enricher {
+                db: &db,
+                quality: &quality_scores,
+                decisions: &decisions,
+                overlay_dir: config.index.overlay_dir.as_deref(),
+                unreachable: db.get_unreachable()?,
+                now: unix_now(),
+            };
+            let skill_md = s.skill_md.clone();
+            let out = output::SkillOutput {
+                schema_version: output::SCHEMA_VERSION,
+                skill: enricher.result(s, false, 0.0),
+                skill_md,
+            };
+            match fields {
+                Some(spec) => {
+                    let mut known = output::field_names::<output::SearchResult>();
+                    known.push("skill_md".to_string());
+                    let fields = output::parse_fields(&spec, &known)?;
+                    let mut record = serde_json::to_value(&out.skill)?;
+                    record["skill_md"] = out.skill_md.into();
+                    println!("{}", serde_json::to_string_pretty(&output::Projection::of_value(record, &fields))?);
+                }
+                None => println!("{}", serde_json::to_string_pretty(&out)?),
+            }
+        }
+        Commands::Show { slug, links: show_links, full, .. } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            print_unicode_warnings(&db, &s)?;
+            let (risk, link_audits) = risk::RiskScore::of_skill(&db, &links::Blocklist::configured(&config.links)?, &s)?;
+            let quality_score = quality_scores.score_for(&s);
+
+            println!("Name: {}", s.name);
+            println!("Registry: {}", s.registry);
+            if catalog_dir.is_some() {
+                let layer = match db.layer_of(&s.registry, &s.slug)? {
+                    db::Layer::System => "system catalog",
+                    db::Layer::User => "user catalog",
+                };
+                println!("Layer: {}", layer);
+            }
+            println!("Channel: {}", github::channel_of(&s.registry).as_str());
+            let tags = db.get_tags(&s.registry, &s.slug)?;
+            if !tags.is_empty() {
+                println!("Tags: {}", tags.join(", "));
+            }
+            let frontmatter = github::parse_skill_frontmatter(&s.skill_md);
+            if let Some(license) = &frontmatter.license {
+                println!("License: {}", license);
+            }
+            if let Some(author) = &frontmatter.author {
+                println!("Author: {}", author);
+            }
+            if !frontmatter.allowed_tools.is_empty() {
+                println!("Allowed tools: {}", frontmatter.allowed_tools.join(", "));
+            }
+            if let Some(dir) = &config.index.overlay_dir {
+                let path = overlay::overlay_path(dir, &s.registry, &s.slug);
+                if path.exists() {
+                    println!("Overlay: {} (searched instead of the catalog copy)", path.display());
+                }
+            }
+            let previously = db.get_predecessors(&s.registry, &s.slug)?;
+            if !previously.is_empty() {
+                let ids: Vec<_> = previously.iter().map(|(r, slug)| format!("{}:{}", r, slug)).collect();
+                println!("Previously: {}", ids.join(", "));
+            }
+            if let Some((status, checked_at)) = db.get_unreachable_status(&s.registry, &s.slug)? {
+                let days = (unix_now() - checked_at).max(0) / 86_400;
+                println!("Unreachable: {} returned HTTP {} (checked {})", s.github_url, status, text::format_age(days));
+            }
+            if let Some(removed_at) = db.get_removed_at(&s.registry, &s.slug)? {
+                let days = (unix_now() - removed_at).max(0) / 86_400;
+                println!("Removed: gone from {} upstream (noticed {}); `prune` deletes it", s.registry, text::format_age(days));
+            }
+            match decisions.decide(&s.registry, &s.slug) {
+                Some((decision, layer)) => {
+                    let by = if layer == db::Layer::System { "system policy" } else { "your config" };
+                    println!("Trusted: {} ({} by {})", if decision == policy::Decision::Trust { "yes" } else { "no" }, decision.as_str(), by);
+                }
+                None => println!("Trusted: {}", if s.trusted { "yes" } else { "no" }),
+            }
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
+            match db.get_repo_stats(&s.registry, &s.slug)? {
+                Some(repo) => {
+                    println!("Stars: {} (GitHub repo {})", s.stars, repo.repo);
+                    let pushed = repo.pushed_at.map(|t| format!(", last commit {}", text::format_age((now - t).max(0) / 86_400))).unwrap_or_default();
+                    println!("Repo: {} open issues{}", repo.open_issues, pushed);
+                }
+                None => println!("Stars: {}", s.stars),
+            }
+            println!("Downloads: {}", s.downloads);
+            println!("Installs: {}", s.installs);
+            if let Some(age) = s.age_days(now) {
+                println!("Updated: {}", text::format_age(age));
+            }
+            if let Some(rank) = db.get_popularity_rank(&s.registry, &s.slug)?.describe() {
+                println!("Popularity: {} in {}", rank, s.registry);
+            }
+            if let Some(rating) = db.get_rating(&s.registry, &s.slug)? {
+                println!("Rating: {:.1}/5 ({} ratings)", rating.average, rating.count);
+                for review in &rating.reviews {
+                    let stars = review.rating.map(|r| format!("{:.0}★ ", r)).unwrap_or_default();
+                    println!("  {}{}: {}", stars, review.author.as_deref().unwrap_or("anonymous"), review.body);
+                }
+            }
+            println!("Quality Score: {}", quality_score);
+            if let Some(status) = db.get_test_status(&s.registry, &s.slug)? {
+                let stale = if status.content_hash != catalog::content_hash(&s) { ", skill changed since" } else { "" };
+                println!("Tests: {} passed, {} failed ({}{})", status.passed, status.failed, text::format_age((now - status.ran_at).max(0) / 86_400), stale);
+            }
+            println!("Description: {}", s.description);
+            println!("URL: {}", s.github_url);
+            println!("Risk Score: {} ({})", risk.score, risk.level());
+            let (injection_risk, injection_signals) = db.get_injection_risk(&s.registry, &s.slug)?;
+            print_injection_risk(injection_risk, &injection_signals);
+            print_env_vars(&db, &s)?;
+            print_audit_summary(&db, &s)?;
+            let flagged = link_audits.iter().filter(|l| !l.flags.is_empty()).count();
+            println!("Links: {} ({} flagged)", link_audits.len(), flagged);
+            if show_links {
+                for l in &link_audits {
+                    let flags: Vec<_> = l.flags.iter().map(|f| f.as_str()).collect();
+                    if flags.is_empty() {
+                        println!("  {}", l.url);
+                    } else {
+                        println!("  {}  [{}]", l.url, flags.join(", "));
+                    }
+                }
+            }
+            if full && !s.skill_md.is_empty() {
+                println!("\n--- SKILL.md ---\n{}", s.skill_md);
+            } else if !s.skill_md.is_empty() {
+                print_skill_md_summary(&s.skill_md);
+            }
+        }
+        Commands::Test { slug, yes } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let Some(skill_dir) = install::local_skill_dir(&repos_dir, &s) else {
+                anyhow::bail!("No local copy of {}:{} to test (try `sync`)", s.registry, s.slug);
+            };
+            let suite = match harness::find_tests(&skill_dir)? {
+                Some(suite) if !suite.commands.is_empty() => suite,
+                _ => {
+                    println!(
+                        "{}:{} declares no tests (looked for {} in {}/)",
+                        s.registry,
+                        s.slug,
+                        harness::MANIFEST_FILE,
+                        harness::TEST_DIRS.join("/ and ")
+                    );
+                    return Ok(());
                 }
+            };
+
+            println!("Tests declared by {}:{} in {}/:", s.registry, s.slug, suite.dir.display());
+            for t in &suite.commands {
+                match &t.name {
+                    Some(name) => println!("  {}: {}", name, t.command_line()),
+                    None => println!("  {}", t.command_line()),
+                }
+            }
+            if !yes {
+                if !interactive() {
+                    anyhow::bail!("Not running skill tests without confirmation; pass --yes to run them non-interactively");
+                }
+                if !confirm("Run these commands? They run in a temporary copy of the skill, with your user's permissions")? {
+                    std::process::exit(1);
+                }
+            }
+
+            let results = harness::run_tests(&s, &skill_dir, &suite, &data_dir.join("staging"))?;
+            for r in &results {
+                if r.passed() {
+                    println!("ok      {}", r.name);
+                } else {
+                    let code = r.exit_code.map(|c| format!(" (exit {})", c)).unwrap_or_default();
+                    println!("FAILED  {}{}\n{}", r.name, code, r.output.trim_end());
+                }
+            }
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
+            db.record_test_run(&s.registry, &s.slug, &catalog::content_hash(&s), &results, now)?;
+            let failed = results.iter().filter(|r| !r.passed()).count();
+            println!("{} passed, {} failed", results.len() - failed, failed);
+            if failed > 0 {
+                std::process::exit(1);
+            }
+        }
+        Commands::Url { slug, copy } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            if copy {
+                launch::copy_to_clipboard(&s.github_url)?;
+                println!("{} (copied to clipboard)", s.github_url);
+            } else {
+                println!("{}", s.github_url);
+            }
+        }
+        Commands::Open { slug, editor } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            if editor {
+                let Some(skill_dir) = install::local_skill_dir(&repos_dir, &s) else {
+                    anyhow::bail!("No synced copy of {}:{} to edit; `open {}` shows it on GitHub", s.registry, s.slug, slug);
+                };
+                launch::open_in_editor(&skill_dir.join("SKILL.md"))?;
+            } else {
+                println!("Opening {}", s.github_url);
+                launch::open_url(&s.github_url)?;
+            }
+        }
+        Commands::Eval { k, judgments, json, schema: _ } => {
+            let user_file = data_dir.join(eval::USER_JUDGMENTS_FILE);
+            let mut files: Vec<_> = judgments.iter().map(PathBuf::as_path).collect();
+            if user_file.exists() {
+                files.insert(0, &user_file);
+            }
+            let report = eval::evaluate(&search_index, &db, &eval::load_judgments(&files)?, k)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
             }
+
+            for q in &report.queries {
+                let ranks: Vec<_> = q
+                    .relevant
+                    .iter()
+                    .map(|(skill, rank)| format!("{} {}", skill, rank.map(|r| format!("#{}", r)).unwrap_or_else(|| "missed".to_string())))
+                    .collect();
+                println!("{:.2}  {}  ({})", q.ndcg, q.query, ranks.join(", "));
+            }
+            if !report.skipped.is_empty() {
+                println!("\nSkipped {} queries whose skills aren't in the catalog", report.skipped.len());
+            }
+            println!(
+                "\n{} queries: P@{} {:.3}  NDCG@{} {:.3}  MRR {:.3}",
+                report.queries.len(),
+                report.k,
+                report.precision,
+                report.k,
+                report.ndcg,
+                report.mrr
+            );
         }
-        Commands::Show { slug } => {
-            let skill = db.get_skill_by_slug(&slug)?;
-            match skill {
-                Some(s) => {
-                    let quality_score = quality_scores
-                        .get_score(&s.registry, &s.slug)
-                        .or_else(|| quality_scores.get_score(&s.registry, &s.name))
-                        .unwrap_or(0);
-                    
-                    println!("Name: {}", s.name);
-                    println!("Registry: {}", s.registry);
-                    println!("Trusted: {}", if s.trusted { "yes" } else { "no" });
-                    println!("Stars: {}", s.stars);
-                    println!("Quality Score: {}", quality_score);
-                    println!("Description: {}", s.description);
-                    println!("URL: {}", s.github_url);
-                    if !s.skill_md.is_empty() {
-                        println!("\n--- SKILL.md ---\n{}", s.skill_md);
+        Commands::Bench { sync, iterations, no_save } => {
+            let baseline_path = data_dir.join(bench::BASELINE_FILE);
+            let previous = bench::Baseline::load(&baseline_path)?;
+            let mut current = bench::Baseline {
+                recorded_at: unix_now(),
+                ..Default::default()
+            };
+
+            if sync {
+                sync::check_space(&db, &repos_dir, &index_path)?;
+                for (name, full) in [("sync_full", true), ("sync_incremental", false)] {
+                    println!("Timing {}...", name.replace('_', " "));
+                    if full {
+                        db.clear_sync_state()?;
                     }
+                    let started = std::time::Instant::now();
+                    db.begin_sync_run(unix_now())?;
+                    sync::sync_catalog(&mut db, &repos_dir, &config.registries, github::DEFAULT_SYNC_JOBS).await?;
+                    current.record(name, started.elapsed());
+                    db.log_action(Action::Sync, "catalog", &format!("bench {}", name), unix_now())?;
                 }
-                None => {
-                    eprintln!("Skill not found: {}", slug);
+            }
+
+            println!("Timing index rebuild...");
+            let started = std::time::Instant::now();
+            search_index.rebuild(&db)?;
+            current.record("index_rebuild", started.elapsed());
+
+            let queries: Vec<_> = eval::load_judgments(&[])?.into_iter().map(|j| j.query).collect();
+            println!("Timing {} searches...", queries.len() * iterations);
+            let latencies = bench::search_latencies(&search_index, &queries, iterations, 20)?;
+            current.record("search_p50", bench::percentile(&latencies, 50.0));
+            current.record("search_p99", bench::percentile(&latencies, 99.0));
+            current.skills = db.count_all_skills()?;
+
+            let names = ["sync_full", "sync_incremental", "index_rebuild", "search_p50", "search_p99"];
+            println!("\n{:<18} {:>12} {:>12} {:>9}", "", "this run", "baseline", "change");
+            for row in bench::compare(&current, &names, previous.as_ref()) {
+                let baseline = row.baseline.map(|b| format!("{:.1}ms", b)).unwrap_or_else(|| "-".to_string());
+                let change = row.change_pct().map(|p| format!("{:+.0}%", p)).unwrap_or_default();
+                println!("{:<18} {:>10.1}ms {:>12} {:>9}  {}", row.name, row.millis, baseline, change, row.verdict());
+            }
+            if let Some(prev) = previous.as_ref().filter(|p| p.skills != current.skills) {
+                println!("Note: the catalog had {} skills at the baseline, {} now", prev.skills, current.skills);
+            }
+
+            if !no_save {
+                if let Some(prev) = &previous {
+                    current.carry_over(prev);
+                }
+                current.save(&baseline_path)?;
+            }
+        }
+        Commands::Quality { command: QualityCommand::BatchScore { .. } } => unreachable!("scored before the database is opened"),
+        Commands::Quality { command: QualityCommand::Import { file, replace } } => {
+            let content = std::fs::read_to_string(&file).map_err(|e| anyhow::anyhow!("Can't read {:?}: {}", file, e))?;
+            let entries = quality::parse_entries(&content).map_err(|e| anyhow::anyhow!("{}: {:#}", file.display(), e))?;
+            db.import_quality_entries(&entries, replace)?;
+            println!("Imported {} scores from {} ({} in the database)", entries.len(), file.display(), db.get_quality_entries()?.len());
+        }
+        Commands::Quality { command: QualityCommand::Refresh { url } } => {
+            let entries = quality::fetch_entries(&url).await?;
+            if entries.is_empty() {
+                anyhow::bail!("{} has no scores; keeping the current ones", url);
+            }
+            let before = QualityScores::from_entries(db.get_quality_entries()?);
+            db.import_quality_entries(&entries, true)?;
+            let changed = entries.iter().filter(|e| before.get_entry(&e.registry, &e.name).is_none_or(|b| b.score != e.score)).count();
+            println!("Refreshed {} scores from {} ({} new or changed)", entries.len(), url, changed);
+        }
+        Commands::Quality { command: QualityCommand::Report { registry, json, schema: _ } } => {
+            let report = quality::calibration_report(&db, &quality_scores, &links::Blocklist::configured(&config.links)?, registry.as_deref())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+
+            let correlation = |c: Option<f64>| c.map(|c| format!("{:+.2}", c)).unwrap_or_else(|| "n/a".to_string());
+            println!("Scored skills: {} ({} in catalog without a score)", report.scored, report.unscored);
+            println!("Score vs stars (rank correlation): {}", correlation(report.stars_correlation));
+            println!("Score vs rating (rank correlation): {}", correlation(report.rating_correlation));
+            for (direction, heading) in [
+                (quality::Direction::Overrated, "Possibly overrated (high score, contradicted by scanners or users)"),
+                (quality::Direction::Underrated, "Possibly underrated (low score, popular or well rated)"),
+            ] {
+                let outliers: Vec<_> = report.outliers.iter().filter(|o| o.direction == direction).collect();
+                if outliers.is_empty() {
+                    continue;
+                }
+                println!("\n{} ({}):", heading, outliers.len());
+                for o in outliers {
+                    println!("  {:>3}  {}:{}  {}", o.score, o.registry, o.slug, o.reasons.join("; "));
+                }
+            }
+            if !report.not_in_catalog.is_empty() {
+                println!("\nCurated entries matching no catalog skill ({}):", report.not_in_catalog.len());
+                for name in report.not_in_catalog.iter().take(20) {
+                    println!("  {}", name);
+                }
+                if report.not_in_catalog.len() > 20 {
+                    println!("  ... and {} more (see --json)", report.not_in_catalog.len() - 20);
+                }
+            }
+        }
+        Commands::Policy { command: PolicyCommand::Show } => {
+            let Some(dir) = &catalog_dir else {
+                println!("No system catalog (--catalog-dir), so no policy applies");
+                return Ok(());
+            };
+            let path = dir.join(policy::POLICY_FILE);
+            if !path.exists() {
+                println!("No {} in {:?}; nothing is restricted", policy::POLICY_FILE, dir);
+                return Ok(());
+            }
+            let signed = dir.join(policy::KEY_FILE).exists();
+            println!("Policy: {}{}", path.display(), if signed { " (signed)" } else { "" });
+            println!("Enforced: {}", if rules.enforced { "yes" } else { "no, flags can override it" });
+            println!("Registries: {}", if rules.registries.is_empty() { "any".to_string() } else { rules.registries.join(", ") });
+            println!("Minimum quality: {}", rules.min_quality.map(|m| m.to_string()).unwrap_or_else(|| "none".to_string()));
+            println!("Installs: {}", if rules.trusted_installs_only { "trusted skills only" } else { "any" });
+            let list = |patterns: &[String]| if patterns.is_empty() { "none".to_string() } else { patterns.join(", ") };
+            println!("Trusted: {}", list(&rules.decisions.trust));
+            println!("Denied: {}", list(&rules.decisions.deny));
+            let may = |allowed: bool| if allowed { "yes" } else { "no" };
+            println!(
+                "Your config may trust: {}, deny: {}",
+                may(rules.user_overrides.trust && !rules.enforced),
+                may(rules.user_overrides.deny)
+            );
+        }
+        Commands::Policy { command: PolicyCommand::Sign { .. } } => unreachable!("signed before the policy is loaded"),
+        Commands::Db { .. } => unreachable!("migrated before the database is opened"),
+        Commands::Index { .. } => unreachable!("inspected before the database is opened"),
+        Commands::Completions { .. } => unreachable!("printed before the database is opened"),
+        Commands::Deps { slug, dot } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let tree = deps::dependency_tree(&db, &s)?;
+            if dot {
+                print!("{}", deps::render_dot(&s, &tree));
+            } else if tree.is_empty() {
+                println!("{}:{} has no skill dependencies", s.registry, s.slug);
+            } else {
+                print!("{}", deps::render_tree(&s, &tree));
+            }
+        }
+        Commands::Audit { slug, shellcheck, json, schema: _ } => {
+            let s = resolve_slug_or_exit(&db, &slug.unwrap_or_default(), &config.resolve.registry_priority)?;
+            if shellcheck {
+                match audit::run_shellcheck(&s.skill_md)? {
+                    Some(findings) => db.set_audit_findings(&s.registry, &s.slug, db::AUDIT_SOURCE_SHELLCHECK, &findings)?,
+                    None => eprintln!("shellcheck not found on PATH; showing heuristic findings only"),
+                }
+            }
+            let findings = db.get_audit_findings(&s.registry, &s.slug)?;
+
+            if json {
+                let out = output::AuditOutput {
+                    schema_version: output::SCHEMA_VERSION,
+                    registry: s.registry,
+                    slug: s.slug,
+                    findings,
+                };
+                println!("{}", serde_json::to_string_pretty(&out)?);
+            } else if findings.is_empty() {
+                println!("No findings in the shell blocks of {} ({})", s.slug, s.registry);
+            } else {
+                for f in &findings {
+                    println!("SKILL.md:{}  [{}] {}: {}", f.line, f.severity.as_str(), f.rule, f.message);
+                    println!("    {}", f.snippet);
+                }
+            }
+        }
+        Commands::Scan { slug, json, schema: _ } => {
+            let s = resolve_slug_or_exit(&db, &slug.unwrap_or_default(), &config.resolve.registry_priority)?;
+            let report = security::scan_installable(&repos_dir, &data_dir.join("staging"), &s, &links::Blocklist::configured(&config.links)?)?;
+            // A system catalog is read-only; the result is only shown
+            if catalog_dir.is_none() {
+                db.set_security_report(&s.registry, &s.slug, &report)?;
+            }
+
+            if json {
+                let out = output::ScanOutput {
+                    schema_version: output::SCHEMA_VERSION,
+                    registry: s.registry,
+                    slug: s.slug,
+                    report,
+                };
+                println!("{}", serde_json::to_string_pretty(&out)?);
+            } else {
+                println!(
+                    "{} ({}): risk {} ({}), SKILL.md and {} bundled file{} scanned",
+                    s.slug,
+                    s.registry,
+                    report.score,
+                    report.level(),
+                    report.files,
+                    if report.files == 1 { "" } else { "s" }
+                );
+                print_security_findings(&report);
+            }
+        }
+        Commands::AuditCatalog { sample, json, verify, schema: _ } => {
+            let key = catalog::load_or_create_key(&data_dir.join("audit.key"))?;
+            if let Some(path) = verify {
+                let signed: catalog::SignedReport = serde_json::from_str(&std::fs::read_to_string(&path)?)
+                    .map_err(|e| anyhow::anyhow!("Invalid report {:?}: {}", path, e))?;
+                if !catalog::verify(&signed)? {
+                    anyhow::bail!("Signature does not match {:?}; the report was modified after signing", path);
+                }
+                println!("Signature OK: {:?}", path);
+                if signed.public_key == catalog::ed25519_public_key(&key)? {
+                    println!("Signed by this machine's audit key");
+                } else {
+                    println!("Signed by public key {}, not this machine's audit key", signed.public_key);
+                }
+                return Ok(());
+            }
+
+            let report = catalog::audit_catalog(&db, &search_index, sample).await?;
+            let problems = report.problems();
+            let signed = catalog::sign(report, &key)?;
+            let path = catalog::write_report(&data_dir.join("reports"), &signed)?;
+            let report = &signed.report;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&signed)?);
+            } else {
+                let c = &report.content;
+                println!(
+                    "Content: {} skills, {} new, {} changed, {} rescanned, {} removed",
+                    c.skills,
+                    c.new,
+                    c.changed.len(),
+                    c.rescanned,
+                    c.removed
+                );
+                for id in &c.changed {
+                    println!("  changed: {}", id);
+                }
+                let ix = &report.index;
+                if ix.consistent() {
+                    println!("Index: consistent ({} documents)", ix.indexed_docs);
+                } else {
+                    println!(
+                        "Index: {} missing, {} orphaned, {} duplicated - run `sync` to rebuild",
+                        ix.missing_from_index.len(),
+                        ix.orphaned_in_index.len(),
+                        ix.duplicated_in_index.len()
+                    );
+                }
+                let reachable = report.urls.iter().filter(|u| u.reachable()).count();
+                println!("URLs: {}/{} reachable", reachable, report.urls.len());
+                for u in report.urls.iter().filter(|u| !u.reachable()) {
+                    let why = u.status.map(|s| s.to_string()).or_else(|| u.error.clone()).unwrap_or_default();
+                    println!("  unreachable: {} {} ({})", u.skill, u.url, why);
+                }
+                if !report.unreachable.is_empty() {
+                    println!("Gone upstream (hidden from search): {}", report.unreachable.len());
+                    for id in &report.unreachable {
+                        println!("  {}", id);
+                    }
+                }
+                println!("Report: {}", path.display());
+            }
+            if problems > 0 {
+                std::process::exit(1);
+            }
+        }
+        Commands::Install { from_file: Some(file), dest, dry_run, force, allow_untrusted, .. } => {
+            let skill_pack = pack::read(&file)?;
+            let signer = skill_pack.verify_signature(&config.packs.trusted_keys)?;
+            if config.packs.require_signature && !matches!(signer, pack::Signer::Trusted(_)) {
+                eprintln!("{} isn't signed by a trusted key, and [packs] require_signature is set", file.display());
+                std::process::exit(1);
+            }
+            let mut packed = skill_pack.skill();
+            packed.trusted = matches!(signer, pack::Signer::Trusted(_));
+            let catalog_entry = skill_pack.catalog_copy(&db)?;
+            let in_catalog = catalog_entry.is_some();
+            let s = catalog_entry.unwrap_or(packed);
+            let quality_score = if in_catalog { quality_scores.score_for(&s) } else { 0 };
+            let waived = decisions.check_install(&s, quality_score, allow_untrusted)?;
+            let skills_dir = match dest {
+                Some(d) => d,
+                None => install::default_skills_dir()?,
+            };
+            let prepared = install::prepare_pack_install(&skill_pack, &data_dir.join("staging"), &skills_dir.join(&s.slug), signer.name().map(str::to_string))?;
+            let plan = &prepared.plan;
+
+            if dry_run {
+                println!("Dry run: would install {} ({}) from {} to {}", s.name, s.registry, file.display(), plan.dest_dir.display());
+            } else {
+                println!("Installing {} ({}) from {} to {}", s.name, s.registry, file.display(), plan.dest_dir.display());
+            }
+            println!("Content hash: {} (verified)", skill_pack.content_hash);
+            match &signer {
+                pack::Signer::Unsigned => eprintln!("WARNING: the skill pack is unsigned"),
+                pack::Signer::Trusted(name) => println!("Signed by: {} (trusted key)", name),
+                pack::Signer::Unknown(key) => eprintln!("WARNING: signed by {}, which isn't one of your [packs] trusted_keys", key),
+            }
+            let provenance = &skill_pack.provenance;
+            println!(
+                "Packed by {} at unix time {}{}",
+                provenance.packed_by,
+                provenance.packed_at,
+                provenance.rev.as_deref().map(|r| format!(" from revision {}", r)).unwrap_or_default()
+            );
+            if let Some(v) = &prepared.installed_version {
+                println!("Version: {}", v);
+            }
+            if in_catalog {
+                println!("Matches the catalog's copy: yes");
+                print_unicode_warnings(&db, &s)?;
+                print_audit_summary(&db, &s)?;
+            } else {
+                println!("Matches the catalog's copy: no - review it before use");
+                for issue in unicode::scan_text("slug", &s.slug).into_iter().chain(unicode::scan_text("name", &s.name)) {
+                    eprintln!("WARNING [{}]: {}", issue.flag.as_str(), issue.detail);
+                }
+                let findings = audit::audit_shell_blocks(&s.skill_md);
+                let high = findings.iter().filter(|f| f.severity == audit::Severity::High).count();
+                println!("Shell audit: {} finding{} ({} high)", findings.len(), if findings.len() == 1 { "" } else { "s" }, high);
+            }
+            let report = security::scan_skill(&s.skill_md, Some(&plan.source_dir), &links::Blocklist::configured(&config.links)?)?;
+            print_security_summary(&s, &report);
+            let injection = injection::assess(&s.skill_md);
+            print_injection_risk(injection.score, &injection.rules());
+            // The preview is for reviewing, so spell the findings out
+            if dry_run {
+                print_security_findings(&report);
+            }
+            if in_catalog && catalog_dir.is_none() {
+                db.set_security_report(&s.registry, &s.slug, &report)?;
+            }
+            println!("Trusted: {}", if decisions.is_trusted(&s) { "yes" } else { "no" });
+            let scripts = plan.scripts().count();
+            println!("Scripts: {}", if scripts == 0 { "none".to_string() } else { format!("{} detected - review before running", scripts) });
+            println!("Files ({}, {}):", plan.files.len(), install::format_size(plan.total_size()));
+            for f in &plan.files {
+                println!(
+                    "  {}  {}{}",
+                    plan.dest_dir.join(&f.rel_path).display(),
+                    install::format_size(f.size),
+                    if f.is_script { "  [script]" } else { "" }
+                );
+            }
+
+            if !dry_run {
+                if waived {
+                    install::log_override(&db, &s)?;
+                }
+                let (_, runs) = prepared.execute(&db, &s, &config.hooks.post_install, force)?;
+                println!("Installed {}", s.slug);
+                print_hook_runs(&runs);
+            }
+        }
+        Commands::Install { slug: None, .. } => unreachable!("clap requires a slug without --from-file"),
+        Commands::Install { slug: Some(slug), dest, dry_run, force, version, rev, unpin, with_deps, allow_untrusted, from_file: None } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let waived = decisions.check_install(&s, quality_scores.score_for(&s), allow_untrusted)?;
+            print_unicode_warnings(&db, &s)?;
+            let skills_dir = match dest {
+                Some(d) => d,
+                None => install::default_skills_dir()?,
+            };
+            let dest_dir = skills_dir.join(&s.slug);
+            let previous = db.get_install(&s.registry, &s.slug, &dest_dir.to_string_lossy())?;
+
+            // --version/--rev pin explicitly; otherwise an existing pin is kept unless --unpin
+            let pin_rev = match install::pin_rev(&db, &s, version.as_deref(), rev, unpin, previous) {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            let prepared = match install::prepare_install(&repos_dir, &data_dir.join("staging"), &s, &dest_dir, pin_rev.as_deref()) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("{}", e);
                     std::process::exit(1);
                 }
+            };
+            let plan = &prepared.plan;
+
+            let (required, missing) = deps::required_skills(&db, &s)?;
+            for reference in &missing {
+                eprintln!("Warning: {} requires {}, which is not in the catalog", s.slug, reference);
+            }
+            if with_deps {
+                // Dependencies install at their latest synced copy; pins apply to the requested skill only
+                for dep in &required {
+                    if decisions.check_install(dep, quality_scores.score_for(dep), allow_untrusted)? && !dry_run {
+                        install::log_override(&db, dep)?;
+                    }
+                }
+                for dep in &required {
+                    let dep_dir = skills_dir.join(&dep.slug);
+                    if dep_dir.exists() && !force {
+                        println!("Dependency {} ({}) already installed", dep.slug, dep.registry);
+                        continue;
+                    }
+                    let prepared = install::prepare_install(&repos_dir, &data_dir.join("staging"), dep, &dep_dir, None)?;
+                    if dry_run {
+                        println!("Dry run: would install dependency {} ({}) to {}", dep.slug, dep.registry, dep_dir.display());
+                    } else {
+                        let (_, runs) = prepared.execute(&db, dep, &config.hooks.post_install, force)?;
+                        println!("Installed dependency {} ({})", dep.slug, dep.registry);
+                        print_hook_runs(&runs);
+                    }
+                }
+            } else if !required.is_empty() {
+                let names: Vec<_> = required.iter().map(|d| d.slug.as_str()).collect();
+                println!("Requires: {} (use --with-deps to install)", names.join(", "));
+            }
+
+            let quality_score = quality_scores.score_for(&s);
+            let scripts: Vec<_> = plan.scripts().collect();
+
+            if dry_run {
+                println!("Dry run: would install {} ({}) to {}", s.name, s.registry, plan.dest_dir.display());
+            } else {
+                println!("Installing {} ({}) to {}", s.name, s.registry, plan.dest_dir.display());
+            }
+            if let Some(v) = &prepared.installed_version {
+                println!("Version: {}", v);
+            }
+            if let Some(r) = &prepared.installed_rev {
+                println!("Revision: {}{}", r, if prepared.pinned { " (pinned)" } else { "" });
+            }
+            println!("Trusted: {}", if decisions.is_trusted(&s) { "yes" } else { "no" });
+            println!("Quality Score: {}", quality_score);
+            println!("Scripts: {}", if scripts.is_empty() { "none".to_string() } else { format!("{} detected - review before running", scripts.len()) });
+            println!("Files ({}, {}):", plan.files.len(), install::format_size(plan.total_size()));
+            for f in &plan.files {
+                println!(
+                    "  {}  {}{}",
+                    plan.dest_dir.join(&f.rel_path).display(),
+                    install::format_size(f.size),
+                    if f.is_script { "  [script]" } else { "" }
+                );
+            }
+            let unset = print_env_vars(&db, &s)?;
+            print_audit_summary(&db, &s)?;
+            let report = security::scan_skill(&s.skill_md, Some(&plan.source_dir), &links::Blocklist::configured(&config.links)?)?;
+            print_security_summary(&s, &report);
+            let injection = injection::assess(&s.skill_md);
+            print_injection_risk(injection.score, &injection.rules());
+            if dry_run {
+                print_security_findings(&report);
+            }
+            if catalog_dir.is_none() {
+                db.set_security_report(&s.registry, &s.slug, &report)?;
+            }
+
+            if !dry_run {
+                if waived {
+                    install::log_override(&db, &s)?;
+                }
+                let (_, runs) = prepared.execute(&db, &s, &config.hooks.post_install, force)?;
+                println!("Installed {}", s.slug);
+                print_hook_runs(&runs);
+            }
+            if !unset.is_empty() {
+                eprintln!("Warning: {} expects {} to be set", s.slug, unset.join(", "));
             }
         }
-        Commands::Url { slug } => {
-            let skill = db.get_skill_by_slug(&slug)?;
-            match skill {
-                Some(s) => println!("{}", s.github_url),
-                None => {
-                    eprintln!("Skill not found: {}", slug);
+        Commands::Try { slug, platform, keep, allow_untrusted } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let platform_name = platform.unwrap_or_else(|| config.sandbox.platform.clone());
+            let Some(platform) = config.sandbox.platform(&platform_name) else {
+                eprintln!("Unknown platform {} (known: {})", platform_name, config.sandbox.platform_names().join(", "));
+                std::process::exit(1);
+            };
+            if decisions.check_install(&s, quality_scores.score_for(&s), allow_untrusted)? {
+                install::log_override(&db, &s)?;
+            }
+            print_unicode_warnings(&db, &s)?;
+            let mut sandbox = match sandbox::Sandbox::create(&repos_dir, &std::env::temp_dir(), &s, &platform) {
+                Ok(sandbox) => sandbox,
+                Err(e) => {
+                    eprintln!("{}", e);
                     std::process::exit(1);
                 }
+            };
+            let report = security::scan_skill(&s.skill_md, Some(&sandbox.skill_dir), &links::Blocklist::configured(&config.links)?)?;
+            print_security_summary(&s, &report);
+
+            println!("Sandbox:   {}", sandbox.workdir.display());
+            println!("Skill:     {}", sandbox.skill_dir.display());
+            println!("SKILL.md:  {}", sandbox.skill_dir.join("SKILL.md").display());
+            println!("\nTry it with {}:\n  {}\n", platform_name, sandbox.invocation(&s, &platform));
+            if keep {
+                sandbox.keep();
+                println!("Kept; remove it with `rm -r {}` when you're done", sandbox.workdir.display());
+                return Ok(());
+            }
+
+            println!("Press Enter (or Ctrl-C) to remove the sandbox");
+            let enter = tokio::task::spawn_blocking(|| std::io::stdin().read_line(&mut String::new()));
+            let interrupted = tokio::select! {
+                _ = enter => false,
+                _ = tokio::signal::ctrl_c() => true,
+            };
+            drop(sandbox);
+            println!("Removed");
+            if interrupted {
+                // The runtime would otherwise wait on the blocked stdin read
+                std::process::exit(130);
+            }
+        }
+        Commands::Pack { slug, output, sign_key } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let prepared = install::prepare_install(&repos_dir, &data_dir.join("staging"), &s, Path::new(&s.slug), None)?;
+            let mut skill_pack = pack::pack(&s, &prepared.plan, prepared.installed_version.clone(), prepared.installed_rev.clone(), unix_now())?;
+            if let Some(key_path) = &sign_key {
+                skill_pack.sign(&catalog::load_or_create_key(key_path)?)?;
+            }
+            let path = output.unwrap_or_else(|| PathBuf::from(format!("{}.{}", s.slug, pack::EXTENSION)));
+            std::fs::write(&path, serde_json::to_string_pretty(&skill_pack)?)?;
+            println!(
+                "Packed {}:{} ({} files, {}) to {}",
+                s.registry,
+                s.slug,
+                skill_pack.files.len(),
+                install::format_size(prepared.plan.total_size()),
+                path.display()
+            );
+            println!("Content hash: {}", skill_pack.content_hash);
+            if let Some(sig) = &skill_pack.signature {
+                println!("Signed with public key {}", sig.public_key);
+            }
+        }
+        Commands::Mirror { collection, dest, json } => {
+            let Some(entries) = config.collections.get(&collection) else {
+                let known: Vec<&str> = config.collections.keys().map(String::as_str).collect();
+                anyhow::bail!("No collection '{}' in config.toml (have: {})", collection, if known.is_empty() { "none".to_string() } else { known.join(", ") });
+            };
+            let skills = mirror::resolve_collection(&db, &entries.skills)?;
+            // A mirror is something to install from, so hold it to the install rules
+            for (s, _) in &skills {
+                decisions.check_install(s, quality_scores.score_for(s), false)?;
+            }
+            let report = mirror::mirror(&repos_dir, &data_dir.join("staging"), &dest, &collection, &skills, unix_now())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report.index)?);
+            } else {
+                for s in &report.index.skills {
+                    println!("{}:{} -> {}{}", s.registry, s.slug, s.path, s.rev.as_deref().map(|r| format!(" at {}", r)).unwrap_or_default());
+                }
+                for path in &report.removed {
+                    println!("Removed {}", path);
+                }
+                println!("Mirrored {} skill{} to {}; review and commit with git", report.index.skills.len(), if report.index.skills.len() == 1 { "" } else { "s" }, dest.display());
             }
         }
-        Commands::Top { limit, trusted, min_score } => {
-            let all_skills = db.get_all_skills()?;
-            let mut skills: Vec<_> = all_skills
+        Commands::Diff { slug, dest, stat } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let install = db
+                .get_installs()?
                 .into_iter()
-                .filter(|s| !trusted || s.trusted)
-                .filter_map(|s| {
-                    let quality_score = quality_scores
-                        .get_score(&s.registry, &s.slug)
-                        .or_else(|| quality_scores.get_score(&s.registry, &s.name))
-                        .unwrap_or(0);
-                    
-                    if quality_score >= min_score {
-                        Some((s, quality_score))
+                .filter(|i| i.registry == s.registry && i.slug == s.slug)
+                .find(|i| dest.as_ref().is_none_or(|d| Path::new(&i.local_path) == d.join(&s.slug)));
+            let Some(install) = install else {
+                eprintln!("{}:{} isn't installed{}", s.registry, s.slug, dest.map(|d| format!(" in {}", d.display())).unwrap_or_default());
+                std::process::exit(1);
+            };
+            let installed_dir = PathBuf::from(&install.local_path);
+            let latest = install::prepare_install(&repos_dir, &data_dir.join("staging"), &s, &installed_dir, None)?;
+            let diffs = diff::diff_dirs(&installed_dir, &latest.plan.source_dir)?;
+            if diffs.is_empty() {
+                println!("{} matches the latest catalog version", installed_dir.display());
+                return Ok(());
+            }
+
+            let color = std::io::stdout().is_terminal();
+            let describe = |version: &Option<String>, rev: &Option<String>| {
+                let rev = rev.as_deref().map(|r| &r[..12.min(r.len())]);
+                match (version, rev) {
+                    (Some(v), Some(r)) => format!("{} ({})", v, r),
+                    (Some(v), None) => v.clone(),
+                    (None, Some(r)) => r.to_string(),
+                    (None, None) => "unknown".to_string(),
+                }
+            };
+            println!(
+                "{}:{} installed {} -> latest {}{}",
+                s.registry,
+                s.slug,
+                describe(&install.installed_version, &install.installed_rev),
+                describe(&latest.installed_version, &latest.installed_rev),
+                if install.pinned { " (pinned; install --unpin to update)" } else { "" }
+            );
+            if stat {
+                print!("{}", diff::render_stat(&diffs, color));
+            } else {
+                print!("{}", diff::render_unified(&diffs, "installed", "latest", color));
+            }
+        }
+        Commands::Update { slug, apply, force } => {
+            let only = match &slug {
+                Some(slug) => Some(resolve_slug_or_exit(&db, slug, &config.resolve.registry_priority)?),
+                None => None,
+            };
+            let installs: Vec<db::InstalledSkill> = db
+                .get_installs()?
+                .into_iter()
+                .filter(|i| only.as_ref().is_none_or(|s| i.registry == s.registry && i.slug == s.slug))
+                .collect();
+            if installs.is_empty() {
+                println!("Nothing installed{}", only.map(|s| format!(" for {}:{}", s.registry, s.slug)).unwrap_or_default());
+                return Ok(());
+            }
+
+            let (mut stale, mut updated) = (0, 0);
+            for install in &installs {
+                let id = format!("{}:{}", install.registry, install.slug);
+                let Some(s) = db.get_skill(&install.registry, &install.slug)? else {
+                    println!("{}  {}  no longer in the catalog", id, install.local_path);
+                    continue;
+                };
+                let check = install::check_update(&repos_dir, install, &s)?;
+                let versions = match (&install.installed_version, &check.latest_version) {
+                    (Some(a), Some(b)) if a != b => format!("  {} -> {}", a, b),
+                    _ => String::new(),
+                };
+                println!("{}  {}{}  {}", id, install.local_path, versions, check.state.as_str());
+                let replace = match check.state {
+                    install::UpdateState::Stale => true,
+                    install::UpdateState::Modified => force,
+                    _ => false,
+                };
+                if matches!(check.state, install::UpdateState::Stale | install::UpdateState::Modified) {
+                    stale += 1;
+                }
+                if !apply || !replace {
+                    continue;
+                }
+                if let Err(e) = decisions.check_install(&s, quality_scores.score_for(&s), false) {
+                    eprintln!("  skipped: {}", e);
+                    continue;
+                }
+                let prepared = install::prepare_install(&repos_dir, &data_dir.join("staging"), &s, Path::new(&install.local_path), None)?;
+                let (_, runs) = prepared.execute(&db, &s, &config.hooks.post_install, true)?;
+                println!("  updated");
+                print_hook_runs(&runs);
+                updated += 1;
+            }
+            if apply {
+                println!("Updated {} of {} stale install{}", updated, stale, if stale == 1 { "" } else { "s" });
+            } else if stale > 0 {
+                println!("{} stale install{}; run `update --apply` to reinstall from the synced copies", stale, if stale == 1 { "" } else { "s" });
+            }
+        }
+        Commands::Uninstall { slug, dest } => {
+            let s = resolve_slug_or_exit(&db, &slug, &config.resolve.registry_priority)?;
+            let skills_dir = match dest {
+                Some(d) => d,
+                None => install::default_skills_dir()?,
+            };
+            let dest_dir = skills_dir.join(&s.slug);
+            if !install::uninstall(&db, &s, &dest_dir)? {
+                eprintln!("{}:{} isn't installed in {}", s.registry, s.slug, skills_dir.display());
+                std::process::exit(1);
+            }
+            println!("Uninstalled {} from {}", s.slug, dest_dir.display());
+        }
+        Commands::AuditLog { command: AuditLogCommand::Export { format, since_days, output } } => {
+            let entries = db.get_audit_log(since_days.map(|d| unix_now() - d * 86_400))?;
+            // A partial export can't be checked from the start of the chain
+            if since_days.is_none() {
+                if let Some(id) = audit_log::find_break(&entries) {
+                    eprintln!("Warning: the audit log's hash chain breaks at entry {}", id);
+                }
+            }
+            let count = entries.len();
+            let text = match format {
+                LogFormat::Jsonl => entries.iter().map(|e| serde_json::to_string(e).map(|line| line + "\n")).collect::<Result<String, _>>()?,
+                LogFormat::Json => serde_json::to_string_pretty(&audit_log::LogExport::new(entries))? + "\n",
+                LogFormat::Csv => audit_log::to_csv(&entries),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, text)?;
+                    eprintln!("Exported {} entries to {}", count, path.display());
+                }
+                None => print!("{}", text),
+            }
+        }
+        Commands::AuditLog { command: AuditLogCommand::Verify } => {
+            let entries = db.get_audit_log(None)?;
+            match audit_log::find_break(&entries) {
+                Some(id) => {
+                    println!("✗ hash chain breaks at entry {} of {}", id, entries.len());
+                    std::process::exit(1);
+                }
+                None => println!("✓ {} entries, hash chain intact", entries.len()),
+            }
+        }
+        Commands::SyncProject { project, dry_run, locked } => {
+            let project = project::Project::discover(&project.unwrap_or(std::env::current_dir()?))?;
+            let staging_root = data_dir.join("staging");
+            let previous_lock = project::Lockfile::load(&project)?;
+            let check = |s: &db::Skill| decisions.check_install(s, quality_scores.score_for(s), false).map(|_| ());
+            let installer = project::Installer { db: &db, repos_dir: &repos_dir, staging_root: &staging_root, hooks: &config.hooks.post_install, check: &check, dry_run };
+            let mut report = |event: project::SyncEvent| match event {
+                project::SyncEvent::Installed { skill, version, dest_dir, .. } if dry_run => {
+                    println!("Would install {} {} ({}) to {}", skill.slug, version, skill.registry, dest_dir.display());
+                }
+                project::SyncEvent::Installed { skill, version, dest_dir, runs } => {
+                    if locked {
+                        println!("Installed {} {} ({})", skill.slug, version, skill.registry);
                     } else {
-                        None
+                        println!("Installed {} {} ({}) to {}", skill.slug, version, skill.registry, dest_dir.display());
                     }
-                })
-                .collect();
-            
-            skills.sort_by(|a, b| b.0.stars.cmp(&a.0.stars));
+                    print_hook_runs(&runs);
+                }
+                project::SyncEvent::Failed { label, error } => eprintln!("{}: {}", label, error),
+            };
+
+            if locked {
+                let Some(lock) = previous_lock else {
+                    eprintln!("No {} found; run sync-project without --locked first", project::LOCK_FILE);
+                    std::process::exit(1);
+                };
+                let drift = lock.drift(&project);
+                if !drift.is_empty() {
+                    for d in drift {
+                        eprintln!("{}", d);
+                    }
+                    eprintln!("{} is out of date; run sync-project without --locked", project::LOCK_FILE);
+                    std::process::exit(1);
+                }
+                if !project::install_locked(&installer, &project, &lock, &mut report)? {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+
+            let statuses = project::check_project(&db, &project)?;
+            let Some(lock) = project::sync_project(&installer, &statuses, previous_lock.as_ref(), &mut report)? else {
+                std::process::exit(1);
+            };
+            if !dry_run && previous_lock.as_ref() != Some(&lock) {
+                lock.save(&project)?;
+                println!("Wrote {}", project.root.join(project::LOCK_FILE).display());
+            }
+        }
+        Commands::CheckProject { project } => {
+            let project = project::Project::discover(&project.unwrap_or(std::env::current_dir()?))?;
+            let statuses = project::check_project(&db, &project)?;
+            let untracked = project::untracked_skills(&project, &statuses)?;
+
+            for status in &statuses {
+                println!("{}: {}", status.label(), status.describe());
+            }
+            for name in &untracked {
+                println!("{}: untracked (installed but not in {})", name, project::MANIFEST_FILE);
+            }
+
+            if !untracked.is_empty() || statuses.iter().any(|s| s.state != project::EntryState::Ok) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Top { limit, trusted, min_score, max_injection_risk, sort, tags, include_unreachable, profile_rank } => {
+            let profile = rank_profile(&config, profile_rank.as_deref())?;
+            let (trusted, include_unreachable) = (trusted || profile.trusted, include_unreachable || profile.include_unreachable);
+            let max_injection_risk = max_injection_risk.or(profile.max_injection_risk);
+            let min_score = rules.min_score(min_score.or(profile.min_score), 80)?;
+            let now = unix_now();
+            let tags: Vec<String> = tags.iter().filter_map(|t| github::normalize_tag(t)).collect();
+            let skills = search::TopList {
+                db: &db,
+                quality: &quality_scores,
+                decisions: &decisions,
+                rules: &rules,
+                sort,
+                trusted,
+                min_score,
+                max_injection_risk,
+                tags: &tags,
+                include_unreachable,
+                profile: &profile,
+                now,
+            }
+            .skills()?;
 
             if skills.is_empty() {
                 println!("No skills found with score >= {}. Try --min-score 0 to see all.", min_score);
             } else {
                 for (i, (s, quality_score)) in skills.iter().take(limit).enumerate() {
                     let trust_icon = if s.trusted { "✓" } else { "⚠" };
+                    let usage = format_usage(s.stars, s.downloads, s.installs);
                     println!(
-                        "{}. [{}] {} ★{} ({}) [Q:{}] - {}",
+                        "{}. [{}] {}{} ({}) [Q:{}] - {}",
                         i + 1,
                         trust_icon,
                         s.name,
-                        s.stars,
+                        if usage.is_empty() { " ★0".to_string() } else { usage },
                         s.registry,
                         quality_score,
                         s.description
@@ -288,6 +2615,161 @@ async fn main() -> Result<()> {
                 }
             }
         }
+        Commands::Tags { registry, limit } => {
+            let counts = db.tag_counts(registry.as_deref())?;
+            if counts.is_empty() {
+                println!("No skills declare tags{}.", registry.map(|r| format!(" in {}", r)).unwrap_or_default());
+            }
+            for (tag, count) in counts.iter().take(limit) {
+                println!("{:>6}  {}", count, tag);
+            }
+            if counts.len() > limit {
+                println!("... and {} more (--limit)", counts.len() - limit);
+            }
+        }
+        Commands::Digest { since, format, min_score } => {
+            let min_score = rules.min_score(min_score, 80)?;
+            let now = unix_now();
+            let allowed = |registry: &str, slug: &str| !decisions.is_denied(registry, slug) && rules.allows_registry(registry);
+            let digest = digest::build(&db, &quality_scores, now - digest::parse_period(&since)?, now, min_score, &allowed)?;
+            match format {
+                DigestFormat::Md => print!("{}", digest::render_markdown(&digest)),
+                DigestFormat::Html => print!("{}", digest::render_html(&digest)),
+            }
+        }
+        Commands::Import { path, registry } => {
+            let exclude = config.registries.get(&registry).map(|c| c.exclude.clone()).unwrap_or_default();
+            // Under a system catalog, into the user layer, as for sync
+            let count = match &user_index {
+                Some(index) => {
+                    if db.system_registries()?.contains(&registry) {
+                        anyhow::bail!("The system catalog already provides {}; import into another registry", registry);
+                    }
+                    let mut user_db = db::Database::open(&data_dir.join("skills.db"))?;
+                    sync::import(&mut user_db, index, &path, &registry, &exclude)?
+                }
+                None => sync::import(&mut db, &search_index, &path, &registry, &exclude)?,
+            };
+            println!("Imported {} skills into {} from {}", count, registry, path.display());
+        }
+        Commands::Prune { dry_run } => {
+            let removed = db.get_removed()?;
+            let now = unix_now();
+            for ((registry, slug), removed_at) in &removed {
+                println!("{}:{} (gone {})", registry, slug, text::format_age((now - removed_at).max(0) / 86_400));
+            }
+            if removed.is_empty() {
+                println!("No skills are gone upstream.");
+            } else if dry_run {
+                println!("{} skills would be deleted (without --dry-run)", removed.len());
+            } else {
+                let keys: Vec<_> = removed.into_iter().map(|(key, _)| key).collect();
+                sync::prune(&mut db, &search_index, &keys)?;
+                println!("Deleted {} skills gone upstream", keys.len());
+            }
+        }
+        Commands::Export { format, registry, trusted, min_score, output } => {
+            rules.check_registry_flag(registry.as_deref())?;
+            let min_score = rules.min_score(min_score, 0)?;
+            let rows = export::rows(&db, &quality_scores, &decisions, &rules, registry.as_deref(), trusted, min_score)?;
+            let text = match format {
+                ExportFormat::Jsonl => export::to_jsonl(&rows)?,
+                ExportFormat::Csv => export::to_csv(&rows),
+                ExportFormat::Md => export::to_markdown(&rows),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, text)?;
+                    eprintln!("Exported {} skills to {}", rows.len(), path.display());
+                }
+                None => print!("{}", text),
+            }
+        }
+        Commands::Registries { failures: failure_limit } => {
+            let now = unix_now();
+            for name in sync::synced_registries() {
+                let (channel, trust) = match github::find_registry(name) {
+                    Some(r) => (r.channel.as_str(), if r.trusted { "trusted" } else { "untrusted" }),
+                    None => ("community", "untrusted"),
+                };
+                let synced = match db.get_last_sync(name)? {
+                    Some((at, _)) => format!("synced {}", text::format_age((now - at).max(0) / 86_400)),
+                    None => "never synced".to_string(),
+                };
+                println!("{} ({}, {}): {} skills, {}", name, channel, trust, db.count_skills(name)?, synced);
+
+                let recent = db.get_sync_failures(name, failure_limit)?;
+                let failed_runs = db.consecutive_failed_syncs(name)?;
+                if failed_runs >= failures::CHRONIC_AFTER {
+                    println!("  ✗ last {} syncs failed", failed_runs);
+                    if let Some(latest) = recent.first() {
+                        println!("    hint: {}", latest.kind.hint());
+                    }
+                }
+                if !recent.is_empty() {
+                    println!("  Recent failures:");
+                    for failure in &recent {
+                        print_failure(failure, now);
+                    }
+                }
+            }
+        }
+        Commands::Serve { addr } => {
+            let state = server::ServerState {
+                db: tokio::sync::Mutex::new(db),
+                db_path,
+                repos_dir,
+                index: search_index,
+                config_path: data_dir.join("config.toml"),
+                config: std::sync::RwLock::new(config),
+                quality: quality_scores,
+                rules,
+                decisions: std::sync::RwLock::new(decisions),
+                syncing: tokio::sync::Mutex::new(()),
+            };
+            server::serve(state, &addr).await?;
+            return Ok(());
+        }
+        Commands::ServeMcp => {
+            let server = mcp::McpServer {
+                db: &db,
+                index: &search_index,
+                user_index: user_index.as_ref(),
+                index_config: &config.index,
+                query_config: &config.query,
+                ranking: &config.ranking,
+                registry_priority: &config.resolve.registry_priority,
+                direct: rebuild_after,
+                quality: &quality_scores,
+                rules: &rules,
+                decisions: &decisions,
+            };
+            server.serve_stdio()?;
+        }
+        Commands::Doctor { offline } => {
+            let indexed = search_index.num_docs() + user_index.as_ref().map_or(0, |i| i.num_docs());
+            let checks = doctor::run(&data_dir, &db, indexed, &config.registries, offline).await?;
+            for check in &checks {
+                println!("{} {}", if check.ok { "✓" } else { "✗" }, check.message);
+                for line in &check.details {
+                    println!("    {}", line);
+                }
+                if let Some(hint) = &check.hint {
+                    println!("    hint: {}", hint);
+                }
+            }
+            let problems = checks.iter().filter(|c| !c.ok).count();
+            if problems > 0 {
+                println!("{} problem(s) found", problems);
+                std::process::exit(1);
+            }
+            println!("No problems found");
+        }
+    }
+
+    if rebuild_after && catalog_dir.is_none() {
+        drop(search_index);
+        workspace::rebuild_index(&index_path, &db, &config.index)?;
     }
 
     Ok(())
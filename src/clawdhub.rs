@@ -0,0 +1,326 @@
+use crate::db::{Database, Review, SkillStats};
+use anyhow::Result;
+use serde::Deserialize;
+
+/// Attempts per page when the API rate-limits us
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+const PAGE_SIZE: usize = 100;
+
+/// Review snippets kept per skill
+const REVIEWS_PER_SKILL: usize = 3;
+
+/// Per-skill review requests made in one sync; the rest catch up next time
+const MAX_REVIEW_FETCHES: usize = 50;
+
+const REVIEW_SNIPPET_CHARS: usize = 280;
+
+#[derive(Debug, Deserialize)]
+pub struct ClawdhubSkill {
+    pub slug: String,
+    pub stats: ClawdhubStats,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClawdhubStats {
+    pub stars: i64,
+    /// Only some deployments report usage counts
+    #[serde(default)]
+    pub downloads: Option<i64>,
+    #[serde(default, rename = "installsAllTime")]
+    pub installs: Option<i64>,
+    /// Average user rating (1-5), where reviews are enabled
+    #[serde(default)]
+    pub rating: Option<f64>,
+    #[serde(default, rename = "ratingCount")]
+    pub rating_count: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClawdhubReview {
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub rating: Option<f64>,
+    #[serde(default)]
+    pub body: String,
+    /// Unix milliseconds
+    #[serde(default, rename = "createdAt")]
+    pub created_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClawdhubReviewsResponse {
+    pub items: Vec<ClawdhubReview>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClawdhubResponse {
+    pub items: Vec<ClawdhubSkill>,
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+/// Client for clawdhub and any API that speaks the same protocol
+pub struct ClawdhubClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl ClawdhubClient {
+    pub fn new(base_url: &str) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .user_agent("skill-search/0.1")
+            .build()?;
+        Ok(Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub fn skills_url(&self, cursor: Option<&str>) -> String {
+        match cursor {
+            Some(c) => format!("{}/api/v1/skills?limit={}&cursor={}", self.base_url, PAGE_SIZE, c),
+            None => format!("{}/api/v1/skills?limit={}", self.base_url, PAGE_SIZE),
+        }
+    }
+
+    /// GET one page of skills. Given the page's `etag` from last time, an
+    /// unchanged page comes back as [`SkillsPage::NotModified`].
+    pub async fn list_skills(&self, cursor: Option<&str>, etag: Option<&str>) -> Result<SkillsPage> {
+        let resp = self.get(&self.skills_url(cursor), etag).await?.ok_or_else(|| anyhow::anyhow!("Clawdhub API at {} has no skills endpoint", self.base_url))?;
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(SkillsPage::NotModified);
+        }
+        let etag = resp.headers().get(reqwest::header::ETAG).and_then(|v| v.to_str().ok()).map(str::to_string);
+        Ok(SkillsPage::Changed { data: resp.json().await?, etag })
+    }
+
+    /// Latest reviews for a skill, or `None` if the API does not serve reviews
+    pub async fn list_reviews(&self, slug: &str) -> Result<Option<ClawdhubReviewsResponse>> {
+        self.get_json(&self.reviews_url(slug)?).await
+    }
+
+    /// Reviews endpoint for `slug`, percent-encoded as one path segment
+    pub fn reviews_url(&self, slug: &str) -> Result<String> {
+        let mut url = url::Url::parse(&format!("{}/api/v1/skills", self.base_url))?;
+        url.path_segments_mut().map_err(|_| anyhow::anyhow!("{} can't have a path", self.base_url))?.push(slug).push("reviews");
+        url.query_pairs_mut().append_pair("limit", &REVIEWS_PER_SKILL.to_string());
+        Ok(url.into())
+    }
+
+    /// GET and decode JSON. A 404 is `None`.
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<Option<T>> {
+        match self.get(url, None).await? {
+            Some(resp) => Ok(Some(resp.json().await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// GET, waiting out 429s (honouring Retry-After, else exponential
+    /// backoff), conditionally on `etag` if given. A 404 is `None`; a 304
+    /// is returned as it is.
+    async fn get(&self, url: &str, etag: Option<&str>) -> Result<Option<reqwest::Response>> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self.http.get(url);
+            if let Some(etag) = etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            let resp = request.send().await?;
+            let status = resp.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt < MAX_RATE_LIMIT_RETRIES {
+                let wait = resp
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .unwrap_or(1 << attempt)
+                    .min(60);
+                attempt += 1;
+                tracing::warn!("{} rate limited, retrying in {}s ({}/{})", self.base_url, wait, attempt, MAX_RATE_LIMIT_RETRIES);
+                tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+                continue;
+            }
+            if status == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            if !status.is_success() && status != reqwest::StatusCode::NOT_MODIFIED {
+                anyhow::bail!("Clawdhub API error from {}: {}", self.base_url, status);
+            }
+            return Ok(Some(resp));
+        }
+    }
+
+    /// Page through every skill and store its star, download, and install
+    /// counts under `registry`. Each page is committed with the cursor, so an
+    /// interrupted fetch resumes from the last stored page on the next sync.
+    /// Pages are fetched conditionally on their ETag from the last sync, and
+    /// unchanged ones skipped.
+    pub async fn sync_stats(&self, db: &mut Database, registry: &str) -> Result<()> {
+        let cursor_key = format!("{}-stars", registry);
+        let (mut cursor, mut page) = match db.get_fetch_cursor(&cursor_key)? {
+            Some((c, pages)) => {
+                tracing::info!("Resuming {} stats fetch after page {}", registry, pages);
+                (Some(c), pages)
+            }
+            None => (None, 0),
+        };
+        let (mut fetched, mut unchanged) = (0, 0);
+
+        loop {
+            let key = page_key(registry, cursor.as_deref());
+            let cached = db.get_page_etag(&key)?;
+            let (data, etag) = match self.list_skills(cursor.as_deref(), cached.as_ref().map(|(e, _)| e.as_str())).await? {
+                SkillsPage::Changed { data, etag } => (data, etag),
+                SkillsPage::NotModified => {
+                    // The counts stored from this page last time still hold
+                    let next_cursor = cached.and_then(|(_, c)| c);
+                    unchanged += 1;
+                    page += 1;
+                    db.apply_stats_page(registry, &cursor_key, &[], next_cursor.as_deref(), page)?;
+                    match next_cursor {
+                        Some(c) => cursor = Some(c),
+                        None => break,
+                    }
+                    continue;
+                }
+            };
+            let stats: Vec<SkillStats> = data
+                .items
+                .into_iter()
+                .map(|s| SkillStats {
+                    slug: s.slug,
+                    stars: s.stats.stars,
+                    downloads: s.stats.downloads,
+                    installs: s.stats.installs,
+                    rating: s.stats.rating.zip(s.stats.rating_count),
+                })
+                .collect();
+            fetched += stats.len();
+            page += 1;
+
+            let next_cursor = data.next_cursor.filter(|c| !c.is_empty());
+            db.apply_stats_page(registry, &cursor_key, &stats, next_cursor.as_deref(), page)?;
+            if let Some(etag) = etag {
+                db.set_page_etag(&key, &etag, next_cursor.as_deref(), unix_now())?;
+            }
+
+            if page % 10 == 0 {
+                tracing::debug!("Fetched {} pages from {}", page, self.base_url);
+            }
+
+            match next_cursor {
+                Some(c) => cursor = Some(c),
+                None => break,
+            }
+        }
+
+        tracing::info!("Fetched stats for {} {} skills ({} unchanged pages skipped)", fetched, registry, unchanged);
+        Ok(())
+    }
+
+    /// Refresh review snippets for skills whose rating count changed
+    pub async fn sync_reviews(&self, db: &mut Database, registry: &str) -> Result<()> {
+        let slugs = db.get_skills_needing_reviews(registry, MAX_REVIEW_FETCHES)?;
+        for slug in &slugs {
+            let Some(resp) = self.list_reviews(slug).await? else {
+                tracing::debug!("{} does not serve reviews, skipping", self.base_url);
+                return Ok(());
+            };
+            let reviews: Vec<Review> = resp
+                .items
+                .into_iter()
+                .take(REVIEWS_PER_SKILL)
+                .map(|r| Review {
+                    author: r.author,
+                    rating: r.rating,
+                    body: snippet(&r.body),
+                    created_at: r.created_at / 1000,
+                })
+                .collect();
+            db.set_reviews(registry, slug, &reviews)?;
+        }
+        if !slugs.is_empty() {
+            tracing::info!("Fetched reviews for {} {} skills", slugs.len(), registry);
+        }
+        Ok(())
+    }
+}
+
+/// A page of [`ClawdhubClient::list_skills`]
+pub enum SkillsPage {
+    Changed { data: ClawdhubResponse, etag: Option<String> },
+    NotModified,
+}
+
+/// `sync_state` key of the stats page fetched at `cursor`
+pub fn page_key(registry: &str, cursor: Option<&str>) -> String {
+    format!("{}{}", page_key_prefix(registry), cursor.unwrap_or(""))
+}
+
+/// Start of every [`page_key`] of a registry
+pub fn page_key_prefix(registry: &str) -> String {
+    format!("{}-stars@", registry)
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+
+fn snippet(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if body.chars().count() > REVIEW_SNIPPET_CHARS {
+        format!("{}...", body.chars().take(REVIEW_SNIPPET_CHARS - 3).collect::<String>())
+    } else {
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skills_url() {
+        let client = ClawdhubClient::new("https://mirror.example.com/").unwrap();
+        assert_eq!(client.skills_url(None), "https://mirror.example.com/api/v1/skills?limit=100");
+        assert_eq!(client.skills_url(Some("abc")), "https://mirror.example.com/api/v1/skills?limit=100&cursor=abc");
+    }
+
+    #[test]
+    fn test_reviews_url_encodes_slug() {
+        let client = ClawdhubClient::new("https://mirror.example.com/").unwrap();
+        assert_eq!(client.reviews_url("pdf").unwrap(), format!("https://mirror.example.com/api/v1/skills/pdf/reviews?limit={}", REVIEWS_PER_SKILL));
+        assert!(client.reviews_url("../a b?x").unwrap().starts_with("https://mirror.example.com/api/v1/skills/..%2Fa%20b%3Fx/reviews?"));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let json = r#"{"items": [
+            {"slug": "trello", "stats": {"stars": 12, "downloads": 340, "installsAllTime": 56, "rating": 4.2, "ratingCount": 9}},
+            {"slug": "notes", "stats": {"stars": 1}}
+        ], "nextCursor": "xyz"}"#;
+        let resp: ClawdhubResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.items[0].slug, "trello");
+        assert_eq!(resp.items[0].stats.stars, 12);
+        assert_eq!(resp.items[0].stats.downloads, Some(340));
+        assert_eq!(resp.items[0].stats.installs, Some(56));
+        assert_eq!(resp.items[0].stats.rating.zip(resp.items[0].stats.rating_count), Some((4.2, 9)));
+        assert_eq!(resp.items[1].stats.downloads, None);
+        assert_eq!(resp.items[1].stats.rating, None);
+        assert_eq!(resp.next_cursor.as_deref(), Some("xyz"));
+    }
+
+    #[test]
+    fn test_parse_reviews_and_snippet() {
+        let json = r#"{"items": [{"author": "sam", "rating": 5, "body": "Works\n  great", "createdAt": 1700000000000}, {"body": "ok"}]}"#;
+        let resp: ClawdhubReviewsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.items[0].rating, Some(5.0));
+        assert_eq!(snippet(&resp.items[0].body), "Works great");
+        assert_eq!(resp.items[1].author, None);
+
+        let long = "x".repeat(400);
+        assert_eq!(snippet(&long).chars().count(), REVIEW_SNIPPET_CHARS);
+    }
+}
@@ -0,0 +1,594 @@
+use crate::policy::Decisions;
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// User configuration, read from `config.toml` in the data directory.
+/// Every section is optional; a missing file means defaults.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub hooks: HooksConfig,
+    pub links: LinksConfig,
+    pub packs: PacksConfig,
+    pub index: IndexConfig,
+    pub query: QueryConfig,
+    pub embeddings: EmbeddingsConfig,
+    pub sandbox: SandboxConfig,
+    pub ranking: RankingConfig,
+    /// Named filter and ranking presets for `search` and `top`
+    pub rank_profiles: BTreeMap<String, RankProfile>,
+    pub resolve: ResolveConfig,
+    /// Per-registry settings, keyed by registry name
+    pub registries: HashMap<String, RegistryConfig>,
+    /// Named, curated sets of skills for `mirror`
+    pub collections: BTreeMap<String, Collection>,
+    /// Skills to trust or hide; under a system catalog, only as far as its
+    /// policy allows
+    pub decisions: Decisions,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RegistryConfig {
+    /// Sync this registry (default true)
+    pub enabled: bool,
+    /// Globs of directories to skip when scanning, e.g. `**/examples/**`.
+    /// Patterns with a `/` match the path under the registry's skills
+    /// directory; others match any single directory name.
+    pub exclude: Vec<String>,
+    /// Keep only skills with at least this many stars. Needs the registry's
+    /// usage counts, so it's skipped for registries without them and for
+    /// syncs where fetching them failed.
+    pub min_stars: Option<i64>,
+    /// Keep only skills changed upstream within this many days; skills of
+    /// unknown age are kept
+    pub max_age_days: Option<i64>,
+    /// Keep at most this many skills, most-starred first
+    pub max_skills: Option<usize>,
+    /// Clone only this many commits of history. Skills changed before then
+    /// have no upstream date, and older versions can only be fetched by
+    /// full commit SHA.
+    pub clone_depth: Option<u32>,
+    /// Clone only the default branch
+    pub single_branch: bool,
+    /// Clone without old file versions, fetching them when needed (default
+    /// true). Needs the git binary.
+    pub partial_clone: bool,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            exclude: Vec::new(),
+            min_stars: None,
+            max_age_days: None,
+            max_skills: None,
+            clone_depth: None,
+            single_branch: false,
+            partial_clone: true,
+        }
+    }
+}
+
+impl RegistryConfig {
+    pub fn has_limits(&self) -> bool {
+        self.min_stars.is_some() || self.max_age_days.is_some() || self.max_skills.is_some()
+    }
+}
+
+/// Whether sync should fetch a registry
+pub fn registry_enabled(registries: &HashMap<String, RegistryConfig>, name: &str) -> bool {
+    registries.get(name).is_none_or(|c| c.enabled)
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// Leave fenced code blocks out of the indexed SKILL.md text
+    pub collapse_code_blocks: bool,
+    pub weights: FieldWeights,
+    /// Drop words nearly every skill uses ("skill", "claude", ...) from
+    /// descriptions and bodies so they don't dominate relevance
+    pub stopwords: bool,
+    /// Additional words to drop, on top of the built-in list
+    pub extra_stopwords: Vec<String>,
+    /// Directory of patched SKILL.md files, `<registry>/<slug>/SKILL.md`,
+    /// that are indexed in place of the catalog copies
+    pub overlay_dir: Option<PathBuf>,
+    /// Typos a word may have and still match in fuzzy search, 1 or 2. When
+    /// a search finds nothing it's retried fuzzily; 0 turns that off.
+    pub fuzzy_distance: u8,
+    /// Soft cap on the SKILL.md text indexed per skill; longer bodies are
+    /// cut at a line break under it. 0 indexes all of it.
+    pub max_content_bytes: usize,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            collapse_code_blocks: false,
+            weights: FieldWeights::default(),
+            stopwords: true,
+            extra_stopwords: Vec::new(),
+            overlay_dir: None,
+            fuzzy_distance: 1,
+            max_content_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// How search queries are rewritten before they reach the index; `search
+/// --verbatim` skips it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct QueryConfig {
+    /// Reduce queries written as requests ("find me a skill that can ...")
+    /// to the words that say what's wanted
+    pub strip_filler: bool,
+    /// Words searched for alongside a query word, e.g. `md = ["markdown"]`
+    pub synonyms: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            strip_filler: true,
+            synonyms: BTreeMap::new(),
+        }
+    }
+}
+
+/// Embedding provider for `search --semantic`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingsConfig {
+    /// OpenAI-compatible embeddings endpoint (default: a local Ollama)
+    pub url: String,
+    pub model: String,
+    /// Environment variable holding an API key, sent as a bearer token
+    pub api_key_env: Option<String>,
+    /// Share of the ranking from description similarity, 0-1; the rest is
+    /// the keyword score
+    pub weight: f32,
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:11434/v1/embeddings".to_string(),
+            model: "nomic-embed-text".to_string(),
+            api_key_env: None,
+            weight: 0.5,
+        }
+    }
+}
+
+/// Weights of the signals search results are ranked by; see
+/// [`crate::rank::blend`]. Only `relevance` set gives the index's own order.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RankingConfig {
+    pub relevance: f32,
+    pub stars: f32,
+    pub quality: f32,
+    pub trust: f32,
+    pub recency: f32,
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            relevance: 1.0,
+            stars: 0.2,
+            quality: 0.2,
+            trust: 0.1,
+            recency: 0.1,
+        }
+    }
+}
+
+/// How a bare slug published in several registries is resolved
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ResolveConfig {
+    /// Registries in order of preference. Unlisted registries rank after
+    /// them, and a tie for first is asked about; empty means always ask.
+    pub registry_priority: Vec<String>,
+}
+
+impl Default for ResolveConfig {
+    fn default() -> Self {
+        Self { registry_priority: ["anthropic", "openai", "clawdhub"].map(String::from).to_vec() }
+    }
+}
+
+/// Days over which a profile's recency boost halves
+pub const RECENCY_HALF_LIFE_DAYS: f32 = 30.0;
+
+/// Filters and ranking picked together with `--profile-rank`. Flags given
+/// alongside take precedence.
+///
+/// ```toml
+/// [rank_profiles.fresh]
+/// min_score = 60
+/// max_age_days = 180
+/// recency_boost = 0.5
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RankProfile {
+    pub trusted: bool,
+    pub min_score: Option<i64>,
+    pub min_stars: Option<i64>,
+    pub max_injection_risk: Option<i64>,
+    pub max_age_days: Option<i64>,
+    pub include_unreachable: bool,
+    /// Share of its own score added to a skill updated upstream today,
+    /// halving every [`RECENCY_HALF_LIFE_DAYS`]
+    pub recency_boost: f32,
+}
+
+impl RankProfile {
+    /// What a skill last updated `age_days` ago has its score multiplied by
+    pub fn recency_factor(&self, age_days: Option<i64>) -> f32 {
+        match age_days {
+            Some(age) if self.recency_boost > 0.0 => 1.0 + self.recency_boost * 0.5f32.powf(age as f32 / RECENCY_HALF_LIFE_DAYS),
+            _ => 1.0,
+        }
+    }
+}
+
+/// Agent platforms `try` can print an invocation for, keyed by name. The
+/// built-in `claude` and `codex` entries can be overridden.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Platform used when `try` isn't given `--platform` (default: claude)
+    pub platform: String,
+    pub platforms: BTreeMap<String, SandboxPlatform>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            platform: "claude".to_string(),
+            platforms: BTreeMap::new(),
+        }
+    }
+}
+
+impl SandboxConfig {
+    /// The named platform, from config or the built-in ones
+    pub fn platform(&self, name: &str) -> Option<SandboxPlatform> {
+        self.platforms.get(name).cloned().or_else(|| match name {
+            "claude" => Some(SandboxPlatform {
+                skills_dir: ".claude/skills".to_string(),
+                command: "cd {workdir} && claude \"Use the {slug} skill\"".to_string(),
+            }),
+            "codex" => Some(SandboxPlatform {
+                skills_dir: ".codex/skills".to_string(),
+                command: "cd {workdir} && codex \"Use the {slug} skill\"".to_string(),
+            }),
+            _ => None,
+        })
+    }
+
+    /// Names of every platform, built-in and configured
+    pub fn platform_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = ["claude", "codex"].iter().map(|n| n.to_string()).collect();
+        names.extend(self.platforms.keys().filter(|n| !names.contains(n)).cloned().collect::<Vec<_>>());
+        names
+    }
+}
+
+/// Where an agent picks up skills, relative to the directory it runs in,
+/// and how to start it there. `command` may use `{workdir}`, `{skill_dir}`,
+/// `{slug}` and `{registry}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SandboxPlatform {
+    pub skills_dir: String,
+    pub command: String,
+}
+
+/// Query-time boosts per field, so a title that names the query outranks a
+/// long body that happens to mention it often
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct FieldWeights {
+    pub name: f32,
+    pub description: f32,
+    pub content: f32,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        Self {
+            name: 3.0,
+            description: 2.0,
+            content: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct LinksConfig {
+    /// File of hosts to flag in skill links, one per line
+    pub blocklist: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PacksConfig {
+    /// Publishers whose signed skill packs are trusted: name to ed25519
+    /// public key (hex)
+    pub trusted_keys: BTreeMap<String, String>,
+    /// Refuse packs that aren't signed by a trusted key
+    pub require_signature: bool,
+}
+
+/// A curated set of skills:
+///
+/// ```toml
+/// [collections.approved]
+/// skills = ["anthropic:pdf", "xlsx", "anthropic:docx@3f2c1a9"]
+/// ```
+///
+/// Entries are a slug or `registry:slug`, optionally pinned to a registry
+/// commit with `@rev`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Collection {
+    pub skills: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Run after every successful install, in order
+    pub post_install: Vec<Hook>,
+}
+
+/// An external command. The installed skill path is appended as the last
+/// argument, and SKILL_PATH / SKILL_SLUG / SKILL_REGISTRY are set.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Hook {
+    pub name: Option<String>,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Hook {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.command)
+    }
+}
+
+impl Config {
+    /// The named ranking profile, from config or the built-in `strict`
+    /// (trusted, quality 90 and up) and `explore` (no filters, recent first)
+    pub fn rank_profile(&self, name: &str) -> Option<RankProfile> {
+        self.rank_profiles.get(name).cloned().or_else(|| match name {
+            "strict" => Some(RankProfile {
+                trusted: true,
+                min_score: Some(90),
+                ..RankProfile::default()
+            }),
+            "explore" => Some(RankProfile {
+                min_score: Some(0),
+                include_unreachable: true,
+                recency_boost: 1.0,
+                ..RankProfile::default()
+            }),
+            _ => None,
+        })
+    }
+
+    /// Names of every ranking profile, built-in and configured
+    pub fn rank_profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = ["strict", "explore"].iter().map(|n| n.to_string()).collect();
+        names.extend(self.rank_profiles.keys().filter(|n| !names.contains(n)).cloned().collect::<Vec<_>>());
+        names
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid config {:?}: {}", path, e))
+    }
+
+    /// What differs in `new`, one line per change, for logging a reload:
+    /// trust and deny entries individually, other sections as a whole
+    pub fn changes(&self, new: &Config) -> Vec<String> {
+        let mut changes = Vec::new();
+        for (list, old, new) in [("trust", &self.decisions.trust, &new.decisions.trust), ("deny", &self.decisions.deny, &new.decisions.deny)] {
+            changes.extend(new.iter().filter(|p| !old.contains(p)).map(|p| format!("decisions.{}: added {}", list, p)));
+            changes.extend(old.iter().filter(|p| !new.contains(p)).map(|p| format!("decisions.{}: removed {}", list, p)));
+        }
+        let sections = [
+            ("hooks", self.hooks != new.hooks),
+            ("links", self.links != new.links),
+            ("packs", self.packs != new.packs),
+            ("index", self.index != new.index),
+            ("embeddings", self.embeddings != new.embeddings),
+            ("sandbox", self.sandbox != new.sandbox),
+            ("query", self.query != new.query),
+            ("ranking", self.ranking != new.ranking),
+            ("rank_profiles", self.rank_profiles != new.rank_profiles),
+            ("resolve", self.resolve != new.resolve),
+            ("registries", self.registries != new.registries),
+            ("collections", self.collections != new.collections),
+        ];
+        changes.extend(sections.iter().filter(|(_, changed)| *changed).map(|(name, _)| format!("[{}] changed", name)));
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_missing_config_is_default() {
+        let dir = tempdir().unwrap();
+        let config = Config::load(&dir.path().join("config.toml")).unwrap();
+        assert!(config.hooks.post_install.is_empty());
+        assert!(config.links.blocklist.is_none());
+        assert!(!config.index.collapse_code_blocks);
+        assert!(config.index.stopwords);
+    }
+
+    #[test]
+    fn test_parse_index_options() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[index]\ncollapse_code_blocks = true\nmax_content_bytes = 0\n").unwrap();
+        let index = Config::load(&path).unwrap().index;
+        assert!(index.collapse_code_blocks);
+        assert_eq!(index.max_content_bytes, 0);
+
+        std::fs::write(&path, "[index]\nstopwords = false\nextra_stopwords = [\"workflow\"]\n").unwrap();
+        let index = Config::load(&path).unwrap().index;
+        assert!(!index.stopwords);
+        assert_eq!(index.extra_stopwords, vec!["workflow"]);
+    }
+
+    #[test]
+    fn test_partial_field_weights_keep_defaults() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[index.weights]\nname = 5.0\n").unwrap();
+        let weights = Config::load(&path).unwrap().index.weights;
+        assert_eq!(weights, FieldWeights { name: 5.0, ..FieldWeights::default() });
+    }
+
+    #[test]
+    fn test_sandbox_platforms() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[sandbox.platforms.claude]\nskills_dir = \"skills\"\ncommand = \"claude\"\n\n[sandbox.platforms.goose]\nskills_dir = \"s\"\ncommand = \"goose\"\n").unwrap();
+        let sandbox = Config::load(&path).unwrap().sandbox;
+        assert_eq!(sandbox.platform, "claude");
+        assert_eq!(sandbox.platform("claude").unwrap().skills_dir, "skills");
+        assert_eq!(sandbox.platform("codex").unwrap().skills_dir, ".codex/skills");
+        assert!(sandbox.platform("cursor").is_none());
+        assert_eq!(sandbox.platform_names(), ["claude", "codex", "goose"]);
+    }
+
+    #[test]
+    fn test_rank_profiles() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[rank_profiles.strict]\nmin_score = 95\n\n[rank_profiles.fresh]\nmax_age_days = 90\nrecency_boost = 0.5\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        let strict = config.rank_profile("strict").unwrap();
+        assert_eq!((strict.min_score, strict.trusted), (Some(95), false));
+        assert!(config.rank_profile("explore").unwrap().include_unreachable);
+        assert!(config.rank_profile("lenient").is_none());
+        assert_eq!(config.rank_profile_names(), ["strict", "explore", "fresh"]);
+
+        let fresh = config.rank_profile("fresh").unwrap();
+        assert_eq!(fresh.recency_factor(Some(0)), 1.5);
+        assert_eq!(fresh.recency_factor(Some(30)), 1.25);
+        assert_eq!(fresh.recency_factor(None), 1.0);
+        assert_eq!(strict.recency_factor(Some(0)), 1.0);
+    }
+
+    #[test]
+    fn test_parse_links_blocklist() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[links]\nblocklist = \"/etc/skill-blocklist.txt\"\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.links.blocklist, Some(PathBuf::from("/etc/skill-blocklist.txt")));
+    }
+
+    #[test]
+    fn test_parse_post_install_hooks() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[hooks.post_install]]
+name = "format"
+command = "prettier"
+args = ["--write"]
+
+[[hooks.post_install]]
+command = "notify-skills"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        let hooks = &config.hooks.post_install;
+        assert_eq!(hooks.len(), 2);
+        assert_eq!(hooks[0].display_name(), "format");
+        assert_eq!(hooks[0].args, vec!["--write"]);
+        assert_eq!(hooks[1].display_name(), "notify-skills");
+        assert!(hooks[1].args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_registry_excludes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[registries.jo]\nexclude = [\"**/examples/**\", \"deprecated-*\"]\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.registries["jo"].exclude, vec!["**/examples/**", "deprecated-*"]);
+        assert!(!config.registries.contains_key("clawdhub"));
+        assert!(registry_enabled(&config.registries, "jo"));
+
+        assert!(!config.registries["jo"].has_limits());
+
+        std::fs::write(&path, "[registries.clawdhub]\nmin_stars = 1\nmax_age_days = 540\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.registries["clawdhub"].min_stars, Some(1));
+        assert!(config.registries["clawdhub"].has_limits());
+
+        std::fs::write(&path, "[registries.skillssh]\nenabled = false\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert!(!registry_enabled(&config.registries, "skillssh"));
+        assert!(registry_enabled(&config.registries, "clawdhub"));
+    }
+
+    #[test]
+    fn test_parse_collections() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[collections.approved]\nskills = [\"anthropic:pdf\", \"xlsx@abc123\"]\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.collections["approved"].skills, vec!["anthropic:pdf", "xlsx@abc123"]);
+    }
+
+    #[test]
+    fn test_changes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[decisions]\ntrust = [\"anthropic:*\"]\ndeny = [\"clawdhub:old\"]\n").unwrap();
+        let old = Config::load(&path).unwrap();
+        assert!(old.changes(&Config::load(&path).unwrap()).is_empty());
+
+        std::fs::write(&path, "[decisions]\ntrust = [\"anthropic:*\"]\ndeny = [\"clawdhub:evil\"]\n[registries.skillssh]\nenabled = false\n").unwrap();
+        let new = Config::load(&path).unwrap();
+        assert_eq!(
+            old.changes(&new),
+            vec!["decisions.deny: added clawdhub:evil", "decisions.deny: removed clawdhub:old", "[registries] changed"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_errors() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "hooks = 3").unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+}
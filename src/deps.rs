@@ -0,0 +1,255 @@
+use crate::db::{Database, Skill};
+use crate::resolve::{resolve_skill, Resolution};
+use crate::text::split_frontmatter;
+use anyhow::Result;
+use std::collections::HashSet;
+
+/// Deepest dependency chain followed; deeper references are left out
+const MAX_DEPTH: usize = 16;
+
+/// How a skill refers to another in its frontmatter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DependencyKind {
+    /// `requires_skills:` - needs the other skill installed to work
+    Requires,
+    /// `extends:` - builds on the other skill's instructions
+    Extends,
+}
+
+impl DependencyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyKind::Requires => "requires",
+            DependencyKind::Extends => "extends",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "extends" => DependencyKind::Extends,
+            _ => DependencyKind::Requires,
+        }
+    }
+}
+
+/// Skills a SKILL.md refers to, as written (`slug` or `registry:slug`), in
+/// frontmatter order without duplicates. Each key takes a string or a list.
+pub fn extract_dependencies(skill_md: &str) -> Vec<(DependencyKind, String)> {
+    let (Some(frontmatter), _) = split_frontmatter(skill_md) else {
+        return Vec::new();
+    };
+    let Ok(serde_yaml::Value::Mapping(map)) = serde_yaml::from_str::<serde_yaml::Value>(frontmatter) else {
+        return Vec::new();
+    };
+
+    let mut deps = Vec::new();
+    for (key, kind) in [("requires_skills", DependencyKind::Requires), ("extends", DependencyKind::Extends)] {
+        let refs = match map.get(key) {
+            Some(serde_yaml::Value::String(s)) => vec![s.as_str()],
+            Some(serde_yaml::Value::Sequence(items)) => items.iter().filter_map(|v| v.as_str()).collect(),
+            _ => continue,
+        };
+        for reference in refs.into_iter().map(str::trim).filter(|r| !r.is_empty()) {
+            if !deps.iter().any(|(k, r)| *k == kind && r == reference) {
+                deps.push((kind, reference.to_string()));
+            }
+        }
+    }
+    deps
+}
+
+/// The skill a reference points at. An unqualified slug published in
+/// several registries means the one in the referring skill's registry,
+/// whatever the registry priority.
+pub fn resolve_reference(db: &Database, from: &Skill, reference: &str) -> Result<Option<Skill>> {
+    Ok(match resolve_skill(db, reference, &[])? {
+        Resolution::Exact(skill) | Resolution::Preferred(skill, _) => Some(skill),
+        Resolution::Ambiguous(skills) => skills.into_iter().find(|s| s.registry == from.registry),
+        Resolution::Candidates(_) => None,
+    })
+}
+
+/// One reference in a dependency tree
+#[derive(Debug)]
+pub struct DepNode {
+    pub kind: DependencyKind,
+    pub reference: String,
+    /// None when nothing in the catalog matches the reference
+    pub skill: Option<Skill>,
+    /// Refers back to a skill already on the path from the root
+    pub cycle: bool,
+    pub children: Vec<DepNode>,
+}
+
+impl DepNode {
+    /// `registry:slug`, or the reference as written when it didn't resolve
+    pub fn id(&self) -> String {
+        match &self.skill {
+            Some(s) => format!("{}:{}", s.registry, s.slug),
+            None => self.reference.clone(),
+        }
+    }
+}
+
+/// Everything `root` refers to, transitively
+pub fn dependency_tree(db: &Database, root: &Skill) -> Result<Vec<DepNode>> {
+    let mut path = vec![(root.registry.clone(), root.slug.clone())];
+    children(db, root, &mut path)
+}
+
+fn children(db: &Database, skill: &Skill, path: &mut Vec<(String, String)>) -> Result<Vec<DepNode>> {
+    let mut nodes = Vec::new();
+    for (kind, reference) in db.get_dependencies(&skill.registry, &skill.slug)? {
+        let target = resolve_reference(db, skill, &reference)?;
+        let key = target.as_ref().map(|t| (t.registry.clone(), t.slug.clone()));
+        let cycle = key.as_ref().is_some_and(|k| path.contains(k));
+        let mut node = DepNode {
+            kind,
+            reference,
+            skill: target,
+            cycle,
+            children: Vec::new(),
+        };
+        if let (Some(target), Some(key), false) = (&node.skill, key, cycle) {
+            if path.len() < MAX_DEPTH {
+                path.push(key);
+                node.children = children(db, target, path)?;
+                path.pop();
+            }
+        }
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+/// Skills `root` requires, transitively, each after the skills it requires
+/// itself. References that don't resolve are returned separately.
+pub fn required_skills(db: &Database, root: &Skill) -> Result<(Vec<Skill>, Vec<String>)> {
+    let mut order = Vec::new();
+    let mut missing = Vec::new();
+    let mut seen = HashSet::from([(root.registry.clone(), root.slug.clone())]);
+    collect_required(&dependency_tree(db, root)?, &mut seen, &mut order, &mut missing);
+    Ok((order, missing))
+}
+
+fn collect_required(nodes: &[DepNode], seen: &mut HashSet<(String, String)>, order: &mut Vec<Skill>, missing: &mut Vec<String>) {
+    for node in nodes.iter().filter(|n| n.kind == DependencyKind::Requires) {
+        match &node.skill {
+            Some(skill) if seen.insert((skill.registry.clone(), skill.slug.clone())) => {
+                collect_required(&node.children, seen, order, missing);
+                order.push(skill.clone());
+            }
+            Some(_) => {}
+            None if !missing.contains(&node.reference) => missing.push(node.reference.clone()),
+            None => {}
+        }
+    }
+}
+
+/// Indented tree, one reference per line
+pub fn render_tree(root: &Skill, nodes: &[DepNode]) -> String {
+    let mut out = format!("{}:{}\n", root.registry, root.slug);
+    render_children(nodes, "", &mut out);
+    out
+}
+
+fn render_children(nodes: &[DepNode], prefix: &str, out: &mut String) {
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        let note = if node.skill.is_none() {
+            " (not in catalog)"
+        } else if node.cycle {
+            " (cycle)"
+        } else {
+            ""
+        };
+        out.push_str(&format!("{}{} {} {}{}\n", prefix, if last { "└──" } else { "├──" }, node.kind.as_str(), node.id(), note));
+        render_children(&node.children, &format!("{}{}", prefix, if last { "    " } else { "│   " }), out);
+    }
+}
+
+/// Graphviz DOT of the tree, each edge once
+pub fn render_dot(root: &Skill, nodes: &[DepNode]) -> String {
+    let mut edges = Vec::new();
+    collect_edges(&format!("{}:{}", root.registry, root.slug), nodes, &mut edges);
+    let mut out = String::from("digraph skills {\n");
+    for (from, to, kind) in edges {
+        let style = match kind {
+            DependencyKind::Requires => "",
+            DependencyKind::Extends => ", style=dashed",
+        };
+        out.push_str(&format!("  \"{}\" -> \"{}\" [label=\"{}\"{}];\n", from, to, kind.as_str(), style));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn collect_edges(from: &str, nodes: &[DepNode], edges: &mut Vec<(String, String, DependencyKind)>) {
+    for node in nodes {
+        let edge = (from.to_string(), node.id(), node.kind);
+        if !edges.contains(&edge) {
+            edges.push(edge);
+        }
+        collect_edges(&node.id(), &node.children, edges);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_test_skill(slug: &str, registry: &str, frontmatter: &str) -> Skill {
+        Skill {
+            skill_md: format!("---\nname: {}\n{}---\n# {}", slug, frontmatter, slug),
+            github_url: String::new(),
+            ..Skill::fixture(registry, slug)
+        }
+    }
+
+    #[test]
+    fn test_extract_dependencies() {
+        let md = "---\nname: report\nrequires_skills:\n  - pdf\n  - anthropic:xlsx\n  - pdf\nextends: charts\n---\n# Report";
+        assert_eq!(
+            extract_dependencies(md),
+            vec![
+                (DependencyKind::Requires, "pdf".to_string()),
+                (DependencyKind::Requires, "anthropic:xlsx".to_string()),
+                (DependencyKind::Extends, "charts".to_string()),
+            ]
+        );
+        assert!(extract_dependencies("# No frontmatter").is_empty());
+        assert!(extract_dependencies("---\nrequires_skills: [unclosed\n---\n").is_empty());
+    }
+
+    #[test]
+    fn test_tree_install_order_and_cycles() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let report = create_test_skill("report", "jo", "requires_skills: [pdf, fonts]\nextends: charts\n");
+        db.upsert_skill(&report).unwrap();
+        db.upsert_skill(&create_test_skill("pdf", "jo", "requires_skills: [fonts, ghost]\n")).unwrap();
+        // Same slug elsewhere: jo's own pdf wins
+        db.upsert_skill(&create_test_skill("pdf", "clawdhub", "")).unwrap();
+        db.upsert_skill(&create_test_skill("fonts", "jo", "requires_skills: report\n")).unwrap();
+        db.upsert_skill(&create_test_skill("charts", "jo", "")).unwrap();
+
+        let tree = dependency_tree(&db, &report).unwrap();
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree[0].id(), "jo:pdf");
+        assert!(tree[0].children[0].children[0].cycle);
+
+        let (order, missing) = required_skills(&db, &report).unwrap();
+        let ids: Vec<_> = order.iter().map(|s| s.slug.as_str()).collect();
+        assert_eq!(ids, ["fonts", "pdf"]);
+        assert_eq!(missing, ["ghost"]);
+
+        let text = render_tree(&report, &tree);
+        assert!(text.contains("│   ├── requires jo:fonts\n"));
+        assert!(text.contains("requires ghost (not in catalog)"));
+        assert!(text.contains("requires jo:report (cycle)"));
+        let dot = render_dot(&report, &tree);
+        assert!(dot.contains("\"jo:report\" -> \"jo:charts\" [label=\"extends\", style=dashed];"));
+        assert_eq!(dot.matches("\"jo:pdf\" -> \"jo:fonts\"").count(), 1);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::install::plan_install;
+use anyhow::Result;
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Lines of context around each change in the unified diff
+const CONTEXT_LINES: usize = 3;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// One file that differs between two copies of a skill
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiff {
+    /// Relative to the skill directory, with `/` separators
+    pub path: String,
+    pub status: FileStatus,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Unified diff hunks; None for binary files
+    pub hunks: Option<String>,
+}
+
+/// Files that differ from `old` to `new`, by path
+pub fn diff_dirs(old: &Path, new: &Path) -> Result<Vec<FileDiff>> {
+    let files = |dir: &Path| -> Result<BTreeSet<String>> {
+        if !dir.exists() {
+            return Ok(BTreeSet::new());
+        }
+        Ok(plan_install(dir, dir)?
+            .files
+            .into_iter()
+            .map(|f| f.rel_path.to_string_lossy().replace('\\', "/"))
+            .collect())
+    };
+    let (old_files, new_files) = (files(old)?, files(new)?);
+
+    let mut diffs = Vec::new();
+    for path in old_files.union(&new_files) {
+        let read = |dir: &Path, present: bool| -> Result<Option<Vec<u8>>> {
+            Ok(if present { Some(std::fs::read(dir.join(path))?) } else { None })
+        };
+        let before = read(old, old_files.contains(path))?;
+        let after = read(new, new_files.contains(path))?;
+        if before == after {
+            continue;
+        }
+        let status = match (&before, &after) {
+            (None, _) => FileStatus::Added,
+            (_, None) => FileStatus::Removed,
+            _ => FileStatus::Modified,
+        };
+        let text = |bytes: &Option<Vec<u8>>| match bytes {
+            Some(b) => std::str::from_utf8(b).ok().map(str::to_string),
+            None => Some(String::new()),
+        };
+        let diff = match (text(&before), text(&after)) {
+            (Some(before), Some(after)) => {
+                let diff = TextDiff::from_lines(&before, &after);
+                let count = |tag| diff.iter_all_changes().filter(|c| c.tag() == tag).count();
+                FileDiff {
+                    path: path.clone(),
+                    status,
+                    insertions: count(ChangeTag::Insert),
+                    deletions: count(ChangeTag::Delete),
+                    hunks: Some(diff.unified_diff().context_radius(CONTEXT_LINES).to_string()),
+                }
+            }
+            _ => FileDiff {
+                path: path.clone(),
+                status,
+                insertions: 0,
+                deletions: 0,
+                hunks: None,
+            },
+        };
+        diffs.push(diff);
+    }
+    Ok(diffs)
+}
+
+fn paint(text: &str, color: &str, enabled: bool) -> String {
+    if enabled {
+        format!("{}{}{}", color, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Unified diff of every changed file, labelled `old_label/path` and
+/// `new_label/path` like `git diff`
+pub fn render_unified(diffs: &[FileDiff], old_label: &str, new_label: &str, color: bool) -> String {
+    let mut out = String::new();
+    for d in diffs {
+        let old = if d.status == FileStatus::Added { "/dev/null".to_string() } else { format!("{}/{}", old_label, d.path) };
+        let new = if d.status == FileStatus::Removed { "/dev/null".to_string() } else { format!("{}/{}", new_label, d.path) };
+        let Some(hunks) = &d.hunks else {
+            out.push_str(&format!("Binary files {} and {} differ\n", old, new));
+            continue;
+        };
+        out.push_str(&paint(&format!("--- {}\n+++ {}\n", old, new), BOLD, color));
+        for line in hunks.lines() {
+            let painted = match line.chars().next() {
+                Some('@') => paint(line, CYAN, color),
+                Some('+') => paint(line, GREEN, color),
+                Some('-') => paint(line, RED, color),
+                _ => line.to_string(),
+            };
+            out.push_str(&painted);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// `git diff --stat`-style summary: one line per file and a total
+pub fn render_stat(diffs: &[FileDiff], color: bool) -> String {
+    let width = diffs.iter().map(|d| d.path.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for d in diffs {
+        let changes = match &d.hunks {
+            Some(_) => format!(
+                "{} {}{}",
+                d.insertions + d.deletions,
+                paint(&"+".repeat(d.insertions.min(40)), GREEN, color),
+                paint(&"-".repeat(d.deletions.min(40)), RED, color)
+            ),
+            None => "Bin".to_string(),
+        };
+        out.push_str(&format!(" {:<width$} | {}\n", d.path, changes, width = width));
+    }
+    let insertions: usize = diffs.iter().map(|d| d.insertions).sum();
+    let deletions: usize = diffs.iter().map(|d| d.deletions).sum();
+    out.push_str(&format!(
+        " {} file{} changed, {} insertion{}(+), {} deletion{}(-)\n",
+        diffs.len(),
+        if diffs.len() == 1 { "" } else { "s" },
+        insertions,
+        if insertions == 1 { "" } else { "s" },
+        deletions,
+        if deletions == 1 { "" } else { "s" }
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_diff_dirs_and_render() {
+        let dir = tempdir().unwrap();
+        let (old, new) = (dir.path().join("old"), dir.path().join("new"));
+        std::fs::create_dir_all(old.join("scripts")).unwrap();
+        std::fs::create_dir_all(&new).unwrap();
+        std::fs::write(old.join("SKILL.md"), "# PDF\nRead PDFs.\n").unwrap();
+        std::fs::write(new.join("SKILL.md"), "# PDF\nRead and fill PDFs.\n").unwrap();
+        std::fs::write(old.join("scripts/run.sh"), "echo hi\n").unwrap();
+        std::fs::write(new.join("README.md"), "new\n").unwrap();
+        std::fs::write(old.join("same.txt"), "same").unwrap();
+        std::fs::write(new.join("same.txt"), "same").unwrap();
+
+        let diffs = diff_dirs(&old, &new).unwrap();
+        let summary: Vec<_> = diffs.iter().map(|d| (d.path.as_str(), d.status, d.insertions, d.deletions)).collect();
+        assert_eq!(
+            summary,
+            [
+                ("README.md", FileStatus::Added, 1, 0),
+                ("SKILL.md", FileStatus::Modified, 1, 1),
+                ("scripts/run.sh", FileStatus::Removed, 0, 1),
+            ]
+        );
+
+        let unified = render_unified(&diffs, "installed", "latest", false);
+        assert!(unified.contains("--- /dev/null\n+++ latest/README.md\n"));
+        assert!(unified.contains("-Read PDFs.\n+Read and fill PDFs.\n"));
+        assert!(unified.contains("--- installed/scripts/run.sh\n+++ /dev/null\n"));
+        assert!(render_unified(&diffs, "a", "b", true).contains("\x1b[32m+Read and fill PDFs.\x1b[0m"));
+
+        let stat = render_stat(&diffs, false);
+        assert!(stat.contains(" SKILL.md       | 2 +-\n"));
+        assert!(stat.ends_with(" 3 files changed, 2 insertions(+), 2 deletions(-)\n"));
+    }
+}
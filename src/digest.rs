@@ -0,0 +1,275 @@
+use crate::db::{Database, Skill};
+use crate::quality::QualityScores;
+use anyhow::Result;
+
+/// Security risk from which a new or updated skill is listed as flagged
+const FLAG_SECURITY_RISK: i64 = 25;
+
+/// A skill as listed in a digest
+#[derive(Debug, Clone)]
+pub struct DigestSkill {
+    pub registry: String,
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub url: String,
+}
+
+impl From<&Skill> for DigestSkill {
+    fn from(s: &Skill) -> Self {
+        Self {
+            registry: s.registry.clone(),
+            slug: s.slug.clone(),
+            name: s.name.clone(),
+            description: s.description.clone(),
+            url: s.github_url.clone(),
+        }
+    }
+}
+
+/// Catalog changes over a period, for people rather than tools
+#[derive(Debug, Clone)]
+pub struct Digest {
+    /// Unix seconds
+    pub since: i64,
+    pub until: i64,
+    /// New skills at or above the quality bar, with their score
+    pub new_skills: Vec<(DigestSkill, i64)>,
+    /// Skills that published a new version: the previous one and the new one
+    pub updated: Vec<(DigestSkill, String, String)>,
+    /// New or updated skills with a security risk, and skills found gone,
+    /// each with the reason
+    pub flagged: Vec<(DigestSkill, String)>,
+    /// Skills superseded by another entry, with where they moved
+    pub deprecated: Vec<(DigestSkill, String)>,
+}
+
+impl Digest {
+    pub fn is_empty(&self) -> bool {
+        self.new_skills.is_empty() && self.updated.is_empty() && self.flagged.is_empty() && self.deprecated.is_empty()
+    }
+}
+
+/// A period such as `7d`, `2w`, or `12h`, in seconds; a bare number is days
+pub fn parse_period(period: &str) -> Result<i64> {
+    let period = period.trim();
+    let (number, unit) = match period.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => period.split_at(i),
+        None => (period, "d"),
+    };
+    let number: i64 = number.parse().map_err(|_| anyhow::anyhow!("Invalid period '{}'; use e.g. 7d, 2w, or 12h", period))?;
+    let unit = match unit {
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => anyhow::bail!("Invalid period '{}'; use e.g. 7d, 2w, or 12h", period),
+    };
+    Ok(number * unit)
+}
+
+/// Collect what changed in the catalog from `since` to `now`. New skills
+/// count when their quality score is at least `min_score`; `allowed` hides
+/// skills the policy denies.
+pub fn build(db: &Database, quality: &QualityScores, since: i64, now: i64, min_score: i64, allowed: &dyn Fn(&str, &str) -> bool) -> Result<Digest> {
+    let mut digest = Digest { since, until: now, new_skills: Vec::new(), updated: Vec::new(), flagged: Vec::new(), deprecated: Vec::new() };
+    let mut changed: Vec<Skill> = Vec::new();
+
+    for (skill, _) in db.get_new_skills(since)? {
+        if !allowed(&skill.registry, &skill.slug) {
+            continue;
+        }
+        let score = quality.score_for(&skill);
+        if score >= min_score {
+            digest.new_skills.push((DigestSkill::from(&skill), score));
+        }
+        changed.push(skill);
+    }
+    digest.new_skills.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.name.cmp(&b.0.name)));
+
+    for change in db.get_version_changes(since)? {
+        if !allowed(&change.registry, &change.slug) {
+            continue;
+        }
+        let Some(skill) = db.get_skill(&change.registry, &change.slug)? else {
+            continue;
+        };
+        if !changed.iter().any(|s| s.registry == skill.registry && s.slug == skill.slug) {
+            changed.push(skill.clone());
+        }
+        digest.updated.push((DigestSkill::from(&skill), change.previous, change.version));
+    }
+
+    for skill in &changed {
+        if let Some(report) = db.get_security_report(&skill.registry, &skill.slug)?.filter(|r| r.score >= FLAG_SECURITY_RISK) {
+            digest.flagged.push((DigestSkill::from(skill), format!("{} security risk ({}/100)", report.level(), report.score)));
+        }
+    }
+    for (registry, slug, status, _) in db.get_unreachable_since(since)? {
+        if let Some(skill) = db.get_skill(&registry, &slug)?.filter(|_| allowed(&registry, &slug)) {
+            digest.flagged.push((DigestSkill::from(&skill), format!("source is gone (HTTP {})", status)));
+        }
+    }
+
+    for moved in db.get_moves_since(since)? {
+        let (registry, slug) = &moved.from;
+        if let Some(skill) = db.get_skill(registry, slug)?.filter(|_| allowed(registry, slug)) {
+            digest.deprecated.push((DigestSkill::from(&skill), format!("{}:{}", moved.to.0, moved.to.1)));
+        }
+    }
+    Ok(digest)
+}
+
+/// `YYYY-MM-DD` of a Unix time, in UTC
+fn date(timestamp: i64) -> String {
+    // Days to civil date, after Howard Hinnant's algorithm
+    let z = timestamp.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn title(digest: &Digest) -> String {
+    format!("Skill catalog changes, {} to {}", date(digest.since), date(digest.until))
+}
+
+/// Sections in display order: heading, then (skill, note) lines
+fn sections(digest: &Digest) -> Vec<(&'static str, Vec<(&DigestSkill, String)>)> {
+    vec![
+        ("New skills", digest.new_skills.iter().map(|(s, score)| (s, format!("quality {}", score))).collect()),
+        ("Updated", digest.updated.iter().map(|(s, from, to)| (s, format!("{} → {}", from, to))).collect()),
+        ("Flagged", digest.flagged.iter().map(|(s, why)| (s, why.clone())).collect()),
+        ("Deprecated", digest.deprecated.iter().map(|(s, to)| (s, format!("moved to {}", to))).collect()),
+    ]
+}
+
+pub fn render_markdown(digest: &Digest) -> String {
+    let mut out = format!("# {}\n", title(digest));
+    if digest.is_empty() {
+        out.push_str("\nNothing changed.\n");
+    }
+    for (heading, entries) in sections(digest).into_iter().filter(|(_, e)| !e.is_empty()) {
+        out.push_str(&format!("\n## {} ({})\n\n", heading, entries.len()));
+        for (skill, note) in entries {
+            let escape = |s: &str| s.replace('[', "\\[").replace(']', "\\]");
+            out.push_str(&format!("- [{}]({}) `{}:{}` ({})", escape(&skill.name), skill.url, skill.registry, skill.slug, note));
+            if !skill.description.is_empty() {
+                out.push_str(&format!(": {}", skill.description));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A standalone page, which also works as the body of an email
+pub fn render_html(digest: &Digest) -> String {
+    let title = escape_html(&title(digest));
+    let mut out = format!("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n<h1>{}</h1>\n", title, title);
+    if digest.is_empty() {
+        out.push_str("<p>Nothing changed.</p>\n");
+    }
+    for (heading, entries) in sections(digest).into_iter().filter(|(_, e)| !e.is_empty()) {
+        out.push_str(&format!("<h2>{} ({})</h2>\n<ul>\n", heading, entries.len()));
+        for (skill, note) in entries {
+            out.push_str(&format!(
+                "<li><a href=\"{}\">{}</a> <code>{}:{}</code> ({})",
+                escape_html(&skill.url),
+                escape_html(&skill.name),
+                escape_html(&skill.registry),
+                escape_html(&skill.slug),
+                escape_html(&note)
+            ));
+            if !skill.description.is_empty() {
+                out.push_str(&format!(": {}", escape_html(&skill.description)));
+            }
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ul>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality::QualityEntry;
+    use tempfile::tempdir;
+
+    fn skill(slug: &str, version: Option<&str>, updated_at: i64) -> Skill {
+        Skill {
+            name: slug.to_uppercase(),
+            description: format!("{} <tools>", slug),
+            skill_md: format!("# {}\n", slug),
+            github_url: format!("https://github.com/jo-inc/skills/tree/master/skills/{}", slug),
+            version: version.map(str::to_string),
+            trusted: true,
+            updated_at,
+            ..Skill::fixture("jo", slug)
+        }
+    }
+
+    #[test]
+    fn test_parse_period() {
+        assert_eq!(parse_period("7d").unwrap(), 7 * 86_400);
+        assert_eq!(parse_period("2w").unwrap(), 14 * 86_400);
+        assert_eq!(parse_period("12h").unwrap(), 12 * 3_600);
+        assert_eq!(parse_period("3").unwrap(), 3 * 86_400);
+        assert!(parse_period("7m").is_err());
+        assert!(parse_period("d").is_err());
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(1_792_108_800), "2026-10-16");
+    }
+
+    #[test]
+    fn test_build_and_render() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let day = 86_400;
+        // Known before the period, then updated in it
+        db.upsert_skill(&skill("pdf", Some("1.0.0"), day)).unwrap();
+        db.record_skill_version("jo", "pdf", "1.0.0", "aaa", day).unwrap();
+        db.record_skill_version("jo", "pdf", "2.0.0", "bbb", 10 * day).unwrap();
+        // New in the period, one good enough and one not
+        db.upsert_skill(&skill("xlsx", None, 9 * day)).unwrap();
+        db.upsert_skill(&skill("draft", None, 9 * day)).unwrap();
+        let entry = |name: &str, score| QualityEntry { name: name.to_string(), registry: "jo".to_string(), score, stars: 0, rationale: String::new(), url: String::new() };
+        let quality = QualityScores::from_entries(vec![entry("xlsx", 90), entry("draft", 40)]);
+
+        let digest = build(&db, &quality, 8 * day, 11 * day, 80, &|_, _| true).unwrap();
+        assert_eq!(digest.new_skills.iter().map(|(s, q)| (s.slug.as_str(), *q)).collect::<Vec<_>>(), [("xlsx", 90)]);
+        assert_eq!(digest.updated.len(), 1);
+        assert_eq!((digest.updated[0].1.as_str(), digest.updated[0].2.as_str()), ("1.0.0", "2.0.0"));
+        assert!(digest.flagged.is_empty() && digest.deprecated.is_empty());
+
+        let md = render_markdown(&digest);
+        assert!(md.starts_with("# Skill catalog changes, 1970-01-09 to 1970-01-12\n"));
+        assert!(md.contains("## New skills (1)\n\n- [XLSX](https://github.com/jo-inc/skills/tree/master/skills/xlsx) `jo:xlsx` (quality 90): xlsx <tools>\n"));
+        assert!(md.contains("(1.0.0 → 2.0.0)"));
+        assert!(!md.contains("Flagged"));
+        let html = render_html(&digest);
+        assert!(html.contains("<h2>Updated (1)</h2>"));
+        assert!(html.contains("xlsx &lt;tools&gt;"));
+
+        let hidden = build(&db, &quality, 8 * day, 11 * day, 80, &|_, slug| slug != "xlsx").unwrap();
+        assert!(hidden.new_skills.is_empty());
+        let quiet = build(&db, &quality, 20 * day, 21 * day, 80, &|_, _| true).unwrap();
+        assert!(quiet.is_empty());
+        assert!(render_markdown(&quiet).contains("Nothing changed."));
+    }
+}
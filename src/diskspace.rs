@@ -0,0 +1,68 @@
+use anyhow::Result;
+use std::path::Path;
+
+pub const MB: u64 = 1024 * 1024;
+
+/// Kept free on top of an operation's estimate, so the rest of the system
+/// (and SQLite's journal) still has room afterwards
+const HEADROOM: u64 = 100 * MB;
+
+/// Free space on the filesystem holding `path`, which need not exist yet
+pub fn available(path: &Path) -> Result<u64> {
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(Path::new("."));
+    Ok(fs4::available_space(existing)?)
+}
+
+/// Fail before starting `what` if it would likely fill the disk
+pub fn ensure_space(path: &Path, needed: u64, what: &str) -> Result<()> {
+    if needed == 0 {
+        return Ok(());
+    }
+    let free = available(path)?;
+    if free < needed + HEADROOM {
+        anyhow::bail!(
+            "Not enough disk space for {}: needs about {} plus {} headroom, but only {} is free at {:?}. \
+             Free up space, or run `sync --force` to try anyway.",
+            what,
+            format_bytes(needed),
+            format_bytes(HEADROOM),
+            format_bytes(free),
+            path
+        );
+    }
+    Ok(())
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const GB: u64 = 1024 * MB;
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else {
+        format!("{} MB", bytes.div_ceil(MB))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ensure_space() {
+        let dir = tempdir().unwrap();
+        // Not created yet: checked against the nearest existing parent
+        let path = dir.path().join("repos").join("clawdhub");
+        assert!(available(&path).unwrap() > 0);
+        ensure_space(&path, 0, "nothing").unwrap();
+
+        let err = ensure_space(&path, u64::MAX / 2, "cloning registries").unwrap_err();
+        assert!(err.to_string().starts_with("Not enough disk space for cloning registries"));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(1), "1 MB");
+        assert_eq!(format_bytes(600 * MB), "600 MB");
+        assert_eq!(format_bytes(3 * 1024 * MB / 2), "1.5 GB");
+    }
+}
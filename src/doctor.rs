@@ -0,0 +1,151 @@
+use crate::config::{registry_enabled, RegistryConfig};
+use crate::db::{Database, SyncFailure};
+use crate::text::format_age;
+use crate::{failures, git, github, skillssh, sync};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A registry not synced for this long is reported
+pub const STALE_SYNC_DAYS: i64 = 7;
+
+/// One thing `doctor` looked at
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub ok: bool,
+    pub message: String,
+    /// Lines that go under the message
+    pub details: Vec<String>,
+    /// What to do about a problem
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn ok(message: String) -> Self {
+        Self { ok: true, message, details: Vec::new(), hint: None }
+    }
+
+    fn problem(message: String, hint: impl Into<String>) -> Self {
+        Self { ok: false, message, details: Vec::new(), hint: Some(hint.into()) }
+    }
+}
+
+/// Everything `doctor` checks: the git backend, the data directory, the
+/// database and its `indexed` documents, when each enabled registry last
+/// synced, whether their hosts answer (unless `offline`), and registries
+/// that keep failing to sync
+pub async fn run(data_dir: &Path, db: &Database, indexed: u64, registries: &HashMap<String, RegistryConfig>, offline: bool) -> Result<Vec<Check>> {
+    let mut checks = Vec::new();
+
+    checks.push(match git::backend() {
+        Ok(backend) => Check::ok(backend),
+        Err(e) => Check::problem(e.to_string(), "install git, or build with the `git2` feature (no git binary needed)"),
+    });
+
+    let probe = data_dir.join(".doctor-probe");
+    checks.push(match std::fs::write(&probe, b"ok").and_then(|_| std::fs::remove_file(&probe)) {
+        Ok(()) => Check::ok(format!("data directory {:?} is writable", data_dir)),
+        Err(e) => Check::problem(
+            format!("data directory {:?} is not writable: {}", data_dir, e),
+            "fix its permissions, or pass --data-dir to use another",
+        ),
+    });
+
+    let damage = db.integrity_check()?;
+    checks.push(if damage.is_empty() {
+        Check::ok("database passes its integrity check".to_string())
+    } else {
+        Check {
+            details: damage.iter().take(5).cloned().collect(),
+            ..Check::problem(
+                format!("database integrity check found {} problem(s):", damage.len()),
+                "the catalog can be rebuilt: move skills.db aside and run `sync`",
+            )
+        }
+    });
+
+    let skills = db.count_all_skills()?;
+    checks.push(if indexed as i64 == skills {
+        Check::ok(format!("search index has all {} skills", skills))
+    } else {
+        Check::problem(format!("search index has {} skills, the database {}", indexed, skills), "run `sync` to rebuild the index")
+    });
+
+    let now = unix_now();
+    for name in sync::synced_registries().into_iter().filter(|n| registry_enabled(registries, n)) {
+        match db.get_last_sync(name)? {
+            Some((at, _)) if now - at < STALE_SYNC_DAYS * 86_400 => {}
+            Some((at, _)) => checks.push(Check::problem(
+                format!("{}: last synced {}", name, format_age((now - at).max(0) / 86_400)),
+                "run `sync`, or schedule it",
+            )),
+            None => checks.push(Check::problem(format!("{}: never synced", name), "run `sync`")),
+        }
+    }
+
+    if !offline {
+        let http = reqwest::Client::builder().user_agent("skill-search/0.1").timeout(Duration::from_secs(10)).build()?;
+        for (name, url) in registry_endpoints(registries) {
+            // Any answer short of a server error means the host is up
+            let reached = match http.get(&url).send().await {
+                Ok(r) if r.status().is_server_error() => Err(anyhow::anyhow!("HTTP {}", r.status())),
+                Ok(_) => Ok(()),
+                Err(e) => Err(e.into()),
+            };
+            checks.push(match reached {
+                Ok(()) => Check::ok(format!("{} is reachable at {}", name, url)),
+                Err(e) => Check::problem(format!("{} is unreachable at {}: {:#}", name, url, e), failures::classify(&e).hint()),
+            });
+        }
+    }
+
+    for name in sync::synced_registries() {
+        let failed_runs = db.consecutive_failed_syncs(name)?;
+        if failed_runs < failures::CHRONIC_AFTER {
+            continue;
+        }
+        let latest = db.get_sync_failures(name, 1)?.into_iter().next();
+        checks.push(Check {
+            ok: false,
+            message: format!("{}: last {} syncs failed", name, failed_runs),
+            details: latest.iter().map(|f| describe_failure(f, now)).collect(),
+            hint: latest.map(|f| f.kind.hint().to_string()),
+        });
+    }
+
+    Ok(checks)
+}
+
+/// e.g. "3 days ago [fetch/network]: connection refused"
+pub fn describe_failure(failure: &SyncFailure, now: i64) -> String {
+    format!(
+        "{} [{}/{}]: {}",
+        format_age((now - failure.occurred_at).max(0) / 86_400),
+        failure.stage,
+        failure.kind.as_str(),
+        failure.message.lines().next().unwrap_or_default()
+    )
+}
+
+/// The hosts each enabled registry syncs from
+fn registry_endpoints(registries: &HashMap<String, RegistryConfig>) -> Vec<(String, String)> {
+    let mut endpoints: Vec<(String, String)> = Vec::new();
+    for registry in github::REGISTRIES.iter().filter(|r| registry_enabled(registries, r.name)) {
+        let url = registry.repo.url.trim_end_matches(".git").to_string();
+        if !endpoints.iter().any(|(_, u)| *u == url) {
+            endpoints.push((registry.name.to_string(), url));
+        }
+        if let Some(api) = registry.api_base_url() {
+            endpoints.push((format!("{} API", registry.name), api));
+        }
+    }
+    if registry_enabled(registries, "skillssh") {
+        endpoints.push(("skillssh".to_string(), skillssh::API_BASE.to_string()));
+    }
+    endpoints
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
@@ -0,0 +1,211 @@
+use crate::config::EmbeddingsConfig;
+use crate::db::{Database, Skill};
+use crate::index::{IndexFilters, SearchResult};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/// Descriptions sent to the embedding provider per request
+const BATCH_SIZE: usize = 64;
+
+/// What gets embedded for a skill: its name and description
+pub fn skill_text(skill: &Skill) -> String {
+    format!("{}: {}", skill.name, skill.description)
+}
+
+/// Identifies the text a stored vector was computed from, so changed
+/// descriptions are embedded again
+pub fn text_hash(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom == 0.0 {
+        0.0
+    } else {
+        dot / denom
+    }
+}
+
+pub fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+pub fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect()
+}
+
+/// Embed texts with the configured OpenAI-compatible `/embeddings`
+/// endpoint (Ollama, llama.cpp, LM Studio, OpenAI, ...), one vector per text
+#[cfg(feature = "embeddings")]
+pub async fn embed(config: &EmbeddingsConfig, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+    #[derive(serde::Deserialize)]
+    struct Response {
+        data: Vec<Item>,
+    }
+    #[derive(serde::Deserialize)]
+    struct Item {
+        index: usize,
+        embedding: Vec<f32>,
+    }
+
+    let http = reqwest::Client::builder().user_agent("skill-search/0.1").build()?;
+    let mut request = http.post(&config.url).json(&serde_json::json!({ "model": config.model, "input": texts }));
+    if let Some(key) = config.api_key_env.as_deref().and_then(|var| std::env::var(var).ok()) {
+        request = request.bearer_auth(key);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Embedding provider {} unavailable: {}", config.url, e))?;
+    if !response.status().is_success() {
+        anyhow::bail!("Embedding provider {} returned {}: {}", config.url, response.status(), response.text().await.unwrap_or_default());
+    }
+    let mut items = response.json::<Response>().await?.data;
+    if items.len() != texts.len() {
+        anyhow::bail!("Embedding provider returned {} vectors for {} texts", items.len(), texts.len());
+    }
+    items.sort_by_key(|i| i.index);
+    Ok(items.into_iter().map(|i| i.embedding).collect())
+}
+
+#[cfg(not(feature = "embeddings"))]
+pub async fn embed(_config: &EmbeddingsConfig, _texts: &[String]) -> Result<Vec<Vec<f32>>> {
+    crate::features::require("embeddings", "search --semantic")?;
+    anyhow::bail!("Embeddings are not compiled in")
+}
+
+/// Embed every skill whose description has no vector from the configured
+/// model yet, returning how many were embedded
+pub async fn update_embeddings(db: &Database, config: &EmbeddingsConfig, skills: &[Skill]) -> Result<usize> {
+    let stored = db.get_embedding_hashes(&config.model)?;
+    let missing: Vec<(&Skill, String)> = skills
+        .iter()
+        .map(|s| (s, skill_text(s)))
+        .filter(|(s, text)| stored.get(&(s.registry.clone(), s.slug.clone())) != Some(&text_hash(text)))
+        .collect();
+    if !missing.is_empty() {
+        tracing::info!("Embedding {} skill descriptions with {}", missing.len(), config.model);
+    }
+    for batch in missing.chunks(BATCH_SIZE) {
+        let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+        let vectors = embed(config, &texts).await?;
+        for ((skill, text), vector) in batch.iter().zip(vectors) {
+            db.set_embedding(&skill.registry, &skill.slug, &config.model, &text_hash(text), &vector)?;
+        }
+    }
+    Ok(missing.len())
+}
+
+/// Rerank keyword results by blending in cosine similarity between the
+/// query and skill descriptions, adding the closest skills keyword search
+/// missed. `weight` is the share of the score from similarity.
+pub async fn semantic_search(db: &Database, config: &EmbeddingsConfig, query: &str, keyword: Vec<SearchResult>, limit: usize, filters: &IndexFilters<'_>) -> Result<Vec<SearchResult>> {
+    crate::features::require("embeddings", "search --semantic")?;
+    let skills: Vec<Skill> = db.get_all_skills()?.into_iter().filter(|s| filters.matches(db, s)).collect();
+    update_embeddings(db, config, &skills).await?;
+    let query_vector = embed(config, &[query.to_string()]).await?.pop().unwrap_or_default();
+    let vectors = db.get_embeddings(&config.model)?;
+
+    let similar: Vec<(SearchResult, f32)> = skills
+        .into_iter()
+        .filter_map(|s| {
+            let similarity = cosine(&query_vector, vectors.get(&(s.registry.clone(), s.slug.clone()))?);
+            Some((
+                SearchResult {
+                    slug: s.slug,
+                    name: s.name,
+                    description: s.description,
+                    registry: s.registry,
+                    overlay: false,
+                    score: 0.0,
+                    fuzzy: false,
+                    snippet: None,
+                },
+                similarity,
+            ))
+        })
+        .collect();
+    Ok(blend(keyword, similar, config.weight, limit))
+}
+
+/// Combine keyword scores (scaled to 0-1 by the best one) with similarity
+/// scores. Candidates are every keyword hit plus the `limit` most similar
+/// skills with any similarity; the result is sorted by blended score and cut to `limit`.
+pub fn blend(keyword: Vec<SearchResult>, mut similar: Vec<(SearchResult, f32)>, weight: f32, limit: usize) -> Vec<SearchResult> {
+    let weight = weight.clamp(0.0, 1.0);
+    let best = keyword.iter().map(|r| r.score).fold(0.0f32, f32::max);
+    let similarity: HashMap<String, f32> = similar.iter().map(|(r, s)| (r.unique_key(), *s)).collect();
+
+    similar.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let keyword_keys: HashSet<String> = keyword.iter().map(|r| r.unique_key()).collect();
+    let mut merged: Vec<SearchResult> = keyword
+        .into_iter()
+        .chain(
+            similar
+                .into_iter()
+                .filter(|(_, s)| *s > 0.0)
+                .take(limit)
+                .map(|(r, _)| r)
+                .filter(|r| !keyword_keys.contains(&r.unique_key())),
+        )
+        .map(|mut r| {
+            let keyword_score = if best > 0.0 { r.score / best } else { 0.0 };
+            let similarity = similarity.get(&r.unique_key()).copied().unwrap_or(0.0).max(0.0);
+            r.score = (1.0 - weight) * keyword_score + weight * similarity;
+            r
+        })
+        .collect();
+    merged.sort_by(|a, b| b.score.total_cmp(&a.score));
+    merged.truncate(limit);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(slug: &str, score: f32) -> SearchResult {
+        SearchResult {
+            slug: slug.to_string(),
+            name: slug.to_string(),
+            description: String::new(),
+            registry: "anthropic".to_string(),
+            overlay: false,
+            score,
+            fuzzy: false,
+            snippet: None,
+        }
+    }
+
+    #[test]
+    fn test_cosine_and_blob_round_trip() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine(&[1.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+        let v = vec![0.25, -1.5, 3.0];
+        assert_eq!(from_blob(&to_blob(&v)), v);
+    }
+
+    #[test]
+    fn test_blend_adds_semantic_matches() {
+        let keyword = vec![result("pdf", 10.0), result("docx", 5.0)];
+        let similar = vec![(result("pdf", 0.0), 0.2), (result("docx", 0.0), 0.1), (result("forms", 0.0), 0.9)];
+
+        let blended = blend(keyword.clone(), similar.clone(), 0.5, 10);
+        let order: Vec<_> = blended.iter().map(|r| r.slug.as_str()).collect();
+        assert_eq!(order, ["pdf", "forms", "docx"]);
+        assert!((blended[0].score - 0.6).abs() < 1e-6);
+
+        // Only similarity counts at weight 1
+        let order: Vec<_> = blend(keyword, similar, 1.0, 2).into_iter().map(|r| r.slug).collect();
+        assert_eq!(order, ["forms", "pdf"]);
+    }
+}
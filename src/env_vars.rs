@@ -0,0 +1,183 @@
+use crate::text::split_frontmatter;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Variables every shell has, and names scripts commonly use for their own
+/// values; referencing them says nothing about setup
+const IGNORED: &[&str] = &[
+    "HOME", "PATH", "USER", "PWD", "OLDPWD", "SHELL", "TERM", "LANG", "TMPDIR", "TEMP", "TMP", "EDITOR",
+    "HOSTNAME", "UID", "EUID", "RANDOM", "SECONDS", "LINENO", "IFS", "PS1", "LC_ALL", "XDG_CONFIG_HOME",
+    "XDG_DATA_HOME", "XDG_CACHE_HOME", "LOGNAME", "PPID", "REPLY", "OPTARG", "OPTIND", "BASH", "BASH_SOURCE",
+    "PAGER", "VISUAL", "DISPLAY", "COLUMNS", "LINES", "CI", "FILE", "DIR", "INPUT", "OUTPUT", "ARGS",
+];
+
+/// Where a variable reference was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EnvVarSource {
+    /// Declared in frontmatter (an `env` key anywhere in the YAML)
+    Frontmatter,
+    /// Referenced in the body (`$VAR`, `process.env.VAR`, `os.environ["VAR"]`, ...)
+    Body,
+}
+
+impl EnvVarSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnvVarSource::Frontmatter => "frontmatter",
+            EnvVarSource::Body => "body",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "frontmatter" => EnvVarSource::Frontmatter,
+            _ => EnvVarSource::Body,
+        }
+    }
+}
+
+fn patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            r"\$\{?([A-Z][A-Z0-9_]+)\b",
+            r"process\.env\.([A-Z][A-Z0-9_]*)\b",
+            r#"process\.env\[['"]([A-Z][A-Z0-9_]*)['"]\]"#,
+            r#"os\.environ(?:\.get)?[\[(]\s*['"]([A-Z][A-Z0-9_]*)['"]"#,
+            r#"os\.getenv\(\s*['"]([A-Z][A-Z0-9_]*)['"]"#,
+            r#"env::var\(\s*"([A-Z][A-Z0-9_]*)""#,
+        ]
+        .iter()
+        .map(|p| Regex::new(p).expect("valid env var pattern"))
+        .collect()
+    })
+}
+
+/// Environment variables a SKILL.md expects, sorted by name. Frontmatter
+/// declarations win over body references for the recorded source.
+pub fn extract_env_vars(skill_md: &str) -> Vec<(String, EnvVarSource)> {
+    let mut found: BTreeMap<String, EnvVarSource> = BTreeMap::new();
+
+    let (frontmatter, body) = split_frontmatter(skill_md);
+    if let Some(fm) = frontmatter {
+        if let Ok(value) = serde_yaml::from_str::<serde_yaml::Value>(fm) {
+            collect_frontmatter_env(&value, false, &mut found);
+        }
+    }
+
+    for pattern in patterns() {
+        for cap in pattern.captures_iter(body) {
+            let name = cap[1].to_string();
+            if !IGNORED.contains(&name.as_str()) {
+                found.entry(name).or_insert(EnvVarSource::Body);
+            }
+        }
+    }
+
+    found.into_iter().collect()
+}
+
+fn collect_frontmatter_env(value: &serde_yaml::Value, under_env_key: bool, found: &mut BTreeMap<String, EnvVarSource>) {
+    use serde_yaml::Value;
+    match value {
+        Value::Mapping(map) => {
+            for (k, v) in map {
+                let key = k.as_str().unwrap_or("");
+                let is_env = matches!(key, "env" | "envs" | "env_vars" | "environment");
+                if under_env_key && is_env_name(key) {
+                    found.insert(key.to_string(), EnvVarSource::Frontmatter);
+                }
+                collect_frontmatter_env(v, is_env, found);
+            }
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                match item.as_str() {
+                    Some(name) if under_env_key && is_env_name(name) => {
+                        found.insert(name.to_string(), EnvVarSource::Frontmatter);
+                    }
+                    _ => collect_frontmatter_env(item, under_env_key, found),
+                }
+            }
+        }
+        Value::String(s) if under_env_key && is_env_name(s) => {
+            found.insert(s.to_string(), EnvVarSource::Frontmatter);
+        }
+        _ => {}
+    }
+}
+
+fn is_env_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Names from `vars` that `env` (e.g. `|v| std::env::var(v).ok()`) has no
+/// value, or an empty one, for
+pub fn unset_vars<'a>(vars: impl IntoIterator<Item = &'a str>, env: impl Fn(&str) -> Option<String>) -> Vec<&'a str> {
+    vars.into_iter().filter(|v| env(v).is_none_or(|val| val.is_empty())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_extract_from_body() {
+        let md = r#"# Notion
+
+```bash
+curl -H "Authorization: Bearer $NOTION_TOKEN" https://api.notion.com
+echo ${NOTION_DB_ID} $HOME $PATH $1 $FILE
+echo $TOKEN ${PASSWORD}
+```
+
+```python
+key = os.environ["OPENAI_API_KEY"]
+other = os.getenv('SLACK_WEBHOOK')
+```
+
+Node: `process.env.GITHUB_TOKEN`
+"#;
+        let names: Vec<_> = extract_env_vars(md).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["GITHUB_TOKEN", "NOTION_DB_ID", "NOTION_TOKEN", "OPENAI_API_KEY", "PASSWORD", "SLACK_WEBHOOK", "TOKEN"]);
+    }
+
+    #[test]
+    fn test_extract_from_frontmatter() {
+        let md = r#"---
+name: notion
+metadata: {"openclaw": {"requires": {"bins": ["curl"], "env": ["NOTION_API_KEY"]}}}
+env:
+  NOTION_WORKSPACE: optional workspace id
+---
+Uses $NOTION_API_KEY and $EXTRA_VAR.
+"#;
+        let vars = extract_env_vars(md);
+        assert_eq!(
+            vars,
+            vec![
+                ("EXTRA_VAR".to_string(), EnvVarSource::Body),
+                ("NOTION_API_KEY".to_string(), EnvVarSource::Frontmatter),
+                ("NOTION_WORKSPACE".to_string(), EnvVarSource::Frontmatter),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_frontmatter_still_scans_body() {
+        let md = "---\nname: [unclosed\n---\nexport API_TOKEN=$MY_TOKEN\n";
+        let names: Vec<_> = extract_env_vars(md).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["MY_TOKEN"]);
+    }
+
+    #[test]
+    fn test_unset_vars() {
+        let env: HashMap<&str, &str> = [("SET_VAR", "1"), ("EMPTY_VAR", "")].into_iter().collect();
+        let unset = unset_vars(["SET_VAR", "EMPTY_VAR", "UNSET_VAR"], |v| env.get(v).map(|val| val.to_string()));
+        assert_eq!(unset, vec!["EMPTY_VAR", "UNSET_VAR"]);
+    }
+}
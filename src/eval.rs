@@ -0,0 +1,188 @@
+use crate::db::Database;
+use crate::index::{IndexFilters, SearchIndex};
+use crate::output::SCHEMA_VERSION;
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Judgments shipped with the binary
+pub const BUNDLED_JUDGMENTS: &str = include_str!("../evals/relevance.toml");
+
+/// File in the data directory whose judgments are added to the bundled ones
+pub const USER_JUDGMENTS_FILE: &str = "relevance.toml";
+
+pub const DEFAULT_K: usize = 10;
+
+/// A query and the skills a good ranking returns for it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Judgment {
+    pub query: String,
+    /// `registry:slug`, or a bare slug matching any registry
+    pub expected: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JudgmentFile {
+    #[serde(default)]
+    judgment: Vec<Judgment>,
+}
+
+pub fn parse_judgments(content: &str) -> Result<Vec<Judgment>> {
+    Ok(toml::from_str::<JudgmentFile>(content)?.judgment)
+}
+
+/// The bundled judgments followed by those in each file
+pub fn load_judgments(files: &[&Path]) -> Result<Vec<Judgment>> {
+    let mut judgments = parse_judgments(BUNDLED_JUDGMENTS).context("bundled relevance judgments")?;
+    for path in files {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        judgments.extend(parse_judgments(&content).with_context(|| format!("Invalid judgments in {:?}", path))?);
+    }
+    Ok(judgments)
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct QueryEval {
+    pub query: String,
+    pub precision: f64,
+    pub ndcg: f64,
+    pub reciprocal_rank: f64,
+    /// `registry:slug` of the relevant skills, with their 1-based rank in
+    /// the top k (`None` when they didn't make it)
+    pub relevant: Vec<(String, Option<usize>)>,
+}
+
+/// Result of `eval`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EvalReport {
+    pub schema_version: u32,
+    pub k: usize,
+    /// Mean over evaluated queries
+    pub precision: f64,
+    pub ndcg: f64,
+    pub mrr: f64,
+    pub queries: Vec<QueryEval>,
+    /// Queries left out because none of their skills are in the catalog
+    pub skipped: Vec<String>,
+}
+
+fn matches(expected: &str, registry: &str, slug: &str) -> bool {
+    match expected.split_once(':') {
+        Some((r, s)) => r.eq_ignore_ascii_case(registry) && s.eq_ignore_ascii_case(slug),
+        None => expected.eq_ignore_ascii_case(slug),
+    }
+}
+
+/// Run every judgment against the index, ranked as `search` ranks them
+/// before its filters
+pub fn evaluate(index: &SearchIndex, db: &Database, judgments: &[Judgment], k: usize) -> Result<EvalReport> {
+    let catalog: Vec<_> = db.get_all_skills()?.into_iter().map(|s| (s.registry, s.slug)).collect();
+    let superseded = db.get_superseded()?;
+    let mut queries = Vec::new();
+    let mut skipped = Vec::new();
+
+    for judgment in judgments {
+        let relevant: HashSet<_> = catalog
+            .iter()
+            .filter(|(r, s)| !superseded.contains(&(r.clone(), s.clone())))
+            .filter(|(r, s)| judgment.expected.iter().any(|e| matches(e, r, s)))
+            .cloned()
+            .collect();
+        if relevant.is_empty() {
+            skipped.push(judgment.query.clone());
+            continue;
+        }
+
+        let ranked: Vec<_> = index
+            .search(&judgment.query, k * 2, &IndexFilters::default())?
+            .into_iter()
+            .map(|r| (r.registry, r.slug))
+            .filter(|key| !superseded.contains(key))
+            .take(k)
+            .collect();
+        let hits: Vec<bool> = ranked.iter().map(|key| relevant.contains(key)).collect();
+
+        let mut found: Vec<_> = relevant
+            .iter()
+            .map(|key| (format!("{}:{}", key.0, key.1), ranked.iter().position(|r| r == key).map(|p| p + 1)))
+            .collect();
+        found.sort_by(|a, b| (a.1.is_none(), a.1, &a.0).cmp(&(b.1.is_none(), b.1, &b.0)));
+
+        queries.push(QueryEval {
+            query: judgment.query.clone(),
+            precision: precision_at_k(&hits, k),
+            ndcg: ndcg_at_k(&hits, relevant.len(), k),
+            reciprocal_rank: hits.iter().position(|h| *h).map(|p| 1.0 / (p + 1) as f64).unwrap_or(0.0),
+            relevant: found,
+        });
+    }
+
+    let mean = |f: fn(&QueryEval) -> f64| {
+        if queries.is_empty() {
+            0.0
+        } else {
+            queries.iter().map(f).sum::<f64>() / queries.len() as f64
+        }
+    };
+    Ok(EvalReport {
+        schema_version: SCHEMA_VERSION,
+        k,
+        precision: mean(|q| q.precision),
+        ndcg: mean(|q| q.ndcg),
+        mrr: mean(|q| q.reciprocal_rank),
+        queries,
+        skipped,
+    })
+}
+
+/// Share of the top k that is relevant
+pub fn precision_at_k(hits: &[bool], k: usize) -> f64 {
+    if k == 0 {
+        return 0.0;
+    }
+    hits.iter().take(k).filter(|h| **h).count() as f64 / k as f64
+}
+
+/// Binary-relevance NDCG: 1.0 when every relevant skill (up to k) leads
+/// the ranking
+pub fn ndcg_at_k(hits: &[bool], relevant: usize, k: usize) -> f64 {
+    let discount = |i: usize| 1.0 / ((i + 2) as f64).log2();
+    let dcg: f64 = hits.iter().take(k).enumerate().filter(|(_, h)| **h).map(|(i, _)| discount(i)).sum();
+    let ideal: f64 = (0..relevant.min(k)).map(discount).sum();
+    if ideal == 0.0 {
+        0.0
+    } else {
+        dcg / ideal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_judgments_parse() {
+        let judgments = parse_judgments(BUNDLED_JUDGMENTS).unwrap();
+        assert!(judgments.len() >= 20);
+        assert!(judgments.iter().all(|j| !j.query.is_empty() && !j.expected.is_empty()));
+    }
+
+    #[test]
+    fn test_metrics() {
+        assert_eq!(precision_at_k(&[true, false, true, false], 4), 0.5);
+        assert_eq!(ndcg_at_k(&[true, true, false], 2, 3), 1.0);
+        assert_eq!(ndcg_at_k(&[false, false, false], 1, 3), 0.0);
+        // One relevant skill at rank 2
+        let ndcg = ndcg_at_k(&[false, true], 1, 10);
+        assert!((ndcg - 1.0 / 3f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("pdf", "openai", "pdf"));
+        assert!(matches("Anthropic:PDF", "anthropic", "pdf"));
+        assert!(!matches("anthropic:pdf", "openai", "pdf"));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::db::{Database, Skill};
+use crate::github::{self, Channel};
+use crate::policy::{DecisionSet, Policy};
+use crate::quality::QualityScores;
+use anyhow::Result;
+use serde::Serialize;
+
+/// One skill of `export`, flat so it reads the same as a JSON line, a CSV
+/// row, or a Markdown table row
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRow {
+    pub registry: String,
+    pub slug: String,
+    pub name: String,
+    pub channel: Channel,
+    pub description: String,
+    pub github_url: String,
+    pub version: Option<String>,
+    pub trusted: bool,
+    pub quality_score: i64,
+    pub stars: i64,
+    pub downloads: i64,
+    pub installs: i64,
+    /// Prompt-injection risk, 0-100
+    pub injection_risk: i64,
+    /// Unix time of the last upstream change, when known
+    pub upstream_updated_at: Option<i64>,
+}
+
+impl ExportRow {
+    pub fn new(skill: Skill, quality_score: i64, trusted: bool, injection_risk: i64) -> Self {
+        Self {
+            channel: github::channel_of(&skill.registry),
+            registry: skill.registry,
+            slug: skill.slug,
+            name: skill.name,
+            description: skill.description,
+            github_url: skill.github_url,
+            version: skill.version,
+            trusted,
+            quality_score,
+            stars: skill.stars,
+            downloads: skill.downloads,
+            installs: skill.installs,
+            injection_risk,
+            upstream_updated_at: skill.upstream_updated_at,
+        }
+    }
+
+    fn cells(&self) -> [String; 14] {
+        let optional = |v: Option<String>| v.unwrap_or_default();
+        [
+            self.registry.clone(),
+            self.slug.clone(),
+            self.name.clone(),
+            self.channel.as_str().to_string(),
+            self.description.clone(),
+            self.github_url.clone(),
+            optional(self.version.clone()),
+            self.trusted.to_string(),
+            self.quality_score.to_string(),
+            self.stars.to_string(),
+            self.downloads.to_string(),
+            self.installs.to_string(),
+            self.injection_risk.to_string(),
+            optional(self.upstream_updated_at.map(|t| t.to_string())),
+        ]
+    }
+}
+
+const COLUMNS: [&str; 14] = [
+    "registry",
+    "slug",
+    "name",
+    "channel",
+    "description",
+    "github_url",
+    "version",
+    "trusted",
+    "quality_score",
+    "stars",
+    "downloads",
+    "installs",
+    "injection_risk",
+    "upstream_updated_at",
+];
+
+/// What `export` writes: the catalog, or one `registry` asked for by name
+/// (which overrides an unenforced allow list), less superseded and denied
+/// skills and those under `min_score`, in (registry, slug) order
+pub fn rows(db: &Database, quality: &QualityScores, decisions: &DecisionSet, rules: &Policy, registry: Option<&str>, trusted: bool, min_score: i64) -> Result<Vec<ExportRow>> {
+    let superseded = db.get_superseded()?;
+    let skills = match registry {
+        Some(registry) => db.get_skills_by_registry(registry)?,
+        None => db.get_all_skills()?,
+    };
+    let mut rows: Vec<ExportRow> = skills
+        .into_iter()
+        .filter(|s| !superseded.contains(&(s.registry.clone(), s.slug.clone())))
+        .filter(|s| !decisions.is_denied(&s.registry, &s.slug))
+        .filter(|s| registry.is_some() || rules.allows_registry(&s.registry))
+        .filter_map(|s| {
+            let (quality_score, is_trusted) = (quality.score_for(&s), decisions.is_trusted(&s));
+            let (injection_risk, _) = db.get_injection_risk(&s.registry, &s.slug).unwrap_or_default();
+            (quality_score >= min_score && (!trusted || is_trusted)).then(|| ExportRow::new(s, quality_score, is_trusted, injection_risk))
+        })
+        .collect();
+    rows.sort_by(|a, b| (&a.registry, &a.slug).cmp(&(&b.registry, &b.slug)));
+    Ok(rows)
+}
+
+/// A CSV field, quoted only when it has to be (RFC 4180)
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// One JSON object per line
+pub fn to_jsonl(rows: &[ExportRow]) -> serde_json::Result<String> {
+    rows.iter().map(|r| serde_json::to_string(r).map(|line| line + "\n")).collect()
+}
+
+/// RFC 4180 CSV, one line per skill after a header
+pub fn to_csv(rows: &[ExportRow]) -> String {
+    let mut out = COLUMNS.join(",") + "\n";
+    for row in rows {
+        out.push_str(&row.cells().map(|c| csv_field(&c)).join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// A Markdown table, one row per skill
+pub fn to_markdown(rows: &[ExportRow]) -> String {
+    // A pipe would end the cell, and a line break the row
+    let cell = |s: &str| s.replace('|', "\\|").split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut out = format!("| {} |\n|{}\n", COLUMNS.join(" | "), "---|".repeat(COLUMNS.len()));
+    for row in rows {
+        out.push_str(&format!("| {} |\n", row.cells().map(|c| cell(&c)).join(" | ")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(slug: &str, description: &str) -> ExportRow {
+        ExportRow {
+            registry: "anthropic".to_string(),
+            slug: slug.to_string(),
+            name: slug.to_uppercase(),
+            channel: Channel::Curated,
+            description: description.to_string(),
+            github_url: format!("https://github.com/anthropics/skills/tree/main/skills/{}", slug),
+            version: None,
+            trusted: true,
+            quality_score: 92,
+            stars: 1200,
+            downloads: 0,
+            installs: 0,
+            injection_risk: 5,
+            upstream_updated_at: Some(1_700_000_000),
+        }
+    }
+
+    #[test]
+    fn test_formats() {
+        let rows = [row("pdf", "Fill, merge, and \"split\" PDFs"), row("xlsx", "Spreadsheets | formulas\nand charts")];
+
+        let csv = to_csv(&rows);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], COLUMNS.join(","));
+        assert!(lines[1].starts_with("anthropic,pdf,PDF,curated,\"Fill, merge, and \"\"split\"\" PDFs\","));
+        assert!(lines[1].ends_with(",,true,92,1200,0,0,5,1700000000"));
+
+        let markdown = to_markdown(&rows);
+        assert_eq!(markdown.lines().count(), 4);
+        assert!(markdown.contains("| Spreadsheets \\| formulas and charts |"));
+
+        let jsonl = to_jsonl(&rows).unwrap();
+        let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(first["quality_score"], 92);
+        assert_eq!(first["channel"], "curated");
+        assert_eq!(first["version"], serde_json::Value::Null);
+    }
+}
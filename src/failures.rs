@@ -0,0 +1,179 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Failed syncs in a row after which a registry's failures count as chronic
+pub const CHRONIC_AFTER: i64 = 3;
+
+/// Failures kept per registry; older ones are dropped
+pub const MAX_FAILURES_PER_REGISTRY: i64 = 20;
+
+/// Broad cause of a sync failure, so recurring problems can be told apart
+/// from one-off blips
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureKind {
+    /// DNS, connection, TLS, timeouts, server errors
+    Network,
+    /// Rejected credentials or missing access
+    Auth,
+    /// git itself failed (diverged history, corrupt clone, ...)
+    Git,
+    /// A response or file that couldn't be understood
+    Parse,
+    /// Full disk or unwritable data directory
+    Disk,
+    Other,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Network => "network",
+            FailureKind::Auth => "auth",
+            FailureKind::Git => "git",
+            FailureKind::Parse => "parse",
+            FailureKind::Disk => "disk",
+            FailureKind::Other => "other",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "network" => FailureKind::Network,
+            "auth" => FailureKind::Auth,
+            "git" => FailureKind::Git,
+            "parse" => FailureKind::Parse,
+            "disk" => FailureKind::Disk,
+            _ => FailureKind::Other,
+        }
+    }
+
+    /// What to check when this kind of failure keeps happening
+    pub fn hint(&self) -> &'static str {
+        match self {
+            FailureKind::Network => "check your connection, DNS, and proxy settings",
+            FailureKind::Auth => "check git credentials and API tokens for this registry",
+            FailureKind::Git => "try `sync --force`, or delete the registry's clone under repos/",
+            FailureKind::Parse => "the registry may have changed format; try updating safe-skill-search",
+            FailureKind::Disk => "free up space or fix permissions in the data directory",
+            FailureKind::Other => "run `sync -v` for details",
+        }
+    }
+}
+
+// Matched case-insensitively against the whole error chain, in order;
+// the first kind with a matching needle wins
+const PATTERNS: &[(FailureKind, &[&str])] = &[
+    (FailureKind::Disk, &["no space left", "not enough disk space", "disk is full", "disk full", "quota exceeded", "read-only file system"]),
+    (
+        FailureKind::Auth,
+        &[
+            "401 unauthorized",
+            "403 forbidden",
+            "returned error: 401",
+            "returned error: 403",
+            "authentication failed",
+            "permission denied (publickey)",
+            "terminal prompts disabled",
+            "could not read username",
+            "remote authentication required",
+        ],
+    ),
+    (
+        FailureKind::Network,
+        &[
+            "could not resolve host",
+            "failed to resolve address",
+            "dns error",
+            "failed to lookup address",
+            "connection refused",
+            "connection reset",
+            "timed out",
+            "network is unreachable",
+            "error sending request",
+            "tls handshake",
+            "ssl",
+            "certificate",
+            "rate limited",
+            "429 too many",
+            "500 internal",
+            "502 bad gateway",
+            "503 service",
+            "504 gateway",
+        ],
+    ),
+    (FailureKind::Parse, &["error decoding", "invalid type", "missing field", "expected value", "eof while parsing", "invalid utf-8", "yaml", "toml"]),
+    (FailureKind::Git, &["git ", "fatal:", "not a git repository", "diverg", "non-fast-forward"]),
+];
+
+/// Categorize a sync error from its type and message
+pub fn classify(err: &anyhow::Error) -> FailureKind {
+    for cause in err.chain() {
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            match io.kind() {
+                std::io::ErrorKind::PermissionDenied => return FailureKind::Disk,
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::ConnectionReset => {
+                    return FailureKind::Network
+                }
+                _ => {}
+            }
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if let Some(status) = e.status() {
+                if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+                    return FailureKind::Auth;
+                }
+            }
+            if e.is_decode() {
+                return FailureKind::Parse;
+            }
+            if e.is_connect() || e.is_timeout() || e.is_request() {
+                return FailureKind::Network;
+            }
+        }
+        if cause.is::<serde_json::Error>() || cause.is::<serde_yaml::Error>() || cause.is::<toml::de::Error>() {
+            return FailureKind::Parse;
+        }
+    }
+
+    let message = format!("{:#}", err).to_lowercase();
+    PATTERNS
+        .iter()
+        .find(|(_, needles)| needles.iter().any(|n| message.contains(n)))
+        .map(|(kind, _)| *kind)
+        .unwrap_or(FailureKind::Other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_messages() {
+        let kind = |msg: &str| classify(&anyhow::anyhow!(msg.to_string()));
+        assert_eq!(kind("git clone failed: fatal: unable to access 'https://github.com/x/y.git/': Could not resolve host: github.com"), FailureKind::Network);
+        assert_eq!(kind("git clone failed: fatal: could not read Username for 'https://github.com': terminal prompts disabled"), FailureKind::Auth);
+        assert_eq!(kind("Clawdhub API error from https://clawhub.com: 401 Unauthorized"), FailureKind::Auth);
+        assert_eq!(kind("git clone failed: fatal: write error: No space left on device"), FailureKind::Disk);
+        assert_eq!(kind("git pull failed: fatal: Not possible to fast-forward, aborting."), FailureKind::Git);
+        assert_eq!(kind("git clone failed: failed to resolve address for github.com: Name or service not known"), FailureKind::Network);
+        assert_eq!(kind("git clone failed: remote authentication required but no callback set"), FailureKind::Auth);
+        assert_eq!(kind("Skills directory not found"), FailureKind::Other);
+    }
+
+    #[test]
+    fn test_classify_error_types() {
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "nope");
+        assert_eq!(classify(&anyhow::Error::from(io).context("writing clone")), FailureKind::Disk);
+
+        let json = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert_eq!(classify(&anyhow::Error::from(json)), FailureKind::Parse);
+    }
+
+    #[test]
+    fn test_kind_round_trip() {
+        for kind in [FailureKind::Network, FailureKind::Auth, FailureKind::Git, FailureKind::Parse, FailureKind::Disk, FailureKind::Other] {
+            assert_eq!(FailureKind::parse(kind.as_str()), kind);
+        }
+    }
+}
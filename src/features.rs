@@ -0,0 +1,58 @@
+use anyhow::Result;
+
+/// Optional cargo features and whether this build has them. A minimal CLI
+/// build (`--no-default-features`) keeps search, show, and install.
+pub const FEATURES: &[(&str, bool)] = &[
+    ("server", cfg!(feature = "server")),
+    ("tui", cfg!(feature = "tui")),
+    ("clipboard", cfg!(feature = "clipboard")),
+    ("embeddings", cfg!(feature = "embeddings")),
+    ("git2", cfg!(feature = "git2")),
+];
+
+pub fn enabled(feature: &str) -> bool {
+    FEATURES.iter().any(|(name, on)| *name == feature && *on)
+}
+
+/// Fail with a rebuild hint when `command` needs a feature this binary was
+/// compiled without
+pub fn require(feature: &str, command: &str) -> Result<()> {
+    if enabled(feature) {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "`{}` needs the `{}` feature, which this build doesn't include. Reinstall with:\n  cargo install safe-skill-search --features {}",
+        command,
+        feature,
+        feature
+    )
+}
+
+/// Comma-separated list of compiled-in features, or "none"
+pub fn summary() -> String {
+    let on: Vec<_> = FEATURES.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+    if on.is_empty() {
+        "none".to_string()
+    } else {
+        on.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_feature_is_disabled() {
+        assert!(!enabled("quantum"));
+        let err = require("quantum", "teleport").unwrap_err().to_string();
+        assert!(err.contains("`teleport` needs the `quantum` feature"));
+        assert!(err.contains("--features quantum"));
+    }
+
+    #[test]
+    fn test_summary_matches_build() {
+        assert_eq!(enabled("server"), cfg!(feature = "server"));
+        assert_eq!(summary() == "none", FEATURES.iter().all(|(_, on)| !on));
+    }
+}
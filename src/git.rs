@@ -0,0 +1,383 @@
+use anyhow::Result;
+use std::path::Path;
+
+#[cfg(feature = "git2")]
+pub use libgit2::*;
+
+#[cfg(not(feature = "git2"))]
+pub use cli::*;
+
+/// How much of a registry repo to download
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CloneOptions {
+    /// Commits of history to fetch; all of them when None
+    pub depth: Option<u32>,
+    /// Fetch only the default branch
+    pub single_branch: bool,
+    /// Skip blobs of old file versions (`--filter=blob:none`), fetching
+    /// them on demand
+    pub partial: bool,
+}
+
+impl Default for CloneOptions {
+    fn default() -> Self {
+        Self { depth: None, single_branch: false, partial: true }
+    }
+}
+
+/// Git operations through libgit2, for machines without the `git` binary.
+/// libgit2 can't make partial or single-branch clones, so registry clones
+/// download every blob of every branch (up to `depth` commits), and sparse
+/// checkouts fetch the whole shallow tree but only write the requested path.
+#[cfg(feature = "git2")]
+mod libgit2 {
+    use super::*;
+    use git2::build::{CheckoutBuilder, RepoBuilder};
+    use git2::{DiffOptions, FetchOptions, Repository, Sort, TreeWalkMode, TreeWalkResult};
+
+    /// One commit deep, except from local paths: libgit2's local transport
+    /// can't fetch shallow
+    fn shallow_fetch_options(url: &str) -> FetchOptions<'static> {
+        let mut options = FetchOptions::new();
+        if !url.starts_with("file://") && !Path::new(url).exists() {
+            options.depth(1);
+        }
+        options
+    }
+
+    /// What does the git work, for `doctor`
+    pub fn backend() -> Result<String> {
+        let (major, minor, patch) = git2::Version::get().libgit2_version();
+        Ok(format!("libgit2 {}.{}.{} (no git binary needed)", major, minor, patch))
+    }
+
+    /// Full clone, or `options.depth` commits deep: commit history (for
+    /// skill dates) and every blob
+    pub fn clone(url: &str, dest: &Path, options: &CloneOptions) -> Result<()> {
+        let mut builder = RepoBuilder::new();
+        // As for shallow fetches, local paths get the whole history
+        if let Some(depth) = options.depth.filter(|_| !url.starts_with("file://") && !Path::new(url).exists()) {
+            let mut fetch_options = FetchOptions::new();
+            fetch_options.depth(depth as i32);
+            builder.fetch_options(fetch_options);
+        }
+        builder
+            .clone(url, dest)
+            .map_err(|e| anyhow::anyhow!("git clone failed: {}", e.message()))?;
+        Ok(())
+    }
+
+    /// Fetch `origin` and fast-forward the checked-out branch to it
+    pub fn pull(repo_dir: &Path) -> Result<()> {
+        let repo = Repository::open(repo_dir)?;
+        // Blobless clones made by the git binary are missing blobs libgit2
+        // can't fetch on demand
+        if repo.config()?.get_bool("remote.origin.promisor").unwrap_or(false) {
+            anyhow::bail!("git pull failed: libgit2 can't update a partial clone");
+        }
+        let head = repo.head()?;
+        let branch = head.shorthand().ok_or_else(|| anyhow::anyhow!("HEAD is not on a branch"))?.to_string();
+        repo.find_remote("origin")?
+            .fetch(&[branch.as_str()], None, None)
+            .map_err(|e| anyhow::anyhow!("git fetch failed: {}", e.message()))?;
+
+        let fetched = repo.reference_to_annotated_commit(&repo.find_reference("FETCH_HEAD")?)?;
+        let (analysis, _) = repo.merge_analysis(&[&fetched])?;
+        if analysis.is_up_to_date() {
+            return Ok(());
+        }
+        if !analysis.is_fast_forward() {
+            anyhow::bail!("git pull failed: {} has diverged from origin (not a fast-forward)", branch);
+        }
+        let refname = head.name().ok_or_else(|| anyhow::anyhow!("HEAD has no name"))?.to_string();
+        repo.find_reference(&refname)?.set_target(fetched.id(), "pull: fast-forward")?;
+        repo.set_head(&refname)?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        Ok(())
+    }
+
+    pub fn resolve_commit(repo_dir: &Path, rev: &str) -> Result<Option<String>> {
+        let repo = Repository::open(repo_dir)?;
+        let commit = repo.revparse_single(rev).and_then(|o| o.peel_to_commit());
+        Ok(commit.ok().map(|c| c.id().to_string()))
+    }
+
+    /// Fetch `rev` from `origin` (one commit deep), leaving it at FETCH_HEAD
+    pub fn fetch(repo_dir: &Path, rev: &str) -> Result<()> {
+        let repo = Repository::open(repo_dir)?;
+        let mut remote = repo.find_remote("origin")?;
+        let mut options = shallow_fetch_options(remote.url().unwrap_or_default());
+        remote
+            .fetch(&[rev], Some(&mut options), None)
+            .map_err(|e| anyhow::anyhow!("git fetch of revision {} failed: {}", rev, e.message()))?;
+        Ok(())
+    }
+
+    /// Repo-relative name and content of every file under `path` at `rev`
+    pub fn tree_files(repo_dir: &Path, rev: &str, path: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let repo = Repository::open(repo_dir)?;
+        let tree = repo.revparse_single(rev)?.peel_to_tree()?;
+        let Ok(entry) = tree.get_path(Path::new(path)) else {
+            return Ok(Vec::new());
+        };
+        let subtree = match entry.to_object(&repo)?.into_tree() {
+            Ok(subtree) => subtree,
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let mut blobs = Vec::new();
+        subtree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                blobs.push((format!("{}/{}{}", path.trim_end_matches('/'), root, entry.name().unwrap_or_default()), entry.id()));
+            }
+            TreeWalkResult::Ok
+        })?;
+        blobs
+            .into_iter()
+            .map(|(name, id)| Ok((name, repo.find_blob(id)?.content().to_vec())))
+            .collect()
+    }
+
+    /// Commit time and changed files under `path` for each commit touching
+    /// it, newest first, like `git log --name-only` (merges list no files)
+    pub fn log_paths(repo_dir: &Path, path: &str) -> Result<Vec<(i64, Vec<String>)>> {
+        let repo = Repository::open(repo_dir)?;
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(Sort::TIME)?;
+        walk.push_head()?;
+
+        let mut log = Vec::new();
+        for id in walk {
+            let commit = repo.find_commit(id?)?;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let parent_tree = match commit.parent_count() {
+                0 => None,
+                _ => Some(commit.parent(0)?.tree()?),
+            };
+            let mut options = DiffOptions::new();
+            options.pathspec(path);
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut options))?;
+            let files: Vec<String> = diff
+                .deltas()
+                .filter_map(|d| d.new_file().path().or(d.old_file().path()).map(|p| p.to_string_lossy().to_string()))
+                .collect();
+            if !files.is_empty() {
+                log.push((commit.time().seconds(), files));
+            }
+        }
+        Ok(log)
+    }
+
+    /// Shallow clone of `url` at `git_ref` into `dest`, writing only the
+    /// files under `path`
+    pub fn sparse_clone(url: &str, git_ref: &str, path: &str, dest: &Path) -> Result<()> {
+        let repo = Repository::init(dest)?;
+        let mut options = shallow_fetch_options(url);
+        let refspecs = [format!("refs/heads/{}", git_ref), format!("refs/tags/{}", git_ref)];
+        repo.remote("origin", url)?
+            .fetch(&refspecs, Some(&mut options), None)
+            .map_err(|e| anyhow::anyhow!("git clone failed: {}", e.message()))?;
+
+        let commit = repo
+            .find_reference("FETCH_HEAD")
+            .and_then(|r| r.peel_to_commit())
+            .map_err(|_| anyhow::anyhow!("git clone failed: {} has no branch or tag {}", url, git_ref))?;
+        repo.set_head_detached(commit.id())?;
+        repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force().path(path)))?;
+        Ok(())
+    }
+}
+
+/// Git operations through the `git` binary
+#[cfg(not(feature = "git2"))]
+mod cli {
+    use super::*;
+    use std::process::Command;
+
+    fn run(dir: &Path, args: &[&str]) -> Result<std::process::Output> {
+        Ok(Command::new("git").args(args).current_dir(dir).output()?)
+    }
+
+    /// What does the git work, for `doctor`
+    pub fn backend() -> Result<String> {
+        let output = Command::new("git")
+            .arg("--version")
+            .output()
+            .map_err(|_| anyhow::anyhow!("git not found: registries can't be cloned or updated"))?;
+        if !output.status.success() {
+            anyhow::bail!("git --version failed: registries can't be cloned or updated");
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// By default a blobless partial clone: full commit history (for skill
+    /// dates) without downloading every old version of every file
+    pub fn clone(url: &str, dest: &Path, options: &CloneOptions) -> Result<()> {
+        let mut args = vec!["clone".to_string(), "-q".to_string()];
+        if options.partial {
+            args.push("--filter=blob:none".to_string());
+        }
+        if let Some(depth) = options.depth {
+            args.push(format!("--depth={}", depth));
+        }
+        if options.single_branch {
+            args.push("--single-branch".to_string());
+        }
+        // No credential prompts: a sync shouldn't hang waiting for input
+        let output = Command::new("git")
+            .args(&args)
+            .arg(url)
+            .arg(dest)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("git clone failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+
+    /// Fetch `origin` and fast-forward the checked-out branch to it
+    pub fn pull(repo_dir: &Path) -> Result<()> {
+        let output = Command::new("git")
+            .args(["pull", "--ff-only", "-q"])
+            .current_dir(repo_dir)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("git pull failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+
+    pub fn resolve_commit(repo_dir: &Path, rev: &str) -> Result<Option<String>> {
+        let output = run(repo_dir, &["rev-parse", "--verify", "-q", &format!("{}^{{commit}}", rev)])?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
+    }
+
+    /// Fetch `rev` from `origin` (one commit deep), leaving it at FETCH_HEAD
+    pub fn fetch(repo_dir: &Path, rev: &str) -> Result<()> {
+        let status = Command::new("git")
+            .args(["fetch", "--depth", "1", "-q", "origin", rev])
+            .current_dir(repo_dir)
+            .status()?;
+        if !status.success() {
+            anyhow::bail!("git fetch of revision {} failed", rev);
+        }
+        Ok(())
+    }
+
+    /// Repo-relative name and content of every file under `path` at `rev`
+    pub fn tree_files(repo_dir: &Path, rev: &str, path: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let output = run(repo_dir, &["ls-tree", "-r", "-z", "--name-only", rev, "--", path])?;
+        if !output.status.success() {
+            anyhow::bail!("git ls-tree failed for {} at {}", path, rev);
+        }
+
+        let mut files = Vec::new();
+        for name in output.stdout.split(|b| *b == 0).filter(|n| !n.is_empty()) {
+            let name = std::str::from_utf8(name)?;
+            let blob = run(repo_dir, &["show", &format!("{}:{}", rev, name)])?;
+            if !blob.status.success() {
+                anyhow::bail!("git show failed for {} at {}", name, rev);
+            }
+            files.push((name.to_string(), blob.stdout));
+        }
+        Ok(files)
+    }
+
+    /// Commit time and changed files under `path` for each commit touching
+    /// it, newest first, from a single `git log` pass
+    pub fn log_paths(repo_dir: &Path, path: &str) -> Result<Vec<(i64, Vec<String>)>> {
+        // --no-renames: rename detection would fetch old blobs in a partial clone
+        let output = run(repo_dir, &["log", "--no-renames", "--name-only", "--format=%x01%ct", "--", path])?;
+        if !output.status.success() {
+            anyhow::bail!("git log failed in {:?}", repo_dir);
+        }
+
+        let mut log: Vec<(i64, Vec<String>)> = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if let Some(ts) = line.strip_prefix('\u{1}') {
+                log.push((ts.trim().parse().unwrap_or(0), Vec::new()));
+            } else if let Some((_, files)) = log.last_mut().filter(|_| !line.is_empty()) {
+                files.push(line.to_string());
+            }
+        }
+        Ok(log)
+    }
+
+    /// Shallow, sparse clone of `url` at `git_ref` into `dest` with only
+    /// `path` checked out
+    pub fn sparse_clone(url: &str, git_ref: &str, path: &str, dest: &Path) -> Result<()> {
+        let output = Command::new("git")
+            .args(["clone", "--depth", "1", "--filter=blob:none", "--sparse", "-q", "--branch", git_ref, url])
+            .arg(dest)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("git clone failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        let output = run(dest, &["sparse-checkout", "set", "--", path])?;
+        if !output.status.success() {
+            anyhow::bail!("git sparse-checkout failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_clone_and_fast_forward_pull() {
+        let dir = tempfile::tempdir().unwrap();
+        let upstream = dir.path().join("upstream");
+        std::fs::create_dir_all(upstream.join("skills/pdf")).unwrap();
+        std::fs::write(upstream.join("skills/pdf/SKILL.md"), "v1").unwrap();
+        git(&upstream, &["init", "-q", "-b", "main"]);
+        git(&upstream, &["add", "-A"]);
+        git(&upstream, &["commit", "-q", "-m", "v1"]);
+
+        let url = format!("file://{}", upstream.display());
+        let clone_dir = dir.path().join("clone");
+        clone(&url, &clone_dir, &CloneOptions::default()).unwrap();
+        assert_eq!(std::fs::read_to_string(clone_dir.join("skills/pdf/SKILL.md")).unwrap(), "v1");
+
+        std::fs::write(upstream.join("skills/pdf/SKILL.md"), "v2").unwrap();
+        std::fs::write(upstream.join("README.md"), "readme").unwrap();
+        git(&upstream, &["add", "-A"]);
+        git(&upstream, &["commit", "-q", "-m", "v2"]);
+        pull(&clone_dir).unwrap();
+        assert_eq!(std::fs::read_to_string(clone_dir.join("skills/pdf/SKILL.md")).unwrap(), "v2");
+        assert_eq!(resolve_commit(&clone_dir, "HEAD").unwrap(), resolve_commit(&upstream, "HEAD").unwrap());
+        assert_eq!(resolve_commit(&clone_dir, "no-such-branch").unwrap(), None);
+
+        let log = log_paths(&clone_dir, "skills").unwrap();
+        assert_eq!(log.len(), 2);
+        assert!(log.iter().all(|(_, files)| files == &["skills/pdf/SKILL.md"]));
+
+        let files = tree_files(&clone_dir, "HEAD~1", "skills/pdf").unwrap();
+        assert_eq!(files, [("skills/pdf/SKILL.md".to_string(), b"v1".to_vec())]);
+
+        // A local commit upstream doesn't have can't be fast-forwarded
+        git(&clone_dir, &["commit", "-q", "--allow-empty", "-m", "local"]);
+        git(&upstream, &["commit", "-q", "--allow-empty", "-m", "v3"]);
+        assert!(pull(&clone_dir).is_err());
+    }
+}
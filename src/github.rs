@@ -1,162 +1,618 @@
+use crate::catalog;
+use crate::clawdhub::ClawdhubClient;
+use crate::config::{registry_enabled, RegistryConfig};
 use crate::db::{Database, Skill};
+use crate::diskspace::MB;
+use crate::failures;
+use crate::git;
+use crate::text::split_frontmatter;
 use anyhow::Result;
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::path::Path;
-use std::process::Command;
-use std::time::{SystemTime, UNIX_EPOCH};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
+/// A git repository skills are synced from. Several registries can read
+/// different paths of the same clone.
+#[derive(Debug, Clone)]
+pub struct SourceRepo {
+    /// Clone directory under `repos/`
+    pub name: &'static str,
+    pub url: &'static str,
+    /// Prefix for links to files in the repo
+    pub web_base: &'static str,
+    /// Rough size of a fresh clone, for the disk space check
+    pub clone_size_estimate: u64,
+}
+
+/// How much review a registry's skills get before they're published
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Maintained or reviewed by the publisher
+    Curated,
+    /// Published by the maintainer as not yet curated
+    Experimental,
+    /// Anyone can publish
+    Community,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Curated => "curated",
+            Channel::Experimental => "experimental",
+            Channel::Community => "community",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "curated" => Some(Channel::Curated),
+            "experimental" => Some(Channel::Experimental),
+            "community" => Some(Channel::Community),
+            _ => None,
+        }
+    }
+}
+
+/// A view of skills in one path of a source repo
 #[derive(Debug, Clone)]
 pub struct Registry {
     pub name: &'static str,
-    pub repo_url: &'static str,
+    pub repo: &'static SourceRepo,
     pub skills_path: &'static str,
     pub trusted: bool,
+    pub channel: Channel,
+    /// Base URL of a clawdhub-compatible API serving usage counts
+    pub api_base: Option<&'static str>,
 }
 
+impl Registry {
+    /// API base URL, overridable with `SKILL_SEARCH_<NAME>_API_URL` (e.g.
+    /// `SKILL_SEARCH_CLAWDHUB_API_URL`) for mirrors and staging
+    pub fn api_base_url(&self) -> Option<String> {
+        let var = format!("SKILL_SEARCH_{}_API_URL", self.name.to_uppercase().replace('-', "_"));
+        match std::env::var(&var) {
+            Ok(url) if !url.trim().is_empty() => Some(url.trim().trim_end_matches('/').to_string()),
+            _ => self.api_base.map(String::from),
+        }
+    }
+}
+
+pub const SOURCE_REPOS: &[SourceRepo] = &[CLAWDHUB_REPO, ANTHROPIC_REPO, OPENAI_REPO, JO_REPO];
+
+const CLAWDHUB_REPO: SourceRepo = SourceRepo {
+    name: "clawdhub",
+    url: "https://github.com/openclaw/skills.git",
+    web_base: "https://github.com/openclaw/skills/tree/main",
+    clone_size_estimate: 600 * MB,
+};
+
+const ANTHROPIC_REPO: SourceRepo = SourceRepo {
+    name: "anthropic",
+    url: "https://github.com/anthropics/skills.git",
+    web_base: "https://github.com/anthropics/skills/tree/main",
+    clone_size_estimate: 40 * MB,
+};
+
+const OPENAI_REPO: SourceRepo = SourceRepo {
+    name: "openai",
+    url: "https://github.com/openai/skills.git",
+    web_base: "https://github.com/openai/skills/tree/main",
+    clone_size_estimate: 40 * MB,
+};
+
+const JO_REPO: SourceRepo = SourceRepo {
+    name: "jo",
+    url: "https://github.com/jo-inc/skills.git",
+    web_base: "https://github.com/jo-inc/skills/tree/master",
+    clone_size_estimate: 20 * MB,
+};
+
 pub const REGISTRIES: &[Registry] = &[
     Registry {
         name: "clawdhub",
-        repo_url: "https://github.com/openclaw/skills.git",
+        repo: &CLAWDHUB_REPO,
         skills_path: "skills",
         trusted: false, // Community skills, need individual verification
+        channel: Channel::Community,
+        api_base: Some("https://clawhub.com"),
     },
     Registry {
         name: "anthropic",
-        repo_url: "https://github.com/anthropics/skills.git",
+        repo: &ANTHROPIC_REPO,
         skills_path: "skills",
         trusted: true, // Official Anthropic skills
+        channel: Channel::Curated,
+        api_base: None,
     },
     Registry {
         name: "openai",
-        repo_url: "https://github.com/openai/skills.git",
+        repo: &OPENAI_REPO,
         skills_path: "skills/.curated",
         trusted: true, // Official OpenAI curated skills
+        channel: Channel::Curated,
+        api_base: None,
     },
     Registry {
         name: "openai-experimental",
-        repo_url: "https://github.com/openai/skills.git",
+        repo: &OPENAI_REPO,
         skills_path: "skills/.experimental",
         trusted: false, // Experimental skills, not yet curated
+        channel: Channel::Experimental,
+        api_base: None,
     },
     Registry {
         name: "jo",
-        repo_url: "https://github.com/jo-inc/skills.git",
+        repo: &JO_REPO,
         skills_path: "skills",
         trusted: true, // Official jo skills
+        channel: Channel::Curated,
+        api_base: None,
     },
 ];
 
-#[derive(Debug, Deserialize)]
-struct ClawdhubSkill {
-    slug: String,
-    stats: ClawdhubStats,
+pub fn find_registry(name: &str) -> Option<&'static Registry> {
+    REGISTRIES.iter().find(|r| r.name == name)
+}
+
+/// Channel of a registry; registries without a repo here (skills.sh) are community
+pub fn channel_of(registry: &str) -> Channel {
+    find_registry(registry).map(|r| r.channel).unwrap_or(Channel::Community)
 }
 
-#[derive(Debug, Deserialize)]
-struct ClawdhubStats {
-    stars: i64,
+/// Clone directory holding a registry's skills
+pub fn repo_dir(repos_dir: &Path, registry: &str) -> PathBuf {
+    match find_registry(registry) {
+        Some(r) => repos_dir.join(r.repo.name),
+        None => repos_dir.join(registry),
+    }
+}
+
+/// Clone directories from before registries shared repos
+const LEGACY_CLONE_DIRS: &[&str] = &["openai-experimental"];
+
+/// Repos fetched at once when `sync --jobs` isn't given: all of them
+pub const DEFAULT_SYNC_JOBS: usize = SOURCE_REPOS.len();
+
+/// How one registry's part of a sync went
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistrySync {
+    pub registry: String,
+    /// Skills in the catalog from this registry afterwards
+    pub skills: i64,
+    /// Fetching its repo (shared with registries in the same repo) plus scanning it
+    pub seconds: f64,
+    /// Skills removed for being over the registry's limits in config.toml
+    pub skipped: usize,
+    /// The step that failed (`fetch` or `scan`) and why
+    pub error: Option<(String, String)>,
+    /// Whether this is from a second try, after the first failed
+    pub retried: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct ClawdhubResponse {
-    items: Vec<ClawdhubSkill>,
-    #[serde(rename = "nextCursor")]
-    next_cursor: Option<String>,
+/// Per-registry results of a sync, in registry order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncSummary {
+    pub registries: Vec<RegistrySync>,
 }
 
-pub async fn sync_all_registries(db: &mut Database, repos_dir: &Path) -> Result<()> {
+impl SyncSummary {
+    pub fn failed(&self) -> usize {
+        self.registries.iter().filter(|r| r.error.is_some()).count()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.registries.iter().map(|r| r.skipped).sum()
+    }
+}
+
+/// Clone or pull every enabled git registry in `configs` into `repos_dir`
+/// and record its skills in `db`. Up to `jobs` repos are fetched at once;
+/// scanning into the database stays sequential. Failures are recorded per
+/// registry and summarized rather than returned; see
+/// [`crate::sync::sync_catalog`] for a full sync.
+pub async fn sync_all_registries(db: &mut Database, repos_dir: &Path, configs: &HashMap<String, RegistryConfig>, jobs: usize) -> Result<SyncSummary> {
     std::fs::create_dir_all(repos_dir)?;
 
-    for registry in REGISTRIES {
-        tracing::info!("Syncing registry: {}", registry.name);
-        if let Err(e) = sync_registry(db, repos_dir, registry).await {
-            tracing::warn!("Failed to sync {}: {}", registry.name, e);
+    for legacy in LEGACY_CLONE_DIRS {
+        let dir = repos_dir.join(legacy);
+        if dir.exists() {
+            tracing::info!("Removing duplicate clone {:?}", dir);
+            std::fs::remove_dir_all(&dir)?;
         }
     }
 
-    // Fetch star counts from clawdhub API
-    tracing::info!("Fetching star counts from clawdhub API...");
-    if let Err(e) = fetch_clawdhub_stars(db).await {
-        tracing::warn!("Failed to fetch clawdhub stars: {}", e);
+    let started = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let existing: HashSet<(String, String)> = db.get_all_slugs()?.into_iter().collect();
+
+    // Cloning and pulling is network- and git-bound, so the repos go in parallel
+    let permits = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut fetches = JoinSet::new();
+    for (i, repo) in SOURCE_REPOS.iter().enumerate() {
+        if !REGISTRIES.iter().any(|r| r.repo.name == repo.name && registry_enabled(configs, r.name)) {
+            continue;
+        }
+        let (permits, repos_dir, options) = (permits.clone(), repos_dir.to_path_buf(), clone_options(configs, repo));
+        fetches.spawn(async move {
+            let _permit = permits.acquire_owned().await?;
+            tracing::info!("Syncing repo: {}", repo.name);
+            let fetch_started = Instant::now();
+            let dir = tokio::task::spawn_blocking(move || update_clone(&repos_dir, repo, &options)).await?;
+            anyhow::Ok((i, dir, fetch_started.elapsed()))
+        });
     }
+    let mut fetched = Vec::new();
+    while let Some(joined) = fetches.join_next().await {
+        fetched.push(joined??);
+    }
+    fetched.sort_by_key(|(i, _, _)| *i);
 
-    Ok(())
-}
+    let mut summary = SyncSummary::default();
+    let mut scanned = Vec::new();
+    for (i, dir, fetch_time) in fetched {
+        let repo = &SOURCE_REPOS[i];
+        let repo_dir = match dir {
+            Ok(dir) => dir,
+            Err(e) => {
+                tracing::warn!("Failed to sync {}: {:#}", repo.name, e);
+                for registry in REGISTRIES.iter().filter(|r| r.repo.name == repo.name) {
+                    record_failure(db, registry.name, "fetch", &e);
+                    if registry_enabled(configs, registry.name) {
+                        summary.registries.push(RegistrySync {
+                            registry: registry.name.to_string(),
+                            skills: db.count_skills(registry.name)?,
+                            seconds: fetch_time.as_secs_f64(),
+                            skipped: 0,
+                            error: Some(("fetch".to_string(), format!("{:#}", e))),
+                            retried: false,
+                        });
+                    }
+                }
+                continue;
+            }
+        };
+        for registry in REGISTRIES.iter().filter(|r| r.repo.name == repo.name && registry_enabled(configs, r.name)) {
+            let scan_started = Instant::now();
+            let exclude = configs.get(registry.name).map(|c| c.exclude.as_slice()).unwrap_or_default();
+            let error = match scan_registry(db, &repo_dir, registry, exclude) {
+                Ok(()) => {
+                    scanned.push(registry.name);
+                    None
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to sync {}: {:#}", registry.name, e);
+                    record_failure(db, registry.name, "scan", &e);
+                    Some(("scan".to_string(), format!("{:#}", e)))
+                }
+            };
+            summary.registries.push(RegistrySync {
+                registry: registry.name.to_string(),
+                skills: db.count_skills(registry.name)?,
+                seconds: (fetch_time + scan_started.elapsed()).as_secs_f64(),
+                skipped: 0,
+                error,
+                retried: false,
+            });
+        }
+    }
+
+    if let Err(e) = migrate_moved_skills(db, &scanned, &existing, started) {
+        tracing::warn!("Failed to detect moved skills: {}", e);
+    }
 
-async fn fetch_clawdhub_stars(db: &mut Database) -> Result<()> {
-    let client = reqwest::Client::builder()
-        .user_agent("skill-search/0.1")
-        .build()?;
+    // Repo topics tag skills whose SKILL.md may have no tags of its own
+    let tagged: Vec<&Registry> = scanned.iter().filter_map(|name| find_registry(name)).collect();
+    crate::topics::sync_topics(db, repos_dir, &tagged).await?;
 
-    let mut stars_map: HashMap<String, i64> = HashMap::new();
-    let mut cursor: Option<String> = None;
-    let mut page = 0;
+    // Registries without a usage API get their GitHub repo's stars
+    let mut with_stars = HashSet::new();
+    let repo_hosted: Vec<&Registry> = REGISTRIES.iter().filter(|r| r.api_base_url().is_none() && registry_enabled(configs, r.name)).collect();
+    with_stars.extend(crate::github_api::sync_repo_stats(db, &repo_hosted).await?);
 
-    loop {
-        let url = match &cursor {
-            Some(c) => format!("https://clawhub.com/api/v1/skills?limit=100&cursor={}", c),
-            None => "https://clawhub.com/api/v1/skills?limit=100".to_string(),
+    // Fetch star/download/install counts from registries with a clawdhub-compatible API
+    for registry in REGISTRIES {
+        let Some(base_url) = registry.api_base_url().filter(|_| registry_enabled(configs, registry.name)) else {
+            continue;
         };
+        tracing::info!("Fetching usage counts from {} API at {}...", registry.name, base_url);
+        let client = match ClawdhubClient::new(&base_url) {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::warn!("Failed to create {} API client: {}", registry.name, e);
+                record_failure(db, registry.name, "stats", &e);
+                continue;
+            }
+        };
+        // Unchanged pages are skipped, so a skill added (or re-added) since
+        // they were fetched would never get its counts
+        let added = db.get_all_slugs()?.into_iter().any(|(r, slug)| r == registry.name && !existing.contains(&(r.clone(), slug)));
+        if added {
+            db.clear_page_etags(&crate::clawdhub::page_key_prefix(registry.name))?;
+        }
+        match client.sync_stats(db, registry.name).await {
+            Ok(()) => {
+                with_stars.insert(registry.name);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch {} stats: {:#}", registry.name, e);
+                record_failure(db, registry.name, "stats", &e);
+            }
+        }
+        if let Err(e) = client.sync_reviews(db, registry.name).await {
+            tracing::warn!("Failed to fetch {} reviews: {:#}", registry.name, e);
+            record_failure(db, registry.name, "reviews", &e);
+        }
+    }
 
-        let resp = client.get(&url).send().await?;
-        if !resp.status().is_success() {
-            anyhow::bail!("Clawdhub API error: {}", resp.status());
+    // Limits go last, once star counts are current. Skills over them are
+    // scanned again next sync and dropped again; installed ones stay.
+    let installed: HashSet<(String, String)> = db.get_installs()?.into_iter().map(|i| (i.registry, i.slug)).collect();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    for entry in &mut summary.registries {
+        let Some(limits) = configs.get(&entry.registry).filter(|c| c.has_limits()) else {
+            continue;
+        };
+        let stars_known = with_stars.contains(entry.registry.as_str());
+        if limits.min_stars.is_some() && !stars_known {
+            tracing::warn!("No star counts for {} this sync; its min_stars limit is skipped", entry.registry);
+        }
+        let skills = db
+            .get_skills_by_registry(&entry.registry)?
+            .into_iter()
+            .filter(|s| !installed.contains(&(s.registry.clone(), s.slug.clone())))
+            .collect();
+        let over = over_limits(skills, limits, stars_known, now);
+        for skill in &over {
+            db.delete_skill(&skill.registry, &skill.slug)?;
+        }
+        if !over.is_empty() {
+            tracing::info!("Skipped {} {} skills over the limits in config.toml", over.len(), entry.registry);
         }
+        entry.skipped = over.len();
+        entry.skills = db.count_skills(&entry.registry)?;
+    }
 
-        let data: ClawdhubResponse = resp.json().await?;
-        
-        for skill in data.items {
-            stars_map.insert(skill.slug, skill.stats.stars);
+    Ok(summary)
+}
+
+/// Skills a registry's `min_stars`, `max_age_days`, and `max_skills` leave
+/// out. `min_stars` only applies when `stars_known`; `max_skills` keeps the
+/// most-starred, then most recently updated.
+pub fn over_limits(skills: Vec<Skill>, limits: &RegistryConfig, stars_known: bool, now: i64) -> Vec<Skill> {
+    let min_stars = limits.min_stars.filter(|_| stars_known);
+    let oldest = limits.max_age_days.map(|days| now - days * 86_400);
+    let (mut kept, mut over): (Vec<Skill>, Vec<Skill>) = skills.into_iter().partition(|s| {
+        min_stars.is_none_or(|min| s.stars >= min) && oldest.is_none_or(|t| s.upstream_updated_at.is_none_or(|u| u >= t))
+    });
+    if let Some(max) = limits.max_skills.filter(|max| kept.len() > *max) {
+        kept.sort_by(|a, b| b.stars.cmp(&a.stars).then(b.upstream_updated_at.cmp(&a.upstream_updated_at)).then(a.slug.cmp(&b.slug)));
+        over.extend(kept.split_off(max));
+    }
+    over
+}
+
+/// Keep a categorized record of a failed sync step; failing to record it
+/// shouldn't fail the sync
+pub fn record_failure(db: &Database, registry: &str, stage: &str, err: &anyhow::Error) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default();
+    let kind = failures::classify(err);
+    if let Err(e) = db.record_sync_failure(registry, stage, kind, &format!("{:#}", err), now) {
+        tracing::warn!("Could not record sync failure for {}: {}", registry, e);
+    }
+}
+
+/// Directories a registry's `exclude` patterns leave out of the catalog
+pub struct ExcludeFilter {
+    /// Patterns with a `/`, matched against the path under the skills directory
+    paths: GlobSet,
+    /// Patterns without one, matched against each directory name in it
+    names: GlobSet,
+}
+
+impl ExcludeFilter {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let (mut paths, mut names) = (GlobSetBuilder::new(), GlobSetBuilder::new());
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid exclude pattern {:?}: {}", pattern, e))?;
+            if pattern.contains('/') {
+                paths.add(glob);
+            } else {
+                names.add(glob);
+            }
         }
+        Ok(Self {
+            paths: paths.build()?,
+            names: names.build()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.names.is_empty()
+    }
+
+    /// Whether a directory, given relative to the skills directory, is excluded
+    pub fn is_excluded(&self, rel_path: &Path) -> bool {
+        self.paths.is_match(rel_path) || rel_path.components().any(|c| self.names.is_match(c.as_os_str()))
+    }
+}
 
-        page += 1;
-        if page % 10 == 0 {
-            tracing::debug!("Fetched {} skills from clawdhub API", stars_map.len());
+/// A skill that left one registry (or slug) and reappeared unchanged under another
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkillMove {
+    pub from: (String, String),
+    pub to: (String, String),
+    pub content_hash: String,
+}
+
+/// Pair skills that disappeared upstream with present skills of identical
+/// content. A hash shared by several present skills is a copy, not a move,
+/// and is skipped.
+pub fn detect_moves(gone: &[Skill], present: &[Skill]) -> Vec<SkillMove> {
+    let mut by_hash: HashMap<String, Vec<&Skill>> = HashMap::new();
+    for skill in present {
+        by_hash.entry(catalog::content_hash(skill)).or_default().push(skill);
+    }
+
+    let mut moves = Vec::new();
+    for old in gone {
+        let hash = catalog::content_hash(old);
+        if let Some([new]) = by_hash.get(&hash).map(Vec::as_slice) {
+            moves.push(SkillMove {
+                from: (old.registry.clone(), old.slug.clone()),
+                to: (new.registry.clone(), new.slug.clone()),
+                content_hash: hash,
+            });
         }
+    }
+    moves
+}
 
-        match data.next_cursor {
-            Some(c) if !c.is_empty() => cursor = Some(c),
-            _ => break,
+/// After a sync, supersede skills that vanished from a scanned registry and
+/// reappeared under a slug added in the same sync: an experimental skill
+/// graduating to curated, or a skill directory renamed upstream. The old
+/// `registry:slug` keeps resolving to the new entry.
+fn migrate_moved_skills(db: &mut Database, scanned: &[&str], existing: &HashSet<(String, String)>, started: i64) -> Result<()> {
+    let superseded = db.get_superseded()?;
+    let (mut gone, mut present) = (Vec::new(), Vec::new());
+    for registry in scanned {
+        for skill in db.get_skills_by_registry(registry)? {
+            let key = (skill.registry.clone(), skill.slug.clone());
+            if skill.updated_at >= started {
+                // Back upstream after moving away: no longer superseded
+                if superseded.contains(&key) {
+                    db.delete_move(&key.0, &key.1)?;
+                }
+                // Only a new slug can be where a vanished skill went
+                if !existing.contains(&key) {
+                    present.push(skill);
+                }
+            } else if !superseded.contains(&key) {
+                gone.push(skill);
+            }
         }
     }
 
-    tracing::info!("Fetched stars for {} clawdhub skills", stars_map.len());
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    for m in detect_moves(&gone, &present) {
+        if m.from.0 == m.to.0 {
+            tracing::info!("{}:{} renamed to {}", m.from.0, m.from.1, m.to.1);
+        } else {
+            tracing::info!("{}:{} moved to {}:{}", m.from.0, m.from.1, m.to.0, m.to.1);
+        }
+        db.record_move((&m.from.0, &m.from.1), (&m.to.0, &m.to.1), &m.content_hash, now)?;
+    }
+    Ok(())
+}
 
-    // Update stars in database
-    for (slug, stars) in stars_map {
-        db.update_stars("clawdhub", &slug, stars)?;
+/// How to clone a repo several registries may share: as much of it as the
+/// enabled registry wanting the most asks for
+pub fn clone_options(configs: &HashMap<String, RegistryConfig>, repo: &SourceRepo) -> git::CloneOptions {
+    let default = RegistryConfig::default();
+    let wanted: Vec<&RegistryConfig> = REGISTRIES
+        .iter()
+        .filter(|r| r.repo.name == repo.name)
+        .map(|r| configs.get(r.name).unwrap_or(&default))
+        .filter(|c| c.enabled)
+        .collect();
+    if wanted.is_empty() {
+        return git::CloneOptions::default();
+    }
+    git::CloneOptions {
+        depth: wanted.iter().map(|c| c.clone_depth).reduce(|a, b| a.zip(b).map(|(a, b)| a.max(b))).flatten(),
+        single_branch: wanted.iter().all(|c| c.single_branch),
+        partial: wanted.iter().all(|c| c.partial_clone),
     }
+}
 
-    Ok(())
+/// Whether syncing this repo means cloning it from scratch
+fn needs_fresh_clone(repos_dir: &Path, repo: &SourceRepo) -> bool {
+    !repos_dir.join(repo.name).join(".git").exists()
 }
 
-async fn sync_registry(db: &mut Database, repos_dir: &Path, registry: &Registry) -> Result<()> {
-    let repo_dir = repos_dir.join(registry.name);
+/// Disk space the next sync needs for fresh clones
+pub fn clone_space_needed(repos_dir: &Path) -> u64 {
+    SOURCE_REPOS
+        .iter()
+        .filter(|r| needs_fresh_clone(repos_dir, r))
+        .map(|r| r.clone_size_estimate)
+        .sum()
+}
 
-    // Clone or pull
+/// Clone or pull a source repo, returning its clone directory
+fn update_clone(repos_dir: &Path, repo: &SourceRepo, options: &git::CloneOptions) -> Result<PathBuf> {
+    let repo_dir = repos_dir.join(repo.name);
+
+    // Clone or pull. Shallow clones stay shallow; skills changed before
+    // their oldest commit just have no upstream date.
     if repo_dir.join(".git").exists() {
-        tracing::info!("Pulling updates for {}", registry.name);
-        let status = Command::new("git")
-            .args(["pull", "--ff-only", "-q"])
-            .current_dir(&repo_dir)
-            .status()?;
-        if !status.success() {
-            tracing::warn!("git pull failed for {}, trying fresh clone", registry.name);
+        tracing::info!("Pulling updates for {}", repo.name);
+        if let Err(e) = git::pull(&repo_dir) {
+            tracing::warn!("Pull failed for {}, trying fresh clone: {:#}", repo.name, e);
             std::fs::remove_dir_all(&repo_dir)?;
-            clone_repo(registry.repo_url, &repo_dir)?;
+            clone_repo(repo.url, &repo_dir, options)?;
         }
     } else {
-        clone_repo(registry.repo_url, &repo_dir)?;
+        clone_repo(repo.url, &repo_dir, options)?;
     }
+    Ok(repo_dir)
+}
 
-    // Scan for skills
+/// Mark the registry's skills this scan didn't see as gone upstream, which
+/// hides them from search until they come back or `prune` deletes them
+fn mark_removed_skills(db: &Database, registry: &str, seen: &HashSet<String>) -> Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let mut removed = 0;
+    for skill in db.get_skills_by_registry(registry)? {
+        if seen.contains(&skill.slug) {
+            db.clear_removed(registry, &skill.slug)?;
+        } else if db.mark_removed(registry, &skill.slug, now)? {
+            tracing::info!("{}:{} is gone upstream", registry, skill.slug);
+            removed += 1;
+        }
+    }
+    if removed > 0 {
+        tracing::info!("{} {} skills removed upstream; `prune` deletes them", removed, registry);
+    }
+    Ok(())
+}
+
+/// Scan one registry's path of an up-to-date clone
+fn scan_registry(db: &mut Database, repo_dir: &Path, registry: &Registry, exclude: &[String]) -> Result<()> {
     let skills_dir = repo_dir.join(registry.skills_path);
     if !skills_dir.exists() {
         anyhow::bail!("Skills directory not found: {:?}", skills_dir);
     }
+    let filter = ExcludeFilter::new(exclude)?;
+
+    let commit = head_commit(repo_dir).ok();
+    let commit_times = path_commit_times(repo_dir, registry.skills_path).unwrap_or_else(|e| {
+        tracing::warn!("Could not read commit history for {}: {}", registry.name, e);
+        HashMap::new()
+    });
+    let seen = scan_skills_dir(db, &ScanTarget::of(registry), &skills_dir, repo_dir, commit.as_deref(), &commit_times, &filter)?;
 
-    scan_skills_dir(db, registry, &skills_dir, &repo_dir)?;
+    // Drop skills synced before a pattern excluded them
+    if !filter.is_empty() {
+        let prefix = format!("{}/{}/", registry.repo.web_base, registry.skills_path);
+        for skill in db.get_skills_by_registry(registry.name)? {
+            let excluded = skill.github_url.strip_prefix(&prefix).is_some_and(|rel| filter.is_excluded(Path::new(rel)));
+            if excluded {
+                tracing::info!("Removing excluded skill {}:{}", skill.registry, skill.slug);
+                db.delete_skill(&skill.registry, &skill.slug)?;
+            }
+        }
+    }
+    mark_removed_skills(db, registry.name, &seen)?;
     
     // Count skills
     let mut count = 0;
@@ -176,30 +632,149 @@ async fn sync_registry(db: &mut Database, repos_dir: &Path, registry: &Registry)
     Ok(())
 }
 
-fn clone_repo(url: &str, dest: &Path) -> Result<()> {
+/// Scan a local directory tree of skills (`<dir>/<skill>/SKILL.md`, or one
+/// level deeper) into `db` under `registry`, the way sync scans a clone. Skills
+/// are linked by `file://` URL, and those of `registry` no longer under `dir`
+/// are removed. Returns how many skills the registry has.
+pub fn import_local(db: &mut Database, dir: &Path, registry: &str, exclude: &[String]) -> Result<usize> {
+    if registry.is_empty() || !registry.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        anyhow::bail!("Registry name '{}' must be lowercase letters, digits, and dashes", registry);
+    }
+    if find_registry(registry).is_some() || registry == "skillssh" {
+        anyhow::bail!("{} is a synced registry; import into another name, such as local", registry);
+    }
+    let dir = dir.canonicalize().map_err(|e| anyhow::anyhow!("Can't read {:?}: {}", dir, e))?;
+    if !dir.is_dir() {
+        anyhow::bail!("{:?} is not a directory", dir);
+    }
+    let filter = ExcludeFilter::new(exclude)?;
+    let web_base = format!("file://{}", dir.display());
+    let target = ScanTarget { registry, web_base: &web_base, trusted: false };
+
+    // Only a repo root's history lines up with paths under it
+    let (commit, commit_times) = if dir.join(".git").exists() {
+        (head_commit(&dir).ok(), path_commit_times(&dir, ".").unwrap_or_default())
+    } else {
+        (None, HashMap::new())
+    };
+    let found = scan_skills_dir(db, &target, &dir, &dir, commit.as_deref(), &commit_times, &filter)?;
+    for skill in db.get_skills_by_registry(registry)? {
+        if !found.contains(&skill.slug) {
+            tracing::info!("Removing {}:{}, gone from {:?}", skill.registry, skill.slug, dir);
+            db.delete_skill(&skill.registry, &skill.slug)?;
+        }
+    }
+    db.set_last_sync(registry, SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64, None)?;
+    db.count_skills(registry).map(|n| n as usize)
+}
+
+fn clone_repo(url: &str, dest: &Path, options: &git::CloneOptions) -> Result<()> {
     tracing::info!("Cloning {} to {:?}", url, dest);
-    let status = Command::new("git")
-        .args(["clone", "--depth", "1", "-q", url])
-        .arg(dest)
-        .status()?;
-    if !status.success() {
-        anyhow::bail!("git clone failed");
+    git::clone(url, dest, options)
+}
+
+/// Shallow, sparse clone of `url` at `git_ref` into `dest` with only `path`
+/// checked out, for skills that have no synced clone; returns the commit
+pub fn sparse_checkout(url: &str, git_ref: &str, path: &str, dest: &Path) -> Result<String> {
+    tracing::info!("Fetching {} from {} ({})", path, url, git_ref);
+    git::sparse_clone(url, git_ref, path, dest)?;
+    if !dest.join(path).join("SKILL.md").exists() {
+        anyhow::bail!("{} has no SKILL.md at {} ({})", url, path, git_ref);
     }
-    Ok(())
+    head_commit(dest)
+}
+
+/// Current HEAD commit of a synced registry clone
+pub fn head_commit(repo_dir: &Path) -> Result<String> {
+    git::resolve_commit(repo_dir, "HEAD")?.ok_or_else(|| anyhow::anyhow!("No HEAD commit in {:?}", repo_dir))
+}
+
+/// Make `rev` available in a (possibly shallow) clone and return its full SHA.
+/// Revisions outside the shallow history must be given as full commit SHAs.
+pub fn fetch_revision(repo_dir: &Path, rev: &str) -> Result<String> {
+    if let Some(sha) = git::resolve_commit(repo_dir, rev)? {
+        return Ok(sha);
+    }
+
+    tracing::info!("Fetching revision {} in {:?}", rev, repo_dir);
+    git::fetch(repo_dir, rev).map_err(|e| anyhow::anyhow!("{:#} (use a full commit SHA)", e))?;
+    git::resolve_commit(repo_dir, "FETCH_HEAD")?.ok_or_else(|| anyhow::anyhow!("Revision {} not found", rev))
+}
+
+/// Write the files under `path` (relative to the repo root) as of commit
+/// `rev` into `dest`, returning how many files were written.
+pub fn export_path_at_rev(repo_dir: &Path, rev: &str, path: &str, dest: &Path) -> Result<usize> {
+    let mut count = 0;
+    for (name, content) in git::tree_files(repo_dir, rev, path)? {
+        let rel = Path::new(&name).strip_prefix(path)?;
+        let target = dest.join(rel);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(target, content)?;
+        count += 1;
+    }
+
+    if count == 0 {
+        anyhow::bail!("{} does not exist at revision {}", path, rev);
+    }
+    Ok(count)
+}
+
+/// Unix time of the newest commit touching each directory under `path`,
+/// keyed by repo-relative path ("skills/pdf"), from a single pass over the log.
+/// In a shallow clone, directories last changed before its oldest commit
+/// have no time.
+pub fn path_commit_times(repo_dir: &Path, path: &str) -> Result<HashMap<String, i64>> {
+    let mut times = HashMap::new();
+    let mut log = git::log_paths(repo_dir, path)?;
+    // The oldest commit of a shallow clone has no parent, so it shows every file as added
+    if repo_dir.join(".git").join("shallow").exists() {
+        log.pop();
+    }
+    for (time, files) in log {
+        // Newest commits come first, so the first time seen for a directory wins
+        for file in &files {
+            let mut dir = Path::new(file).parent();
+            while let Some(d) = dir.filter(|d| !d.as_os_str().is_empty()) {
+                times.entry(d.to_string_lossy().replace('\\', "/")).or_insert(time);
+                dir = d.parent();
+            }
+        }
+    }
+    Ok(times)
 }
 
-fn scan_skills_dir(db: &mut Database, registry: &Registry, dir: &Path, repo_root: &Path) -> Result<()> {
+/// Where scanned skills are recorded, and the URL their paths hang off
+struct ScanTarget<'a> {
+    registry: &'a str,
+    web_base: &'a str,
+    trusted: bool,
+}
+
+impl<'a> ScanTarget<'a> {
+    fn of(registry: &'a Registry) -> Self {
+        Self { registry: registry.name, web_base: registry.repo.web_base, trusted: registry.trusted }
+    }
+}
+
+/// Record each skill under `dir`, returning the slugs of every skill
+/// directory found, including ones that failed to record this time
+fn scan_skills_dir(db: &mut Database, target: &ScanTarget, dir: &Path, repo_root: &Path, commit: Option<&str>, commit_times: &HashMap<String, i64>, filter: &ExcludeFilter) -> Result<HashSet<String>> {
+    let excluded = |path: &Path| filter.is_excluded(path.strip_prefix(dir).unwrap_or(path));
+    let mut found = HashSet::new();
     for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
         
-        if !path.is_dir() {
+        if !path.is_dir() || excluded(&path) {
             continue;
         }
 
         let skill_md_path = path.join("SKILL.md");
         if skill_md_path.exists() {
-            if let Err(e) = process_skill(db, registry, &path, &skill_md_path, repo_root) {
+            found.insert(skill_slug(&path));
+            if let Err(e) = process_skill(db, target, &path, &skill_md_path, repo_root, commit, commit_times) {
                 tracing::debug!("Skipping {:?}: {}", path, e);
             }
         } else {
@@ -207,10 +782,11 @@ fn scan_skills_dir(db: &mut Database, registry: &Registry, dir: &Path, repo_root
             if let Ok(entries) = std::fs::read_dir(&path) {
                 for sub in entries.flatten() {
                     let sub_path = sub.path();
-                    if sub_path.is_dir() {
+                    if sub_path.is_dir() && !excluded(&sub_path) {
                         let sub_skill_md = sub_path.join("SKILL.md");
                         if sub_skill_md.exists() {
-                            if let Err(e) = process_skill(db, registry, &sub_path, &sub_skill_md, repo_root) {
+                            found.insert(skill_slug(&sub_path));
+                            if let Err(e) = process_skill(db, target, &sub_path, &sub_skill_md, repo_root, commit, commit_times) {
                                 tracing::debug!("Skipping {:?}: {}", sub_path, e);
                             }
                         }
@@ -219,88 +795,264 @@ fn scan_skills_dir(db: &mut Database, registry: &Registry, dir: &Path, repo_root
             }
         }
     }
-    Ok(())
+    Ok(found)
+}
+
+/// A skill's slug is its directory name
+fn skill_slug(skill_dir: &Path) -> String {
+    skill_dir.file_name().and_then(|n| n.to_str()).unwrap_or("unknown").to_string()
 }
 
-fn process_skill(db: &mut Database, registry: &Registry, skill_dir: &Path, skill_md_path: &Path, repo_root: &Path) -> Result<()> {
+fn process_skill(db: &mut Database, target: &ScanTarget, skill_dir: &Path, skill_md_path: &Path, repo_root: &Path, commit: Option<&str>, commit_times: &HashMap<String, i64>) -> Result<String> {
     let skill_md = std::fs::read_to_string(skill_md_path)?;
-    let (name, description, version) = parse_skill_frontmatter(&skill_md);
+    let SkillFrontmatter { name, description, version, .. } = parse_skill_frontmatter(&skill_md);
 
-    // Extract slug from directory name
-    let slug = skill_dir
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    let slug = skill_slug(skill_dir);
 
     // Build GitHub URL from relative path
     let rel_path = skill_dir.strip_prefix(repo_root).unwrap_or(skill_dir);
-    let github_url = match registry.name {
-        "clawdhub" => format!("https://github.com/openclaw/skills/tree/main/{}", rel_path.display()),
-        "anthropic" => format!("https://github.com/anthropics/skills/tree/main/{}", rel_path.display()),
-        "openai" | "openai-experimental" => format!("https://github.com/openai/skills/tree/main/{}", rel_path.display()),
-        "jo" => format!("https://github.com/jo-inc/skills/tree/master/{}", rel_path.display()),
-        _ => format!("https://github.com/unknown/{}", rel_path.display()),
-    };
+    let github_url = format!("{}/{}", target.web_base, rel_path.display());
 
     let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    // Keep counts from earlier (possibly partial) API fetches; refreshed after the scan
+    let (stars, downloads, installs) = db
+        .get_skill(target.registry, &slug)?
+        .map(|s| (s.stars, s.downloads, s.installs))
+        .unwrap_or_default();
 
     let skill = Skill {
         id: 0,
         slug,
         name,
-        registry: registry.name.to_string(),
+        registry: target.registry.to_string(),
         description,
         skill_md,
         github_url,
         version,
-        stars: 0, // Will be updated from clawdhub API
-        trusted: registry.trusted,
+        stars,
+        trusted: target.trusted,
         updated_at: now,
+        downloads,
+        installs,
+        upstream_updated_at: commit_times.get(&rel_path.to_string_lossy().replace('\\', "/")).copied(),
     };
 
     db.upsert_skill(&skill)?;
-    Ok(())
+    if let (Some(version), Some(commit)) = (&skill.version, commit) {
+        db.record_skill_version(&skill.registry, &skill.slug, version, commit, now)?;
+    }
+    Ok(skill.slug)
 }
 
-pub fn parse_skill_frontmatter(content: &str) -> (String, String, Option<String>) {
-    let mut name = String::new();
-    let mut description = String::new();
-    let mut version = None;
+/// Replace a skill's SKILL.md with another copy of it (a local edit or an
+/// overlay), taking the name and description from its frontmatter where set
+pub fn apply_skill_md(skill: &mut Skill, skill_md: String) {
+    let SkillFrontmatter { name, description, .. } = parse_skill_frontmatter(&skill_md);
+    if !name.is_empty() {
+        skill.name = name;
+    }
+    if !description.is_empty() {
+        skill.description = description;
+    }
+    skill.skill_md = skill_md;
+}
 
-    if content.starts_with("---") {
-        if let Some(end_idx) = content[3..].find("---") {
-            let frontmatter = &content[3..3 + end_idx];
+/// What the catalog takes from a SKILL.md's frontmatter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkillFrontmatter {
+    pub name: String,
+    /// On one line, however the YAML wrapped it
+    pub description: String,
+    /// `version`, or `metadata.version`
+    pub version: Option<String>,
+    pub license: Option<String>,
+    /// `author`, or `metadata.author`; a `{name, email}` map gives the name
+    pub author: Option<String>,
+    /// `allowed-tools`, as a list or one comma- or space-separated string
+    pub allowed_tools: Vec<String>,
+    /// `tags` and `category`/`categories`, top-level or under `metadata`,
+    /// normalized with [`normalize_tag`]
+    pub tags: Vec<String>,
+    /// The scalar values of the `metadata` map
+    pub metadata: BTreeMap<String, String>,
+}
 
-            for line in frontmatter.lines() {
-                let line = line.trim();
-                if let Some(val) = line.strip_prefix("name:") {
-                    name = val.trim().trim_matches('"').trim_matches('\'').to_string();
-                } else if let Some(val) = line.strip_prefix("description:") {
-                    description = val.trim().trim_matches('"').trim_matches('\'').to_string();
-                } else if let Some(val) = line.strip_prefix("version:") {
-                    version = Some(val.trim().trim_matches('"').trim_matches('\'').to_string());
-                }
-            }
+/// Frontmatter keys as written; anything else is ignored
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawFrontmatter {
+    name: Option<serde_yaml::Value>,
+    description: Option<serde_yaml::Value>,
+    version: Option<serde_yaml::Value>,
+    license: Option<serde_yaml::Value>,
+    author: Option<serde_yaml::Value>,
+    #[serde(rename = "allowed-tools", alias = "allowed_tools")]
+    allowed_tools: Option<serde_yaml::Value>,
+    tags: Option<serde_yaml::Value>,
+    category: Option<serde_yaml::Value>,
+    categories: Option<serde_yaml::Value>,
+    metadata: Option<serde_yaml::Mapping>,
+}
+
+/// Tags compare lowercased, with runs of spaces or underscores as one `-`
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_matches('"').trim_matches('\'').trim().to_lowercase();
+    let tag = tag.split(|c: char| c.is_whitespace() || c == '_').filter(|w| !w.is_empty()).collect::<Vec<_>>().join("-");
+    (!tag.is_empty() && tag.len() <= 40).then_some(tag)
+}
+
+fn unquote(value: &str) -> String {
+    value.trim().trim_matches('"').trim_matches('\'').to_string()
+}
+
+/// A string, number, or boolean as text; `version: 1.0` is a float to YAML
+fn scalar(value: &serde_yaml::Value) -> Option<String> {
+    let text = match value {
+        serde_yaml::Value::String(s) => s.trim().to_string(),
+        serde_yaml::Value::Number(n) => n.to_string(),
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// The items of a list, or of a string separated by `separators`
+fn items(value: &serde_yaml::Value, separators: &[char]) -> Vec<String> {
+    match value {
+        serde_yaml::Value::Sequence(values) => values.iter().filter_map(scalar).collect(),
+        value => scalar(value).map(|s| s.split(separators).map(str::to_string).collect()).unwrap_or_default(),
+    }
+}
+
+/// Split `Read, Grep` or `Read Bash(git add:*)` into tools, leaving the
+/// spaces inside a tool's parentheses alone
+fn split_tools(tools: &str) -> Vec<String> {
+    let mut split = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    for c in tools.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if (c == ',' || c.is_whitespace()) && depth == 0 {
+            split.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
         }
     }
+    split.push(current);
+    split.into_iter().filter(|t| !t.is_empty()).collect()
+}
+
+fn author_name(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Mapping(map) => map.get("name").and_then(scalar),
+        serde_yaml::Value::Sequence(authors) => {
+            let names: Vec<String> = authors.iter().filter_map(author_name).collect();
+            (!names.is_empty()).then(|| names.join(", "))
+        }
+        value => scalar(value),
+    }
+}
+
+fn from_yaml(raw: RawFrontmatter) -> SkillFrontmatter {
+    let metadata = raw.metadata.unwrap_or_default();
+    let meta = |key: &str| metadata.get(key);
+    let mut tags = Vec::new();
+    for value in [raw.tags.as_ref(), raw.category.as_ref(), raw.categories.as_ref(), meta("tags"), meta("category"), meta("categories")].into_iter().flatten() {
+        tags.extend(items(value, &[',']));
+    }
+    SkillFrontmatter {
+        name: raw.name.as_ref().and_then(scalar).unwrap_or_default(),
+        description: raw.description.as_ref().and_then(scalar).map(|d| d.split_whitespace().collect::<Vec<_>>().join(" ")).unwrap_or_default(),
+        version: raw.version.as_ref().or(meta("version")).and_then(scalar),
+        license: raw.license.as_ref().and_then(scalar),
+        author: raw.author.as_ref().or(meta("author")).and_then(author_name),
+        allowed_tools: match &raw.allowed_tools {
+            Some(serde_yaml::Value::Sequence(tools)) => tools.iter().filter_map(scalar).collect(),
+            Some(tools) => scalar(tools).map(|t| split_tools(&t)).unwrap_or_default(),
+            None => Vec::new(),
+        },
+        tags: normalize_tags(&tags),
+        metadata: metadata.iter().filter_map(|(k, v)| Some((scalar(k)?, scalar(v)?))).collect(),
+    }
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized = Vec::new();
+    for tag in tags.iter().filter_map(|t| normalize_tag(t)) {
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Line by line, for frontmatter that isn't valid YAML. Unquoted colons in
+/// a description are the usual reason (`description: Use when: ...`).
+fn from_lines(frontmatter: &str) -> SkillFrontmatter {
+    let mut fm = SkillFrontmatter::default();
+    // Set after a tags key with no value, for the `- tag` lines under it
+    let mut in_tag_list = false;
+    let mut tags = Vec::new();
+
+    for line in frontmatter.lines() {
+        let line = line.trim();
+        if let (true, Some(item)) = (in_tag_list, line.strip_prefix('-')) {
+            tags.push(item.to_string());
+            continue;
+        }
+        in_tag_list = false;
+        if let Some(val) = line.strip_prefix("name:") {
+            fm.name = unquote(val);
+        } else if let Some(val) = line.strip_prefix("description:") {
+            fm.description = unquote(val);
+        } else if let Some(val) = line.strip_prefix("version:") {
+            fm.version = Some(unquote(val));
+        } else if let Some(val) = line.strip_prefix("license:") {
+            fm.license = Some(unquote(val)).filter(|l| !l.is_empty());
+        } else if let Some(val) = ["tags:", "category:", "categories:"].iter().find_map(|key| line.strip_prefix(key)) {
+            let val = val.trim();
+            in_tag_list = val.is_empty();
+            // `[a, b]` or `a, b` on one line
+            tags.extend(val.trim_start_matches('[').trim_end_matches(']').split(',').map(str::to_string));
+        }
+    }
+    fm.tags = normalize_tags(&tags);
+    fm
+}
+
+pub fn parse_skill_frontmatter(content: &str) -> SkillFrontmatter {
+    let mut fm = match split_frontmatter(content) {
+        (Some(frontmatter), _) => match serde_yaml::from_str::<Option<RawFrontmatter>>(frontmatter) {
+            Ok(raw) => from_yaml(raw.unwrap_or_default()),
+            Err(e) => {
+                tracing::debug!("Frontmatter isn't valid YAML ({}), reading it line by line", e);
+                from_lines(frontmatter)
+            }
+        },
+        (None, _) => SkillFrontmatter::default(),
+    };
 
     // Fallback: use first heading as name
-    if name.is_empty() {
+    if fm.name.is_empty() {
         for line in content.lines() {
             if let Some(heading) = line.strip_prefix("# ") {
-                name = heading.trim().to_string();
+                fm.name = heading.trim().to_string();
                 break;
             }
         }
     }
 
-    (name, description, version)
+    fm
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::process::Command;
 
     #[test]
     fn test_parse_frontmatter_complete() {
@@ -314,12 +1066,47 @@ version: 1.0.0
 
 Some content here.
 "#;
-        let (name, description, version) = parse_skill_frontmatter(content);
+        let SkillFrontmatter { name, description, version, .. } = parse_skill_frontmatter(content);
         assert_eq!(name, "test-skill");
         assert_eq!(description, "A test skill for testing");
         assert_eq!(version, Some("1.0.0".to_string()));
     }
 
+    #[test]
+    fn test_parse_frontmatter_tags() {
+        let content = "---\nname: sheets\ntags: [Data, \"CSV files\", data]\ncategory: productivity\nmetadata:\n  categories:\n    - Office_Docs\n    - \nversion: 1.0\n---\n- not a tag\n";
+        let fm = parse_skill_frontmatter(content);
+        assert_eq!(fm.tags, vec!["data", "csv-files", "productivity", "office-docs"]);
+        assert_eq!(fm.version.as_deref(), Some("1.0"));
+        assert_eq!(parse_skill_frontmatter("---\ntags: pdf, forms\n---\n").tags, vec!["pdf", "forms"]);
+    }
+
+    #[test]
+    fn test_parse_frontmatter_yaml() {
+        let content = "---\nname: pdf\ndescription: >\n  Fill in PDF forms,\n  merge documents.\nlicense: Apache-2.0\nallowed-tools: Read, Bash(git add:*) Grep\nmetadata:\n  version: 1.2\n  author:\n    name: Jo\n    email: jo@example.com\n  tags: [forms]\n  internal: true\n---\n# PDF\n";
+        let fm = parse_skill_frontmatter(content);
+        assert_eq!(fm.name, "pdf");
+        assert_eq!(fm.description, "Fill in PDF forms, merge documents.");
+        assert_eq!(fm.version.as_deref(), Some("1.2"));
+        assert_eq!(fm.license.as_deref(), Some("Apache-2.0"));
+        assert_eq!(fm.author.as_deref(), Some("Jo"));
+        assert_eq!(fm.allowed_tools, vec!["Read", "Bash(git add:*)", "Grep"]);
+        assert_eq!(fm.tags, vec!["forms"]);
+        assert_eq!(fm.metadata.get("internal").map(String::as_str), Some("true"));
+        assert!(!fm.metadata.contains_key("author"));
+
+        let literal = parse_skill_frontmatter("---\ndescription: |\n  First line.\n  Second line.\nallowed-tools:\n  - Read\n  - Write\nauthor: Sam\n---\n");
+        assert_eq!(literal.description, "First line. Second line.");
+        assert_eq!(literal.allowed_tools, vec!["Read", "Write"]);
+        assert_eq!(literal.author.as_deref(), Some("Sam"));
+
+        // Not YAML (a second unquoted colon), so read line by line
+        let malformed = parse_skill_frontmatter("---\nname: xlsx\ndescription: Use when: editing spreadsheets\nlicense: MIT\n---\n");
+        assert_eq!(malformed.name, "xlsx");
+        assert_eq!(malformed.description, "Use when: editing spreadsheets");
+        assert_eq!(malformed.license.as_deref(), Some("MIT"));
+    }
+
     #[test]
     fn test_parse_frontmatter_quoted_values() {
         let content = r#"---
@@ -328,7 +1115,7 @@ description: 'Single quoted description'
 version: "2.0"
 ---
 "#;
-        let (name, description, version) = parse_skill_frontmatter(content);
+        let SkillFrontmatter { name, description, version, .. } = parse_skill_frontmatter(content);
         assert_eq!(name, "quoted-skill");
         assert_eq!(description, "Single quoted description");
         assert_eq!(version, Some("2.0".to_string()));
@@ -341,7 +1128,7 @@ name: simple-skill
 description: Just a simple skill
 ---
 "#;
-        let (name, description, version) = parse_skill_frontmatter(content);
+        let SkillFrontmatter { name, description, version, .. } = parse_skill_frontmatter(content);
         assert_eq!(name, "simple-skill");
         assert_eq!(description, "Just a simple skill");
         assert!(version.is_none());
@@ -353,7 +1140,7 @@ description: Just a simple skill
 
 This skill does cool things.
 "#;
-        let (name, description, version) = parse_skill_frontmatter(content);
+        let SkillFrontmatter { name, description, version, .. } = parse_skill_frontmatter(content);
         assert_eq!(name, "My Cool Skill");
         assert_eq!(description, "");
         assert!(version.is_none());
@@ -362,7 +1149,7 @@ This skill does cool things.
     #[test]
     fn test_parse_frontmatter_empty_content() {
         let content = "";
-        let (name, description, version) = parse_skill_frontmatter(content);
+        let SkillFrontmatter { name, description, version, .. } = parse_skill_frontmatter(content);
         assert_eq!(name, "");
         assert_eq!(description, "");
         assert!(version.is_none());
@@ -371,12 +1158,137 @@ This skill does cool things.
     #[test]
     fn test_parse_frontmatter_no_frontmatter_with_heading() {
         let content = "Some text before\n# The Heading\nMore content";
-        let (name, description, version) = parse_skill_frontmatter(content);
+        let SkillFrontmatter { name, description, version, .. } = parse_skill_frontmatter(content);
         assert_eq!(name, "The Heading");
         assert_eq!(description, "");
         assert!(version.is_none());
     }
 
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_export_path_at_rev() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let skill_dir = repo.join("skills").join("pdf");
+        std::fs::create_dir_all(&skill_dir).unwrap();
+        git(&repo, &["init", "-q"]);
+
+        std::fs::write(skill_dir.join("SKILL.md"), "version: 1").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "v1"]);
+        let v1 = head_commit(&repo).unwrap();
+
+        std::fs::write(skill_dir.join("SKILL.md"), "version: 2").unwrap();
+        std::fs::write(skill_dir.join("extra.txt"), "new").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "v2"]);
+
+        assert_eq!(fetch_revision(&repo, &v1).unwrap(), v1);
+
+        let dest = dir.path().join("out");
+        let count = export_path_at_rev(&repo, &v1, "skills/pdf", &dest).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(std::fs::read_to_string(dest.join("SKILL.md")).unwrap(), "version: 1");
+        assert!(!dest.join("extra.txt").exists());
+
+        assert!(export_path_at_rev(&repo, &v1, "skills/missing", &dest).is_err());
+    }
+
+    #[test]
+    fn test_sparse_checkout_fetches_one_skill() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        for name in ["pdf", "notes"] {
+            std::fs::create_dir_all(repo.join("skills").join(name)).unwrap();
+            std::fs::write(repo.join("skills").join(name).join("SKILL.md"), name).unwrap();
+        }
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "skills"]);
+
+        let url = format!("file://{}", repo.display());
+        let dest = dir.path().join("checkout");
+        let sha = sparse_checkout(&url, "main", "skills/pdf", &dest).unwrap();
+        assert_eq!(sha, head_commit(&repo).unwrap());
+        assert_eq!(std::fs::read_to_string(dest.join("skills/pdf/SKILL.md")).unwrap(), "pdf");
+        assert!(!dest.join("skills/notes").exists());
+
+        assert!(sparse_checkout(&url, "main", "skills/missing", &dir.path().join("other")).is_err());
+    }
+
+    #[test]
+    fn test_path_commit_times() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        git(repo, &["init", "-q"]);
+        let commit_at = |ts: &str, msg: &str| {
+            git(repo, &["add", "-A"]);
+            let status = Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-q", "-m", msg])
+                .env("GIT_COMMITTER_DATE", format!("@{} +0000", ts))
+                .current_dir(repo)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+
+        for name in ["pdf", "notes"] {
+            std::fs::create_dir_all(repo.join("skills").join(name)).unwrap();
+            std::fs::write(repo.join("skills").join(name).join("SKILL.md"), "v1").unwrap();
+        }
+        commit_at("1600000000", "add skills");
+        std::fs::write(repo.join("skills/notes/SKILL.md"), "v2").unwrap();
+        commit_at("1700000000", "update notes");
+
+        let times = path_commit_times(repo, "skills").unwrap();
+        assert_eq!(times.get("skills/pdf"), Some(&1600000000));
+        assert_eq!(times.get("skills/notes"), Some(&1700000000));
+        assert_eq!(times.get("skills"), Some(&1700000000));
+
+        // One commit deep, pdf's last change is out of reach
+        std::fs::write(repo.join("skills/notes/SKILL.md"), "v3").unwrap();
+        commit_at("1750000000", "update notes again");
+        let shallow = dir.path().join("shallow");
+        let url = format!("file://{}", repo.display());
+        git(dir.path(), &["clone", "-q", "--depth=2", &url, shallow.to_str().unwrap()]);
+        let times = path_commit_times(&shallow, "skills").unwrap();
+        assert_eq!(times.get("skills/notes"), Some(&1750000000));
+        assert_eq!(times.get("skills/pdf"), None);
+    }
+
+    #[test]
+    fn test_clone_options_cover_every_registry_of_a_repo() {
+        assert_eq!(clone_options(&HashMap::new(), &OPENAI_REPO), git::CloneOptions::default());
+
+        let shallow = RegistryConfig { clone_depth: Some(10), single_branch: true, partial_clone: false, ..Default::default() };
+        let configs = HashMap::from([("openai".to_string(), shallow.clone())]);
+        // openai-experimental shares the clone and still wants its history
+        let options = clone_options(&configs, &OPENAI_REPO);
+        assert_eq!(options, git::CloneOptions { depth: None, single_branch: false, partial: false });
+
+        let configs = HashMap::from([
+            ("openai".to_string(), shallow.clone()),
+            ("openai-experimental".to_string(), RegistryConfig { clone_depth: Some(50), ..shallow.clone() }),
+        ]);
+        assert_eq!(clone_options(&configs, &OPENAI_REPO), git::CloneOptions { depth: Some(50), single_branch: true, partial: false });
+
+        // A disabled registry doesn't hold the others back
+        let configs = HashMap::from([
+            ("openai".to_string(), shallow.clone()),
+            ("openai-experimental".to_string(), RegistryConfig { enabled: false, ..Default::default() }),
+        ]);
+        assert_eq!(clone_options(&configs, &OPENAI_REPO).depth, Some(10));
+    }
+
     #[test]
     fn test_registries_configuration() {
         assert_eq!(REGISTRIES.len(), 5);
@@ -401,4 +1313,229 @@ This skill does cool things.
         assert_eq!(jo.name, "jo");
         assert!(jo.trusted);
     }
+
+    #[test]
+    fn test_openai_channels_share_one_clone() {
+        let openai = find_registry("openai").unwrap();
+        let experimental = find_registry("openai-experimental").unwrap();
+        assert_eq!(openai.repo.name, experimental.repo.name);
+        assert_eq!(openai.channel, Channel::Curated);
+        assert_eq!(experimental.channel, Channel::Experimental);
+        assert_eq!(channel_of("skillssh"), Channel::Community);
+
+        let repos = Path::new("/data/repos");
+        assert_eq!(repo_dir(repos, "openai-experimental"), repos.join("openai"));
+
+        let mut clone_names: Vec<_> = SOURCE_REPOS.iter().map(|r| r.name).collect();
+        clone_names.dedup();
+        assert_eq!(clone_names.len(), SOURCE_REPOS.len());
+        for registry in REGISTRIES {
+            assert!(SOURCE_REPOS.iter().any(|r| r.name == registry.repo.name), "{} has no source repo", registry.name);
+        }
+    }
+
+    #[test]
+    fn test_clone_space_needed_counts_fresh_clones() {
+        let dir = tempfile::tempdir().unwrap();
+        let all: u64 = SOURCE_REPOS.iter().map(|r| r.clone_size_estimate).sum();
+        assert_eq!(clone_space_needed(dir.path()), all);
+
+        // Existing clones only pull, shallow ones included
+        std::fs::create_dir_all(dir.path().join("clawdhub").join(".git")).unwrap();
+        std::fs::create_dir_all(dir.path().join("jo").join(".git")).unwrap();
+        std::fs::write(dir.path().join("jo").join(".git").join("shallow"), "").unwrap();
+        assert_eq!(clone_space_needed(dir.path()), all - CLAWDHUB_REPO.clone_size_estimate - JO_REPO.clone_size_estimate);
+    }
+
+    #[test]
+    fn test_exclude_filter() {
+        let filter = ExcludeFilter::new(&["**/examples/**".to_string(), "deprecated-*".to_string()]).unwrap();
+        assert!(filter.is_excluded(Path::new("examples/demo")));
+        assert!(filter.is_excluded(Path::new("alice/examples/demo")));
+        assert!(filter.is_excluded(Path::new("deprecated-pdf")));
+        assert!(filter.is_excluded(Path::new("alice/deprecated-pdf")));
+        assert!(!filter.is_excluded(Path::new("alice/pdf")));
+        assert!(!filter.is_excluded(Path::new("examples")));
+        assert!(ExcludeFilter::new(&[]).unwrap().is_empty());
+        assert!(ExcludeFilter::new(&["[unclosed".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_scan_skips_excluded_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let skills_dir = dir.path().join("skills");
+        for rel in ["pdf", "deprecated-old", "examples/demo", "alice/csv"] {
+            let skill_dir = skills_dir.join(rel);
+            std::fs::create_dir_all(&skill_dir).unwrap();
+            std::fs::write(skill_dir.join("SKILL.md"), "---\nname: test\ndescription: test\n---\n").unwrap();
+        }
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+        let registry = find_registry("jo").unwrap();
+        let filter = ExcludeFilter::new(&["**/examples/**".to_string(), "deprecated-*".to_string()]).unwrap();
+
+        scan_skills_dir(&mut db, &ScanTarget::of(registry), &skills_dir, dir.path(), None, &HashMap::new(), &filter).unwrap();
+
+        let mut slugs: Vec<_> = db.get_skills_by_registry("jo").unwrap().into_iter().map(|s| s.slug).collect();
+        slugs.sort();
+        assert_eq!(slugs, vec!["csv", "pdf"]);
+    }
+
+    #[test]
+    fn test_skills_gone_upstream_are_marked_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = find_registry("jo").unwrap();
+        let skills_dir = dir.path().join(registry.skills_path);
+        for slug in ["pdf", "csv"] {
+            std::fs::create_dir_all(skills_dir.join(slug)).unwrap();
+            std::fs::write(skills_dir.join(slug).join("SKILL.md"), "---\nname: test\ndescription: test\n---\n").unwrap();
+        }
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+        scan_registry(&mut db, dir.path(), registry, &[]).unwrap();
+
+        std::fs::remove_dir_all(skills_dir.join("csv")).unwrap();
+        scan_registry(&mut db, dir.path(), registry, &[]).unwrap();
+        assert!(db.get_removed_at("jo", "csv").unwrap().is_some());
+        assert!(db.get_skill("jo", "csv").unwrap().is_some());
+        assert_eq!(db.get_removed_at("jo", "pdf").unwrap(), None);
+
+        // Back upstream
+        std::fs::create_dir_all(skills_dir.join("csv")).unwrap();
+        std::fs::write(skills_dir.join("csv").join("SKILL.md"), "---\nname: test\ndescription: test\n---\n").unwrap();
+        scan_registry(&mut db, dir.path(), registry, &[]).unwrap();
+        assert!(db.get_removed().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_skills_failing_a_scan_are_not_marked_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = find_registry("jo").unwrap();
+        let skills_dir = dir.path().join(registry.skills_path);
+        std::fs::create_dir_all(skills_dir.join("pdf")).unwrap();
+        std::fs::write(skills_dir.join("pdf").join("SKILL.md"), "---\nname: test\ndescription: test\n---\n").unwrap();
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+        scan_registry(&mut db, dir.path(), registry, &[]).unwrap();
+
+        // Still upstream, but unreadable this time
+        std::fs::write(skills_dir.join("pdf").join("SKILL.md"), b"\xff\xfe not utf-8").unwrap();
+        scan_registry(&mut db, dir.path(), registry, &[]).unwrap();
+        assert_eq!(db.get_removed_at("jo", "pdf").unwrap(), None);
+    }
+
+    #[test]
+    fn test_import_local() {
+        let dir = tempfile::tempdir().unwrap();
+        let tree = dir.path().join("monorepo");
+        for rel in ["deploy", "data/etl", "examples/demo"] {
+            std::fs::create_dir_all(tree.join(rel)).unwrap();
+            std::fs::write(tree.join(rel).join("SKILL.md"), "---\nname: internal\ndescription: Internal tooling\n---\n").unwrap();
+        }
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+
+        assert_eq!(import_local(&mut db, &tree, "local", &["examples".to_string()]).unwrap(), 2);
+        let deploy = db.get_skill("local", "deploy").unwrap().unwrap();
+        assert_eq!(deploy.github_url, format!("file://{}/deploy", tree.canonicalize().unwrap().display()));
+        assert!(!deploy.trusted);
+        assert!(db.get_skill("local", "etl").unwrap().is_some());
+
+        // Importing again drops what's gone
+        std::fs::remove_dir_all(tree.join("deploy")).unwrap();
+        assert_eq!(import_local(&mut db, &tree, "local", &["examples".to_string()]).unwrap(), 1);
+        assert!(db.get_skill("local", "deploy").unwrap().is_none());
+
+        assert!(import_local(&mut db, &tree, "anthropic", &[]).is_err());
+        assert!(import_local(&mut db, &tree, "Local Skills", &[]).is_err());
+        assert!(import_local(&mut db, &dir.path().join("missing"), "local", &[]).is_err());
+    }
+
+    #[test]
+    fn test_over_limits() {
+        let day = 86_400;
+        let skill = |slug: &str, stars: i64, updated: Option<i64>| Skill {
+            github_url: String::new(),
+            stars,
+            upstream_updated_at: updated,
+            ..Skill::fixture("clawdhub", slug)
+        };
+        let now = 1000 * day;
+        let skills = vec![
+            skill("popular", 50, Some(now - day)),
+            skill("unstarred", 0, Some(now - day)),
+            skill("stale", 9, Some(now - 600 * day)),
+            skill("undated", 3, None),
+        ];
+        let slugs = |over: Vec<Skill>| over.into_iter().map(|s| s.slug).collect::<Vec<_>>();
+        let limits = RegistryConfig { min_stars: Some(1), max_age_days: Some(540), ..Default::default() };
+
+        assert_eq!(slugs(over_limits(skills.clone(), &limits, true, now)), ["unstarred", "stale"]);
+        // Without this sync's star counts, only the age limit applies
+        assert_eq!(slugs(over_limits(skills.clone(), &limits, false, now)), ["stale"]);
+        let capped = RegistryConfig { max_skills: Some(2), ..Default::default() };
+        assert_eq!(slugs(over_limits(skills.clone(), &capped, true, now)), ["undated", "unstarred"]);
+        assert!(over_limits(skills, &RegistryConfig::default(), true, now).is_empty());
+    }
+
+    #[test]
+    fn test_detect_moves_by_content() {
+        let skill = |registry: &str, slug: &str, body: &str| Skill {
+            skill_md: body.to_string(),
+            github_url: String::new(),
+            ..Skill::fixture(registry, slug)
+        };
+        let gone = vec![
+            skill("openai-experimental", "pdf", "# PDF"),
+            skill("openai-experimental", "edited", "# Old body"),
+            skill("clawdhub", "copied", "# Shared"),
+        ];
+        let present = vec![
+            skill("openai", "pdf", "# PDF"),
+            skill("openai", "edited", "# New body"),
+            skill("anthropic", "copied", "# Shared"),
+            skill("jo", "copied", "# Shared"),
+        ];
+
+        let moves = detect_moves(&gone, &present);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].from, ("openai-experimental".to_string(), "pdf".to_string()));
+        assert_eq!(moves[0].to, ("openai".to_string(), "pdf".to_string()));
+    }
+
+    #[test]
+    fn test_rename_within_registry_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+        let skill = |slug: &str, body: &str, updated_at| Skill {
+            name: "CSV".to_string(),
+            skill_md: body.to_string(),
+            github_url: String::new(),
+            trusted: true,
+            updated_at,
+            ..Skill::fixture("jo", slug)
+        };
+        db.upsert_skill(&skill("csv", "# CSV", 1)).unwrap();
+        db.upsert_skill(&skill("sheets", "# Sheets", 1)).unwrap();
+        db.record_skill_version("jo", "csv", "1.0.0", "aaa", 1).unwrap();
+        let mut existing: HashSet<_> = db.get_all_slugs().unwrap().into_iter().collect();
+
+        // This sync: csv was renamed to csv-tools, sheets is gone, and an
+        // older skill happens to match sheets' content
+        db.upsert_skill(&skill("csv-tools", "# CSV", 100)).unwrap();
+        db.upsert_skill(&skill("legacy-sheets", "# Sheets", 100)).unwrap();
+        existing.insert(("jo".to_string(), "legacy-sheets".to_string()));
+        migrate_moved_skills(&mut db, &["jo"], &existing, 50).unwrap();
+
+        assert_eq!(db.get_successor("jo", "csv").unwrap(), Some(("jo".to_string(), "csv-tools".to_string())));
+        assert_eq!(db.get_known_versions("jo", "csv-tools").unwrap(), vec!["1.0.0"]);
+        assert!(db.get_successor("jo", "sheets").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_registry_api_base_env_override() {
+        let clawdhub = &REGISTRIES[0];
+        assert_eq!(REGISTRIES[1].api_base_url(), None);
+
+        std::env::set_var("SKILL_SEARCH_CLAWDHUB_API_URL", "http://localhost:8080/");
+        assert_eq!(clawdhub.api_base_url().as_deref(), Some("http://localhost:8080"));
+        std::env::remove_var("SKILL_SEARCH_CLAWDHUB_API_URL");
+        assert_eq!(clawdhub.api_base_url().as_deref(), Some("https://clawhub.com"));
+    }
 }
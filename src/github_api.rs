@@ -0,0 +1,154 @@
+use crate::db::Database;
+use crate::github::{record_failure, Registry, SourceRepo};
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const API_BASE: &str = "https://api.github.com";
+
+/// Popularity and activity of a registry's GitHub repo
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoStats {
+    /// `owner/name`
+    pub repo: String,
+    pub stars: i64,
+    /// Open issues and pull requests, as GitHub counts them
+    pub open_issues: i64,
+    /// Last push to any branch
+    pub pushed_at: Option<i64>,
+    pub fetched_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoResponse {
+    full_name: String,
+    stargazers_count: i64,
+    open_issues_count: i64,
+    pushed_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopicsResponse {
+    names: Vec<String>,
+}
+
+/// The GitHub REST API. Uses `GITHUB_TOKEN` when set, for the higher rate
+/// limit.
+pub struct GithubClient {
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl GithubClient {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder().user_agent("skill-search/0.1").build()?,
+            token: std::env::var("GITHUB_TOKEN").ok().filter(|t| !t.trim().is_empty()),
+        })
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let mut request = self.http.get(format!("{}{}", API_BASE, path)).header("Accept", "application/vnd.github+json");
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    /// Topics set on a repo, as written on GitHub
+    pub async fn topics(&self, repo: &str) -> Result<Vec<String>> {
+        let response: TopicsResponse = self.get(&format!("/repos/{}/topics", repo)).await?;
+        Ok(response.names)
+    }
+
+    pub async fn repo_stats(&self, repo: &str) -> Result<RepoStats> {
+        let response: RepoResponse = self.get(&format!("/repos/{}", repo)).await?;
+        Ok(RepoStats {
+            repo: response.full_name,
+            stars: response.stargazers_count,
+            open_issues: response.open_issues_count,
+            pushed_at: response.pushed_at.as_deref().and_then(parse_timestamp),
+            fetched_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+        })
+    }
+}
+
+/// `owner/name` of a repo cloned from GitHub
+pub fn github_repo(repo: &SourceRepo) -> Option<&str> {
+    let path = repo.url.strip_prefix("https://github.com/")?;
+    let path = path.strip_suffix(".git").unwrap_or(path).trim_end_matches('/');
+    (path.split('/').count() == 2).then_some(path)
+}
+
+/// Give the skills of registries without a usage API their repo's stars,
+/// open issues, and last push. A registry whose repo can't be fetched keeps
+/// what it had. Returns the registries that got current stars.
+pub async fn sync_repo_stats(db: &mut Database, registries: &[&Registry]) -> Result<Vec<&'static str>> {
+    let client = GithubClient::new()?;
+    let mut fetched: HashMap<&str, RepoStats> = HashMap::new();
+    let mut updated = Vec::new();
+    for registry in registries {
+        let Some(repo) = github_repo(registry.repo) else {
+            continue;
+        };
+        if !fetched.contains_key(repo) {
+            match client.repo_stats(repo).await {
+                Ok(stats) => {
+                    fetched.insert(repo, stats);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch GitHub stats of {}: {:#}", repo, e);
+                    record_failure(db, registry.name, "repo-stats", &e);
+                    continue;
+                }
+            }
+        }
+        match db.set_repo_stats(registry.name, &fetched[repo]) {
+            Ok(()) => updated.push(registry.name),
+            Err(e) => {
+                tracing::warn!("Failed to store GitHub stats of {}: {:#}", registry.name, e);
+                record_failure(db, registry.name, "repo-stats", &e);
+            }
+        }
+    }
+    Ok(updated)
+}
+
+/// Unix time of a GitHub timestamp such as `2025-03-14T09:26:53Z`
+fn parse_timestamp(s: &str) -> Option<i64> {
+    let (date, time) = s.strip_suffix('Z')?.split_once('T')?;
+    let mut date_parts = date.splitn(3, '-').map(str::parse::<i64>);
+    let (y, m, d) = (date_parts.next()?.ok()?, date_parts.next()?.ok()?, date_parts.next()?.ok()?);
+    let mut time_parts = time.splitn(3, ':').map(str::parse::<i64>);
+    let (hh, mm, ss) = (time_parts.next()?.ok()?, time_parts.next()?.ok()?, time_parts.next()?.ok()?);
+    // Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant)
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some(days * 86_400 + hh * 3600 + mm * 60 + ss)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::find_registry;
+
+    #[test]
+    fn test_github_repo() {
+        assert_eq!(github_repo(find_registry("anthropic").unwrap().repo), Some("anthropics/skills"));
+        let elsewhere = SourceRepo { name: "x", url: "https://gitlab.com/x/skills.git", web_base: "", clone_size_estimate: 0 };
+        assert_eq!(github_repo(&elsewhere), None);
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2025-03-14T09:26:53Z"), Some(1_741_944_413));
+        assert_eq!(parse_timestamp("2024-02-29T00:00:00Z"), Some(1_709_164_800));
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+}
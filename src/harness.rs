@@ -0,0 +1,232 @@
+use crate::db::{Skill, TestResult};
+use crate::install;
+use anyhow::Result;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Directories a skill can keep its tests in, checked in order
+pub const TEST_DIRS: &[&str] = &["evals", "tests"];
+
+/// Manifest inside a test directory declaring the commands to run
+pub const MANIFEST_FILE: &str = "tests.toml";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Keep stored output small; the tail is where failures usually are
+const MAX_OUTPUT_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    test: Vec<TestCommand>,
+}
+
+/// One declared test, run with the test directory as working directory
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestCommand {
+    pub name: Option<String>,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Kill the command after this long (default: 300)
+    pub timeout_secs: Option<u64>,
+}
+
+impl TestCommand {
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.command.as_str()).chain(self.args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ")
+    }
+
+    pub fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.command_line())
+    }
+}
+
+/// A skill's declared tests
+#[derive(Debug)]
+pub struct TestSuite {
+    /// Test directory, relative to the skill directory
+    pub dir: PathBuf,
+    pub commands: Vec<TestCommand>,
+}
+
+/// Find the first test directory with a manifest in a skill directory
+pub fn find_tests(skill_dir: &Path) -> Result<Option<TestSuite>> {
+    for dir in TEST_DIRS {
+        let manifest_path = skill_dir.join(dir).join(MANIFEST_FILE);
+        if !manifest_path.exists() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&manifest_path)?;
+        let manifest: Manifest =
+            toml::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid test manifest {:?}: {}", manifest_path, e))?;
+        return Ok(Some(TestSuite {
+            dir: PathBuf::from(dir),
+            commands: manifest.test,
+        }));
+    }
+    Ok(None)
+}
+
+/// Run a suite against a copy of the skill in a fresh directory under
+/// `sandbox_root`, removed afterwards. Commands get a scrubbed environment
+/// (PATH only, with HOME and TMPDIR inside the sandbox) so they can't read
+/// tokens from yours, but they run with your user's permissions.
+pub fn run_tests(skill: &Skill, skill_dir: &Path, suite: &TestSuite, sandbox_root: &Path) -> Result<Vec<TestResult>> {
+    let sandbox = sandbox_root.join(format!("test-{}-{}-{}", skill.registry, skill.slug, std::process::id()));
+    if sandbox.exists() {
+        std::fs::remove_dir_all(&sandbox)?;
+    }
+    let result = run_in_sandbox(skill, skill_dir, suite, &sandbox);
+    if let Err(e) = std::fs::remove_dir_all(&sandbox) {
+        tracing::warn!("Could not remove test sandbox {:?}: {}", sandbox, e);
+    }
+    result
+}
+
+fn run_in_sandbox(skill: &Skill, skill_dir: &Path, suite: &TestSuite, sandbox: &Path) -> Result<Vec<TestResult>> {
+    let skill_copy = sandbox.join("skill");
+    install::execute_plan(&install::plan_install(skill_dir, &skill_copy)?, false)?;
+    let home = sandbox.join("home");
+    let tmp = sandbox.join("tmp");
+    std::fs::create_dir_all(&home)?;
+    std::fs::create_dir_all(&tmp)?;
+
+    let mut results = Vec::new();
+    for (i, test) in suite.commands.iter().enumerate() {
+        let log_path = sandbox.join(format!("test-{}.log", i));
+        let log = std::fs::File::create(&log_path)?;
+        let mut cmd = Command::new(&test.command);
+        cmd.args(&test.args)
+            .current_dir(skill_copy.join(&suite.dir))
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env("HOME", &home)
+            .env("TMPDIR", &tmp)
+            .env("SKILL_PATH", &skill_copy)
+            .env("SKILL_SLUG", &skill.slug)
+            .env("SKILL_REGISTRY", &skill.registry)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+
+        let timeout = Duration::from_secs(test.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        let (exit_code, note) = match cmd.spawn() {
+            Ok(mut child) => {
+                let started = Instant::now();
+                loop {
+                    if let Some(status) = child.try_wait()? {
+                        break (status.code(), None);
+                    }
+                    if started.elapsed() > timeout {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        break (None, Some(format!("timed out after {}s", timeout.as_secs())));
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+            }
+            Err(e) => (None, Some(format!("failed to run {}: {}", test.command, e))),
+        };
+
+        let mut output = tail(&std::fs::read(&log_path).unwrap_or_default());
+        if let Some(note) = note {
+            output.push_str(&note);
+        }
+        results.push(TestResult {
+            name: test.display_name(),
+            exit_code,
+            output,
+        });
+    }
+    Ok(results)
+}
+
+fn tail(bytes: &[u8]) -> String {
+    let start = bytes.len().saturating_sub(MAX_OUTPUT_BYTES);
+    String::from_utf8_lossy(&bytes[start..]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_test_skill() -> Skill {
+        Skill {
+            github_url: String::new(),
+            trusted: true,
+            ..Skill::fixture("jo", "csv")
+        }
+    }
+
+    #[test]
+    fn test_find_tests_prefers_evals() {
+        let dir = tempdir().unwrap();
+        assert!(find_tests(dir.path()).unwrap().is_none());
+
+        std::fs::create_dir_all(dir.path().join("tests")).unwrap();
+        std::fs::write(dir.path().join("tests").join(MANIFEST_FILE), "[[test]]\ncommand = \"true\"\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("evals")).unwrap();
+        std::fs::write(
+            dir.path().join("evals").join(MANIFEST_FILE),
+            "[[test]]\nname = \"parse\"\ncommand = \"python3\"\nargs = [\"eval.py\"]\n",
+        )
+        .unwrap();
+
+        let suite = find_tests(dir.path()).unwrap().unwrap();
+        assert_eq!(suite.dir, PathBuf::from("evals"));
+        assert_eq!(suite.commands[0].display_name(), "parse");
+
+        std::fs::write(dir.path().join("evals").join(MANIFEST_FILE), "test = 3").unwrap();
+        assert!(find_tests(dir.path()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tests_in_sandbox() {
+        let dir = tempdir().unwrap();
+        let skill_dir = dir.path().join("skill");
+        std::fs::create_dir_all(skill_dir.join("tests")).unwrap();
+        std::fs::write(skill_dir.join("SKILL.md"), "# CSV").unwrap();
+        std::fs::write(skill_dir.join("tests").join("fixture.csv"), "a,b\n").unwrap();
+        std::fs::write(
+            skill_dir.join("tests").join(MANIFEST_FILE),
+            r#"
+[[test]]
+name = "fixture present"
+command = "sh"
+args = ["-c", "test -f fixture.csv && echo ok"]
+
+[[test]]
+command = "sh"
+args = ["-c", "test -z \"$SECRET_TOKEN\" && echo scrubbed && exit 3"]
+
+[[test]]
+name = "slow"
+command = "sleep"
+args = ["5"]
+timeout_secs = 0
+"#,
+        )
+        .unwrap();
+
+        std::env::set_var("SECRET_TOKEN", "hunter2");
+        let suite = find_tests(&skill_dir).unwrap().unwrap();
+        let sandbox_root = dir.path().join("staging");
+        let results = run_tests(&create_test_skill(), &skill_dir, &suite, &sandbox_root).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].passed());
+        assert_eq!(results[0].output.trim(), "ok");
+        assert_eq!(results[1].exit_code, Some(3));
+        assert_eq!(results[1].output.trim(), "scrubbed");
+        assert!(!results[2].passed());
+        assert!(results[2].output.contains("timed out"));
+        // The sandbox is gone and the skill itself untouched
+        assert_eq!(std::fs::read_dir(&sandbox_root).unwrap().count(), 0);
+        assert!(!skill_dir.join("home").exists());
+    }
+}
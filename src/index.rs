@@ -1,98 +1,501 @@
-use crate::db::Database;
+use crate::config::{FieldWeights, IndexConfig};
+use crate::db::{Database, Skill};
+use crate::github::parse_skill_frontmatter;
+use crate::overlay;
+use crate::text::{index_text, trim_index_text};
+use crate::unicode::normalize_for_search;
 use anyhow::Result;
-use std::path::Path;
-use tantivy::collector::TopDocs;
-use tantivy::query::{BooleanQuery, Occur, QueryParser, TermQuery};
-use tantivy::schema::{IndexRecordOption, Schema, STORED, STRING, TEXT, Field, Value};
-use tantivy::{Index, IndexWriter, Term, TantivyDocument};
+use schemars::JsonSchema;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tantivy::collector::{DocSetCollector, TopDocs};
+use tantivy::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use tantivy::directory::{Directory, DirectoryLock, FileHandle, Lock, MmapDirectory, WatchCallback, WatchHandle, WritePtr};
+use std::ops::Bound;
+use tantivy::query::{AllQuery, BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, Query, QueryParser, RangeQuery, TermQuery};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::schema::{IndexRecordOption, Schema, FAST, INDEXED, STORED, STRING, TEXT, Field, TextFieldIndexing, TextOptions, Value};
+use tantivy::tokenizer::{LowerCaser, RemoveLongFilter, SimpleTokenizer, StopWordFilter, TextAnalyzer};
+use tantivy::{Index, IndexReader, IndexWriter, Term, TantivyDocument};
 
+/// Index size per byte of skill text: postings and positions for the
+/// content field plus the stored fields, which keep the text for snippets,
+/// with room for unmerged segments. An upper bound, since bodies over
+/// `max_content_bytes` are only indexed up to it.
+const INDEX_BYTES_PER_CONTENT_BYTE: u64 = 4;
+
+/// Disk space a rebuild needs. The old segments stay on disk until the new
+/// ones are committed, so this is on top of the current index.
+pub fn rebuild_space_needed(db: &Database) -> Result<u64> {
+    Ok(db.content_bytes()? * INDEX_BYTES_PER_CONTENT_BYTE)
+}
+
+/// Memory-mapped reads only. Searching takes tantivy's meta lock, which
+/// means creating a lock file; nothing here writes, so locks are no-ops.
+#[derive(Clone, Debug)]
+struct ReadOnlyDirectory(MmapDirectory);
+
+fn read_only_error() -> Arc<std::io::Error> {
+    Arc::new(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "search index is opened read-only"))
+}
+
+impl Directory for ReadOnlyDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        self.0.get_file_handle(path)
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        Err(DeleteError::IoError { io_error: read_only_error(), filepath: PathBuf::from(path) })
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.0.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        Err(OpenWriteError::IoError { io_error: read_only_error(), filepath: PathBuf::from(path) })
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.0.atomic_read(path)
+    }
+
+    fn atomic_write(&self, _path: &Path, _data: &[u8]) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "search index is opened read-only"))
+    }
+
+    fn sync_directory(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn acquire_lock(&self, _lock: &Lock) -> Result<DirectoryLock, LockError> {
+        Ok(DirectoryLock::from(Box::new(())))
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tantivy::Result<WatchHandle> {
+        // Watching only reads meta.json, and lets the shared reader pick up
+        // a rebuild made wherever the index is written
+        self.0.watch(watch_callback)
+    }
+}
+
+/// Tokenizer for descriptions and SKILL.md bodies
+const SKILL_TEXT_TOKENIZER: &str = "skill_text";
+
+/// Words nearly every skill uses, which say nothing about what it does
+pub const STOPWORDS: &[&str] = &[
+    "skill", "skills", "use", "uses", "using", "used", "claude", "agent", "agents", "assistant",
+];
+
+/// The default tokenizer plus stopword removal, as configured
+fn skill_text_analyzer(config: &IndexConfig) -> TextAnalyzer {
+    let mut words: Vec<String> = Vec::new();
+    if config.stopwords {
+        words.extend(STOPWORDS.iter().map(|w| w.to_string()));
+        words.extend(config.extra_stopwords.iter().map(|w| w.to_lowercase()));
+    }
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(StopWordFilter::remove(words))
+        .build()
+}
+
+/// Full-text index of a catalog's skills
 pub struct SearchIndex {
     index: Index,
-    #[allow(dead_code)]
+    /// Shared by every search, so a batch of queries opens the segments once
+    reader: IndexReader,
     schema: Schema,
     slug_field: Field,
     name_field: Field,
     description_field: Field,
     content_field: Field,
+    raw_field: Field,
     registry_field: Field,
+    overlay_field: Field,
+    stars_field: Field,
+    trusted_field: Field,
+    has_version_field: Field,
+    tags_field: Field,
+    key_field: Field,
+    config: IndexConfig,
+    recreated: bool,
+}
+
+fn build_schema() -> Schema {
+    let skill_text = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(SKILL_TEXT_TOKENIZER)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    );
+
+    let mut schema_builder = Schema::builder();
+    schema_builder.add_text_field("slug", TEXT | STORED);
+    schema_builder.add_text_field("name", TEXT | STORED);
+    schema_builder.add_text_field("description", skill_text.clone() | STORED);
+    // Searched but not stored: `show` reads the full SKILL.md from the
+    // database, and snippets are cut from `raw`
+    schema_builder.add_text_field("content", skill_text);
+    // Stored only, the body text the snippets under each hit come from
+    schema_builder.add_text_field("raw", STORED);
+    schema_builder.add_text_field("registry", STRING | STORED);
+    schema_builder.add_bool_field("overlay", STORED);
+    // Facets, filtered on inside the query
+    schema_builder.add_i64_field("stars", INDEXED | FAST);
+    schema_builder.add_bool_field("trusted", INDEXED | FAST);
+    schema_builder.add_bool_field("has_version", INDEXED | FAST);
+    schema_builder.add_text_field("tags", STRING);
+    // `registry:slug`, untokenized, so one skill's document can be replaced
+    schema_builder.add_text_field("key", STRING);
+    schema_builder.build()
 }
 
 impl SearchIndex {
-    pub fn open_or_create(index_path: &Path) -> Result<Self> {
+    /// Open the index in `index_path`, recreating it (empty) when it was
+    /// built with a different schema
+    pub fn open_or_create(index_path: &Path, config: &IndexConfig) -> Result<Self> {
         std::fs::create_dir_all(index_path)?;
-
-        let mut schema_builder = Schema::builder();
-        let slug_field = schema_builder.add_text_field("slug", TEXT | STORED);
-        let name_field = schema_builder.add_text_field("name", TEXT | STORED);
-        let description_field = schema_builder.add_text_field("description", TEXT | STORED);
-        let content_field = schema_builder.add_text_field("content", TEXT);
-        let registry_field = schema_builder.add_text_field("registry", STRING | STORED);
-        let schema = schema_builder.build();
+        let schema = build_schema();
 
         let index = if index_path.join("meta.json").exists() {
-            Index::open_in_dir(index_path)?
+            let existing = Index::open_in_dir(index_path)?;
+            if existing.schema() == schema {
+                existing
+            } else {
+                // Built by an older version; it's derived data, so start over
+                tracing::info!("Search index schema changed, recreating {:?}", index_path);
+                drop(existing);
+                return Self::recreate(index_path, config);
+            }
         } else {
             Index::create_in_dir(index_path, schema.clone())?
         };
+        Self::from_index(index, config, false)
+    }
+
+    /// Open an index built elsewhere without writing to its directory, which
+    /// may be on a read-only filesystem. It can be searched, not rebuilt.
+    pub fn open_read_only(index_path: &Path, config: &IndexConfig) -> Result<Self> {
+        if !index_path.join("meta.json").exists() {
+            anyhow::bail!("No search index at {:?}", index_path);
+        }
+        let index = Index::open(ReadOnlyDirectory(MmapDirectory::open(index_path)?))?;
+        if index.schema() != build_schema() {
+            anyhow::bail!("Search index at {:?} was built by a different version; rebuild it there", index_path);
+        }
+        Self::from_index(index, config, false)
+    }
+
+    /// Replace whatever is at `index_path` with an empty index, to be rebuilt
+    pub fn recreate(index_path: &Path, config: &IndexConfig) -> Result<Self> {
+        if index_path.exists() {
+            std::fs::remove_dir_all(index_path)?;
+        }
+        std::fs::create_dir_all(index_path)?;
+        Self::from_index(Index::create_in_dir(index_path, build_schema())?, config, true)
+    }
 
+    /// A throwaway index held in memory, for searching an ad-hoc set of skills
+    pub fn in_memory(config: &IndexConfig) -> Result<Self> {
+        Self::from_index(Index::create_in_ram(build_schema()), config, false)
+    }
+
+    fn from_index(index: Index, config: &IndexConfig, recreated: bool) -> Result<Self> {
+        index.tokenizers().register(SKILL_TEXT_TOKENIZER, skill_text_analyzer(config));
+        let schema = index.schema();
         Ok(Self {
+            slug_field: schema.get_field("slug")?,
+            name_field: schema.get_field("name")?,
+            description_field: schema.get_field("description")?,
+            content_field: schema.get_field("content")?,
+            raw_field: schema.get_field("raw")?,
+            registry_field: schema.get_field("registry")?,
+            overlay_field: schema.get_field("overlay")?,
+            stars_field: schema.get_field("stars")?,
+            trusted_field: schema.get_field("trusted")?,
+            has_version_field: schema.get_field("has_version")?,
+            tags_field: schema.get_field("tags")?,
+            key_field: schema.get_field("key")?,
+            reader: index.reader()?,
             index,
             schema,
-            slug_field,
-            name_field,
-            description_field,
-            content_field,
-            registry_field,
+            config: config.clone(),
+            recreated,
         })
     }
 
+    /// True if an outdated on-disk index was discarded and needs a rebuild
+    pub fn recreated(&self) -> bool {
+        self.recreated
+    }
+
+    /// Replace the index's contents with every skill in `db`
     pub fn rebuild(&self, db: &Database) -> Result<()> {
+        let mut skills = db.get_all_skills()?;
+        tracing::info!("Indexing {} skills", skills.len());
+        let mut overlaid = HashSet::new();
+        if let Some(dir) = &self.config.overlay_dir {
+            for skill in &mut skills {
+                if overlay::apply(dir, skill) {
+                    overlaid.insert((skill.registry.clone(), skill.slug.clone()));
+                }
+            }
+            tracing::info!("{} skills shadowed by the overlay in {:?}", overlaid.len(), dir);
+        }
+        self.write_documents(&skills, &overlaid, &db.topics_by_skill()?)?;
+        tracing::info!("Index rebuilt");
+        Ok(())
+    }
+
+    /// Replace the indexed documents with these skills
+    pub fn index_skills(&self, skills: &[Skill]) -> Result<()> {
+        self.write_documents(skills, &HashSet::new(), &HashMap::new())
+    }
+
+    /// `topics` are tags from each skill's repo, added to its frontmatter's
+    fn write_documents(&self, skills: &[Skill], overlaid: &HashSet<(String, String)>, topics: &HashMap<(String, String), Vec<String>>) -> Result<()> {
         let mut index_writer: IndexWriter = self.index.writer(50_000_000)?;
         index_writer.delete_all_documents()?;
 
-        let skills = db.get_all_skills()?;
-        tracing::info!("Indexing {} skills", skills.len());
-
         for skill in skills {
-            let mut doc = TantivyDocument::new();
-            doc.add_text(self.slug_field, &skill.slug);
-            doc.add_text(self.name_field, &skill.name);
-            doc.add_text(self.description_field, &skill.description);
-            doc.add_text(self.registry_field, &skill.registry);
-            // Combine name, description, and skill_md for full-text search
-            let content = format!("{} {} {}", skill.name, skill.description, skill.skill_md);
-            doc.add_text(self.content_field, &content);
-            index_writer.add_document(doc)?;
+            let key = (skill.registry.clone(), skill.slug.clone());
+            index_writer.add_document(self.document(skill, overlaid.contains(&key), topics.get(&key).map_or(&[][..], Vec::as_slice)))?;
         }
 
         index_writer.commit()?;
-        tracing::info!("Index rebuilt");
+        // Don't wait for the reader to notice the commit on its own
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Re-index just these skills from `db`, dropping the ones it no longer
+    /// has. Much cheaper than [`rebuild`](Self::rebuild) after a sync that
+    /// changed a few.
+    pub fn update(&self, db: &Database, keys: &[(String, String)]) -> Result<()> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let mut index_writer: IndexWriter = self.index.writer(50_000_000)?;
+        let topics = db.topics_by_skill()?;
+        for (registry, slug) in keys {
+            index_writer.delete_term(Term::from_field_text(self.key_field, &format!("{}:{}", registry, slug)));
+            let Some(mut skill) = db.get_skill(registry, slug)? else {
+                continue;
+            };
+            let overlaid = self.config.overlay_dir.as_deref().is_some_and(|dir| overlay::apply(dir, &mut skill));
+            let skill_topics = topics.get(&(registry.clone(), slug.clone())).map_or(&[][..], Vec::as_slice);
+            index_writer.add_document(self.document(&skill, overlaid, skill_topics))?;
+        }
+        index_writer.commit()?;
+        self.reader.reload()?;
+        tracing::info!("Re-indexed {} skills", keys.len());
         Ok(())
     }
 
-    pub fn search(&self, query_str: &str, limit: usize, registry: Option<&str>) -> Result<Vec<SearchResult>> {
-        let reader = self.index.reader()?;
-        let searcher = reader.searcher();
+    fn document(&self, skill: &Skill, overlaid: bool, topics: &[String]) -> TantivyDocument {
+        let mut doc = TantivyDocument::new();
+        doc.add_text(self.key_field, format!("{}:{}", skill.registry, skill.slug));
+        doc.add_text(self.slug_field, &skill.slug);
+        // Index lookalike letters and invisible characters in their plain
+        // form so disguised names still match an ordinary query
+        let name = normalize_for_search(&skill.name);
+        let description = normalize_for_search(&skill.description);
+        doc.add_text(self.name_field, &name);
+        doc.add_text(self.description_field, &description);
+        doc.add_text(self.registry_field, &skill.registry);
+        // Combine name, description, and the SKILL.md body for full-text
+        // search; the frontmatter would only repeat name and description.
+        // A huge body is indexed up to the quota, which keeps one generated
+        // reference from bloating the postings and skewing term statistics.
+        let body = normalize_for_search(&index_text(&skill.skill_md, self.config.collapse_code_blocks));
+        let body = trim_index_text(&body, self.config.max_content_bytes);
+        doc.add_text(self.content_field, format!("{} {} {}", name, description, body));
+        doc.add_text(self.raw_field, body);
+        doc.add_bool(self.overlay_field, overlaid);
+        doc.add_i64(self.stars_field, skill.stars);
+        doc.add_bool(self.trusted_field, skill.trusted);
+        doc.add_bool(self.has_version_field, skill.version.is_some());
+        let mut tags = parse_skill_frontmatter(&skill.skill_md).tags;
+        for topic in topics {
+            if !tags.contains(topic) {
+                tags.push(topic.clone());
+            }
+        }
+        for tag in tags {
+            doc.add_text(self.tags_field, &tag);
+        }
+        doc
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.num_docs() == 0)
+    }
+
+    /// Indexed skills
+    pub fn num_docs(&self) -> u64 {
+        self.reader.searcher().num_docs()
+    }
+
+    /// Merge segments until at most `target` are left, dropping deleted
+    /// documents on the way. The smallest are merged together and the
+    /// `target - 1` largest kept. Returns the segment counts before and
+    /// after, or `None` if there was nothing to merge.
+    pub fn optimize(&self, target: usize) -> Result<Option<(usize, usize)>> {
+        let target = target.max(1);
+        let mut metas = self.index.searchable_segment_metas()?;
+        let before = metas.len();
+        let deleted = metas.iter().any(|m| m.has_deletes());
+        if before <= target && !deleted {
+            return Ok(None);
+        }
+        metas.sort_by_key(|m| std::cmp::Reverse(m.num_docs()));
+        let merged: Vec<_> = metas.iter().skip(target - 1).map(|m| m.id()).collect();
+
+        let mut index_writer: IndexWriter = self.index.writer(50_000_000)?;
+        // A lone segment is still rewritten, without its deleted documents
+        index_writer.merge(&merged).wait()?;
+        index_writer.garbage_collect_files().wait()?;
+        index_writer.wait_merging_threads()?;
+        self.reader.reload()?;
+        Ok(Some((before, self.index.searchable_segment_ids()?.len())))
+    }
+
+    /// Segments, documents, and where the bytes go, with the `largest`
+    /// documents by stored text
+    pub fn stats(&self, largest: usize) -> Result<IndexStats> {
+        let searcher = self.reader.searcher();
+        let usage = searcher.space_usage()?;
+        let mut fields: Vec<FieldStats> = Vec::new();
+        let mut store_bytes = 0;
+        for (segment_reader, segment) in searcher.segment_readers().iter().zip(usage.segments()) {
+            store_bytes += segment.store().total().get_bytes();
+            for (name, field) in [("slug", self.slug_field), ("name", self.name_field), ("description", self.description_field), ("content", self.content_field), ("registry", self.registry_field), ("tags", self.tags_field)] {
+                let bytes: u64 = [segment.termdict(), segment.postings(), segment.positions(), segment.fieldnorms()]
+                    .iter()
+                    .flat_map(|per_field| per_field.fields())
+                    .filter(|(f, _)| **f == field)
+                    .map(|(_, u)| u.total().get_bytes())
+                    .sum();
+                let terms = segment_reader.inverted_index(field)?.terms().num_terms() as u64;
+                match fields.iter_mut().find(|s| s.field == name) {
+                    Some(stats) => {
+                        stats.terms += terms;
+                        stats.bytes += bytes;
+                    }
+                    None => fields.push(FieldStats { field: name.to_string(), terms, bytes }),
+                }
+            }
+        }
+
+        let mut documents = Vec::new();
+        for address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let field = |f| doc.get_first(f).and_then(|v| v.as_str()).unwrap_or("").to_string();
+            let bytes = [self.name_field, self.description_field, self.raw_field].iter().map(|&f| field(f).len() as u64).sum();
+            documents.push(DocumentSize { registry: field(self.registry_field), slug: field(self.slug_field), bytes });
+        }
+        documents.sort_by_key(|d| std::cmp::Reverse(d.bytes));
+        documents.truncate(largest);
 
-        let query_parser = QueryParser::for_index(
+        Ok(IndexStats {
+            schema_version: crate::output::SCHEMA_VERSION,
+            segments: searcher.segment_readers().len(),
+            docs: searcher.segment_readers().iter().map(|s| s.num_docs() as u64).sum(),
+            deleted_docs: searcher.segment_readers().iter().map(|s| s.num_deleted_docs() as u64).sum(),
+            bytes: usage.total().get_bytes(),
+            store_bytes,
+            fields,
+            largest: documents,
+        })
+    }
+
+    /// (registry, slug) of every indexed document, duplicates included
+    pub fn keys(&self) -> Result<Vec<(String, String)>> {
+        let searcher = self.reader.searcher();
+        let mut keys = Vec::new();
+        for address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let field = |f| doc.get_first(f).and_then(|v| v.as_str()).unwrap_or("").to_string();
+            keys.push((field(self.registry_field), field(self.slug_field)));
+        }
+        Ok(keys)
+    }
+
+    /// Best `limit` matches for `query_str` among the skills `filters` keep
+    pub fn search(&self, query_str: &str, limit: usize, filters: &IndexFilters) -> Result<Vec<SearchResult>> {
+        let mut query_parser = QueryParser::for_index(
             &self.index,
             vec![self.name_field, self.description_field, self.content_field],
         );
-        let text_query = query_parser.parse_query(query_str)?;
+        let weights = &self.config.weights;
+        query_parser.set_field_boost(self.name_field, weights.name);
+        query_parser.set_field_boost(self.description_field, weights.description);
+        query_parser.set_field_boost(self.content_field, weights.content);
+        let text_query = query_parser.parse_query(&normalize_for_search(query_str))?;
+        self.run(text_query, limit, filters, false)
+    }
 
-        // Build final query with optional registry filter
-        let final_query: Box<dyn tantivy::query::Query> = if let Some(reg) = registry {
+    /// Like `search`, but each word also matches words a few typos away,
+    /// up to the configured edit distance. Scores only count which fields
+    /// matched, so they don't compare with `search`'s.
+    pub fn search_fuzzy(&self, query_str: &str, limit: usize, filters: &IndexFilters) -> Result<Vec<SearchResult>> {
+        let mut analyzer = skill_text_analyzer(&self.config);
+        let normalized = normalize_for_search(query_str);
+        let mut stream = analyzer.token_stream(&normalized);
+        let mut words: Vec<String> = Vec::new();
+        while stream.advance() {
+            if !words.contains(&stream.token().text) {
+                words.push(stream.token().text.clone());
+            }
+        }
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let weights = &self.config.weights;
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for word in &words {
+            let distance = fuzzy_distance(word, self.config.fuzzy_distance.clamp(1, 2));
+            for (field, boost) in [(self.name_field, weights.name), (self.description_field, weights.description), (self.content_field, weights.content)] {
+                let term = Term::from_field_text(field, word);
+                let query: Box<dyn Query> = if distance == 0 {
+                    Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+                } else {
+                    // A swapped pair of letters is one typo, not two
+                    Box::new(FuzzyTermQuery::new(term, distance, true))
+                };
+                clauses.push((Occur::Should, Box::new(BoostQuery::new(query, boost))));
+            }
+        }
+        self.run(Box::new(BooleanQuery::new(clauses)), limit, filters, true)
+    }
+
+    fn run(&self, text_query: Box<dyn Query>, limit: usize, filters: &IndexFilters, fuzzy: bool) -> Result<Vec<SearchResult>> {
+        let searcher = self.reader.searcher();
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, text_query)];
+        if let Some(reg) = filters.registry {
             let registry_term = Term::from_field_text(self.registry_field, reg);
-            let registry_query = TermQuery::new(registry_term, IndexRecordOption::Basic);
-            Box::new(BooleanQuery::new(vec![
-                (Occur::Must, text_query),
-                (Occur::Must, Box::new(registry_query)),
-            ]))
-        } else {
-            text_query
-        };
+            clauses.push((Occur::Must, Box::new(TermQuery::new(registry_term, IndexRecordOption::Basic))));
+        }
+        for tag in filters.tags {
+            clauses.push((Occur::Must, Box::new(TermQuery::new(Term::from_field_text(self.tags_field, tag), IndexRecordOption::Basic))));
+        }
+        if let Some(min) = filters.min_stars {
+            clauses.push((Occur::Must, Box::new(RangeQuery::new_i64_bounds(self.schema.get_field_name(self.stars_field).to_string(), Bound::Included(min), Bound::Unbounded))));
+        }
+        for (wanted, field) in [(filters.trusted, self.trusted_field), (filters.has_version, self.has_version_field)] {
+            if wanted {
+                clauses.push((Occur::Must, Box::new(TermQuery::new(Term::from_field_bool(field, true), IndexRecordOption::Basic))));
+            }
+        }
+        let final_query: Box<dyn Query> = if clauses.len() == 1 { clauses.remove(0).1 } else { Box::new(BooleanQuery::new(clauses)) };
 
         let top_docs = searcher.search(&*final_query, &TopDocs::with_limit(limit))?;
+        // Fuzzy terms aren't known words, so those hits go without
+        let snippets = if fuzzy { None } else { Some(SnippetGenerator::create(&searcher, &*final_query, self.content_field)?) };
 
         let mut results = Vec::new();
         for (score, doc_address) in top_docs {
@@ -114,13 +517,21 @@ impl SearchIndex {
                 .and_then(|v| v.as_str())
                 .unwrap_or("")
                 .to_string();
+            let overlay = doc.get_first(self.overlay_field)
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let raw = doc.get_first(self.raw_field).and_then(|v| v.as_str()).unwrap_or("");
+            let snippet = snippets.as_ref().map(|g| g.snippet(raw)).filter(|s| !s.is_empty()).map(|s| Snippet::from(&s));
 
             results.push(SearchResult {
                 slug,
                 name,
                 description,
                 registry,
+                overlay,
                 score,
+                fuzzy,
+                snippet,
             });
         }
 
@@ -128,13 +539,141 @@ impl SearchIndex {
     }
 }
 
+/// Edits a word may be from the query's and still match: none for very
+/// short words, where one typo makes a different word, and no more than
+/// `max`
+fn fuzzy_distance(word: &str, max: u8) -> u8 {
+    match word.chars().count() {
+        0..=2 => 0,
+        3..=5 => max.min(1),
+        _ => max,
+    }
+}
+
+/// Restrictions applied inside the index query, so a search's limit counts
+/// only skills that pass them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IndexFilters<'a> {
+    pub registry: Option<&'a str>,
+    pub min_stars: Option<i64>,
+    /// Only skills the catalog marks trusted; trust decisions aren't indexed,
+    /// so callers leave this off when a decision could add trust
+    pub trusted: bool,
+    pub has_version: bool,
+    /// Tags a skill must all have, normalized with
+    /// [`crate::github::normalize_tag`]
+    pub tags: &'a [String],
+}
+
+impl<'a> IndexFilters<'a> {
+    pub fn registry(registry: Option<&'a str>) -> Self {
+        Self { registry, ..Self::default() }
+    }
+
+    /// The same test for catalog skills searched without the index
+    pub fn matches(&self, db: &Database, skill: &Skill) -> bool {
+        self.registry.is_none_or(|r| skill.registry == r)
+            && self.min_stars.is_none_or(|min| skill.stars >= min)
+            && (!self.trusted || skill.trusted)
+            && (!self.has_version || skill.version.is_some())
+            && (self.tags.is_empty() || {
+                let tags = db.get_tags(&skill.registry, &skill.slug).unwrap_or_default();
+                self.tags.iter().all(|t| tags.contains(t))
+            })
+    }
+}
+
+/// Output of `index stats`
+#[derive(Debug, serde::Serialize, JsonSchema)]
+pub struct IndexStats {
+    pub schema_version: u32,
+    pub segments: usize,
+    /// Live documents, one per indexed skill
+    pub docs: u64,
+    /// Replaced documents still on disk until their segments are merged
+    pub deleted_docs: u64,
+    /// On disk, all segments together
+    pub bytes: u64,
+    /// Of `bytes`, the stored fields kept for results and snippets
+    pub store_bytes: u64,
+    /// Indexed text fields, with terms summed over segments
+    pub fields: Vec<FieldStats>,
+    /// Largest documents first
+    pub largest: Vec<DocumentSize>,
+}
+
+#[derive(Debug, serde::Serialize, JsonSchema)]
+pub struct FieldStats {
+    pub field: String,
+    pub terms: u64,
+    /// Term dictionary, postings, positions, and norms
+    pub bytes: u64,
+}
+
+/// Name, description, and text of a document as stored
+#[derive(Debug, serde::Serialize, JsonSchema)]
+pub struct DocumentSize {
+    pub registry: String,
+    pub slug: String,
+    pub bytes: u64,
+}
+
+/// One search hit; `score` is only comparable within one search
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SearchResult {
     pub slug: String,
     pub name: String,
     pub description: String,
     pub registry: String,
+    /// Indexed from the overlay copy rather than the catalog's
+    pub overlay: bool,
     pub score: f32,
+    /// Found by `search_fuzzy`, so it may only match a close spelling
+    #[serde(default)]
+    pub fuzzy: bool,
+    /// Where the query matched in the skill's text
+    #[serde(default)]
+    pub snippet: Option<Snippet>,
+}
+
+/// An excerpt of a skill's text around the words a search matched
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, JsonSchema)]
+pub struct Snippet {
+    pub text: String,
+    /// Byte ranges of `text` that matched, as `[start, end)` pairs
+    pub highlights: Vec<(usize, usize)>,
+}
+
+impl From<&tantivy::snippet::Snippet> for Snippet {
+    fn from(snippet: &tantivy::snippet::Snippet) -> Self {
+        Self {
+            // Line breaks would split the excerpt; swapping them for spaces
+            // keeps the byte offsets
+            text: snippet.fragment().replace(['\n', '\r', '\t'], " "),
+            highlights: snippet.highlighted().iter().map(|r| (r.start, r.end)).collect(),
+        }
+    }
+}
+
+impl Snippet {
+    /// The excerpt on one line, with matches in bold when `color` is set
+    pub fn render(&self, color: bool) -> String {
+        if !color {
+            return self.text.clone();
+        }
+        let mut out = String::new();
+        let mut from = 0;
+        for &(start, end) in &self.highlights {
+            if start < from || end > self.text.len() {
+                continue;
+            }
+            out.push_str(&self.text[from..start]);
+            out.push_str(&format!("\x1b[1m{}\x1b[0m", &self.text[start..end]));
+            from = end;
+        }
+        out.push_str(&self.text[from..]);
+        out
+    }
 }
 
 impl SearchResult {
@@ -143,25 +682,135 @@ impl SearchResult {
     }
 }
 
+/// Results from the system and user layers' indexes in one ranking. A skill
+/// both return keeps the system hit.
+pub fn merge_layers(system: Vec<SearchResult>, user: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut seen = HashSet::new();
+    let mut merged: Vec<_> = system.into_iter().chain(user).filter(|r| seen.insert(r.unique_key())).collect();
+    merged.sort_by(|a, b| b.score.total_cmp(&a.score));
+    merged
+}
+
+/// BM25 score, per query word, of a match that's as likely good as not.
+/// A word found in a skill's name and description scores well above it;
+/// one found only somewhere in the body, well below.
+const HALF_CONFIDENCE_SCORE: f32 = 10.0;
+
+/// What a ranking's scores are, for [`confidence`]
+#[derive(Debug, Clone, Copy)]
+pub enum ScoreScale {
+    /// From the index or the database fallback, for a query of this many
+    /// words
+    Keyword { words: usize },
+    /// Already between 0 and 1, as blended semantic scores are
+    Unit,
+}
+
+impl ScoreScale {
+    pub fn keyword(query: &str) -> Self {
+        ScoreScale::Keyword { words: query.split_whitespace().count().max(1) }
+    }
+}
+
+/// How sure a hit is to be what was searched for, 0-1: how strong its score
+/// is on its own, scaled down by how far it trails the best hit and by how
+/// little it leads the next one. A top hit tied with the next gets half
+/// the confidence of one that stands alone.
+pub fn confidence(score: f32, next: Option<f32>, best: f32, scale: ScoreScale) -> f32 {
+    if score <= 0.0 || best <= 0.0 {
+        return 0.0;
+    }
+    let absolute = match scale {
+        ScoreScale::Keyword { words } => score / (score + HALF_CONFIDENCE_SCORE * words as f32),
+        ScoreScale::Unit => score.min(1.0),
+    };
+    let lead = next.map(|n| ((score - n) / score).clamp(0.0, 1.0)).unwrap_or(1.0);
+    let confidence = absolute * (score / best).min(1.0) * (0.5 + 0.5 * lead);
+    (confidence * 100.0).round() / 100.0
+}
+
+/// Pair each item of a ranking, best first, with its [`confidence`]. Looks
+/// one item ahead, so apply it before cutting the ranking to a limit.
+pub fn with_confidence<T>(items: impl Iterator<Item = T>, scale: ScoreScale, score: impl Fn(&T) -> f32) -> impl Iterator<Item = (T, f32)> {
+    let mut items = items.peekable();
+    let mut best = None;
+    std::iter::from_fn(move || {
+        let item = items.next()?;
+        let this = score(&item);
+        let best = *best.get_or_insert(this);
+        let next = items.peek().map(&score);
+        Some((item, confidence(this, next, best, scale)))
+    })
+}
+
+/// Substring search straight over the database, for when the index can't be
+/// used. Every term has to appear somewhere; results rank by which fields
+/// they appear in, using the index's field weights, then by stars.
+pub fn search_database(db: &Database, query: &str, limit: usize, filters: &IndexFilters, weights: &FieldWeights) -> Result<Vec<SearchResult>> {
+    let terms: Vec<String> = normalize_for_search(query)
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty() && !STOPWORDS.contains(t))
+        .map(str::to_string)
+        .collect();
+
+    let mut scored: Vec<(f32, Skill)> = db
+        .search_like(&terms, filters.registry)?
+        .into_iter()
+        .filter(|skill| filters.matches(db, skill))
+        .map(|skill| {
+            let (name, description, body) = (skill.name.to_lowercase(), skill.description.to_lowercase(), skill.skill_md.to_lowercase());
+            let score = terms
+                .iter()
+                .map(|t| {
+                    if name.contains(t.as_str()) {
+                        weights.name
+                    } else if description.contains(t.as_str()) {
+                        weights.description
+                    } else if body.contains(t.as_str()) {
+                        weights.content
+                    } else {
+                        0.0
+                    }
+                })
+                .sum();
+            (score, skill)
+        })
+        .collect();
+    scored.sort_by(|(a, x), (b, y)| b.total_cmp(a).then(y.stars.cmp(&x.stars)));
+
+    Ok(scored
+        .into_iter()
+        .take(limit)
+        .map(|(score, s)| SearchResult {
+            slug: s.slug,
+            name: s.name,
+            description: s.description,
+            registry: s.registry,
+            overlay: false,
+            score,
+            fuzzy: false,
+            snippet: None,
+        })
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::FieldWeights;
     use crate::db::{Database, Skill};
     use tempfile::tempdir;
 
     fn create_test_skill(slug: &str, name: &str, description: &str, registry: &str) -> Skill {
         Skill {
-            id: 0,
-            slug: slug.to_string(),
             name: name.to_string(),
-            registry: registry.to_string(),
             description: description.to_string(),
             skill_md: format!("# {}\n\n{}", name, description),
-            github_url: format!("https://github.com/test/{}", slug),
             version: Some("1.0.0".to_string()),
-            stars: 0,
             trusted: registry == "anthropic",
             updated_at: 1234567890,
+            ..Skill::fixture(registry, slug)
         }
     }
 
@@ -169,10 +818,60 @@ mod tests {
     fn test_search_index_create() {
         let dir = tempdir().unwrap();
         let index_path = dir.path().join("index");
-        let _index = SearchIndex::open_or_create(&index_path).unwrap();
+        let _index = SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap();
         assert!(index_path.join("meta.json").exists());
     }
 
+    #[test]
+    fn test_index_stats() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let mut long = create_test_skill("pdf", "PDF Tools", "Fill PDF forms", "anthropic");
+        long.skill_md.push_str(&"\n\nMerge, split, and rotate pages.".repeat(50));
+        db.upsert_skill(&long).unwrap();
+        db.upsert_skill(&create_test_skill("docx", "Word", "Edit documents", "anthropic")).unwrap();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+
+        let stats = index.stats(1).unwrap();
+        assert_eq!(stats.segments, 1);
+        assert_eq!((stats.docs, stats.deleted_docs), (2, 0));
+        assert!(stats.bytes > stats.store_bytes && stats.store_bytes > 0);
+        let content = stats.fields.iter().find(|f| f.field == "content").unwrap();
+        assert!(content.terms >= 10 && content.bytes > 0);
+        assert_eq!(stats.largest.len(), 1);
+        assert_eq!(stats.largest[0].slug, "pdf");
+        assert!(stats.largest[0].bytes > 1000);
+    }
+
+    #[test]
+    fn test_optimize_merges_segments() {
+        let index = SearchIndex::in_memory(&IndexConfig::default()).unwrap();
+        let mut index_writer: IndexWriter = index.index.writer(15_000_000).unwrap();
+        for slugs in [&["pdf", "pptx"][..], &["docx"], &["xlsx"], &["csv"]] {
+            for slug in slugs {
+                let mut doc = TantivyDocument::new();
+                doc.add_text(index.slug_field, slug);
+                doc.add_text(index.name_field, slug);
+                doc.add_text(index.registry_field, "anthropic");
+                index_writer.add_document(doc).unwrap();
+            }
+            index_writer.commit().unwrap();
+        }
+        index_writer.delete_term(Term::from_field_text(index.slug_field, "pptx"));
+        index_writer.commit().unwrap();
+        drop(index_writer);
+        index.reader.reload().unwrap();
+        assert_eq!(index.stats(0).unwrap().segments, 4);
+
+        assert_eq!(index.optimize(2).unwrap(), Some((4, 2)));
+        assert_eq!(index.optimize(1).unwrap(), Some((2, 1)));
+        let stats = index.stats(0).unwrap();
+        assert_eq!((stats.docs, stats.deleted_docs), (4, 0));
+        assert_eq!(index.optimize(1).unwrap(), None);
+        assert_eq!(index.search("docx", 10, &IndexFilters::default()).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_search_index_rebuild_and_search() {
         let dir = tempdir().unwrap();
@@ -184,14 +883,81 @@ mod tests {
         db.upsert_skill(&create_test_skill("pdf-reader", "PDF Reader", "Read and extract PDF content", "anthropic")).unwrap();
         db.upsert_skill(&create_test_skill("browser", "Browser Automation", "Automate browser tasks", "openai")).unwrap();
 
-        let index = SearchIndex::open_or_create(&index_path).unwrap();
+        let index = SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap();
         index.rebuild(&db).unwrap();
 
-        let results = index.search("calendar", 10, None).unwrap();
+        let results = index.search("calendar", 10, &IndexFilters::default()).unwrap();
         assert!(!results.is_empty());
         assert_eq!(results[0].slug, "calendar");
     }
 
+    #[test]
+    fn test_update_replaces_only_the_given_skills() {
+        let dir = tempdir().unwrap();
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("calendar", "Calendar Manager", "Manage your calendar events", "clawdhub")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf-reader", "PDF Reader", "Read and extract PDF content", "anthropic")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf-reader", "PDF Reader", "Read PDFs", "clawdhub")).unwrap();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+
+        db.upsert_skill(&create_test_skill("calendar", "Calendar Manager", "Schedule meetings", "clawdhub")).unwrap();
+        db.delete_skill("clawdhub", "pdf-reader").unwrap();
+        db.upsert_skill(&create_test_skill("browser", "Browser Automation", "Automate browser tasks", "openai")).unwrap();
+        let keys = [("clawdhub", "calendar"), ("clawdhub", "pdf-reader"), ("openai", "browser")].map(|(r, s)| (r.to_string(), s.to_string()));
+        index.update(&db, &keys).unwrap();
+
+        assert_eq!(index.num_docs(), 3);
+        assert_eq!(index.search("meetings", 10, &IndexFilters::default()).unwrap()[0].slug, "calendar");
+        assert!(index.search("events", 10, &IndexFilters::default()).unwrap().is_empty());
+        assert_eq!(index.search("browser", 10, &IndexFilters::default()).unwrap()[0].registry, "openai");
+        // Same slug, other registry: left alone
+        let pdf: Vec<_> = index.search("pdf", 10, &IndexFilters::default()).unwrap().into_iter().map(|r| r.registry).collect();
+        assert_eq!(pdf, ["anthropic"]);
+    }
+
+    #[test]
+    fn test_merge_layers_prefers_system() {
+        let hit = |registry: &str, slug: &str, score: f32| SearchResult {
+            slug: slug.to_string(),
+            name: slug.to_string(),
+            description: String::new(),
+            registry: registry.to_string(),
+            overlay: false,
+            score,
+            fuzzy: false,
+            snippet: None,
+        };
+        let merged = merge_layers(
+            vec![hit("anthropic", "pdf", 2.0), hit("openai", "pdf", 1.0)],
+            vec![hit("mine", "pdf", 3.0), hit("anthropic", "pdf", 9.0)],
+        );
+        let ids: Vec<_> = merged.iter().map(|r| (r.unique_key(), r.score)).collect();
+        assert_eq!(ids, [("mine:pdf".to_string(), 3.0), ("anthropic:pdf".to_string(), 2.0), ("openai:pdf".to_string(), 1.0)]);
+    }
+
+    #[test]
+    fn test_open_read_only_searches_without_writing() {
+        let dir = tempdir().unwrap();
+        let index_path = dir.path().join("index");
+        assert!(SearchIndex::open_read_only(&index_path, &IndexConfig::default()).is_err());
+
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf-reader", "PDF Reader", "Read and extract PDF content", "anthropic")).unwrap();
+        SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap().rebuild(&db).unwrap();
+        let listing = || {
+            let mut names: Vec<_> = std::fs::read_dir(&index_path).unwrap().map(|e| e.unwrap().file_name()).collect();
+            names.sort();
+            names
+        };
+        let before = listing();
+
+        let index = SearchIndex::open_read_only(&index_path, &IndexConfig::default()).unwrap();
+        assert_eq!(index.search("pdf", 10, &IndexFilters::default()).unwrap()[0].slug, "pdf-reader");
+        assert!(index.rebuild(&db).is_err());
+        assert_eq!(listing(), before);
+    }
+
     #[test]
     fn test_search_with_registry_filter() {
         let dir = tempdir().unwrap();
@@ -202,14 +968,49 @@ mod tests {
         db.upsert_skill(&create_test_skill("skill1", "Test Skill One", "A test skill", "clawdhub")).unwrap();
         db.upsert_skill(&create_test_skill("skill2", "Test Skill Two", "Another test skill", "anthropic")).unwrap();
 
-        let index = SearchIndex::open_or_create(&index_path).unwrap();
+        let index = SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap();
         index.rebuild(&db).unwrap();
 
-        let results = index.search("test skill", 10, Some("anthropic")).unwrap();
+        let results = index.search("test skill", 10, &IndexFilters::registry(Some("anthropic"))).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].registry, "anthropic");
     }
 
+    #[test]
+    fn test_search_with_facet_filters() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        // More low-star matches than the limit, which post-filtering would lose
+        for i in 0..5 {
+            db.upsert_skill(&create_test_skill(&format!("pdf{}", i), "PDF Tools", "Edit pdf files", "clawdhub")).unwrap();
+        }
+        let mut popular = create_test_skill("pdf-pro", "PDF Pro", "Edit pdf files", "clawdhub");
+        popular.stars = 500;
+        popular.version = None;
+        popular.skill_md = "---\ntags: [Forms, Office]\n---\n# PDF Pro\n\nEdit pdf files".to_string();
+        db.upsert_skill(&popular).unwrap();
+        db.upsert_skill(&create_test_skill("pdf", "PDF", "Edit pdf files", "anthropic")).unwrap();
+
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+        let slugs = |filters: IndexFilters| -> Vec<String> { index.search("pdf", 2, &filters).unwrap().into_iter().map(|r| r.slug).collect() };
+
+        assert_eq!(slugs(IndexFilters { min_stars: Some(100), ..Default::default() }), ["pdf-pro"]);
+        assert_eq!(slugs(IndexFilters { trusted: true, ..Default::default() }), ["pdf"]);
+        assert!(!slugs(IndexFilters { has_version: true, ..Default::default() }).contains(&"pdf-pro".to_string()));
+        assert!(slugs(IndexFilters { min_stars: Some(100), has_version: true, ..Default::default() }).is_empty());
+        let forms = ["forms".to_string()];
+        assert_eq!(slugs(IndexFilters { tags: &forms, ..Default::default() }), ["pdf-pro"]);
+        assert!(slugs(IndexFilters { tags: &["forms".to_string(), "legal".to_string()], ..Default::default() }).is_empty());
+        assert_eq!(db.tag_counts(None).unwrap(), [("forms".to_string(), 1), ("office".to_string(), 1)]);
+
+        let filters = IndexFilters { min_stars: Some(100), ..Default::default() };
+        let results = search_database(&db, "pdf", 2, &filters, &FieldWeights::default()).unwrap();
+        assert_eq!(results.iter().map(|r| r.slug.as_str()).collect::<Vec<_>>(), ["pdf-pro"]);
+        let results = search_database(&db, "pdf", 2, &IndexFilters { tags: &forms, ..Default::default() }, &FieldWeights::default()).unwrap();
+        assert_eq!(results.iter().map(|r| r.slug.as_str()).collect::<Vec<_>>(), ["pdf-pro"]);
+    }
+
     #[test]
     fn test_search_no_results() {
         let dir = tempdir().unwrap();
@@ -219,10 +1020,10 @@ mod tests {
         let db = Database::open(&db_path).unwrap();
         db.upsert_skill(&create_test_skill("calendar", "Calendar", "Calendar app", "clawdhub")).unwrap();
 
-        let index = SearchIndex::open_or_create(&index_path).unwrap();
+        let index = SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap();
         index.rebuild(&db).unwrap();
 
-        let results = index.search("nonexistent xyz abc", 10, None).unwrap();
+        let results = index.search("nonexistent xyz abc", 10, &IndexFilters::default()).unwrap();
         assert!(results.is_empty());
     }
 
@@ -233,7 +1034,10 @@ mod tests {
             name: "Test Skill".to_string(),
             description: "A test".to_string(),
             registry: "clawdhub".to_string(),
+            overlay: false,
             score: 1.0,
+            fuzzy: false,
+            snippet: None,
         };
         assert_eq!(result.unique_key(), "clawdhub:test-skill");
     }
@@ -254,10 +1058,10 @@ mod tests {
             )).unwrap();
         }
 
-        let index = SearchIndex::open_or_create(&index_path).unwrap();
+        let index = SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap();
         index.rebuild(&db).unwrap();
 
-        let results = index.search("test skill", 3, None).unwrap();
+        let results = index.search("test skill", 3, &IndexFilters::default()).unwrap();
         assert_eq!(results.len(), 3);
     }
 
@@ -272,11 +1076,271 @@ mod tests {
         skill.skill_md = "# Unique Skill\n\nThis skill handles XYZABC123 tasks.".to_string();
         db.upsert_skill(&skill).unwrap();
 
-        let index = SearchIndex::open_or_create(&index_path).unwrap();
+        let index = SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap();
         index.rebuild(&db).unwrap();
 
-        let results = index.search("XYZABC123", 10, None).unwrap();
+        let results = index.search("XYZABC123", 10, &IndexFilters::default()).unwrap();
         assert!(!results.is_empty());
         assert_eq!(results[0].slug, "unique");
     }
+
+    #[test]
+    fn test_long_bodies_are_indexed_up_to_the_quota() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let mut skill = create_test_skill("reference", "API Reference", "Endpoint docs", "clawdhub");
+        skill.skill_md = format!("# Reference\n\nStart with QWERTY42 here.\n{}Appendix ZXCVB99.\n", "Padding line of text.\n".repeat(100));
+        db.upsert_skill(&skill).unwrap();
+
+        let config = IndexConfig { max_content_bytes: 200, ..Default::default() };
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &config).unwrap();
+        index.rebuild(&db).unwrap();
+        let results = index.search("QWERTY42", 10, &IndexFilters::default()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].snippet.as_ref().unwrap().text.contains("QWERTY42"));
+        assert!(index.search("ZXCVB99", 10, &IndexFilters::default()).unwrap().is_empty());
+        assert!(index.stats(1).unwrap().largest[0].bytes < 300);
+        // The catalog keeps the whole body for `show`
+        assert!(db.get_skill("clawdhub", "reference").unwrap().unwrap().skill_md.contains("ZXCVB99"));
+    }
+
+    #[test]
+    fn test_search_matches_homoglyph_names() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        // Cyrillic "а" in the name
+        db.upsert_skill(&create_test_skill("pdf-reаder", "PDF Reаder", "Reads files", "clawdhub")).unwrap();
+
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+
+        let results = index.search("reader", 10, &IndexFilters::default()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].slug, "pdf-reаder");
+    }
+
+    #[test]
+    fn test_frontmatter_and_code_are_not_indexed() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let mut skill = create_test_skill("notes", "Notes", "Take notes", "clawdhub");
+        skill.skill_md = "---\nname: notes\nlicense: Apache\n---\n# Notes\n\n```bash\nnotectl sync\n```\n".to_string();
+        db.upsert_skill(&skill).unwrap();
+
+        let config = IndexConfig { collapse_code_blocks: true, ..Default::default() };
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &config).unwrap();
+        index.rebuild(&db).unwrap();
+
+        assert!(index.search("apache", 10, &IndexFilters::default()).unwrap().is_empty());
+        assert!(index.search("notectl", 10, &IndexFilters::default()).unwrap().is_empty());
+        assert_eq!(index.search("notes", 10, &IndexFilters::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_name_match_outranks_body_mentions() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let mut wordy = create_test_skill("office", "Office Suite", "Documents, sheets, and slides", "clawdhub");
+        wordy.skill_md = "# Office\n\nSpreadsheet tips. Spreadsheet formulas. Spreadsheet charts. Spreadsheet pivots. Spreadsheet macros.\n".to_string();
+        db.upsert_skill(&wordy).unwrap();
+        db.upsert_skill(&create_test_skill("xlsx", "Spreadsheet", "Edit xlsx files", "anthropic")).unwrap();
+
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+        let boosted = index.search("spreadsheet", 10, &IndexFilters::default()).unwrap();
+        assert_eq!(boosted[0].slug, "xlsx");
+
+        let flat = IndexConfig {
+            weights: FieldWeights { name: 1.0, description: 1.0, content: 1.0 },
+            ..Default::default()
+        };
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &flat).unwrap();
+        let unboosted = index.search("spreadsheet", 10, &IndexFilters::default()).unwrap();
+        assert!(unboosted[0].score < boosted[0].score);
+    }
+
+    #[test]
+    fn test_stopwords_do_not_match() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("notes", "Notes", "A Claude skill for taking notes", "clawdhub")).unwrap();
+
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+        assert!(index.search("claude", 10, &IndexFilters::default()).unwrap().is_empty());
+        assert_eq!(index.search("claude notes", 10, &IndexFilters::default()).unwrap().len(), 1);
+
+        let config = IndexConfig { stopwords: false, ..Default::default() };
+        let index = SearchIndex::open_or_create(&dir.path().join("index2"), &config).unwrap();
+        index.rebuild(&db).unwrap();
+        assert_eq!(index.search("claude", 10, &IndexFilters::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_confidence() {
+        let scale = ScoreScale::keyword("pdf forms");
+        let ranked = |scores: &[f32], scale| with_confidence(scores.iter().copied(), scale, |s| *s).map(|(_, c)| c).collect::<Vec<_>>();
+        // A strong hit well ahead of the rest
+        let clear = ranked(&[60.0, 10.0, 9.0], scale);
+        assert!(clear[0] > 0.6, "{:?}", clear);
+        assert!(clear[1] < 0.3 && clear[2] < 0.3, "{:?}", clear);
+        // The same score tied with the next is worth choosing between
+        let tied = ranked(&[60.0, 59.0], scale);
+        assert!(tied[0] < clear[0] && tied[0] > 0.3, "{:?}", tied);
+        // Weak matches stay weak, however far ahead
+        assert!(ranked(&[4.0], scale)[0] < 0.3);
+        assert_eq!(ranked(&[0.9, 0.0], ScoreScale::Unit), vec![0.9, 0.0]);
+        assert!(ranked(&[], scale).is_empty());
+    }
+
+    #[test]
+    fn test_search_snippets() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let mut skill = create_test_skill("sheets", "Sheets", "Work with tables", "clawdhub");
+        skill.skill_md = "# Sheets\n\nOpen a spreadsheet,\nthen sort its rows.".to_string();
+        db.upsert_skill(&skill).unwrap();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+
+        let results = index.search("spreadsheet", 10, &IndexFilters::default()).unwrap();
+        let snippet = results[0].snippet.as_ref().unwrap();
+        assert!(!snippet.text.contains('\n'));
+        let (start, end) = snippet.highlights[0];
+        assert_eq!(&snippet.text[start..end], "spreadsheet");
+        assert!(snippet.render(true).contains("\x1b[1mspreadsheet\x1b[0m"));
+        assert_eq!(snippet.render(false), snippet.text);
+        assert!(index.search_fuzzy("spredsheet", 10, &IndexFilters::default()).unwrap()[0].snippet.is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_search_tolerates_typos() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("calendar", "Calendar", "Schedule meetings on a calendar", "clawdhub")).unwrap();
+        db.upsert_skill(&create_test_skill("browser", "Browser", "Drive a headless browser", "openai")).unwrap();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+
+        assert!(index.search("calender", 10, &IndexFilters::default()).unwrap().is_empty());
+        let results = index.search_fuzzy("calender", 10, &IndexFilters::default()).unwrap();
+        assert_eq!(results.iter().map(|r| r.slug.as_str()).collect::<Vec<_>>(), vec!["calendar"]);
+        assert!(results[0].fuzzy);
+        // A swapped pair of letters is a single edit
+        assert_eq!(index.search_fuzzy("borwser", 10, &IndexFilters::default()).unwrap()[0].slug, "browser");
+        assert!(index.search_fuzzy("borwser", 10, &IndexFilters::registry(Some("clawdhub"))).unwrap().is_empty());
+        // Two typos are too many at the default distance
+        assert!(index.search_fuzzy("calenderr", 10, &IndexFilters::default()).unwrap().is_empty());
+
+        let config = IndexConfig { fuzzy_distance: 2, ..Default::default() };
+        let index = SearchIndex::open_or_create(&dir.path().join("index2"), &config).unwrap();
+        index.rebuild(&db).unwrap();
+        assert_eq!(index.search_fuzzy("calenderr", 10, &IndexFilters::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_extra_stopwords() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("notes", "Notes", "Workflow for taking notes", "clawdhub")).unwrap();
+
+        let config = IndexConfig { extra_stopwords: vec!["Workflow".to_string()], ..Default::default() };
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &config).unwrap();
+        index.rebuild(&db).unwrap();
+        assert!(index.search("workflow", 10, &IndexFilters::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_overlay_shadows_catalog_content() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf", "PDF", "Read documents", "clawdhub")).unwrap();
+        db.upsert_skill(&create_test_skill("csv", "CSV", "Spreadsheet files", "clawdhub")).unwrap();
+
+        let overlay_dir = dir.path().join("overlay");
+        let path = overlay::overlay_path(&overlay_dir, "clawdhub", "pdf");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "---\ndescription: Read documents through the internal archive\n---\n").unwrap();
+
+        let config = IndexConfig { overlay_dir: Some(overlay_dir), ..IndexConfig::default() };
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &config).unwrap();
+        index.rebuild(&db).unwrap();
+
+        let results = index.search("archive", 10, &IndexFilters::default()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].overlay);
+        assert_eq!(results[0].description, "Read documents through the internal archive");
+
+        let results = index.search("spreadsheet", 10, &IndexFilters::default()).unwrap();
+        assert!(!results[0].overlay);
+    }
+
+    #[test]
+    fn test_in_memory_index() {
+        let index = SearchIndex::in_memory(&IndexConfig::default()).unwrap();
+        index
+            .index_skills(&[
+                create_test_skill("csv", "CSV", "Spreadsheet files", "clawdhub"),
+                create_test_skill("pdf", "PDF", "Read documents", "anthropic"),
+            ])
+            .unwrap();
+
+        let results = index.search("spreadsheet", 10, &IndexFilters::default()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].slug, "csv");
+        assert_eq!(index.keys().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_outdated_schema_is_recreated() {
+        let dir = tempdir().unwrap();
+        let index_path = dir.path().join("index");
+        let mut builder = Schema::builder();
+        builder.add_text_field("slug", TEXT | STORED);
+        std::fs::create_dir_all(&index_path).unwrap();
+        Index::create_in_dir(&index_path, builder.build()).unwrap();
+
+        let index = SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap();
+        assert!(index.recreated());
+        let index = SearchIndex::open_or_create(&index_path, &IndexConfig::default()).unwrap();
+        assert!(!index.recreated());
+    }
+
+    #[test]
+    fn test_corrupt_index_can_be_recreated() {
+        let dir = tempdir().unwrap();
+        let index_path = dir.path().join("index");
+        std::fs::create_dir_all(&index_path).unwrap();
+        std::fs::write(index_path.join("meta.json"), "{ truncated").unwrap();
+
+        assert!(SearchIndex::open_or_create(&index_path, &IndexConfig::default()).is_err());
+        let index = SearchIndex::recreate(&index_path, &IndexConfig::default()).unwrap();
+        assert!(index.recreated());
+        assert!(index.is_empty().unwrap());
+        assert!(SearchIndex::open_or_create(&index_path, &IndexConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn test_search_database_without_index() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf-reader", "PDF Reader", "Read and extract PDF content", "anthropic")).unwrap();
+        let mut mentions = create_test_skill("docs", "Docs", "Write documentation", "clawdhub");
+        mentions.skill_md = "Export to PDF with pandoc".to_string();
+        mentions.stars = 100;
+        db.upsert_skill(&mentions).unwrap();
+        db.upsert_skill(&create_test_skill("csv_tools", "CSV Tools", "100% local CSV editing", "clawdhub")).unwrap();
+
+        let weights = FieldWeights::default();
+        let results = search_database(&db, "pdf", 10, &IndexFilters::default(), &weights).unwrap();
+        let slugs: Vec<_> = results.iter().map(|r| r.slug.as_str()).collect();
+        assert_eq!(slugs, ["pdf-reader", "docs"]);
+
+        // Every term must match, and LIKE wildcards are taken literally
+        assert_eq!(search_database(&db, "pdf pandoc", 10, &IndexFilters::default(), &weights).unwrap().len(), 1);
+        assert!(search_database(&db, "pdf", 10, &IndexFilters::registry(Some("openai")), &weights).unwrap().is_empty());
+        let matches = db.search_like(&["%".to_string()], None).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].slug, "csv_tools");
+    }
 }
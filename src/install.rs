@@ -0,0 +1,655 @@
+use crate::audit_log::Action;
+use crate::config::Hook;
+use crate::db::{Database, HookRun, InstalledSkill, Skill};
+use crate::github;
+use crate::pack::{self, SkillPack};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCRIPT_EXTENSIONS: &[&str] = &["sh", "bash", "zsh", "fish", "py", "js", "mjs", "ts", "rb", "pl", "ps1", "bat", "cmd"];
+
+#[derive(Debug, Clone)]
+pub struct PlannedFile {
+    /// Path relative to the skill directory
+    pub rel_path: PathBuf,
+    pub size: u64,
+    pub is_script: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct InstallPlan {
+    pub source_dir: PathBuf,
+    pub dest_dir: PathBuf,
+    pub files: Vec<PlannedFile>,
+}
+
+impl InstallPlan {
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    pub fn scripts(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files.iter().filter(|f| f.is_script)
+    }
+
+    /// SHA-256 over the relative paths and contents of the source files
+    pub fn content_hash(&self) -> Result<String> {
+        let mut hasher = Sha256::new();
+        for file in &self.files {
+            let bytes = std::fs::read(self.source_dir.join(&file.rel_path))?;
+            hasher.update(file.rel_path.to_string_lossy().replace('\\', "/").as_bytes());
+            hasher.update([0]);
+            hasher.update((bytes.len() as u64).to_le_bytes());
+            hasher.update(&bytes);
+        }
+        Ok(format!("sha256:{}", hex::encode(hasher.finalize())))
+    }
+}
+
+/// Default skills directory: `~/.claude/skills`
+pub fn default_skills_dir() -> Result<PathBuf> {
+    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
+    Ok(PathBuf::from(home).join(".claude").join("skills"))
+}
+
+/// Path of a skill inside its registry repo: the part after
+/// `/tree/<branch>/` in its GitHub URL.
+pub fn skill_repo_path(skill: &Skill) -> Option<&str> {
+    let (_, after_tree) = skill.github_url.split_once("/tree/")?;
+    let (_, rel_path) = after_tree.split_once('/')?;
+    Some(rel_path)
+}
+
+/// Where to fetch a skill from GitHub directly: the repo's clone URL, the
+/// branch or tag, and the skill's path in the repo
+pub fn github_source(skill: &Skill) -> Option<(String, &str, &str)> {
+    let rest = skill.github_url.strip_prefix("https://github.com/")?;
+    let (repo, after_tree) = rest.split_once("/tree/")?;
+    let (git_ref, rel_path) = after_tree.split_once('/')?;
+    if repo.split('/').count() != 2 || rel_path.is_empty() {
+        return None;
+    }
+    Some((format!("https://github.com/{}.git", repo), git_ref, rel_path))
+}
+
+/// Locate the synced copy of a skill inside `repos_dir`
+pub fn local_skill_dir(repos_dir: &Path, skill: &Skill) -> Option<PathBuf> {
+    // Imported skills stay where they were imported from
+    let dir = match skill.github_url.strip_prefix("file://") {
+        Some(path) => PathBuf::from(path),
+        None => github::repo_dir(repos_dir, &skill.registry).join(skill_repo_path(skill)?),
+    };
+    dir.join("SKILL.md").exists().then_some(dir)
+}
+
+/// Installed skills as they are on disk: catalog entries whose SKILL.md,
+/// name, and description are taken from the most recently installed local
+/// copy, so local edits are what gets searched. Installs whose copy was
+/// deleted fall back to the catalog content.
+pub fn installed_skills(db: &Database) -> Result<Vec<Skill>> {
+    let mut skills: Vec<Skill> = Vec::new();
+    for install in db.get_installs()? {
+        if skills.iter().any(|s| s.registry == install.registry && s.slug == install.slug) {
+            continue;
+        }
+        let Some(mut skill) = db.get_skill(&install.registry, &install.slug)? else {
+            continue;
+        };
+        if let Ok(skill_md) = std::fs::read_to_string(Path::new(&install.local_path).join("SKILL.md")) {
+            github::apply_skill_md(&mut skill, skill_md);
+        }
+        skills.push(skill);
+    }
+    Ok(skills)
+}
+
+/// An install ready to execute: the file plan plus what it will record.
+/// Files exported from a pinned revision are staged and removed on drop.
+pub struct PreparedInstall {
+    pub plan: InstallPlan,
+    pub installed_version: Option<String>,
+    pub installed_rev: Option<String>,
+    pub pinned: bool,
+    /// Trusted key name or public key of whoever signed the skill pack
+    pub signer: Option<String>,
+    staging_dir: Option<PathBuf>,
+}
+
+/// Prepare installing `skill` into `dest_dir`, either from the synced clone
+/// or, with `pin_rev`, from that commit of the registry repo. Skills with no
+/// synced copy are fetched from GitHub with a sparse checkout.
+pub fn prepare_install(repos_dir: &Path, staging_root: &Path, skill: &Skill, dest_dir: &Path, pin_rev: Option<&str>) -> Result<PreparedInstall> {
+    let repo_dir = github::repo_dir(repos_dir, &skill.registry);
+
+    let (source_dir, installed_rev, staging_dir) = match pin_rev {
+        Some(rev) => {
+            let repo_path = skill_repo_path(skill)
+                .ok_or_else(|| anyhow::anyhow!("{} is not hosted in a git registry", skill.slug))?;
+            let sha = github::fetch_revision(&repo_dir, rev)?;
+            let staging = staging_root.join(format!("{}-{}", skill.registry, skill.slug));
+            if staging.exists() {
+                std::fs::remove_dir_all(&staging)?;
+            }
+            github::export_path_at_rev(&repo_dir, &sha, repo_path, &staging)?;
+            (staging.clone(), Some(sha), Some(staging))
+        }
+        None => match (local_skill_dir(repos_dir, skill), github_source(skill)) {
+            (Some(dir), _) => (dir, github::head_commit(&repo_dir).ok(), None),
+            (None, Some((url, git_ref, repo_path))) => {
+                let staging = staging_root.join(format!("{}-{}", skill.registry, skill.slug));
+                if staging.exists() {
+                    std::fs::remove_dir_all(&staging)?;
+                }
+                let sha = github::sparse_checkout(&url, git_ref, repo_path, &staging)?;
+                (staging.join(repo_path), Some(sha), Some(staging))
+            }
+            (None, None) => anyhow::bail!(
+                "No local copy of {}:{} (try `sync`, or install manually from {})",
+                skill.registry,
+                skill.slug,
+                skill.github_url
+            ),
+        },
+    };
+
+    let skill_md = std::fs::read_to_string(source_dir.join("SKILL.md")).unwrap_or_default();
+    let installed_version = github::parse_skill_frontmatter(&skill_md).version;
+
+    Ok(PreparedInstall {
+        plan: plan_install(&source_dir, dest_dir)?,
+        installed_version,
+        installed_rev,
+        pinned: pin_rev.is_some(),
+        signer: None,
+        staging_dir,
+    })
+}
+
+/// Prepare installing a skill pack into `dest_dir`. The pack is unpacked
+/// into staging and checked against its content hash first; `signer` is who
+/// the caller verified its signature came from.
+pub fn prepare_pack_install(pack: &SkillPack, staging_root: &Path, dest_dir: &Path, signer: Option<String>) -> Result<PreparedInstall> {
+    let staging = staging_root.join(format!("{}-{}.{}", pack.manifest.registry, pack.manifest.slug, pack::EXTENSION));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    pack.unpack(&staging)?;
+    Ok(PreparedInstall {
+        plan: plan_install(&staging, dest_dir)?,
+        installed_version: pack.manifest.version.clone(),
+        installed_rev: pack.provenance.rev.clone(),
+        pinned: false,
+        signer,
+        staging_dir: Some(staging),
+    })
+}
+
+impl PreparedInstall {
+    /// Copy the files, record the install, then run post-install hooks
+    pub fn execute(&self, db: &Database, skill: &Skill, hooks: &[Hook], force: bool) -> Result<(InstalledSkill, Vec<HookRun>)> {
+        execute_plan(&self.plan, force)?;
+        let record = InstalledSkill {
+            registry: skill.registry.clone(),
+            slug: skill.slug.clone(),
+            local_path: self.plan.dest_dir.to_string_lossy().to_string(),
+            installed_version: self.installed_version.clone(),
+            installed_rev: self.installed_rev.clone(),
+            pinned: self.pinned,
+            installed_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+            content_hash: Some(self.plan.content_hash()?),
+        };
+        db.record_install(&record)?;
+        let rev = record.installed_rev.as_deref().map(|r| format!(" at {}", r)).unwrap_or_default();
+        let signer = self.signer.as_deref().map(|s| format!(", signed by {}", s)).unwrap_or_default();
+        db.log_action(Action::Install, &format!("{}:{}", skill.registry, skill.slug), &format!("{}{}{}", record.local_path, rev, signer), record.installed_at)?;
+
+        let runs = run_post_install_hooks(hooks, skill, &self.plan.dest_dir);
+        db.record_hook_runs(&record, &runs)?;
+        Ok((record, runs))
+    }
+}
+
+impl Drop for PreparedInstall {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.staging_dir {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// The commit `install` pins: the one recorded for `version`, else `rev`,
+/// else the pin of the `previous` install there unless `unpin`
+pub fn pin_rev(db: &Database, skill: &Skill, version: Option<&str>, rev: Option<String>, unpin: bool, previous: Option<InstalledSkill>) -> Result<Option<String>> {
+    Ok(match (version, rev) {
+        (Some(v), _) => match db.get_version_commit(&skill.registry, &skill.slug, v)? {
+            Some(sha) => Some(sha),
+            None => {
+                let known = db.get_known_versions(&skill.registry, &skill.slug)?;
+                anyhow::bail!(
+                    "No commit recorded for {} version {} (known versions: {})",
+                    skill.slug,
+                    v,
+                    if known.is_empty() { "none".to_string() } else { known.join(", ") }
+                );
+            }
+        },
+        (None, Some(r)) => Some(r),
+        (None, None) if !unpin => previous.filter(|p| p.pinned).and_then(|p| p.installed_rev),
+        (None, None) => None,
+    })
+}
+
+/// Record in the audit log that `--allow-untrusted` let `skill` past the
+/// install policy
+pub fn log_override(db: &Database, skill: &Skill) -> Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    db.log_action(Action::Override, &format!("{}:{}", skill.registry, skill.slug), "--allow-untrusted", now)?;
+    Ok(())
+}
+
+/// Delete an installed copy and its install record. False when nothing was
+/// installed there; a directory we didn't install is left alone.
+pub fn uninstall(db: &Database, skill: &Skill, dest_dir: &Path) -> Result<bool> {
+    let local_path = dest_dir.to_string_lossy();
+    if db.get_install(&skill.registry, &skill.slug, &local_path)?.is_none() {
+        return Ok(false);
+    }
+    if dest_dir.exists() {
+        std::fs::remove_dir_all(dest_dir)?;
+    }
+    db.remove_install(&skill.registry, &skill.slug, &local_path)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    db.log_action(Action::Uninstall, &format!("{}:{}", skill.registry, skill.slug), &local_path, now)?;
+    Ok(true)
+}
+
+/// Where an install stands against the latest synced copy of its skill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateState {
+    UpToDate,
+    /// The synced copy differs; `update --apply` reinstalls it
+    Stale,
+    /// Stale, but pinned to its revision
+    Pinned,
+    /// Stale, and the installed files changed since they were installed
+    /// (by hand or by a hook); reinstalling would lose that
+    Modified,
+    /// The installed directory is gone
+    Missing,
+    /// No synced clone holds the skill, so there's nothing to compare with
+    NotSynced,
+}
+
+impl UpdateState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateState::UpToDate => "up to date",
+            UpdateState::Stale => "stale",
+            UpdateState::Pinned => "pinned",
+            UpdateState::Modified => "modified locally",
+            UpdateState::Missing => "missing",
+            UpdateState::NotSynced => "not synced",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateCheck {
+    pub state: UpdateState,
+    /// Version in the synced copy's frontmatter
+    pub latest_version: Option<String>,
+}
+
+/// Compare an install with the synced copy of its skill. Only local files
+/// are read; nothing is fetched.
+pub fn check_update(repos_dir: &Path, install: &InstalledSkill, skill: &Skill) -> Result<UpdateCheck> {
+    let installed_dir = Path::new(&install.local_path);
+    let Some(latest_dir) = local_skill_dir(repos_dir, skill) else {
+        return Ok(UpdateCheck { state: UpdateState::NotSynced, latest_version: None });
+    };
+    let skill_md = std::fs::read_to_string(latest_dir.join("SKILL.md")).unwrap_or_default();
+    let latest_version = github::parse_skill_frontmatter(&skill_md).version;
+    if !installed_dir.is_dir() {
+        return Ok(UpdateCheck { state: UpdateState::Missing, latest_version });
+    }
+
+    let current = plan_install(installed_dir, installed_dir)?.content_hash()?;
+    let state = if current == plan_install(&latest_dir, installed_dir)?.content_hash()? {
+        UpdateState::UpToDate
+    } else if install.pinned {
+        UpdateState::Pinned
+    } else if install.content_hash.as_ref().is_some_and(|h| *h != current) {
+        UpdateState::Modified
+    } else {
+        UpdateState::Stale
+    };
+    Ok(UpdateCheck { state, latest_version })
+}
+
+pub fn plan_install(source_dir: &Path, dest_dir: &Path) -> Result<InstallPlan> {
+    let mut files = Vec::new();
+    collect_files(source_dir, Path::new(""), &mut files)?;
+    files.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+
+    Ok(InstallPlan {
+        source_dir: source_dir.to_path_buf(),
+        dest_dir: dest_dir.to_path_buf(),
+        files,
+    })
+}
+
+fn collect_files(root: &Path, rel: &Path, files: &mut Vec<PlannedFile>) -> Result<()> {
+    for entry in std::fs::read_dir(root.join(rel))? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let rel_path = rel.join(entry.file_name());
+
+        // Never follow symlinks out of the skill directory
+        if file_type.is_symlink() || entry.file_name() == ".git" {
+            continue;
+        }
+        if file_type.is_dir() {
+            collect_files(root, &rel_path, files)?;
+        } else if file_type.is_file() {
+            let path = entry.path();
+            files.push(PlannedFile {
+                size: entry.metadata()?.len(),
+                is_script: is_script(&path),
+                rel_path,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn is_script(path: &Path) -> bool {
+    let by_extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| SCRIPT_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false);
+    if by_extension {
+        return true;
+    }
+
+    let mut buf = [0u8; 2];
+    std::fs::File::open(path)
+        .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut buf))
+        .map(|_| &buf == b"#!")
+        .unwrap_or(false)
+}
+
+pub fn execute_plan(plan: &InstallPlan, force: bool) -> Result<()> {
+    if plan.dest_dir.exists() {
+        if !force {
+            anyhow::bail!("{:?} already exists (use --force to overwrite)", plan.dest_dir);
+        }
+        std::fs::remove_dir_all(&plan.dest_dir)?;
+    }
+
+    for file in &plan.files {
+        let dest = plan.dest_dir.join(&file.rel_path);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(plan.source_dir.join(&file.rel_path), &dest)?;
+    }
+    Ok(())
+}
+
+/// Run post-install hooks against an installed skill. Failures are
+/// reported in the returned runs rather than aborting the install.
+pub fn run_post_install_hooks(hooks: &[Hook], skill: &Skill, installed_dir: &Path) -> Vec<HookRun> {
+    hooks
+        .iter()
+        .map(|hook| {
+            let result = Command::new(&hook.command)
+                .args(&hook.args)
+                .arg(installed_dir)
+                .env("SKILL_PATH", installed_dir)
+                .env("SKILL_SLUG", &skill.slug)
+                .env("SKILL_REGISTRY", &skill.registry)
+                .output();
+            match result {
+                Ok(out) => HookRun {
+                    hook: hook.display_name().to_string(),
+                    exit_code: out.status.code(),
+                    output: format!("{}{}", String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr)),
+                },
+                Err(e) => HookRun {
+                    hook: hook.display_name().to_string(),
+                    exit_code: None,
+                    output: format!("failed to run {}: {}", hook.command, e),
+                },
+            }
+        })
+        .collect()
+}
+
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_skill_dir(root: &Path) -> PathBuf {
+        let dir = root.join("skill");
+        std::fs::create_dir_all(dir.join("scripts")).unwrap();
+        std::fs::write(dir.join("SKILL.md"), "# Test\n").unwrap();
+        std::fs::write(dir.join("scripts").join("run.sh"), "echo hi\n").unwrap();
+        std::fs::write(dir.join("scripts").join("tool"), "#!/usr/bin/env python3\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "plain").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_plan_lists_files_and_scripts() {
+        let dir = tempdir().unwrap();
+        let source = create_skill_dir(dir.path());
+
+        let plan = plan_install(&source, &dir.path().join("dest")).unwrap();
+        let paths: Vec<_> = plan.files.iter().map(|f| f.rel_path.to_string_lossy().replace('\\', "/")).collect();
+        assert_eq!(paths, vec!["SKILL.md", "notes.txt", "scripts/run.sh", "scripts/tool"]);
+        assert_eq!(plan.scripts().count(), 2);
+        assert_eq!(plan.total_size(), 7 + 8 + 23 + 5);
+    }
+
+    #[test]
+    fn test_content_hash_tracks_content_and_paths() {
+        let dir = tempdir().unwrap();
+        let source = create_skill_dir(dir.path());
+
+        let hash = plan_install(&source, &dir.path().join("dest")).unwrap().content_hash().unwrap();
+        assert!(hash.starts_with("sha256:"));
+        assert_eq!(hash, plan_install(&source, &dir.path().join("other")).unwrap().content_hash().unwrap());
+
+        std::fs::write(source.join("notes.txt"), "changed").unwrap();
+        let changed = plan_install(&source, &dir.path().join("dest")).unwrap().content_hash().unwrap();
+        assert_ne!(hash, changed);
+
+        std::fs::rename(source.join("notes.txt"), source.join("renamed.txt")).unwrap();
+        let renamed = plan_install(&source, &dir.path().join("dest")).unwrap().content_hash().unwrap();
+        assert_ne!(changed, renamed);
+    }
+
+    #[test]
+    fn test_execute_plan_copies_files() {
+        let dir = tempdir().unwrap();
+        let source = create_skill_dir(dir.path());
+        let dest = dir.path().join("dest");
+
+        let plan = plan_install(&source, &dest).unwrap();
+        execute_plan(&plan, false).unwrap();
+        assert!(dest.join("scripts").join("run.sh").exists());
+
+        // Second install without force refuses to overwrite
+        assert!(execute_plan(&plan, false).is_err());
+        execute_plan(&plan, true).unwrap();
+    }
+
+    #[test]
+    fn test_local_skill_dir_from_github_url() {
+        let dir = tempdir().unwrap();
+        let skill_dir = dir.path().join("clawdhub").join("skills").join("alice").join("pdf");
+        std::fs::create_dir_all(&skill_dir).unwrap();
+        std::fs::write(skill_dir.join("SKILL.md"), "# PDF").unwrap();
+
+        let skill = Skill {
+            name: "PDF".to_string(),
+            github_url: "https://github.com/openclaw/skills/tree/main/skills/alice/pdf".to_string(),
+            ..Skill::fixture("clawdhub", "pdf")
+        };
+        assert_eq!(skill_repo_path(&skill), Some("skills/alice/pdf"));
+        assert_eq!(local_skill_dir(dir.path(), &skill), Some(skill_dir));
+
+        let remote_only = Skill {
+            github_url: "https://skills.sh/skills/foo".to_string(),
+            ..skill
+        };
+        assert!(local_skill_dir(dir.path(), &remote_only).is_none());
+        assert!(github_source(&remote_only).is_none());
+        assert_eq!(
+            github_source(&Skill {
+                github_url: "https://github.com/openclaw/skills/tree/main/skills/alice/pdf".to_string(),
+                ..remote_only
+            }),
+            Some(("https://github.com/openclaw/skills.git".to_string(), "main", "skills/alice/pdf"))
+        );
+    }
+
+    #[test]
+    fn test_check_update() {
+        let dir = tempdir().unwrap();
+        let repos_dir = dir.path().join("repos");
+        let synced = repos_dir.join("clawdhub").join("skills").join("alice").join("pdf");
+        std::fs::create_dir_all(&synced).unwrap();
+        std::fs::write(synced.join("SKILL.md"), "---\nversion: 1.0.0\n---\n# PDF").unwrap();
+        let skill = Skill {
+            name: "PDF".to_string(),
+            github_url: "https://github.com/openclaw/skills/tree/main/skills/alice/pdf".to_string(),
+            ..Skill::fixture("clawdhub", "pdf")
+        };
+        let installed_dir = dir.path().join("skills").join("pdf");
+        let plan = plan_install(&synced, &installed_dir).unwrap();
+        let mut install = InstalledSkill {
+            registry: "clawdhub".to_string(),
+            slug: "pdf".to_string(),
+            local_path: installed_dir.to_string_lossy().to_string(),
+            installed_version: Some("1.0.0".to_string()),
+            installed_rev: None,
+            pinned: false,
+            installed_at: 1,
+            content_hash: Some(plan.content_hash().unwrap()),
+        };
+        let state = |install: &InstalledSkill| check_update(&repos_dir, install, &skill).unwrap().state;
+        assert_eq!(state(&install), UpdateState::Missing);
+
+        execute_plan(&plan, false).unwrap();
+        assert_eq!(state(&install), UpdateState::UpToDate);
+
+        std::fs::write(synced.join("SKILL.md"), "---\nversion: 1.1.0\n---\n# PDF").unwrap();
+        let check = check_update(&repos_dir, &install, &skill).unwrap();
+        assert_eq!((check.state, check.latest_version.as_deref()), (UpdateState::Stale, Some("1.1.0")));
+        install.pinned = true;
+        assert_eq!(state(&install), UpdateState::Pinned);
+        install.pinned = false;
+
+        std::fs::write(installed_dir.join("notes.md"), "mine").unwrap();
+        assert_eq!(state(&install), UpdateState::Modified);
+        // Installs recorded before hashes were kept can't tell
+        install.content_hash = None;
+        assert_eq!(state(&install), UpdateState::Stale);
+
+        std::fs::remove_dir_all(&synced).unwrap();
+        assert_eq!(state(&install), UpdateState::NotSynced);
+    }
+
+    #[test]
+    fn test_installed_skills_use_local_copies() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let skill = |slug: &str| Skill {
+            description: "Upstream description".to_string(),
+            skill_md: "# Upstream".to_string(),
+            github_url: String::new(),
+            ..Skill::fixture("clawdhub", slug)
+        };
+        for slug in ["csv", "pdf", "not-installed"] {
+            db.upsert_skill(&skill(slug)).unwrap();
+        }
+
+        let local = dir.path().join("skills");
+        std::fs::create_dir_all(local.join("csv")).unwrap();
+        std::fs::write(local.join("csv").join("SKILL.md"), "---\ndescription: Patched for TSV\n---\n# CSV\nAlso handles TSV.").unwrap();
+        for slug in ["csv", "pdf"] {
+            db.record_install(&InstalledSkill {
+                registry: "clawdhub".to_string(),
+                slug: slug.to_string(),
+                local_path: local.join(slug).to_string_lossy().to_string(),
+                installed_version: None,
+                installed_rev: None,
+                pinned: false,
+                installed_at: 1,
+                content_hash: None,
+            })
+            .unwrap();
+        }
+
+        let mut skills = installed_skills(&db).unwrap();
+        skills.sort_by(|a, b| a.slug.cmp(&b.slug));
+        assert_eq!(skills.len(), 2);
+        assert_eq!(skills[0].description, "Patched for TSV");
+        assert!(skills[0].skill_md.contains("Also handles TSV."));
+        // The pdf copy was deleted, so the catalog content stands in
+        assert_eq!(skills[1].skill_md, "# Upstream");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_post_install_hooks() {
+        let dir = tempdir().unwrap();
+        let skill = Skill {
+            name: "PDF".to_string(),
+            github_url: String::new(),
+            trusted: true,
+            ..Skill::fixture("anthropic", "pdf")
+        };
+        let hooks = vec![
+            Hook {
+                name: Some("echo".to_string()),
+                command: "sh".to_string(),
+                args: vec!["-c".to_string(), "echo $SKILL_SLUG $1".to_string(), "hook".to_string()],
+            },
+            Hook {
+                name: None,
+                command: "definitely-not-a-real-command".to_string(),
+                args: vec![],
+            },
+        ];
+
+        let runs = run_post_install_hooks(&hooks, &skill, dir.path());
+        assert!(runs[0].success());
+        assert_eq!(runs[0].output.trim(), format!("pdf {}", dir.path().display()));
+        assert!(!runs[1].success());
+        assert_eq!(runs[1].hook, "definitely-not-a-real-command");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2.0 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Open a URL in the browser: `$BROWSER` if set, otherwise the platform's
+/// default handler
+pub fn open_url(url: &str) -> Result<()> {
+    let mut cmd = match std::env::var("BROWSER").ok().and_then(|b| split_command(&b)) {
+        Some((program, args)) => {
+            let mut cmd = Command::new(program);
+            cmd.args(args);
+            cmd
+        }
+        None => default_opener(),
+    };
+    let program = format!("{:?}", cmd.get_program());
+    let status = cmd
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| anyhow::anyhow!("Could not run {} to open {}: {}", program, url, e))?;
+    if !status.success() {
+        anyhow::bail!("{} could not open {} ({})", program, url, status);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn default_opener() -> Command {
+    Command::new("open")
+}
+
+#[cfg(windows)]
+fn default_opener() -> Command {
+    let mut cmd = Command::new("cmd");
+    // The empty argument is the window title `start` expects first
+    cmd.args(["/C", "start", ""]);
+    cmd
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn default_opener() -> Command {
+    Command::new("xdg-open")
+}
+
+/// Open a file in `$VISUAL` or `$EDITOR` and wait for the editor to exit
+pub fn open_in_editor(path: &Path) -> Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().and_then(|v| split_command(&v)))
+        .ok_or_else(|| anyhow::anyhow!("Set $EDITOR (or $VISUAL) to open skills in an editor"))?;
+    let (program, args) = editor;
+    let status = Command::new(&program)
+        .args(&args)
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Could not run editor {}: {}", program, e))?;
+    if !status.success() {
+        anyhow::bail!("Editor {} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+/// Put text on the system clipboard
+#[cfg(feature = "clipboard")]
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| anyhow::anyhow!("Clipboard unavailable: {}", e))?;
+    clipboard.set_text(text).map_err(|e| anyhow::anyhow!("Could not copy to the clipboard: {}", e))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_text: &str) -> Result<()> {
+    crate::features::require("clipboard", "url --copy")
+}
+
+/// Split a command from an environment variable into program and arguments;
+/// editors are often set with flags, like `code --wait`
+fn split_command(value: &str) -> Option<(String, Vec<String>)> {
+    let mut parts = value.split_whitespace().map(String::from);
+    let program = parts.next()?;
+    Some((program, parts.collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(split_command("vim"), Some(("vim".to_string(), vec![])));
+        assert_eq!(split_command(" code --wait "), Some(("code".to_string(), vec!["--wait".to_string()])));
+        assert_eq!(split_command("  "), None);
+    }
+}
@@ -0,0 +1,89 @@
+//! Local search, audit, and install of agent skills across registries.
+//! The `safe-skill-search` binary is a thin CLI over these modules.
+//!
+//! The types most tools need are re-exported here. Open a [`Database`],
+//! fill it with [`sync_catalog`], index it with a [`SearchIndex`], and rank
+//! results with [`QualityScores`]:
+//!
+//! ```no_run
+//! use safe_skill_search::{sync_catalog, Config, Database, QualityScores, SearchIndex};
+//! use std::path::Path;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let dir = Path::new("/tmp/skills");
+//! let config = Config::load(&dir.join("config.toml"))?;
+//! let mut db = Database::open(&dir.join("skills.db"))?;
+//! let summary = sync_catalog(&mut db, &dir.join("repos"), &config.registries, 4).await?;
+//! println!("{} registries failed", summary.failed());
+//!
+//! let index = SearchIndex::open_or_create(&dir.join("index"), &config.index)?;
+//! index.rebuild(&db)?;
+//! let scores = QualityScores::load(&db)?;
+//! for result in index.search("pdf forms", 10, &Default::default())? {
+//!     let score = scores.get_score(&result.registry, &result.slug);
+//!     println!("{}:{} {:?}", result.registry, result.slug, score);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The other modules are public too, for the CLI's own use; they change more
+//! freely than the re-exports.
+
+pub mod audit;
+pub mod audit_log;
+pub mod bench;
+pub mod catalog;
+pub mod clawdhub;
+pub mod config;
+pub mod db;
+pub mod deps;
+pub mod diff;
+pub mod digest;
+pub mod diskspace;
+pub mod doctor;
+pub mod embeddings;
+pub mod env_vars;
+pub mod eval;
+pub mod export;
+pub mod failures;
+pub mod features;
+pub mod git;
+pub mod github;
+pub mod github_api;
+pub mod harness;
+pub mod index;
+pub mod injection;
+pub mod install;
+pub mod launch;
+pub mod links;
+pub mod mcp;
+pub mod migrations;
+pub mod mirror;
+pub mod output;
+pub mod overlay;
+pub mod pack;
+pub mod policy;
+pub mod project;
+pub mod quality;
+pub mod query;
+pub mod rank;
+pub mod resolve;
+pub mod server;
+pub mod risk;
+pub mod sandbox;
+pub mod search;
+pub mod security;
+pub mod skillssh;
+pub mod sync;
+pub mod text;
+pub mod topics;
+pub mod unicode;
+pub mod workspace;
+
+pub use config::Config;
+pub use db::{Database, Skill};
+pub use github::sync_all_registries;
+pub use index::{SearchIndex, SearchResult};
+pub use quality::{QualityEntry, QualityScores};
+pub use sync::sync_catalog;
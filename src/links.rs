@@ -0,0 +1,194 @@
+use crate::config::LinksConfig;
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// TLDs that are cheap to register and dominate abuse feeds
+const SUSPICIOUS_TLDS: &[&str] = &[
+    "zip", "mov", "top", "xyz", "tk", "ml", "ga", "cf", "gq", "click", "country", "kim", "work", "loan", "rest",
+    "cam", "icu", "su",
+];
+
+const SHORTENERS: &[&str] = &[
+    "bit.ly", "tinyurl.com", "t.co", "goo.gl", "ow.ly", "is.gd", "buff.ly", "rebrand.ly", "cutt.ly", "shorturl.at",
+    "tiny.cc", "rb.gy", "t.ly", "v.gd",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkFlag {
+    /// Host is listed in the local blocklist
+    Blocklisted,
+    /// Host is a bare IP address instead of a domain
+    RawIp,
+    /// Known URL shortener hiding the real destination
+    Shortener,
+    SuspiciousTld,
+    /// Plain http:// to a non-local host
+    Insecure,
+}
+
+impl LinkFlag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkFlag::Blocklisted => "blocklisted",
+            LinkFlag::RawIp => "raw-ip",
+            LinkFlag::Shortener => "shortener",
+            LinkFlag::SuspiciousTld => "suspicious-tld",
+            LinkFlag::Insecure => "insecure",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkAudit {
+    pub url: String,
+    pub host: String,
+    pub flags: Vec<LinkFlag>,
+}
+
+/// Hosts to flag, one per line (`#` comments allowed). A listed domain
+/// also matches its subdomains.
+#[derive(Debug, Default)]
+pub struct Blocklist {
+    hosts: HashSet<String>,
+}
+
+impl Blocklist {
+    /// The `[links] blocklist` file, or an empty list without one
+    pub fn configured(config: &LinksConfig) -> Result<Self> {
+        match &config.blocklist {
+            Some(path) => Self::load(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read link blocklist {:?}: {}", path, e))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let hosts = content
+            .lines()
+            .map(|l| l.split('#').next().unwrap_or("").trim().trim_start_matches("*.").to_lowercase())
+            .filter(|l| !l.is_empty())
+            .collect();
+        Self { hosts }
+    }
+
+    pub fn contains(&self, host: &str) -> bool {
+        let mut h = host;
+        loop {
+            if self.hosts.contains(h) {
+                return true;
+            }
+            match h.split_once('.') {
+                Some((_, rest)) => h = rest,
+                None => return false,
+            }
+        }
+    }
+}
+
+fn url_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r#"(?i)\bhttps?://[^\s<>()\[\]{}"'`|\\]+"#).expect("valid url pattern"))
+}
+
+/// Every distinct http(s) URL in the text, in order of first appearance
+pub fn extract_urls(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut urls = Vec::new();
+    for m in url_pattern().find_iter(text) {
+        let url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', '*', '_']);
+        if seen.insert(url.to_string()) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+pub fn audit_links(text: &str, blocklist: &Blocklist) -> Vec<LinkAudit> {
+    extract_urls(text)
+        .into_iter()
+        .filter_map(|raw| {
+            let parsed = url::Url::parse(&raw).ok()?;
+            let host = parsed.host_str()?.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+            let ip = host.parse::<IpAddr>().ok();
+            let local = host == "localhost" || ip.is_some_and(|ip| ip.is_loopback());
+
+            let mut flags = Vec::new();
+            if blocklist.contains(&host) {
+                flags.push(LinkFlag::Blocklisted);
+            }
+            if ip.is_some() && !local {
+                flags.push(LinkFlag::RawIp);
+            }
+            if SHORTENERS.contains(&host.as_str()) {
+                flags.push(LinkFlag::Shortener);
+            }
+            if ip.is_none() && host.rsplit('.').next().is_some_and(|tld| SUSPICIOUS_TLDS.contains(&tld)) {
+                flags.push(LinkFlag::SuspiciousTld);
+            }
+            if parsed.scheme() == "http" && !local {
+                flags.push(LinkFlag::Insecure);
+            }
+            Some(LinkAudit { url: raw, host, flags })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_urls() {
+        let md = "See [docs](https://docs.example.com/api). Also https://docs.example.com/api, and `curl http://10.0.0.5:8080/x`.";
+        assert_eq!(extract_urls(md), vec!["https://docs.example.com/api", "http://10.0.0.5:8080/x"]);
+    }
+
+    #[test]
+    fn test_audit_flags() {
+        let md = r#"
+https://github.com/anthropics/skills
+https://bit.ly/3abc
+http://192.168.1.20/payload.sh
+https://free-tools.xyz/setup
+http://localhost:3000/callback
+https://cdn.evil.example.org/x.js
+"#;
+        let blocklist = Blocklist::parse("# known bad\nevil.example.org\n");
+        let audits = audit_links(md, &blocklist);
+        let flags: Vec<_> = audits.iter().map(|a| (a.host.as_str(), a.flags.clone())).collect();
+        assert_eq!(
+            flags,
+            vec![
+                ("github.com", vec![]),
+                ("bit.ly", vec![LinkFlag::Shortener]),
+                ("192.168.1.20", vec![LinkFlag::RawIp, LinkFlag::Insecure]),
+                ("free-tools.xyz", vec![LinkFlag::SuspiciousTld]),
+                ("localhost", vec![]),
+                ("cdn.evil.example.org", vec![LinkFlag::Blocklisted]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocklist_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blocklist.txt");
+        std::fs::write(&path, "*.Bad.Test  # wildcard\n\n").unwrap();
+        let blocklist = Blocklist::load(&path).unwrap();
+        assert!(blocklist.contains("bad.test"));
+        assert!(blocklist.contains("a.b.bad.test"));
+        assert!(!blocklist.contains("notbad.test"));
+        assert!(Blocklist::load(&dir.path().join("missing.txt")).is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use crate::config::{IndexConfig, QueryConfig, RankingConfig};
+use crate::db::{Database, Skill};
+use crate::index::{IndexFilters, ScoreScale, SearchIndex};
+use crate::output::{self, Enricher, SearchFilters};
+use crate::policy::{DecisionSet, Policy};
+use crate::quality::QualityScores;
+use crate::query;
+use crate::rank::Sort;
+use crate::resolve::{self, Resolution, ResolutionError};
+use crate::search::{Indexes, SearchService};
+use crate::text::{self, Heading};
+use crate::unicode::UnicodeFlag;
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Newest MCP revision spoken here; older ones a client asks for are accepted
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Quality floor for `search_skills`, as for `search`
+const DEFAULT_MIN_SCORE: i64 = 80;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SearchArgs {
+    /// Words describing the task, e.g. "fill pdf forms"
+    query: String,
+    /// Maximum results (default 10)
+    limit: Option<usize>,
+    /// Only skills from this registry
+    registry: Option<String>,
+    /// Minimum quality score, 0-100 (default 80)
+    min_score: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SkillArgs {
+    /// Skill slug, optionally as registry:slug
+    skill: String,
+}
+
+/// One `search_skills` result
+#[derive(Debug, Serialize)]
+pub struct SkillHit {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub quality_score: i64,
+    pub stars: i64,
+    pub trusted: bool,
+    pub summary: String,
+    /// How likely this is the skill asked for, 0-1
+    pub confidence: f32,
+}
+
+/// What `get_skill` returns: everything `show` prints but the SKILL.md
+#[derive(Debug, Serialize)]
+pub struct SkillDetail {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub github_url: String,
+    pub quality_score: i64,
+    pub stars: i64,
+    pub trusted: bool,
+    /// Prompt-injection risk, 0-100
+    pub injection_risk: i64,
+    pub unicode_flags: Vec<UnicodeFlag>,
+    pub headings: Vec<Heading>,
+    pub summary: String,
+}
+
+/// Serves the catalog as MCP tools, one JSON-RPC message per line
+pub struct McpServer<'a> {
+    pub db: &'a Database,
+    pub index: &'a SearchIndex,
+    /// The user layer's index, alongside a system catalog's
+    pub user_index: Option<&'a SearchIndex>,
+    pub index_config: &'a IndexConfig,
+    pub query_config: &'a QueryConfig,
+    pub ranking: &'a RankingConfig,
+    /// Which registry a bare slug in several means
+    pub registry_priority: &'a [String],
+    /// Search the database instead; a read-only system catalog's index can
+    /// be missing
+    pub direct: bool,
+    pub quality: &'a QualityScores,
+    pub rules: &'a Policy,
+    pub decisions: &'a DecisionSet,
+}
+
+impl McpServer<'_> {
+    /// Answer one line from the client; None for notifications
+    pub fn handle_line(&self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(m) => m,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            let id = message.get("id").cloned().unwrap_or(Value::Null);
+            return Some(error_response(id, INVALID_REQUEST, "missing method"));
+        };
+        // Notifications (initialized, cancelled) get no response
+        let id = message.get("id").cloned()?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => {
+                let requested = params.get("protocolVersion").and_then(Value::as_str).unwrap_or_default();
+                let version = PROTOCOL_VERSIONS.iter().find(|v| **v == requested).unwrap_or(&PROTOCOL_VERSIONS[0]);
+                Ok(json!({
+                    "protocolVersion": version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "safe-skill-search", "version": env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => self.call_tool(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or_default();
+        let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+        let invalid = |e: serde_json::Error| (INVALID_PARAMS, format!("invalid arguments for {}: {}", name, e));
+        let outcome = match name {
+            "search_skills" => self.search_skills(serde_json::from_value(arguments).map_err(invalid)?).and_then(|hits| Ok(serde_json::to_string_pretty(&hits)?)),
+            "get_skill" => self.get_skill(serde_json::from_value(arguments).map_err(invalid)?).and_then(|detail| Ok(serde_json::to_string_pretty(&detail)?)),
+            "get_skill_content" => self.get_skill_content(serde_json::from_value(arguments).map_err(invalid)?),
+            _ => return Err((INVALID_PARAMS, format!("unknown tool {}", name))),
+        };
+        // Failures the agent can act on are tool results, not protocol errors
+        Ok(match outcome {
+            Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+            Err(e) => json!({ "content": [{ "type": "text", "text": format!("{:#}", e) }], "isError": true }),
+        })
+    }
+
+
+    fn summary_of(&self, s: &Skill) -> String {
+        self.db.get_summary(&s.registry, &s.slug).ok().flatten().unwrap_or_else(|| text::compact_summary(&s.skill_md, &s.description))
+    }
+
+    fn search_skills(&self, args: SearchArgs) -> Result<Vec<SkillHit>> {
+        self.rules.check_registry_flag(args.registry.as_deref())?;
+        let min_score = self.rules.min_score(args.min_score, DEFAULT_MIN_SCORE)?;
+        let registry = args.registry.as_deref();
+        let service = SearchService {
+            enricher: Enricher {
+                db: self.db,
+                quality: self.quality,
+                decisions: self.decisions,
+                overlay_dir: self.index_config.overlay_dir.as_deref(),
+                unreachable: self.db.get_unreachable()?,
+                now: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0),
+            },
+            rules: self.rules,
+            ranking: self.ranking,
+            sort: Sort::Relevance,
+            filters: SearchFilters { min_score, ..Default::default() },
+            registry,
+            has_code: None,
+            superseded: self.db.get_superseded()?,
+            installed: None,
+            limit: args.limit.unwrap_or(10),
+        };
+        let filters = IndexFilters::registry(registry);
+        // Agents tend to ask in whole sentences
+        let keywords = query::key_terms(&args.query, self.query_config);
+        let terms = query::expand(&keywords, self.query_config);
+        let mut indexes = Indexes {
+            db: self.db,
+            catalog: self.index,
+            user: self.user_index,
+            local: None,
+            weights: &self.index_config.weights,
+            direct: self.direct,
+        };
+        let results = service.fetch(|limit| indexes.search(&terms, &keywords, false, limit, &filters))?;
+        let hits = service.results(results, ScoreScale::keyword(&terms)).map(|r| SkillHit {
+            id: format!("{}:{}", r.registry, r.slug),
+            name: r.name,
+            description: r.description,
+            quality_score: r.quality_score,
+            stars: r.stars,
+            trusted: r.trusted,
+            summary: r.summary,
+            confidence: r.confidence,
+        });
+        Ok(hits.collect())
+    }
+
+    /// The skill a tool call names, unless it's ambiguous, unknown, or denied
+    fn resolve(&self, query: &str) -> Result<Skill> {
+        let (error, candidates) = match resolve::resolve_skill(self.db, query, self.registry_priority)? {
+            Resolution::Exact(s) | Resolution::Preferred(s, _) if self.decisions.is_denied(&s.registry, &s.slug) => anyhow::bail!("{}:{} is denied", s.registry, s.slug),
+            Resolution::Exact(s) | Resolution::Preferred(s, _) => return Ok(s),
+            Resolution::Ambiguous(c) => ("ambiguous", c),
+            Resolution::Candidates(c) => ("not_found", c),
+        };
+        anyhow::bail!("{}", serde_json::to_string_pretty(&ResolutionError::new(error, query, &candidates))?)
+    }
+
+    fn get_skill(&self, args: SkillArgs) -> Result<SkillDetail> {
+        let s = self.resolve(&args.skill)?;
+        let (injection_risk, _) = self.db.get_injection_risk(&s.registry, &s.slug).unwrap_or_default();
+        let unicode_flags = self.db.get_unicode_flags(&s.registry, &s.slug)?.into_iter().map(|i| i.flag).collect();
+        let headings = output::skill_outline(self.db, &s);
+        Ok(SkillDetail {
+            id: format!("{}:{}", s.registry, s.slug),
+            quality_score: self.quality.score_for(&s),
+            trusted: self.decisions.is_trusted(&s),
+            summary: self.summary_of(&s),
+            name: s.name,
+            description: s.description,
+            github_url: s.github_url,
+            stars: s.stars,
+            injection_risk,
+            unicode_flags,
+            headings,
+        })
+    }
+
+    fn get_skill_content(&self, args: SkillArgs) -> Result<String> {
+        Ok(self.resolve(&args.skill)?.skill_md)
+    }
+
+    /// Answer requests from stdin on stdout until the client hangs up
+    #[cfg(feature = "server")]
+    pub fn serve_stdio(&self) -> Result<()> {
+        use std::io::{BufRead, Write};
+        let mut stdout = std::io::stdout().lock();
+        for line in std::io::stdin().lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_line(&line) {
+                writeln!(stdout, "{}", response)?;
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "server"))]
+    pub fn serve_stdio(&self) -> Result<()> {
+        crate::features::require("server", "serve-mcp")
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn input_schema<T: JsonSchema>() -> Value {
+    let mut schema = serde_json::to_value(schemars::schema_for!(T)).expect("schema serializes");
+    if let Some(schema) = schema.as_object_mut() {
+        schema.remove("$schema");
+        schema.remove("title");
+    }
+    schema
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "search_skills",
+            "description": "Search agent skills across registries by what they do. Returns ids, descriptions, quality scores (0-100), ~100-token summaries, and a match confidence (0-1), best first. Above 0.6 the first hit is a safe pick; below 0.3 nothing matched well, and when several hits are close, ask the user to choose.",
+            "inputSchema": input_schema::<SearchArgs>(),
+        },
+        {
+            "name": "get_skill",
+            "description": "Details of one skill: URL, quality and injection-risk scores, trust, suspicious unicode, and the SKILL.md outline.",
+            "inputSchema": input_schema::<SkillArgs>(),
+        },
+        {
+            "name": "get_skill_content",
+            "description": "The full SKILL.md of one skill.",
+            "inputSchema": input_schema::<SkillArgs>(),
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Decisions;
+    use tempfile::tempdir;
+
+    fn skill(slug: &str, description: &str) -> Skill {
+        Skill {
+            description: description.to_string(),
+            skill_md: format!("# {}\n\n## Usage\n{}\n", slug, description),
+            github_url: format!("https://github.com/anthropics/skills/tree/main/{}", slug),
+            stars: 5,
+            trusted: true,
+            updated_at: 1234567890,
+            ..Skill::fixture("anthropic", slug)
+        }
+    }
+
+    #[test]
+    fn test_tools_over_json_rpc() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("skills.db")).unwrap();
+        db.upsert_skill(&skill("pdf", "Fill and merge PDF forms")).unwrap();
+        db.upsert_skill(&skill("docx", "Edit Word documents")).unwrap();
+        let config = IndexConfig::default();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &config).unwrap();
+        index.rebuild(&db).unwrap();
+        let (quality, rules) = (QualityScores::bundled(), Policy::default());
+        let decisions = DecisionSet::new(None, Decisions::default());
+        let server = McpServer {
+            db: &db,
+            index: &index,
+            user_index: None,
+            index_config: &config,
+            query_config: &QueryConfig::default(),
+            ranking: &RankingConfig::default(),
+            registry_priority: &[],
+            direct: false,
+            quality: &quality,
+            rules: &rules,
+            decisions: &decisions,
+        };
+        let call = |line: &str| server.handle_line(line).unwrap();
+
+        let init = call(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26"}}"#);
+        assert_eq!(init["result"]["protocolVersion"], "2025-03-26");
+        assert!(server.handle_line(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#).is_none());
+
+        let list = call(r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#);
+        let names: Vec<_> = list["result"]["tools"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["search_skills", "get_skill", "get_skill_content"]);
+        assert_eq!(list["result"]["tools"][0]["inputSchema"]["required"], json!(["query"]));
+
+        let search = call(r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"search_skills","arguments":{"query":"Find me a skill that can fill in PDF forms","min_score":0}}}"#);
+        let hits: Vec<Value> = serde_json::from_str(search["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["id"], "anthropic:pdf");
+
+        let detail = call(r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"get_skill","arguments":{"skill":"pdf"}}}"#);
+        let detail: Value = serde_json::from_str(detail["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(detail["headings"][1]["title"], "Usage");
+
+        let content = call(r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"get_skill_content","arguments":{"skill":"anthropic:docx"}}}"#);
+        assert!(content["result"]["content"][0]["text"].as_str().unwrap().contains("Edit Word documents"));
+
+        let missing = call(r#"{"jsonrpc":"2.0","id":6,"method":"tools/call","params":{"name":"get_skill","arguments":{"skill":"nope"}}}"#);
+        assert_eq!(missing["result"]["isError"], true);
+        assert_eq!(call(r#"{"jsonrpc":"2.0","id":7,"method":"tools/call","params":{"name":"get_skill","arguments":{}}}"#)["error"]["code"], INVALID_PARAMS);
+        assert_eq!(call(r#"{"jsonrpc":"2.0","id":8,"method":"resources/list"}"#)["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(call("not json")["error"]["code"], PARSE_ERROR);
+    }
+}
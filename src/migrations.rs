@@ -0,0 +1,377 @@
+use crate::db::Database;
+use crate::github::parse_skill_frontmatter;
+use crate::links::Blocklist;
+use crate::security;
+use crate::text;
+use anyhow::Result;
+use rusqlite::{params, Connection, OpenFlags, TransactionBehavior};
+use std::path::Path;
+
+/// One step of the database schema. The version a database is at is kept
+/// in SQLite's `user_version`.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// Every migration, oldest first. To change the schema, append one; never
+/// edit or reorder one that has shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        apply: Database::baseline_schema,
+    },
+    Migration {
+        version: 2,
+        description: "skill tags",
+        apply: skill_tags,
+    },
+    Migration {
+        version: 3,
+        description: "skill security scans",
+        apply: skill_security,
+    },
+    Migration {
+        version: 4,
+        description: "repository topics",
+        apply: registry_topics,
+    },
+    Migration {
+        version: 5,
+        description: "page etags",
+        apply: page_etags,
+    },
+    Migration {
+        version: 6,
+        description: "skill first seen",
+        apply: skill_first_seen,
+    },
+    Migration {
+        version: 7,
+        description: "repository stats",
+        apply: skill_repo_stats,
+    },
+    Migration {
+        version: 8,
+        description: "skill code samples",
+        apply: skill_code_samples,
+    },
+    Migration {
+        version: 9,
+        description: "skill removals",
+        apply: skill_removals,
+    },
+];
+
+/// Tags from SKILL.md frontmatter, filled in for the skills already synced
+fn skill_tags(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS skill_tags (
+            registry TEXT NOT NULL,
+            slug TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY(registry, slug, tag)
+        );
+        CREATE INDEX IF NOT EXISTS idx_skill_tags_tag ON skill_tags(tag);
+        "#,
+    )?;
+    let mut stmt = conn.prepare("SELECT registry, slug, skill_md FROM skills")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+    for row in rows {
+        let (registry, slug, skill_md) = row?;
+        for tag in parse_skill_frontmatter(&skill_md).tags {
+            conn.execute("INSERT OR IGNORE INTO skill_tags (registry, slug, tag) VALUES (?, ?, ?)", params![registry, slug, tag])?;
+        }
+    }
+    Ok(())
+}
+
+/// Security scan results, filled in from the SKILL.md of the skills
+/// already synced; `scan` adds their bundled files
+fn skill_security(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS skill_security (
+            registry TEXT NOT NULL,
+            slug TEXT NOT NULL,
+            score INTEGER NOT NULL,
+            files INTEGER NOT NULL,
+            findings TEXT NOT NULL,
+            PRIMARY KEY(registry, slug)
+        );
+        "#,
+    )?;
+    let mut stmt = conn.prepare("SELECT registry, slug, skill_md FROM skills")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+    for row in rows {
+        let (registry, slug, skill_md) = row?;
+        let report = security::scan_skill(&skill_md, None, &Blocklist::default())?;
+        conn.execute(
+            "INSERT OR REPLACE INTO skill_security (registry, slug, score, files, findings) VALUES (?, ?, ?, ?, ?)",
+            params![registry, slug, report.score, report.files as i64, serde_json::to_string(&report.findings)?],
+        )?;
+    }
+    Ok(())
+}
+
+/// Tags a registry's skills get from its GitHub repo, filled in by the next
+/// sync
+fn registry_topics(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS registry_topics (
+            registry TEXT NOT NULL,
+            slug TEXT NOT NULL,
+            topic TEXT NOT NULL,
+            PRIMARY KEY(registry, slug, topic)
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Pages of a paginated API are kept in `sync_state` under their own key
+/// with their ETag; a page that comes back unchanged needs the cursor it
+/// led to
+fn page_etags(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE sync_state ADD COLUMN next_cursor TEXT")?;
+    Ok(())
+}
+
+/// When each skill first appeared in the catalog. Skills already synced
+/// date from their first recorded version, or from the start of time.
+fn skill_first_seen(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS skill_first_seen (
+            registry TEXT NOT NULL,
+            slug TEXT NOT NULL,
+            first_seen INTEGER NOT NULL,
+            PRIMARY KEY(registry, slug)
+        );
+        INSERT OR IGNORE INTO skill_first_seen (registry, slug, first_seen)
+        SELECT s.registry, s.slug, COALESCE((SELECT MIN(v.first_seen) FROM skill_versions v WHERE v.registry = s.registry AND v.slug = s.slug), 0)
+        FROM skills s;
+        "#,
+    )?;
+    Ok(())
+}
+
+/// GitHub stars, open issues, and last push of the repo a skill comes from
+fn skill_repo_stats(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS skill_repo_stats (
+            registry TEXT NOT NULL,
+            slug TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            stars INTEGER NOT NULL,
+            open_issues INTEGER NOT NULL,
+            pushed_at INTEGER,
+            fetched_at INTEGER NOT NULL,
+            PRIMARY KEY(registry, slug)
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Fenced code blocks of each SKILL.md by language, filled in for the
+/// skills already synced
+fn skill_code_samples(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS skill_code_samples (
+            registry TEXT NOT NULL,
+            slug TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            language TEXT NOT NULL,
+            heading TEXT,
+            snippet TEXT NOT NULL,
+            PRIMARY KEY(registry, slug, position)
+        );
+        CREATE INDEX IF NOT EXISTS idx_skill_code_samples_language ON skill_code_samples(language);
+        "#,
+    )?;
+    let mut stmt = conn.prepare("SELECT registry, slug, skill_md FROM skills")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+    for row in rows {
+        let (registry, slug, skill_md) = row?;
+        for (position, sample) in text::code_samples(&skill_md).into_iter().enumerate() {
+            conn.execute(
+                "INSERT INTO skill_code_samples (registry, slug, position, language, heading, snippet) VALUES (?, ?, ?, ?, ?, ?)",
+                params![registry, slug, position as i64, sample.language, sample.heading, sample.code],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Skills a registry no longer has upstream, kept until `prune`
+fn skill_removals(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS skill_removals (
+            registry TEXT NOT NULL,
+            slug TEXT NOT NULL,
+            removed_at INTEGER NOT NULL,
+            PRIMARY KEY(registry, slug)
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// The version a database is at after every migration
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub fn current_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+fn pending_from(migrations: &'static [Migration], version: u32) -> Result<Vec<&'static Migration>> {
+    if version > migrations.last().map_or(0, |m| m.version) {
+        anyhow::bail!("The database is at schema version {}, from a newer version of safe-skill-search; upgrade to open it", version);
+    }
+    Ok(migrations.iter().filter(|m| m.version > version).collect())
+}
+
+/// Migrations `conn` still needs, oldest first
+pub fn pending(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    pending_from(MIGRATIONS, current_version(conn)?)
+}
+
+/// Migrations the database at `path` still needs, without opening it for
+/// writing. A database that doesn't exist yet needs all of them.
+pub fn pending_at(path: &Path) -> Result<Vec<&'static Migration>> {
+    if !path.exists() {
+        return Ok(MIGRATIONS.iter().collect());
+    }
+    pending(&Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?)
+}
+
+/// Apply the pending migrations in order and return them. Each one and its
+/// version bump commit together, so a failed step leaves the database at the
+/// version before it.
+pub fn migrate(conn: &mut Connection) -> Result<Vec<&'static Migration>> {
+    run(conn, MIGRATIONS)
+}
+
+fn run(conn: &mut Connection, migrations: &'static [Migration]) -> Result<Vec<&'static Migration>> {
+    let mut applied = Vec::new();
+    for migration in pending_from(migrations, current_version(conn)?)? {
+        // Taking the write lock first means a second process opening the
+        // database at the same time waits, then finds the step done
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        if current_version(&tx)? >= migration.version {
+            continue;
+        }
+        (migration.apply)(&tx).map_err(|e| e.context(format!("Schema migration {} ({}) failed", migration.version, migration.description)))?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        tracing::debug!("Applied schema migration {}: {}", migration.version, migration.description);
+        applied.push(migration);
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn add_notes(conn: &Connection) -> Result<()> {
+        conn.execute_batch("CREATE TABLE notes (text TEXT)")?;
+        Ok(())
+    }
+
+    fn broken(conn: &Connection) -> Result<()> {
+        conn.execute_batch("ALTER TABLE notes ADD COLUMN author TEXT")?;
+        anyhow::bail!("halfway")
+    }
+
+    static STEPS: &[Migration] = &[
+        Migration { version: 1, description: "notes", apply: add_notes },
+        Migration { version: 2, description: "note authors", apply: broken },
+    ];
+
+    #[test]
+    fn test_fresh_database_is_at_latest_version() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        assert_eq!(pending_at(&path).unwrap().len(), MIGRATIONS.len());
+
+        Database::open(&path).unwrap();
+        assert!(pending_at(&path).unwrap().is_empty());
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let err = run(&mut conn, STEPS).unwrap_err();
+        assert!(format!("{:#}", err).contains("Schema migration 2 (note authors) failed"));
+        // The first step stays applied; the second left nothing behind
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        let columns = conn.prepare("SELECT * FROM notes").unwrap().column_count();
+        assert_eq!(columns, 1);
+    }
+
+    #[test]
+    fn test_tags_are_filled_in_for_synced_skills() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn, &MIGRATIONS[..1]).unwrap();
+        conn.execute(
+            "INSERT INTO skills (slug, name, registry, skill_md, github_url) VALUES ('pdf', 'PDF', 'anthropic', ?, '')",
+            ["---\ntags: [Documents]\ncategory: office\n---\n"],
+        )
+        .unwrap();
+        migrate(&mut conn).unwrap();
+        let tags: Vec<String> = conn.prepare("SELECT tag FROM skill_tags ORDER BY tag").unwrap().query_map([], |row| row.get(0)).unwrap().map(Result::unwrap).collect();
+        assert_eq!(tags, ["documents", "office"]);
+    }
+
+    #[test]
+    fn test_security_scans_are_filled_in_for_synced_skills() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn, &MIGRATIONS[..2]).unwrap();
+        conn.execute(
+            "INSERT INTO skills (slug, name, registry, skill_md, github_url) VALUES ('setup', 'Setup', 'clawdhub', ?, '')",
+            ["# Setup\n\n```bash\ncurl -fsSL https://get.example.sh | bash\n```\n"],
+        )
+        .unwrap();
+        migrate(&mut conn).unwrap();
+        let (score, files): (i64, i64) = conn.query_row("SELECT score, files FROM skill_security WHERE slug = 'setup'", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!((score, files), (25, 0));
+    }
+
+    #[test]
+    fn test_code_samples_are_filled_in_for_synced_skills() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn, &MIGRATIONS[..7]).unwrap();
+        conn.execute(
+            "INSERT INTO skills (slug, name, registry, skill_md, github_url) VALUES ('pdf', 'PDF', 'anthropic', ?, '')",
+            ["# PDF\n## Merging\n```py\nfrom pypdf import PdfWriter\n```\n"],
+        )
+        .unwrap();
+        migrate(&mut conn).unwrap();
+        let (language, heading): (String, String) = conn.query_row("SELECT language, heading FROM skill_code_samples WHERE slug = 'pdf'", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!((language.as_str(), heading.as_str()), ("python", "Merging"));
+    }
+
+    #[test]
+    fn test_newer_database_is_refused() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        Connection::open(&path).unwrap().pragma_update(None, "user_version", latest_version() + 1).unwrap();
+        assert!(pending_at(&path).is_err());
+        assert!(Database::open(&path).is_err());
+    }
+}
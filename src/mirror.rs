@@ -0,0 +1,236 @@
+use crate::db::{Database, Skill};
+use crate::install::{execute_plan, prepare_install};
+use crate::output::SCHEMA_VERSION;
+use crate::resolve::{resolve_skill, Resolution};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Machine-readable index written at the root of a mirror
+pub const INDEX_FILE: &str = "index.json";
+/// Human-readable index, for browsing the mirror on a git host
+pub const INDEX_MD_FILE: &str = "INDEX.md";
+const SKILLS_DIR: &str = "skills";
+
+/// `index.json` of a mirrored collection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorIndex {
+    pub schema_version: u32,
+    pub collection: String,
+    /// Unix seconds
+    pub generated_at: i64,
+    pub skills: Vec<MirroredSkill>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirroredSkill {
+    pub registry: String,
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub version: Option<String>,
+    /// Where the skill was copied from
+    pub source_url: String,
+    /// Registry commit the files were copied at
+    pub rev: Option<String>,
+    /// SHA-256 over the copied files, as `install` computes it
+    pub content_hash: String,
+    /// Directory in the mirror, relative to its root
+    pub path: String,
+}
+
+/// What a mirror run changed
+#[derive(Debug)]
+pub struct MirrorReport {
+    pub index: MirrorIndex,
+    /// Paths of skills from the previous index that were dropped
+    pub removed: Vec<String>,
+}
+
+/// Split a collection entry into the skill query and its pinned revision
+pub fn parse_entry(entry: &str) -> (&str, Option<&str>) {
+    match entry.split_once('@') {
+        Some((query, rev)) if !rev.is_empty() => (query, Some(rev)),
+        _ => (entry.trim_end_matches('@'), None),
+    }
+}
+
+/// Look up every entry of a collection. Entries must name exactly one
+/// skill; ambiguous or unknown ones are errors rather than guesses.
+pub fn resolve_collection(db: &Database, entries: &[String]) -> Result<Vec<(Skill, Option<String>)>> {
+    let mut skills: Vec<(Skill, Option<String>)> = Vec::new();
+    for entry in entries {
+        let (query, rev) = parse_entry(entry);
+        // No registry priority, so a slug in several registries is ambiguous
+        let skill = match resolve_skill(db, query, &[])? {
+            Resolution::Exact(s) | Resolution::Preferred(s, _) => s,
+            Resolution::Ambiguous(c) => anyhow::bail!(
+                "'{}' is in several registries ({}); write it as registry:slug",
+                query,
+                c.iter().map(|s| s.registry.as_str()).collect::<Vec<_>>().join(", ")
+            ),
+            Resolution::Candidates(_) => anyhow::bail!("'{}' is not in the catalog", query),
+        };
+        if skills.iter().any(|(s, _)| s.registry == skill.registry && s.slug == skill.slug) {
+            anyhow::bail!("{}:{} is listed more than once", skill.registry, skill.slug);
+        }
+        skills.push((skill, rev.map(str::to_string)));
+    }
+    Ok(skills)
+}
+
+/// Copy `skills` into `dest`, a git work tree, as `skills/<registry>/<slug>`
+/// and write the indexes. Unpinned skills are copied from the synced clone
+/// and recorded at its commit. Skills the previous index listed that are no
+/// longer in the collection are deleted; other files are left alone.
+pub fn mirror(repos_dir: &Path, staging_root: &Path, dest: &Path, collection: &str, skills: &[(Skill, Option<String>)], now: i64) -> Result<MirrorReport> {
+    if !dest.join(".git").exists() {
+        anyhow::bail!("{} is not a git repository (run `git init` there first)", dest.display());
+    }
+    let previous = read_index(dest)?;
+
+    let mut mirrored = Vec::new();
+    for (skill, rev) in skills {
+        let path = format!("{}/{}/{}", SKILLS_DIR, skill.registry, skill.slug);
+        let prepared = prepare_install(repos_dir, staging_root, skill, &dest.join(&path), rev.as_deref())?;
+        execute_plan(&prepared.plan, true)?;
+        mirrored.push(MirroredSkill {
+            registry: skill.registry.clone(),
+            slug: skill.slug.clone(),
+            name: skill.name.clone(),
+            description: skill.description.clone(),
+            version: prepared.installed_version.clone(),
+            source_url: skill.github_url.clone(),
+            rev: prepared.installed_rev.clone(),
+            content_hash: prepared.plan.content_hash()?,
+            path,
+        });
+    }
+
+    let mut removed = Vec::new();
+    for old in previous.map(|p| p.skills).unwrap_or_default() {
+        // Only ever delete under skills/, whatever an edited index says
+        let safe = old.path.starts_with(&format!("{}/", SKILLS_DIR)) && !old.path.split('/').any(|c| c == ".." || c.is_empty());
+        if safe && !mirrored.iter().any(|m| m.path == old.path) && dest.join(&old.path).exists() {
+            std::fs::remove_dir_all(dest.join(&old.path))?;
+            removed.push(old.path);
+        }
+    }
+
+    let index = MirrorIndex {
+        schema_version: SCHEMA_VERSION,
+        collection: collection.to_string(),
+        generated_at: now,
+        skills: mirrored,
+    };
+    std::fs::write(dest.join(INDEX_FILE), serde_json::to_string_pretty(&index)? + "\n")?;
+    std::fs::write(dest.join(INDEX_MD_FILE), render_markdown(&index))?;
+    Ok(MirrorReport { index, removed })
+}
+
+fn read_index(dest: &Path) -> Result<Option<MirrorIndex>> {
+    let path = dest.join(INDEX_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)?;
+    let index = serde_json::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid mirror index {}: {}", path.display(), e))?;
+    Ok(Some(index))
+}
+
+fn render_markdown(index: &MirrorIndex) -> String {
+    let mut out = format!(
+        "# {}\n\nMirrored by safe-skill-search; regenerate with `mirror --collection {}` rather than editing.\n\n| Skill | Version | Source | Revision |\n|---|---|---|---|\n",
+        index.collection, index.collection
+    );
+    for s in &index.skills {
+        out.push_str(&format!(
+            "| [{}]({}) | {} | [{}:{}]({}) | {} |\n",
+            s.name.replace('|', "\\|"),
+            s.path,
+            s.version.as_deref().unwrap_or("-"),
+            s.registry,
+            s.slug,
+            s.source_url,
+            s.rev.as_deref().map(|r| &r[..r.len().min(12)]).unwrap_or("-")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github;
+    use tempfile::tempdir;
+
+    fn skill(registry: &str, slug: &str) -> Skill {
+        Skill {
+            name: slug.to_uppercase(),
+            description: format!("{} tools", slug),
+            skill_md: format!("# {}\n", slug),
+            github_url: format!("https://github.com/anthropics/skills/tree/main/skills/{}", slug),
+            trusted: true,
+            ..Skill::fixture(registry, slug)
+        }
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        assert_eq!(parse_entry("anthropic:pdf"), ("anthropic:pdf", None));
+        assert_eq!(parse_entry("pdf@3f2c1a9"), ("pdf", Some("3f2c1a9")));
+        assert_eq!(parse_entry("pdf@"), ("pdf", None));
+    }
+
+    #[test]
+    fn test_resolve_collection() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&skill("anthropic", "pdf")).unwrap();
+        db.upsert_skill(&skill("openai", "pdf")).unwrap();
+        db.upsert_skill(&skill("anthropic", "xlsx")).unwrap();
+
+        let resolved = resolve_collection(&db, &["anthropic:pdf@abc".to_string(), "xlsx".to_string()]).unwrap();
+        assert_eq!(resolved.len(), 2);
+        assert_eq!((resolved[0].0.registry.as_str(), resolved[0].1.as_deref()), ("anthropic", Some("abc")));
+        assert_eq!(resolved[1].0.slug, "xlsx");
+
+        assert!(resolve_collection(&db, &["pdf".to_string()]).unwrap_err().to_string().contains("anthropic, openai"));
+        assert!(resolve_collection(&db, &["nope".to_string()]).is_err());
+        assert!(resolve_collection(&db, &["xlsx".to_string(), "anthropic:xlsx".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_mirror_writes_skills_and_index() {
+        let dir = tempdir().unwrap();
+        let repos_dir = dir.path().join("repos");
+        for slug in ["pdf", "xlsx"] {
+            let skill_dir = github::repo_dir(&repos_dir, "anthropic").join("skills").join(slug);
+            std::fs::create_dir_all(&skill_dir).unwrap();
+            std::fs::write(skill_dir.join("SKILL.md"), format!("---\nname: {}\nversion: 1.0.0\n---\n", slug)).unwrap();
+        }
+        let dest = dir.path().join("mirror");
+        let staging = dir.path().join("staging");
+        let both = [(skill("anthropic", "pdf"), None), (skill("anthropic", "xlsx"), None)];
+        assert!(mirror(&repos_dir, &staging, &dest, "approved", &both, 1).is_err());
+
+        std::fs::create_dir_all(dest.join(".git")).unwrap();
+        std::fs::write(dest.join("README.md"), "ours").unwrap();
+        let report = mirror(&repos_dir, &staging, &dest, "approved", &both, 1).unwrap();
+        assert_eq!(report.index.skills.len(), 2);
+        assert_eq!(report.index.skills[0].version.as_deref(), Some("1.0.0"));
+        assert!(report.index.skills[0].content_hash.starts_with("sha256:"));
+        assert!(dest.join("skills/anthropic/xlsx/SKILL.md").exists());
+        assert!(std::fs::read_to_string(dest.join(INDEX_MD_FILE)).unwrap().contains("[PDF](skills/anthropic/pdf)"));
+
+        // Dropping a skill from the collection deletes its copy only
+        let report = mirror(&repos_dir, &staging, &dest, "approved", &both[..1], 2).unwrap();
+        assert_eq!(report.removed, vec!["skills/anthropic/xlsx"]);
+        assert!(!dest.join("skills/anthropic/xlsx").exists());
+        assert!(dest.join("skills/anthropic/pdf/SKILL.md").exists());
+        assert_eq!(std::fs::read_to_string(dest.join("README.md")).unwrap(), "ours");
+        let index: MirrorIndex = serde_json::from_str(&std::fs::read_to_string(dest.join(INDEX_FILE)).unwrap()).unwrap();
+        assert_eq!(index.generated_at, 2);
+        assert_eq!(index.skills.len(), 1);
+    }
+}
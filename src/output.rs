@@ -0,0 +1,368 @@
+use crate::audit::Finding;
+use crate::db::{Database, Layer, Skill};
+use crate::github::{self, Channel};
+use crate::index::Snippet;
+use crate::policy::DecisionSet;
+use crate::quality::QualityScores;
+use crate::security::SecurityReport;
+use crate::text::{self, Heading};
+use crate::unicode::UnicodeFlag;
+use crate::{catalog, overlay};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Version of the `--json` output structs below. Fields serialize in struct
+/// order; bump this when a field is renamed, removed, or changes meaning
+/// (adding one is not a breaking change).
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Output of `search --json`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchOutput {
+    pub schema_version: u32,
+    pub query: String,
+    pub results: Vec<SearchResult>,
+}
+
+impl SearchOutput {
+    pub fn new(query: String, results: Vec<SearchResult>) -> Self {
+        Self { schema_version: SCHEMA_VERSION, query, results }
+    }
+}
+
+/// Output of `search --queries-file`, and of `POST /skills/search/batch`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BatchSearchOutput {
+    pub schema_version: u32,
+    /// Results of each query, keyed by the query as written
+    pub results: BTreeMap<String, Vec<SearchResult>>,
+}
+
+/// [`SearchOutput`] with only the `--fields` of each result
+#[derive(Debug, Serialize)]
+pub struct ProjectedSearchOutput {
+    pub schema_version: u32,
+    pub query: String,
+    pub results: Vec<Projection>,
+}
+
+/// [`BatchSearchOutput`] with only the `--fields` of each result
+#[derive(Debug, Serialize)]
+pub struct ProjectedBatchOutput {
+    pub schema_version: u32,
+    pub results: BTreeMap<String, Vec<Projection>>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct SearchResult {
+    pub slug: String,
+    pub name: String,
+    pub registry: String,
+    /// Review level of the registry: curated, experimental, or community
+    pub channel: Channel,
+    pub description: String,
+    pub github_url: String,
+    pub stars: i64,
+    pub downloads: i64,
+    pub installs: i64,
+    /// Average rating (1-5), if the registry reports ratings
+    pub rating: Option<f64>,
+    pub rating_count: i64,
+    /// Rank within the registry by stars, as "top N%"
+    pub stars_top_pct: Option<f64>,
+    /// Rank within the registry by downloads, as "top N%"
+    pub downloads_top_pct: Option<f64>,
+    /// Days since the skill last changed upstream, when known
+    pub age_days: Option<i64>,
+    pub trusted: bool,
+    /// Matched against a local overlay copy that shadows the catalog's
+    pub overlay: bool,
+    /// Every declared test passed against the current content (`test`)
+    pub tested: bool,
+    /// The skill's URL answered 404 or 410 when last checked; only listed
+    /// with `--include-unreachable`
+    pub unreachable: bool,
+    /// Unix time a sync found the skill gone from its registry upstream;
+    /// only listed with `--include-removed`
+    pub removed_at: Option<i64>,
+    pub search_score: f32,
+    /// Only matched a close spelling of the query; `search_score` then
+    /// doesn't compare with exact matches
+    pub fuzzy: bool,
+    /// How likely this is the skill searched for, 0-1, from its score and
+    /// its lead over the next result. As a rule of thumb, above 0.6 the top
+    /// result can be used as is, and below 0.3 nothing matched well.
+    pub confidence: f32,
+    /// Excerpt of the skill's text around the words that matched; none for
+    /// fuzzy and semantic-only hits
+    pub snippet: Option<Snippet>,
+    pub quality_score: i64,
+    /// Prompt-injection risk, 0-100
+    pub injection_risk: i64,
+    /// Risk from the last security scan, 0-100: SKILL.md only unless `scan`
+    /// or `install` also covered the bundled files
+    pub security_risk: i64,
+    pub unicode_flags: Vec<UnicodeFlag>,
+    /// Catalog layer the skill comes from; always `user` without a system
+    /// catalog
+    pub layer: Layer,
+    /// Level 1 and 2 headings of the SKILL.md, in order; `show --outline`
+    /// lists them all
+    pub headings: Vec<String>,
+    /// Tags and categories from the frontmatter, normalized
+    pub tags: Vec<String>,
+    /// About 100 tokens on what the skill does, for agent prompts
+    pub summary: String,
+}
+
+/// One skill with the same fields as a search result, plus its SKILL.md
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SkillOutput {
+    pub schema_version: u32,
+    pub skill: SearchResult,
+    pub skill_md: String,
+}
+
+/// What turning a catalog skill into a [`SearchResult`] looks up besides
+/// the skill itself
+pub struct Enricher<'a> {
+    pub db: &'a Database,
+    pub quality: &'a QualityScores,
+    pub decisions: &'a DecisionSet,
+    /// Copies indexed from here replace the catalog's SKILL.md
+    pub overlay_dir: Option<&'a Path>,
+    /// From [`Database::get_unreachable`]; empty to mark nothing
+    pub unreachable: HashSet<(String, String)>,
+    pub now: i64,
+}
+
+impl Enricher<'_> {
+    /// `overlay` says the result matched the skill's overlay copy
+    pub fn result(&self, mut s: Skill, overlay: bool, search_score: f32) -> SearchResult {
+        let db = self.db;
+        s.trusted = self.decisions.is_trusted(&s);
+        if let (true, Some(dir)) = (overlay, self.overlay_dir) {
+            overlay::apply(dir, &mut s);
+        }
+        // Only a pass against exactly this content counts
+        let tested = db
+            .get_test_status(&s.registry, &s.slug)
+            .ok()
+            .flatten()
+            .is_some_and(|t| t.passes_for(&catalog::content_hash(&s)));
+        let quality_score = self.quality.score_for(&s);
+        let (injection_risk, _) = db.get_injection_risk(&s.registry, &s.slug).unwrap_or_default();
+        let security_risk = db.get_security_report(&s.registry, &s.slug).ok().flatten().map_or(0, |r| r.score);
+        let rating = db.get_rating(&s.registry, &s.slug).ok().flatten();
+        let rank = db.get_popularity_rank(&s.registry, &s.slug).unwrap_or_default();
+        let age_days = s.age_days(self.now);
+        let unreachable = self.unreachable.contains(&(s.registry.clone(), s.slug.clone()));
+        let removed_at = db.get_removed_at(&s.registry, &s.slug).ok().flatten();
+        let unicode_flags = db
+            .get_unicode_flags(&s.registry, &s.slug)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|i| i.flag)
+            .collect();
+        let layer = db.layer_of(&s.registry, &s.slug).unwrap_or(Layer::User);
+        let outline = if overlay { text::outline(&s.skill_md) } else { skill_outline(db, &s) };
+        let headings = outline.into_iter().filter(|h| h.level <= 2).map(|h| h.title).collect();
+        let tags = if overlay { github::parse_skill_frontmatter(&s.skill_md).tags } else { db.get_tags(&s.registry, &s.slug).unwrap_or_default() };
+        let stored_summary = if overlay { None } else { db.get_summary(&s.registry, &s.slug).ok().flatten() };
+        let summary = stored_summary.unwrap_or_else(|| text::compact_summary(&s.skill_md, &s.description));
+
+        SearchResult {
+            slug: s.slug,
+            name: s.name,
+            channel: github::channel_of(&s.registry),
+            registry: s.registry,
+            description: s.description,
+            github_url: s.github_url,
+            stars: s.stars,
+            downloads: s.downloads,
+            installs: s.installs,
+            rating: rating.as_ref().map(|r| r.average),
+            rating_count: rating.as_ref().map(|r| r.count).unwrap_or(0),
+            stars_top_pct: rank.stars_top_pct,
+            downloads_top_pct: rank.downloads_top_pct,
+            age_days,
+            trusted: s.trusted,
+            overlay,
+            tested,
+            unreachable,
+            removed_at,
+            search_score,
+            fuzzy: false,
+            confidence: 0.0,
+            snippet: None,
+            quality_score,
+            injection_risk,
+            security_risk,
+            unicode_flags,
+            layer,
+            headings,
+            tags,
+            summary,
+        }
+    }
+}
+
+/// The `search` flags that filter enriched results
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    pub trusted: bool,
+    pub channel: Option<Channel>,
+    pub include_unreachable: bool,
+    pub include_removed: bool,
+    pub min_score: i64,
+    pub max_injection_risk: Option<i64>,
+    pub max_age_days: Option<i64>,
+}
+
+impl SearchFilters {
+    pub fn keep(&self, r: &SearchResult) -> bool {
+        (!self.trusted || r.trusted)
+            && self.channel.is_none_or(|c| r.channel == c)
+            && (self.include_unreachable || !r.unreachable)
+            && (self.include_removed || r.removed_at.is_none())
+            && r.quality_score >= self.min_score
+            && self.max_injection_risk.is_none_or(|max| r.injection_risk <= max)
+            // Skills whose age is unknown are kept
+            && self.max_age_days.is_none_or(|max| r.age_days.is_none_or(|age| age <= max))
+    }
+}
+
+/// Heading outline stored at sync time, or parsed now for skills last
+/// synced before outlines were stored
+pub fn skill_outline(db: &Database, skill: &Skill) -> Vec<Heading> {
+    match db.get_headings(&skill.registry, &skill.slug) {
+        Ok(headings) if !headings.is_empty() => headings,
+        _ => text::outline(&skill.skill_md),
+    }
+}
+
+/// Output of `audit --json`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AuditOutput {
+    pub schema_version: u32,
+    pub registry: String,
+    pub slug: String,
+    pub findings: Vec<Finding>,
+}
+
+/// Output of `scan --json`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ScanOutput {
+    pub schema_version: u32,
+    pub registry: String,
+    pub slug: String,
+    #[serde(flatten)]
+    pub report: SecurityReport,
+}
+
+/// Top-level fields of a serialized `T`, as `--fields` names them
+pub fn field_names<T: JsonSchema>() -> Vec<String> {
+    schemars::schema_for!(T).schema.object.map(|o| o.properties.keys().cloned().collect()).unwrap_or_default()
+}
+
+/// Parse a comma-separated `--fields` list, refusing names not in `known`
+pub fn parse_fields(spec: &str, known: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut fields = Vec::new();
+    for field in spec.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        if !known.iter().any(|k| k == field) {
+            anyhow::bail!("Unknown field {} (fields: {})", field, known.join(", "));
+        }
+        if !fields.iter().any(|f| f == field) {
+            fields.push(field.to_string());
+        }
+    }
+    if fields.is_empty() {
+        anyhow::bail!("--fields needs at least one field (fields: {})", known.join(", "));
+    }
+    Ok(fields)
+}
+
+/// Some fields of a record, serialized in the order they were asked for
+#[derive(Debug)]
+pub struct Projection(Vec<(String, serde_json::Value)>);
+
+impl Projection {
+    pub fn new<T: Serialize>(record: &T, fields: &[String]) -> serde_json::Result<Self> {
+        Ok(Self::of_value(serde_json::to_value(record)?, fields))
+    }
+
+    /// Project an already serialized record, e.g. one with fields added
+    pub fn of_value(mut value: serde_json::Value, fields: &[String]) -> Self {
+        let values = fields
+            .iter()
+            .map(|f| (f.clone(), value.get_mut(f.as_str()).map(serde_json::Value::take).unwrap_or_default()))
+            .collect();
+        Self(values)
+    }
+}
+
+impl Serialize for Projection {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (field, value) in &self.0 {
+            map.serialize_entry(field, value)?;
+        }
+        map.end()
+    }
+}
+
+/// JSON Schema for a command's `--json` output, pretty-printed
+pub fn schema_for<T: JsonSchema>() -> String {
+    serde_json::to_string_pretty(&schemars::schema_for!(T)).expect("schema serializes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_order_is_stable() {
+        let out = AuditOutput {
+            schema_version: SCHEMA_VERSION,
+            registry: "anthropic".to_string(),
+            slug: "pdf".to_string(),
+            findings: vec![],
+        };
+        assert_eq!(
+            serde_json::to_string(&out).unwrap(),
+            r#"{"schema_version":1,"registry":"anthropic","slug":"pdf","findings":[]}"#
+        );
+    }
+
+    #[test]
+    fn test_schema_lists_fields() {
+        let schema: serde_json::Value = serde_json::from_str(&schema_for::<SearchOutput>()).unwrap();
+        assert_eq!(schema["title"], "SearchOutput");
+        assert!(schema["required"].as_array().unwrap().contains(&"schema_version".into()));
+        let result = &schema["definitions"]["SearchResult"]["properties"];
+        assert!(result["injection_risk"].is_object());
+        assert!(schema["definitions"]["UnicodeFlag"].is_object());
+    }
+
+    #[test]
+    fn test_field_projection() {
+        let known = field_names::<AuditOutput>();
+        assert_eq!(known, ["findings", "registry", "schema_version", "slug"]);
+        let fields = parse_fields("slug, registry,slug", &known).unwrap();
+        assert_eq!(fields, ["slug", "registry"]);
+        assert!(parse_fields("slug,skill_md", &known).unwrap_err().to_string().starts_with("Unknown field skill_md"));
+        assert!(parse_fields(" , ", &known).is_err());
+
+        let out = AuditOutput {
+            schema_version: SCHEMA_VERSION,
+            registry: "jo".to_string(),
+            slug: "pdf".to_string(),
+            findings: Vec::new(),
+        };
+        let projected = Projection::new(&out, &fields).unwrap();
+        assert_eq!(serde_json::to_string(&projected).unwrap(), r#"{"slug":"pdf","registry":"jo"}"#);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::db::Skill;
+use crate::github;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Where the overlay copy of a skill lives: `<dir>/<registry>/<slug>/SKILL.md`
+pub fn overlay_path(dir: &Path, registry: &str, slug: &str) -> PathBuf {
+    dir.join(registry).join(slug).join("SKILL.md")
+}
+
+/// Shadow a catalog skill with its overlay copy, if the overlay has one.
+/// Returns whether it did.
+pub fn apply(dir: &Path, skill: &mut Skill) -> bool {
+    let path = overlay_path(dir, &skill.registry, &skill.slug);
+    match std::fs::read_to_string(&path) {
+        Ok(skill_md) => {
+            github::apply_skill_md(skill, skill_md);
+            true
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => {
+            tracing::warn!("Could not read overlay {:?}: {}", path, e);
+            false
+        }
+    }
+}
+
+/// Newest modification time in the overlay. Directories count too, so a
+/// removed copy registers as a change.
+pub fn last_modified(dir: &Path) -> Option<SystemTime> {
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    let mut newest = None;
+    for registry in std::fs::read_dir(dir).ok()?.flatten() {
+        newest = newest.max(modified(&registry.path()));
+        for skill in std::fs::read_dir(registry.path()).into_iter().flatten().flatten() {
+            newest = newest.max(modified(&skill.path())).max(modified(&skill.path().join("SKILL.md")));
+        }
+    }
+    newest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_test_skill(registry: &str, slug: &str) -> Skill {
+        Skill {
+            description: "Upstream description".to_string(),
+            skill_md: "# Upstream".to_string(),
+            github_url: String::new(),
+            ..Skill::fixture(registry, slug)
+        }
+    }
+
+    #[test]
+    fn test_apply_overlay() {
+        let dir = tempdir().unwrap();
+        assert!(last_modified(dir.path()).is_none());
+
+        let path = overlay_path(dir.path(), "clawdhub", "pdf");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "---\ndescription: Patched to use our PDF service\n---\n# PDF\n").unwrap();
+
+        let mut pdf = create_test_skill("clawdhub", "pdf");
+        assert!(apply(dir.path(), &mut pdf));
+        assert_eq!(pdf.description, "Patched to use our PDF service");
+        assert!(pdf.skill_md.starts_with("---"));
+
+        // Same slug in another registry is a different skill
+        let mut other = create_test_skill("anthropic", "pdf");
+        assert!(!apply(dir.path(), &mut other));
+        assert_eq!(other.skill_md, "# Upstream");
+
+        assert!(last_modified(dir.path()).is_some());
+    }
+}
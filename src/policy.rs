@@ -0,0 +1,358 @@
+use crate::db::{Layer, Skill};
+use anyhow::{bail, Result};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+
+/// Admin policy file in a system catalog directory
+pub const POLICY_FILE: &str = "policy.toml";
+
+/// Signature over the policy file, next to it
+pub const SIGNATURE_FILE: &str = "policy.toml.sig";
+
+/// Key the policy is signed with. Once it exists, an unsigned or altered
+/// policy is refused.
+pub const KEY_FILE: &str = "policy.key";
+
+const SIGNATURE_PREFIX: &str = "hmac-sha256:";
+
+/// Skills marked trusted or denied, each as `registry:slug` or `registry:*`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Decisions {
+    pub trust: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Trust,
+    Deny,
+}
+
+impl Decision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Decision::Trust => "trusted",
+            Decision::Deny => "denied",
+        }
+    }
+}
+
+fn matches(pattern: &str, registry: &str, slug: &str) -> bool {
+    match pattern.split_once(':') {
+        Some((r, "*")) => r.eq_ignore_ascii_case(registry),
+        Some((r, s)) => r.eq_ignore_ascii_case(registry) && s.eq_ignore_ascii_case(slug),
+        None => false,
+    }
+}
+
+impl Decisions {
+    /// Deny wins when a skill matches both lists
+    pub fn decide(&self, registry: &str, slug: &str) -> Option<Decision> {
+        if self.deny.iter().any(|p| matches(p, registry, slug)) {
+            Some(Decision::Deny)
+        } else if self.trust.iter().any(|p| matches(p, registry, slug)) {
+            Some(Decision::Trust)
+        } else {
+            None
+        }
+    }
+}
+
+/// Which of the user's own decisions a system policy lets through
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UserOverrides {
+    pub trust: bool,
+    pub deny: bool,
+}
+
+impl Default for UserOverrides {
+    /// Users may hide more, but not vouch for what the admin hasn't
+    fn default() -> Self {
+        Self { trust: false, deny: true }
+    }
+}
+
+/// `policy.toml` shipped alongside a system catalog
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Refuse flags that would loosen the rules below, instead of treating
+    /// them as defaults
+    pub enforced: bool,
+    /// Registries skills may come from; empty allows all
+    pub registries: Vec<String>,
+    /// Quality score `search` and `top` default to, and under enforcement
+    /// the lowest they (and `install`) accept
+    pub min_quality: Option<i64>,
+    /// Only install trusted skills
+    pub trusted_installs_only: bool,
+    pub decisions: Decisions,
+    pub user_overrides: UserOverrides,
+}
+
+impl Policy {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid policy {:?}: {}", path, e))
+    }
+
+    /// Load the policy in a catalog directory, checking its signature when
+    /// the directory has a policy key
+    pub fn load_verified(dir: &Path) -> Result<Self> {
+        let (path, key_path, sig_path) = (dir.join(POLICY_FILE), dir.join(KEY_FILE), dir.join(SIGNATURE_FILE));
+        if key_path.exists() {
+            let content = std::fs::read(&path).unwrap_or_default();
+            let key = crate::catalog::load_or_create_key(&key_path)?;
+            let signature = std::fs::read_to_string(&sig_path).unwrap_or_default();
+            if !verify(&content, signature.trim(), &key)? {
+                bail!("The policy in {:?} doesn't match its signature; re-sign it with `policy sign`", dir);
+            }
+        } else if sig_path.exists() {
+            bail!("The policy in {:?} is signed, but there's no {} to check it with", dir, KEY_FILE);
+        }
+        Self::load(&path)
+    }
+
+    pub fn allows_registry(&self, registry: &str) -> bool {
+        self.registries.is_empty() || self.registries.iter().any(|r| r.eq_ignore_ascii_case(registry))
+    }
+
+    /// `--min-score` as given, else the policy's minimum, else `default`
+    pub fn min_score(&self, flag: Option<i64>, default: i64) -> Result<i64> {
+        match (flag, self.min_quality) {
+            (Some(score), Some(min)) if self.enforced && score < min => {
+                bail!("The system policy enforces a minimum quality score of {}", min)
+            }
+            (Some(score), _) => Ok(score),
+            (None, min) => Ok(min.unwrap_or(default)),
+        }
+    }
+
+    /// Under enforcement, `--registry` has to name an allowed registry
+    pub fn check_registry_flag(&self, registry: Option<&str>) -> Result<()> {
+        match registry {
+            Some(r) if self.enforced && !self.allows_registry(r) => {
+                bail!("The system policy only allows skills from {}", self.registries.join(", "))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn mac(content: &[u8], key: &[u8]) -> Result<Hmac<Sha256>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| anyhow::anyhow!("Invalid policy key: {}", e))?;
+    mac.update(content);
+    Ok(mac)
+}
+
+/// Signature over a policy file's bytes
+pub fn sign(content: &[u8], key: &[u8]) -> Result<String> {
+    Ok(format!("{}{}", SIGNATURE_PREFIX, hex::encode(mac(content, key)?.finalize().into_bytes())))
+}
+
+pub fn verify(content: &[u8], signature: &str, key: &[u8]) -> Result<bool> {
+    let Some(Ok(sig)) = signature.strip_prefix(SIGNATURE_PREFIX).map(hex::decode) else {
+        return Ok(false);
+    };
+    Ok(mac(content, key)?.verify_slice(&sig).is_ok())
+}
+
+/// `policy sign`: sign the policy in a catalog directory with its key,
+/// creating the key first if need be. Returns the policy's path.
+pub fn sign_in(dir: &Path) -> Result<PathBuf> {
+    let path = dir.join(POLICY_FILE);
+    let content = std::fs::read(&path).map_err(|e| anyhow::anyhow!("Can't read {:?}: {}", path, e))?;
+    let key = crate::catalog::load_or_create_key(&dir.join(KEY_FILE))?;
+    std::fs::write(dir.join(SIGNATURE_FILE), sign(&content, &key)?)?;
+    Ok(path)
+}
+
+/// System and user decisions together
+#[derive(Debug, Clone, Default)]
+pub struct DecisionSet {
+    /// None without a system catalog; the user's decisions then all apply
+    system: Option<Policy>,
+    user: Decisions,
+}
+
+impl DecisionSet {
+    pub fn new(system: Option<Policy>, user: Decisions) -> Self {
+        Self { system, user }
+    }
+
+    /// Replace the user's decisions, keeping the system policy
+    pub fn set_user(&mut self, user: Decisions) {
+        self.user = user;
+    }
+
+    /// The decision for a skill and the layer it came from. A user decision
+    /// the policy allows takes precedence over the system's.
+    pub fn decide(&self, registry: &str, slug: &str) -> Option<(Decision, Layer)> {
+        let allowed = |d: &Decision| match (&self.system, d) {
+            (None, _) => true,
+            // Trusting past an enforced policy would undo it
+            (Some(p), Decision::Trust) => p.user_overrides.trust && !p.enforced,
+            (Some(p), Decision::Deny) => p.user_overrides.deny,
+        };
+        if let Some(d) = self.user.decide(registry, slug).filter(allowed) {
+            return Some((d, Layer::User));
+        }
+        self.system.as_ref()?.decisions.decide(registry, slug).map(|d| (d, Layer::System))
+    }
+
+    pub fn is_denied(&self, registry: &str, slug: &str) -> bool {
+        matches!(self.decide(registry, slug), Some((Decision::Deny, _)))
+    }
+
+    /// True if some trust decision applies, so a skill the registry doesn't
+    /// trust may still count as trusted
+    pub fn adds_trust(&self) -> bool {
+        let user = !self.user.trust.is_empty() && self.system.as_ref().is_none_or(|p| p.user_overrides.trust && !p.enforced);
+        user || self.system.as_ref().is_some_and(|p| !p.decisions.trust.is_empty())
+    }
+
+    /// Trusted as decided, or as the registry says when undecided
+    pub fn is_trusted(&self, skill: &Skill) -> bool {
+        match self.decide(&skill.registry, &skill.slug) {
+            Some((decision, _)) => decision == Decision::Trust,
+            None => skill.trusted,
+        }
+    }
+
+    /// Refuse an install that a deny decision or the system policy rules out.
+    /// `allow_untrusted` waives the trusted-only rule unless it's enforced;
+    /// returns whether it had to.
+    pub fn check_install(&self, skill: &Skill, quality_score: i64, allow_untrusted: bool) -> Result<bool> {
+        let id = format!("{}:{}", skill.registry, skill.slug);
+        if let Some((Decision::Deny, layer)) = self.decide(&skill.registry, &skill.slug) {
+            let by = if layer == Layer::System { "the system policy" } else { "your config" };
+            bail!("{} is denied by {}", id, by);
+        }
+        let Some(policy) = &self.system else {
+            return Ok(false);
+        };
+        if policy.enforced && !policy.allows_registry(&skill.registry) {
+            bail!("{} is from a registry the system policy doesn't allow ({} only)", id, policy.registries.join(", "));
+        }
+        if let Some(min) = policy.min_quality.filter(|min| policy.enforced && quality_score < *min) {
+            bail!("{} has quality score {}; the system policy requires {}", id, quality_score, min);
+        }
+        if policy.trusted_installs_only && !self.is_trusted(skill) {
+            if policy.enforced {
+                bail!("{} isn't trusted, and the system policy only allows installing trusted skills", id);
+            }
+            if !allow_untrusted {
+                bail!("{} isn't trusted, and the system policy only installs trusted skills; pass --allow-untrusted to install it anyway", id);
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decisions(trust: &[&str], deny: &[&str]) -> Decisions {
+        Decisions {
+            trust: trust.iter().map(|s| s.to_string()).collect(),
+            deny: deny.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_deny_beats_trust() {
+        let d = decisions(&["clawdhub:*"], &["clawdhub:evil"]);
+        assert_eq!(d.decide("clawdhub", "evil"), Some(Decision::Deny));
+        assert_eq!(d.decide("ClawdHub", "pdf"), Some(Decision::Trust));
+        assert_eq!(d.decide("openai", "pdf"), None);
+    }
+
+    #[test]
+    fn test_user_overrides_only_where_allowed() {
+        let policy: Policy = toml::from_str("[decisions]\ndeny = [\"clawdhub:evil\"]\ntrust = [\"jo:pdf\"]\n").unwrap();
+        let user = decisions(&["clawdhub:evil", "clawdhub:fine"], &["jo:pdf"]);
+        let set = DecisionSet::new(Some(policy.clone()), user.clone());
+        // Trusting is the admin's call by default; denying isn't
+        assert_eq!(set.decide("clawdhub", "evil"), Some((Decision::Deny, Layer::System)));
+        assert_eq!(set.decide("clawdhub", "fine"), None);
+        assert_eq!(set.decide("jo", "pdf"), Some((Decision::Deny, Layer::User)));
+
+        let permissive = Policy {
+            user_overrides: UserOverrides { trust: true, deny: false },
+            ..policy
+        };
+        let set = DecisionSet::new(Some(permissive), user.clone());
+        assert_eq!(set.decide("clawdhub", "evil"), Some((Decision::Trust, Layer::User)));
+        assert_eq!(set.decide("jo", "pdf"), Some((Decision::Trust, Layer::System)));
+
+        let standalone = DecisionSet::new(None, user);
+        assert_eq!(standalone.decide("clawdhub", "fine"), Some((Decision::Trust, Layer::User)));
+    }
+
+    fn skill(registry: &str, slug: &str, trusted: bool) -> Skill {
+        Skill {
+            github_url: String::new(),
+            trusted,
+            ..Skill::fixture(registry, slug)
+        }
+    }
+
+    #[test]
+    fn test_enforced_policy_refuses_overrides() {
+        let mut policy: Policy = toml::from_str(
+            "registries = [\"anthropic\", \"jo\"]\nmin_quality = 90\ntrusted_installs_only = true\n[user_overrides]\ntrust = true\n",
+        )
+        .unwrap();
+        let user = decisions(&["jo:*"], &[]);
+
+        // Unenforced, the rules are defaults
+        assert_eq!(policy.min_score(None, 80).unwrap(), 90);
+        assert_eq!(policy.min_score(Some(50), 80).unwrap(), 50);
+        assert!(policy.check_registry_flag(Some("clawdhub")).is_ok());
+        let set = DecisionSet::new(Some(policy.clone()), user.clone());
+        let untrusted = skill("clawdhub", "pdf", false);
+        assert!(set.check_install(&untrusted, 0, false).is_err());
+        assert!(set.check_install(&untrusted, 0, true).unwrap());
+        assert!(!set.check_install(&skill("jo", "pdf", false), 0, false).unwrap());
+        assert!(set.adds_trust());
+
+        policy.enforced = true;
+        assert!(policy.min_score(Some(50), 80).is_err());
+        assert_eq!(policy.min_score(Some(95), 80).unwrap(), 95);
+        assert!(policy.check_registry_flag(Some("clawdhub")).is_err());
+        assert!(policy.check_registry_flag(Some("JO")).is_ok());
+        let set = DecisionSet::new(Some(policy), user);
+        assert!(set.check_install(&skill("clawdhub", "pdf", true), 95, true).is_err());
+        assert!(set.check_install(&skill("anthropic", "pdf", true), 80, false).is_err());
+        assert!(set.check_install(&skill("anthropic", "pdf", true), 95, false).is_ok());
+        // The user's trust no longer counts
+        assert!(set.check_install(&skill("jo", "pdf", false), 95, true).is_err());
+        assert!(!set.adds_trust());
+    }
+
+    #[test]
+    fn test_signed_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(POLICY_FILE);
+        std::fs::write(&path, "enforced = true\n").unwrap();
+        assert!(Policy::load_verified(dir.path()).unwrap().enforced);
+
+        let key = crate::catalog::load_or_create_key(&dir.path().join(KEY_FILE)).unwrap();
+        // A key without a signature means the policy was meant to be signed
+        assert!(Policy::load_verified(dir.path()).is_err());
+        std::fs::write(dir.path().join(SIGNATURE_FILE), sign(b"enforced = true\n", &key).unwrap()).unwrap();
+        assert!(Policy::load_verified(dir.path()).unwrap().enforced);
+
+        std::fs::write(&path, "enforced = false\n").unwrap();
+        assert!(Policy::load_verified(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,857 @@
+use crate::config::Hook;
+use crate::db::{Database, HookRun, Skill};
+use crate::github::parse_skill_frontmatter;
+use crate::install;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "skills.toml";
+pub const LOCK_FILE: &str = "skills.lock";
+const LOCK_VERSION: u32 = 1;
+const DEFAULT_SKILLS_DIR: &str = ".claude/skills";
+
+/// A project's `skills.toml`:
+///
+/// ```toml
+/// [project]
+/// skills_dir = ".claude/skills"
+///
+/// # Optional: install into several agent directories instead of skills_dir
+/// [targets.claude]
+/// skills_dir = ".claude/skills"
+/// [targets.codex]
+/// skills_dir = ".codex/skills"
+///
+/// [skills]
+/// pdf = "^1.2"
+/// "anthropic:docx" = "*"
+/// trello = { version = ">=0.3", registry = "clawdhub", targets = ["codex"] }
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct ProjectManifest {
+    #[serde(default)]
+    pub project: ProjectSettings,
+    #[serde(default)]
+    pub targets: BTreeMap<String, TargetSettings>,
+    #[serde(default)]
+    pub skills: BTreeMap<String, SkillRequirement>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TargetSettings {
+    /// Install directory, relative to the project root
+    pub skills_dir: String,
+}
+
+/// One install destination. `name` is None for the implicit target
+/// built from `project.skills_dir` when no `[targets]` are declared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub name: Option<String>,
+    pub skills_dir: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    /// Install directory, relative to the project root
+    pub skills_dir: String,
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            skills_dir: DEFAULT_SKILLS_DIR.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SkillRequirement {
+    Version(String),
+    Detailed {
+        version: Option<String>,
+        registry: Option<String>,
+        /// Limit installs to these targets (default: all)
+        targets: Option<Vec<String>>,
+    },
+}
+
+impl SkillRequirement {
+    pub fn version_req(&self) -> &str {
+        match self {
+            SkillRequirement::Version(v) => v,
+            SkillRequirement::Detailed { version, .. } => version.as_deref().unwrap_or("*"),
+        }
+    }
+
+    fn registry(&self) -> Option<&str> {
+        match self {
+            SkillRequirement::Version(_) => None,
+            SkillRequirement::Detailed { registry, .. } => registry.as_deref(),
+        }
+    }
+
+    fn targets(&self) -> Option<&[String]> {
+        match self {
+            SkillRequirement::Version(_) => None,
+            SkillRequirement::Detailed { targets, .. } => targets.as_deref(),
+        }
+    }
+}
+
+pub struct Project {
+    pub root: PathBuf,
+    pub manifest: ProjectManifest,
+}
+
+impl Project {
+    /// Load `skills.toml` from `start` or its nearest ancestor
+    pub fn discover(start: &Path) -> Result<Self> {
+        let start = start.canonicalize()?;
+        for dir in start.ancestors() {
+            let path = dir.join(MANIFEST_FILE);
+            if path.exists() {
+                return Self::load(&path);
+            }
+        }
+        anyhow::bail!("No {} found in {:?} or its parents", MANIFEST_FILE, start)
+    }
+
+    pub fn load(manifest_path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(manifest_path)?;
+        let manifest: ProjectManifest =
+            toml::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid {:?}: {}", manifest_path, e))?;
+        let root = manifest_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+        for (name, requirement) in &manifest.skills {
+            for target in requirement.targets().unwrap_or_default() {
+                if !manifest.targets.contains_key(target) {
+                    anyhow::bail!("{}: unknown target '{}' in {:?}", name, target, manifest_path);
+                }
+            }
+        }
+        Ok(Self { root, manifest })
+    }
+
+    pub fn targets(&self) -> Vec<Target> {
+        if self.manifest.targets.is_empty() {
+            return vec![Target {
+                name: None,
+                skills_dir: self.root.join(&self.manifest.project.skills_dir),
+            }];
+        }
+        self.manifest
+            .targets
+            .iter()
+            .map(|(name, t)| Target {
+                name: Some(name.clone()),
+                skills_dir: self.root.join(&t.skills_dir),
+            })
+            .collect()
+    }
+
+    /// Every (manifest key, requirement, target) install the manifest asks for
+    pub fn entries(&self) -> Vec<(&str, &SkillRequirement, Target)> {
+        let targets = self.targets();
+        let mut entries = Vec::new();
+        for (name, requirement) in &self.manifest.skills {
+            for target in &targets {
+                let wanted = match (requirement.targets(), &target.name) {
+                    (Some(only), Some(t)) => only.contains(t),
+                    _ => true,
+                };
+                if wanted {
+                    entries.push((name.as_str(), requirement, target.clone()));
+                }
+            }
+        }
+        entries
+    }
+}
+
+/// `skills.lock`: the exact upstream commit and content hash installed for
+/// each manifest entry, so installs can be reproduced and verified.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default, rename = "skill")]
+    pub skills: Vec<LockedSkill>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedSkill {
+    /// Manifest key this entry satisfies
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub registry: String,
+    pub slug: String,
+    pub version: Option<String>,
+    pub commit: String,
+    pub content_hash: String,
+}
+
+impl Lockfile {
+    pub fn new(mut skills: Vec<LockedSkill>) -> Self {
+        skills.sort_by(|a, b| (&a.name, &a.target).cmp(&(&b.name, &b.target)));
+        Self {
+            version: LOCK_VERSION,
+            skills,
+        }
+    }
+
+    pub fn load(project: &Project) -> Result<Option<Self>> {
+        let path = project.root.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let lock: Lockfile = toml::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid {:?}: {}", path, e))?;
+        if lock.version != LOCK_VERSION {
+            anyhow::bail!("Unsupported {} version {}", LOCK_FILE, lock.version);
+        }
+        Ok(Some(lock))
+    }
+
+    pub fn save(&self, project: &Project) -> Result<()> {
+        let content = format!(
+            "# Generated by safe-skill-search sync-project. Do not edit by hand.\n{}",
+            toml::to_string(self)?
+        );
+        std::fs::write(project.root.join(LOCK_FILE), content)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str, target: Option<&str>) -> Option<&LockedSkill> {
+        self.skills.iter().find(|s| s.name == name && s.target.as_deref() == target)
+    }
+
+    /// Manifest entries missing from the lock, and lock entries no longer in the manifest
+    pub fn drift(&self, project: &Project) -> Vec<String> {
+        let entries = project.entries();
+        let mut drift: Vec<String> = entries
+            .iter()
+            .filter(|(name, _, target)| self.get(name, target.name.as_deref()).is_none())
+            .map(|(name, _, target)| format!("{} is not locked", entry_label(name, target.name.as_deref())))
+            .collect();
+        drift.extend(
+            self.skills
+                .iter()
+                .filter(|s| !entries.iter().any(|(name, _, t)| *name == s.name && t.name == s.target))
+                .map(|s| format!("{} is locked but no longer in {}", entry_label(&s.name, s.target.as_deref()), MANIFEST_FILE)),
+        );
+        drift
+    }
+}
+
+/// `name`, or `name [target]` for named targets
+pub fn entry_label(name: &str, target: Option<&str>) -> String {
+    match target {
+        Some(t) => format!("{} [{}]", name, t),
+        None => name.to_string(),
+    }
+}
+
+fn parse_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim().trim_start_matches('v');
+    if let Ok(v) = semver::Version::parse(version) {
+        return Some(v);
+    }
+    // Accept short forms like "2" or "2.1"
+    let parts = version.split('.').count();
+    if parts < 3 {
+        let padded = format!("{}{}", version, ".0".repeat(3 - parts));
+        return semver::Version::parse(&padded).ok();
+    }
+    None
+}
+
+/// Whether `version` satisfies a Cargo-style requirement (`"1.2"` means
+/// `^1.2`). Non-semver versions only match `*` or the identical string.
+pub fn version_matches(req: &str, version: Option<&str>) -> bool {
+    let req = req.trim();
+    if req == "*" || req.is_empty() {
+        return true;
+    }
+    let Some(version) = version else {
+        return false;
+    };
+    match (semver::VersionReq::parse(req), parse_version(version)) {
+        (Ok(r), Some(v)) => r.matches(&v),
+        _ => req == version,
+    }
+}
+
+/// Highest version in `versions` satisfying `req`
+pub fn best_version<'a>(req: &str, versions: &'a [String]) -> Option<&'a str> {
+    versions
+        .iter()
+        .filter(|v| version_matches(req, Some(v)))
+        .max_by(|a, b| parse_version(a).cmp(&parse_version(b)))
+        .map(|v| v.as_str())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryState {
+    Ok,
+    Missing,
+    /// The installed version no longer satisfies the requirement
+    Unsatisfied,
+    /// A different version satisfying the requirement is available
+    Outdated,
+    NotInCatalog,
+    /// Several registries carry the slug; the manifest must name one
+    Ambiguous(Vec<String>),
+    /// No known version satisfies the requirement
+    NoMatchingVersion,
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryStatus {
+    /// Manifest key, e.g. `pdf` or `anthropic:pdf`
+    pub name: String,
+    pub target: Target,
+    pub requirement: String,
+    pub skill: Option<Skill>,
+    pub dest_dir: Option<PathBuf>,
+    pub installed_version: Option<String>,
+    pub target_version: Option<String>,
+    /// Commit to install when the target is not the latest synced copy
+    pub target_rev: Option<String>,
+    pub state: EntryState,
+}
+
+impl EntryStatus {
+    pub fn label(&self) -> String {
+        entry_label(&self.name, self.target.name.as_deref())
+    }
+
+    pub fn needs_install(&self) -> bool {
+        matches!(self.state, EntryState::Missing | EntryState::Unsatisfied | EntryState::Outdated)
+    }
+
+    /// e.g. "ok (1.2.0)" or "outdated (installed 1.1.0, available 1.2.0)"
+    pub fn describe(&self) -> String {
+        let installed = self.installed_version.as_deref().unwrap_or("unversioned");
+        let target = self.target_version.as_deref().unwrap_or("unversioned");
+        match &self.state {
+            EntryState::Ok => format!("ok ({})", installed),
+            EntryState::Missing => format!("missing (wants {}, would install {})", self.requirement, target),
+            EntryState::Unsatisfied => format!("installed {} does not satisfy {} (would install {})", installed, self.requirement, target),
+            EntryState::Outdated => format!("outdated (installed {}, available {})", installed, target),
+            EntryState::NotInCatalog => "not found in catalog".to_string(),
+            EntryState::Ambiguous(c) => format!("ambiguous, qualify with a registry: {}", c.join(", ")),
+            EntryState::NoMatchingVersion => format!("no known version satisfies {}", self.requirement),
+        }
+    }
+}
+
+/// Compare every manifest entry against the catalog and each target's skills directory
+pub fn check_project(db: &Database, project: &Project) -> Result<Vec<EntryStatus>> {
+    let mut statuses = Vec::new();
+    for (name, requirement, target) in project.entries() {
+        statuses.push(check_entry(db, name, requirement, target)?);
+    }
+    Ok(statuses)
+}
+
+fn check_entry(db: &Database, name: &str, requirement: &SkillRequirement, target: Target) -> Result<EntryStatus> {
+    let req = requirement.version_req();
+    let (key_registry, slug) = match name.split_once(':') {
+        Some((r, s)) => (Some(r), s),
+        None => (None, name),
+    };
+    let dest_root = target.skills_dir.clone();
+    let mut status = EntryStatus {
+        name: name.to_string(),
+        target,
+        requirement: req.to_string(),
+        skill: None,
+        dest_dir: None,
+        installed_version: None,
+        target_version: None,
+        target_rev: None,
+        state: EntryState::Ok,
+    };
+
+    let mut matches = db.get_skills_by_slug(slug)?;
+    if let Some(registry) = requirement.registry().or(key_registry) {
+        matches.retain(|s| s.registry.eq_ignore_ascii_case(registry));
+    }
+    let skill = match matches.len() {
+        0 => {
+            status.state = EntryState::NotInCatalog;
+            return Ok(status);
+        }
+        1 => matches.remove(0),
+        _ => {
+            status.state = EntryState::Ambiguous(matches.iter().map(|s| format!("{}:{}", s.registry, s.slug)).collect());
+            return Ok(status);
+        }
+    };
+
+    // Prefer the latest synced copy; fall back to the best recorded version
+    if version_matches(req, skill.version.as_deref()) {
+        status.target_version = skill.version.clone();
+    } else {
+        let known = db.get_known_versions(&skill.registry, &skill.slug)?;
+        match best_version(req, &known) {
+            Some(v) => {
+                status.target_rev = db.get_version_commit(&skill.registry, &skill.slug, v)?;
+                status.target_version = Some(v.to_string());
+            }
+            None => status.state = EntryState::NoMatchingVersion,
+        }
+    }
+
+    let dest_dir = dest_root.join(&skill.slug);
+    let installed_md = std::fs::read_to_string(dest_dir.join("SKILL.md")).ok();
+    if status.state == EntryState::Ok {
+        status.state = match &installed_md {
+            None => EntryState::Missing,
+            Some(md) => {
+                let installed_version = parse_skill_frontmatter(md).version;
+                let state = if !version_matches(req, installed_version.as_deref()) {
+                    EntryState::Unsatisfied
+                } else if installed_version != status.target_version {
+                    EntryState::Outdated
+                } else {
+                    EntryState::Ok
+                };
+                status.installed_version = installed_version;
+                state
+            }
+        };
+    }
+
+    status.dest_dir = Some(dest_dir);
+    status.skill = Some(skill);
+    Ok(status)
+}
+
+/// How `sync-project` installs: from which clones, through which staging
+/// directory, with which hooks, and only what `check` lets through
+pub struct Installer<'a> {
+    pub db: &'a Database,
+    pub repos_dir: &'a Path,
+    pub staging_root: &'a Path,
+    pub hooks: &'a [Hook],
+    /// Vets each skill before it's installed, e.g. against the install policy
+    pub check: &'a dyn Fn(&Skill) -> Result<()>,
+    /// Only report what would be installed
+    pub dry_run: bool,
+}
+
+/// One entry `sync-project` installed (or would have, on a dry run), or
+/// couldn't
+pub enum SyncEvent<'a> {
+    Installed {
+        skill: &'a Skill,
+        /// The version, or the locked commit
+        version: &'a str,
+        dest_dir: &'a Path,
+        runs: Vec<HookRun>,
+    },
+    Failed {
+        label: String,
+        error: String,
+    },
+}
+
+/// `sync-project --locked`: install exactly the commits in the lock,
+/// refusing any whose content no longer hashes the same. Returns whether
+/// every entry installed.
+pub fn install_locked(installer: &Installer, project: &Project, lock: &Lockfile, report: &mut dyn FnMut(SyncEvent)) -> Result<bool> {
+    let mut ok = true;
+    let targets = project.targets();
+    for entry in &lock.skills {
+        let label = entry_label(&entry.name, entry.target.as_deref());
+        let Some(s) = installer.db.get_skill(&entry.registry, &entry.slug)? else {
+            report(SyncEvent::Failed { label, error: format!("{}:{} not found in catalog", entry.registry, entry.slug) });
+            ok = false;
+            continue;
+        };
+        if let Err(e) = (installer.check)(&s) {
+            report(SyncEvent::Failed { label, error: e.to_string() });
+            ok = false;
+            continue;
+        }
+        let target = targets.iter().find(|t| t.name == entry.target).expect("lock matches manifest targets");
+        let dest_dir = target.skills_dir.join(&s.slug);
+        let result = install::prepare_install(installer.repos_dir, installer.staging_root, &s, &dest_dir, Some(&entry.commit)).and_then(|prepared| {
+            let hash = prepared.plan.content_hash()?;
+            if hash != entry.content_hash {
+                anyhow::bail!("upstream content no longer matches {} (expected {}, got {})", LOCK_FILE, entry.content_hash, hash);
+            }
+            if installer.dry_run {
+                return Ok(Vec::new());
+            }
+            Ok(prepared.execute(installer.db, &s, installer.hooks, true)?.1)
+        });
+        match result {
+            Ok(runs) => {
+                let version = &entry.commit[..12.min(entry.commit.len())];
+                report(SyncEvent::Installed { skill: &s, version, dest_dir: &dest_dir, runs });
+            }
+            Err(e) => {
+                report(SyncEvent::Failed { label, error: e.to_string() });
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+/// `sync-project`: install every manifest entry that's missing or not the
+/// version it should be, and lock what's installed. The lock keeps
+/// `previous` entries that still describe the installed version. Returns
+/// None once any entry failed.
+pub fn sync_project(installer: &Installer, statuses: &[EntryStatus], previous: Option<&Lockfile>, report: &mut dyn FnMut(SyncEvent)) -> Result<Option<Lockfile>> {
+    let mut ok = true;
+    let mut locked = Vec::new();
+    for status in statuses {
+        let (Some(s), Some(dest_dir)) = (&status.skill, &status.dest_dir) else {
+            report(SyncEvent::Failed { label: status.label(), error: status.describe() });
+            ok = false;
+            continue;
+        };
+        if !status.needs_install() {
+            if status.state != EntryState::Ok {
+                report(SyncEvent::Failed { label: status.label(), error: status.describe() });
+                ok = false;
+                continue;
+            }
+            if installer.dry_run {
+                continue;
+            }
+            // Already installed: keep the lock entry if it still describes this version
+            let kept = previous
+                .and_then(|l| l.get(&status.name, status.target.name.as_deref()))
+                .filter(|l| l.registry == s.registry && l.version == status.installed_version)
+                .cloned();
+            let entry = match kept {
+                Some(e) => Ok(e),
+                None => lock_installed_skill(installer, status, s, dest_dir),
+            };
+            match entry {
+                Ok(e) => locked.push(e),
+                Err(e) => {
+                    report(SyncEvent::Failed { label: status.label(), error: format!("cannot lock: {}", e) });
+                    ok = false;
+                }
+            }
+            continue;
+        }
+
+        if let Err(e) = (installer.check)(s) {
+            report(SyncEvent::Failed { label: status.label(), error: e.to_string() });
+            ok = false;
+            continue;
+        }
+        let version = status.target_version.as_deref().unwrap_or("latest");
+        if installer.dry_run {
+            report(SyncEvent::Installed { skill: s, version, dest_dir, runs: Vec::new() });
+            continue;
+        }
+        let result = install::prepare_install(installer.repos_dir, installer.staging_root, s, dest_dir, status.target_rev.as_deref()).and_then(|prepared| {
+            let content_hash = prepared.plan.content_hash()?;
+            let (record, runs) = prepared.execute(installer.db, s, installer.hooks, true)?;
+            Ok((record, runs, content_hash))
+        });
+        match result {
+            Ok((record, runs, content_hash)) => {
+                report(SyncEvent::Installed { skill: s, version, dest_dir, runs });
+                match record.installed_rev {
+                    Some(commit) => locked.push(LockedSkill {
+                        name: status.name.clone(),
+                        target: status.target.name.clone(),
+                        registry: s.registry.clone(),
+                        slug: s.slug.clone(),
+                        version: record.installed_version,
+                        commit,
+                        content_hash,
+                    }),
+                    None => {
+                        report(SyncEvent::Failed { label: status.label(), error: "cannot lock: installed commit unknown".to_string() });
+                        ok = false;
+                    }
+                }
+            }
+            Err(e) => {
+                report(SyncEvent::Failed { label: status.label(), error: format!("install failed: {}", e) });
+                ok = false;
+            }
+        }
+    }
+    Ok(ok.then(|| Lockfile::new(locked)))
+}
+
+/// Build a lock entry for a skill that is already installed, hashing the
+/// upstream content at the commit it was installed from.
+fn lock_installed_skill(installer: &Installer, status: &EntryStatus, skill: &Skill, dest_dir: &Path) -> Result<LockedSkill> {
+    let commit = installer
+        .db
+        .get_install(&skill.registry, &skill.slug, &dest_dir.to_string_lossy())?
+        .and_then(|i| i.installed_rev)
+        .ok_or_else(|| anyhow::anyhow!("installed commit unknown; reinstall it with sync-project"))?;
+    let prepared = install::prepare_install(installer.repos_dir, installer.staging_root, skill, dest_dir, Some(&commit))?;
+    Ok(LockedSkill {
+        name: status.name.clone(),
+        target: status.target.name.clone(),
+        registry: skill.registry.clone(),
+        slug: skill.slug.clone(),
+        version: prepared.installed_version.clone(),
+        commit,
+        content_hash: prepared.plan.content_hash()?,
+    })
+}
+
+/// Skill directories present in a target's skills directory but not
+/// listed in the manifest for that target, labelled like entries.
+pub fn untracked_skills(project: &Project, statuses: &[EntryStatus]) -> Result<Vec<String>> {
+    let mut untracked = Vec::new();
+    for target in project.targets() {
+        if !target.skills_dir.exists() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&target.skills_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let tracked = statuses.iter().filter(|s| s.target == target).any(|s| {
+                s.skill.as_ref().map(|k| k.slug == name).unwrap_or(false) || s.name == name
+            });
+            if !tracked {
+                untracked.push(entry_label(&name, target.name.as_deref()));
+            }
+        }
+    }
+    untracked.sort();
+    Ok(untracked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_test_skill(slug: &str, registry: &str, version: Option<&str>) -> Skill {
+        Skill {
+            github_url: format!("https://github.com/test/skills/tree/main/skills/{}", slug),
+            version: version.map(String::from),
+            ..Skill::fixture(registry, slug)
+        }
+    }
+
+    fn write_project(root: &Path, manifest: &str) -> Project {
+        std::fs::write(root.join(MANIFEST_FILE), manifest).unwrap();
+        Project::load(&root.join(MANIFEST_FILE)).unwrap()
+    }
+
+    fn install_fake(project: &Project, slug: &str, version: &str) {
+        let dir = project.targets()[0].skills_dir.join(slug);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("SKILL.md"), format!("---\nname: {}\nversion: {}\n---\n", slug, version)).unwrap();
+    }
+
+    #[test]
+    fn test_version_matches() {
+        assert!(version_matches("*", None));
+        assert!(version_matches("1.2", Some("1.4.0")));
+        assert!(!version_matches("1.2", Some("2.0.0")));
+        assert!(version_matches(">=2", Some("2.0")));
+        assert!(version_matches("=1.0.0", Some("v1.0.0")));
+        assert!(!version_matches("1.0", None));
+        assert!(version_matches("nightly", Some("nightly")));
+    }
+
+    #[test]
+    fn test_best_version() {
+        let versions = vec!["1.0.0".to_string(), "1.3.0".to_string(), "1.10.0".to_string(), "2.0.0".to_string()];
+        assert_eq!(best_version("^1", &versions), Some("1.10.0"));
+        assert_eq!(best_version("<1.2", &versions), Some("1.0.0"));
+        assert_eq!(best_version("3", &versions), None);
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let dir = tempdir().unwrap();
+        let project = write_project(
+            dir.path(),
+            r#"
+[project]
+skills_dir = "agents/skills"
+
+[skills]
+pdf = "^1.2"
+trello = { version = ">=0.3", registry = "clawdhub" }
+"anthropic:docx" = { registry = "anthropic" }
+"#,
+        );
+        assert_eq!(project.targets()[0].skills_dir, dir.path().join("agents/skills"));
+        assert_eq!(project.targets()[0].name, None);
+        assert_eq!(project.manifest.skills["pdf"].version_req(), "^1.2");
+        assert_eq!(project.manifest.skills["trello"].registry(), Some("clawdhub"));
+        assert_eq!(project.manifest.skills["anthropic:docx"].version_req(), "*");
+    }
+
+    #[test]
+    fn test_discover_walks_up() {
+        let dir = tempdir().unwrap();
+        write_project(dir.path(), "[skills]\n");
+        let nested = dir.path().join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let project = Project::discover(&nested).unwrap();
+        assert_eq!(project.root, dir.path().canonicalize().unwrap());
+        assert!(Project::discover(tempdir().unwrap().path()).is_err());
+    }
+
+    #[test]
+    fn test_check_project_states() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf", "anthropic", Some("1.3.0"))).unwrap();
+        db.upsert_skill(&create_test_skill("docx", "anthropic", Some("2.0.0"))).unwrap();
+        db.upsert_skill(&create_test_skill("trello", "clawdhub", Some("0.5.0"))).unwrap();
+        db.upsert_skill(&create_test_skill("notes", "clawdhub", Some("1.0.0"))).unwrap();
+        db.upsert_skill(&create_test_skill("notes", "jo", Some("1.0.0"))).unwrap();
+        db.record_skill_version("anthropic", "docx", "1.1.0", "abc123", 1).unwrap();
+
+        let project = write_project(
+            dir.path(),
+            r#"
+[skills]
+pdf = "^1.2"
+docx = "1"
+trello = "0.5"
+notes = "*"
+missing = "*"
+"clawdhub:calendar" = "*"
+"#,
+        );
+        install_fake(&project, "pdf", "1.3.0");
+        install_fake(&project, "trello", "0.4.0");
+        install_fake(&project, "handmade", "1.0.0");
+
+        let statuses = check_project(&db, &project).unwrap();
+        let state = |name: &str| statuses.iter().find(|s| s.name == name).unwrap().clone();
+
+        assert_eq!(state("pdf").state, EntryState::Ok);
+        assert_eq!(state("trello").state, EntryState::Unsatisfied);
+
+        let docx = state("docx");
+        assert_eq!(docx.state, EntryState::Missing);
+        assert_eq!(docx.target_version.as_deref(), Some("1.1.0"));
+        assert_eq!(docx.target_rev.as_deref(), Some("abc123"));
+
+        assert!(matches!(state("notes").state, EntryState::Ambiguous(_)));
+        assert_eq!(state("missing").state, EntryState::NotInCatalog);
+        assert_eq!(state("clawdhub:calendar").state, EntryState::NotInCatalog);
+
+        assert_eq!(untracked_skills(&project, &statuses).unwrap(), vec!["handmade"]);
+    }
+
+    #[test]
+    fn test_lockfile_roundtrip_and_drift() {
+        let dir = tempdir().unwrap();
+        let project = write_project(dir.path(), "[skills]\npdf = \"1\"\ndocx = \"*\"\n");
+        assert!(Lockfile::load(&project).unwrap().is_none());
+
+        let locked = |name: &str| LockedSkill {
+            name: name.to_string(),
+            target: None,
+            registry: "anthropic".to_string(),
+            slug: name.to_string(),
+            version: Some("1.0.0".to_string()),
+            commit: "abc123".to_string(),
+            content_hash: "sha256:00".to_string(),
+        };
+        let lock = Lockfile::new(vec![locked("pdf"), locked("stale")]);
+        lock.save(&project).unwrap();
+
+        let loaded = Lockfile::load(&project).unwrap().unwrap();
+        assert_eq!(loaded, lock);
+        assert_eq!(loaded.get("pdf", None).unwrap().commit, "abc123");
+        assert_eq!(
+            loaded.drift(&project),
+            vec!["docx is not locked", "stale is locked but no longer in skills.toml"]
+        );
+    }
+
+    #[test]
+    fn test_multi_target_entries() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf", "anthropic", Some("1.0.0"))).unwrap();
+        db.upsert_skill(&create_test_skill("trello", "clawdhub", Some("1.0.0"))).unwrap();
+
+        let project = write_project(
+            dir.path(),
+            r#"
+[targets.claude]
+skills_dir = ".claude/skills"
+[targets.codex]
+skills_dir = ".codex/skills"
+
+[skills]
+pdf = "*"
+trello = { targets = ["codex"] }
+"#,
+        );
+        let codex_pdf = dir.path().join(".codex/skills/pdf");
+        std::fs::create_dir_all(&codex_pdf).unwrap();
+        std::fs::write(codex_pdf.join("SKILL.md"), "---\nversion: 1.0.0\n---\n").unwrap();
+        std::fs::create_dir_all(dir.path().join(".claude/skills/trello")).unwrap();
+
+        let statuses = check_project(&db, &project).unwrap();
+        let labels: Vec<_> = statuses.iter().map(|s| (s.label(), s.state.clone())).collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("pdf [claude]".to_string(), EntryState::Missing),
+                ("pdf [codex]".to_string(), EntryState::Ok),
+                ("trello [codex]".to_string(), EntryState::Missing),
+            ]
+        );
+        assert_eq!(statuses[0].dest_dir, Some(dir.path().join(".claude/skills/pdf")));
+
+        // trello is not wanted in the claude target, so its directory there is untracked
+        assert_eq!(untracked_skills(&project, &statuses).unwrap(), vec!["trello [claude]"]);
+
+        let lock = Lockfile::new(vec![]);
+        assert_eq!(lock.drift(&project).len(), 3);
+    }
+
+    #[test]
+    fn test_unknown_target_rejected() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(MANIFEST_FILE),
+            "[targets.claude]\nskills_dir = \".claude/skills\"\n[skills]\npdf = { targets = [\"codex\"] }\n",
+        )
+        .unwrap();
+        assert!(Project::load(&dir.path().join(MANIFEST_FILE)).is_err());
+    }
+
+    #[test]
+    fn test_outdated_when_newer_version_synced() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf", "anthropic", Some("1.4.0"))).unwrap();
+
+        let project = write_project(dir.path(), "[skills]\npdf = \"1\"\n");
+        install_fake(&project, "pdf", "1.3.0");
+
+        let statuses = check_project(&db, &project).unwrap();
+        assert_eq!(statuses[0].state, EntryState::Outdated);
+        assert!(statuses[0].needs_install());
+        assert!(statuses[0].target_rev.is_none());
+    }
+}
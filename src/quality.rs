@@ -1,27 +1,67 @@
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::audit::Severity;
+use crate::db::{Database, Skill};
+use crate::install::plan_install;
+use crate::links::{self, Blocklist};
+use crate::output::SCHEMA_VERSION;
+use crate::risk::RiskScore;
+use crate::text::{outline, split_frontmatter};
+use anyhow::Result;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Deserialize)]
+/// Curated scores at or above this are "recommended"; the search default
+const HIGH_SCORE: i64 = 80;
+/// Curated scores below this mark a skill as poor
+const LOW_SCORE: i64 = 50;
+/// Combined risk at which a highly scored skill is suspect
+const SUSPECT_RISK: i64 = 40;
+/// How long `quality refresh` waits for the score file
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(60);
+/// Ratings needed before the community average counts
+const MIN_RATINGS: i64 = 5;
+/// Stars rank ("top N%" in its registry) that makes a skill popular
+const POPULAR_TOP_PCT: f64 = 10.0;
+
+/// Where `quality refresh` fetches scores from unless told otherwise
+pub const DEFAULT_REFRESH_URL: &str = "https://raw.githubusercontent.com/jo-inc/skill-search/main/skills.json";
+
+/// A curated score: a row of the `quality_scores` table, and one entry of
+/// `skills.json`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct QualityEntry {
     pub name: String,
     pub registry: String,
     pub score: i64,
-    #[allow(dead_code)]
+    #[serde(default)]
     pub stars: i64,
-    #[allow(dead_code)]
+    #[serde(default)]
     pub rationale: String,
     pub url: String,
 }
 
+/// Curated quality scores, looked up by registry and slug
 pub struct QualityScores {
     scores: HashMap<String, QualityEntry>,
 }
 
 impl QualityScores {
-    pub fn load() -> Self {
-        let json_data = include_str!("../skills.json");
-        let entries: Vec<QualityEntry> = serde_json::from_str(json_data).unwrap_or_default();
-        
+    /// The scores in the database
+    pub fn load(db: &Database) -> Result<Self> {
+        Ok(Self::from_entries(db.get_quality_entries()?))
+    }
+
+    /// The scores bundled with this build, which a new database starts with
+    pub fn bundled() -> Self {
+        Self::from_entries(bundled_entries())
+    }
+
+    /// Later entries win when two have the same key
+    pub fn from_entries(entries: Vec<QualityEntry>) -> Self {
         let mut scores = HashMap::new();
         for entry in entries {
             let key = format!("{}:{}", entry.registry, normalize_slug(&entry.name));
@@ -40,18 +80,462 @@ impl QualityScores {
         self.scores.get(&key).map(|e| e.score)
     }
 
-    #[allow(dead_code)]
+    /// A skill's score, by slug or else by name; 0 without one
+    pub fn score_for(&self, skill: &Skill) -> i64 {
+        self.get_score(&skill.registry, &skill.slug).or_else(|| self.get_score(&skill.registry, &skill.name)).unwrap_or(0)
+    }
+
     pub fn get_entry(&self, registry: &str, slug: &str) -> Option<&QualityEntry> {
         let key = format!("{}:{}", registry, normalize_slug(slug));
         self.scores.get(&key)
     }
 
-    #[allow(dead_code)]
     pub fn all_entries(&self) -> impl Iterator<Item = &QualityEntry> {
         self.scores.values()
     }
 }
 
+fn bundled_entries() -> Vec<QualityEntry> {
+    serde_json::from_str(include_str!("../skills.json")).unwrap_or_default()
+}
+
+/// Give a database without scores the bundled ones. True if it had none.
+pub fn seed_bundled(db: &mut Database) -> Result<bool> {
+    if !db.get_quality_entries()?.is_empty() {
+        return Ok(false);
+    }
+    db.import_quality_entries(&bundled_entries(), false)?;
+    Ok(true)
+}
+
+/// Read scores in `skills.json` form: a JSON array, or one entry per line
+/// as `quality batch-score` writes them
+pub fn parse_entries(content: &str) -> Result<Vec<QualityEntry>> {
+    let entries: Vec<QualityEntry> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(content).map_err(|e| anyhow::anyhow!("Invalid scores: {}", e))?
+    } else {
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| serde_json::from_str(line).map_err(|e| anyhow::anyhow!("Invalid score on line {}: {}", i + 1, e)))
+            .collect::<Result<_>>()?
+    };
+    if let Some(e) = entries.iter().find(|e| !(0..=100).contains(&e.score)) {
+        anyhow::bail!("{}:{} has score {}; scores range from 0 to 100", e.registry, e.name, e.score);
+    }
+    if let Some(e) = entries.iter().find(|e| e.url.is_empty()) {
+        anyhow::bail!("{}:{} has no url", e.registry, e.name);
+    }
+    Ok(entries)
+}
+
+/// Fetch scores in `skills.json` form from `url`
+pub async fn fetch_entries(url: &str) -> Result<Vec<QualityEntry>> {
+    let http = reqwest::Client::builder().user_agent("skill-search/0.1").timeout(REFRESH_TIMEOUT).build()?;
+    let response = http.get(url).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Fetching {} failed: HTTP {}", url, response.status());
+    }
+    parse_entries(&response.text().await?)
+}
+
+/// What the scanners and the community say about a curated skill
+#[derive(Debug, Clone, Default)]
+pub struct Signals {
+    pub score: i64,
+    pub risk: RiskScore,
+    /// Injection rules that fired
+    pub injection_rules: Vec<String>,
+    pub high_findings: usize,
+    pub unicode_flags: usize,
+    pub unreachable: bool,
+    pub stars_top_pct: Option<f64>,
+    /// Weighted average and number of ratings
+    pub rating: Option<(f64, i64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Scored high, but the scanners or the community disagree
+    Overrated,
+    /// Scored low, but the community likes it
+    Underrated,
+}
+
+/// A curated score its other signals contradict
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Outlier {
+    pub registry: String,
+    pub slug: String,
+    pub score: i64,
+    pub direction: Direction,
+    pub reasons: Vec<String>,
+}
+
+/// Result of `quality report`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CalibrationReport {
+    pub schema_version: u32,
+    /// Catalog skills with a curated score
+    pub scored: usize,
+    /// Catalog skills without one
+    pub unscored: usize,
+    /// Rank correlation (-1 to 1) of curated score with stars, over scored
+    /// skills that have stars; `None` with fewer than 3 of them
+    pub stars_correlation: Option<f64>,
+    /// Same, with the weighted community rating
+    pub rating_correlation: Option<f64>,
+    /// `registry:name` of curated entries that match no catalog skill, in
+    /// registries that have been synced
+    pub not_in_catalog: Vec<String>,
+    /// Overrated first, each group by how far off the score looks
+    pub outliers: Vec<Outlier>,
+}
+
+/// Why a curated score looks wrong, if it does
+pub fn find_outlier(signals: &Signals) -> Option<(Direction, Vec<String>)> {
+    let mut reasons = Vec::new();
+    if signals.score >= HIGH_SCORE {
+        if !signals.injection_rules.is_empty() {
+            reasons.push(format!("injection patterns: {}", signals.injection_rules.join(", ")));
+        }
+        if signals.risk.score >= SUSPECT_RISK {
+            reasons.push(format!("{} risk ({})", signals.risk.level(), signals.risk.score));
+        }
+        if signals.high_findings > 0 {
+            reasons.push(format!("{} high-severity shell finding(s)", signals.high_findings));
+        }
+        if signals.unicode_flags > 0 {
+            reasons.push(format!("{} suspicious unicode flag(s)", signals.unicode_flags));
+        }
+        if signals.unreachable {
+            reasons.push("gone upstream".to_string());
+        }
+        if let Some((weighted, count)) = signals.rating.filter(|(w, c)| *c >= MIN_RATINGS && *w < 2.5) {
+            reasons.push(format!("rated {:.1} by {} users", weighted, count));
+        }
+        return (!reasons.is_empty()).then_some((Direction::Overrated, reasons));
+    }
+    if signals.score < LOW_SCORE && !signals.unreachable && signals.risk.score < SUSPECT_RISK {
+        if let Some(pct) = signals.stars_top_pct.filter(|p| *p <= POPULAR_TOP_PCT) {
+            reasons.push(format!("top {:.0}% by stars", pct.ceil()));
+        }
+        if let Some((weighted, count)) = signals.rating.filter(|(w, c)| *c >= MIN_RATINGS && *w >= 4.0) {
+            reasons.push(format!("rated {:.1} by {} users", weighted, count));
+        }
+        return (!reasons.is_empty()).then_some((Direction::Underrated, reasons));
+    }
+    None
+}
+
+/// Compare every curated score in the catalog against the scanners and
+/// community signals
+pub fn calibration_report(db: &Database, scores: &QualityScores, blocklist: &Blocklist, registry: Option<&str>) -> Result<CalibrationReport> {
+    let unreachable = db.get_unreachable()?;
+    let mut synced = HashSet::new();
+    let mut matched = HashSet::new();
+    let mut scored = 0;
+    let mut unscored = 0;
+    let mut stars_pairs = Vec::new();
+    let mut rating_pairs = Vec::new();
+    let mut outliers = Vec::new();
+
+    for skill in db.get_all_skills()? {
+        if registry.is_some_and(|r| r != skill.registry) {
+            continue;
+        }
+        synced.insert(skill.registry.clone());
+        let Some(entry) = scores.get_entry(&skill.registry, &skill.slug).or_else(|| scores.get_entry(&skill.registry, &skill.name)) else {
+            unscored += 1;
+            continue;
+        };
+        scored += 1;
+        matched.insert((entry.registry.clone(), entry.name.clone()));
+
+        let findings = db.get_audit_findings(&skill.registry, &skill.slug)?;
+        let (injection_score, injection_rules) = db.get_injection_risk(&skill.registry, &skill.slug)?;
+        let rating = db.get_rating(&skill.registry, &skill.slug)?.map(|r| (r.weighted(), r.count));
+        let signals = Signals {
+            score: entry.score,
+            risk: RiskScore::assess(&findings, &links::audit_links(&skill.skill_md, blocklist), injection_score),
+            injection_rules,
+            high_findings: findings.iter().filter(|f| f.severity == Severity::High).count(),
+            unicode_flags: db.get_unicode_flags(&skill.registry, &skill.slug)?.len(),
+            unreachable: unreachable.contains(&(skill.registry.clone(), skill.slug.clone())),
+            stars_top_pct: db.get_popularity_rank(&skill.registry, &skill.slug)?.stars_top_pct,
+            rating,
+        };
+
+        if skill.stars > 0 {
+            stars_pairs.push((entry.score as f64, skill.stars as f64));
+        }
+        if let Some((weighted, _)) = rating {
+            rating_pairs.push((entry.score as f64, weighted));
+        }
+        if let Some((direction, reasons)) = find_outlier(&signals) {
+            outliers.push(Outlier {
+                registry: skill.registry.clone(),
+                slug: skill.slug.clone(),
+                score: entry.score,
+                direction,
+                reasons,
+            });
+        }
+    }
+
+    // Most confident claims first: overrated by highest score, underrated by lowest
+    outliers.sort_by(|a, b| {
+        (a.direction == Direction::Underrated)
+            .cmp(&(b.direction == Direction::Underrated))
+            .then_with(|| match a.direction {
+                Direction::Overrated => b.score.cmp(&a.score),
+                Direction::Underrated => a.score.cmp(&b.score),
+            })
+            .then_with(|| (&a.registry, &a.slug).cmp(&(&b.registry, &b.slug)))
+    });
+
+    let mut not_in_catalog: Vec<_> = scores
+        .all_entries()
+        .filter(|e| synced.contains(&e.registry))
+        .filter(|e| !matched.contains(&(e.registry.clone(), e.name.clone())))
+        .map(|e| format!("{}:{}", e.registry, e.name))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    not_in_catalog.sort();
+
+    Ok(CalibrationReport {
+        schema_version: SCHEMA_VERSION,
+        scored,
+        unscored,
+        stars_correlation: rank_correlation(&stars_pairs),
+        rating_correlation: rank_correlation(&rating_pairs),
+        not_in_catalog,
+        outliers,
+    })
+}
+
+/// Spearman rank correlation, ties sharing their average rank
+fn rank_correlation(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 3 {
+        return None;
+    }
+    let xs = ranks(&pairs.iter().map(|p| p.0).collect::<Vec<_>>());
+    let ys = ranks(&pairs.iter().map(|p| p.1).collect::<Vec<_>>());
+    let n = xs.len() as f64;
+    let (mx, my) = (xs.iter().sum::<f64>() / n, ys.iter().sum::<f64>() / n);
+    let cov: f64 = xs.iter().zip(&ys).map(|(x, y)| (x - mx) * (y - my)).sum();
+    let vx: f64 = xs.iter().map(|x| (x - mx).powi(2)).sum();
+    let vy: f64 = ys.iter().map(|y| (y - my).powi(2)).sum();
+    (vx > 0.0 && vy > 0.0).then(|| cov / (vx * vy).sqrt())
+}
+
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        for &k in &order[i..=j] {
+            ranks[k] = rank;
+        }
+        i = j + 1;
+    }
+    ranks
+}
+
+/// Score of a skill with neutral signals
+const BASE_SCORE: i64 = 50;
+/// Score of a skill with no SKILL.md
+const EMPTY_SCORE: i64 = 30;
+
+/// Patterns behind [`heuristic_score`]
+struct Rubric {
+    /// Headings of a workflow section
+    workflow_heading: Regex,
+    /// A numbered list item
+    numbered_step: Regex,
+    /// A code fence line
+    fence: Regex,
+    /// Bundled scripts, reference docs, templates, and assets
+    bundled_kinds: [Regex; 4],
+    /// Phrases saying when to use the skill
+    trigger: Regex,
+    error_handling: Regex,
+    best_practices: Regex,
+    /// Links out, or a references section
+    references: Regex,
+    /// Package installs, told apart by package
+    install: Regex,
+    /// Unfinished content
+    placeholder: Regex,
+    /// Marketing words
+    hype: Regex,
+}
+
+fn rubric() -> &'static Rubric {
+    static RUBRIC: OnceLock<Rubric> = OnceLock::new();
+    RUBRIC.get_or_init(|| {
+        let re = |pattern: &str| Regex::new(pattern).expect("valid rubric pattern");
+        Rubric {
+            workflow_heading: re(r"(?i)workflow|steps?\b|process|procedure|quick ?start|usage|how to"),
+            numbered_step: re(r"(?m)^\s*\d+[.)]\s"),
+            fence: re(r"(?m)^\s*(```|~~~)"),
+            bundled_kinds: [
+                re(r"(?i)^scripts/|\.(sh|py|js|ts|rb)$"),
+                re(r"(?i)^(references|docs)/|\.md$"),
+                re(r"(?i)^templates/"),
+                re(r"(?i)^assets/|\.(png|jpe?g|svg|json|ya?ml|csv)$"),
+            ],
+            trigger: re(r"(?i)\buse (this skill )?when\b|\bwhen (the )?user\b|\btrigger(s|ed)?\b|\bactivate[sd]? when\b"),
+            error_handling: re(r"(?i)\berrors?\b|troubleshoot|\bfail(ure|s|ed)?\b|exception|\bretry"),
+            best_practices: re(r"(?i)best practices?|\btips\b|do'?s and don'?ts|guidelines|pitfalls"),
+            references: re(r"\]\(https?://|(?im)^#+\s*(references|resources|see also)\b"),
+            install: re(r"\b(pip3? install|npm (install|i)|yarn add|brew install|apt(-get)? install|cargo install|go install|gem install)\s+[\w@/.-]+"),
+            placeholder: re(r"(?i)\bTODO\b|\bTBD\b|lorem ipsum|placeholder|\[insert|your[-_ ]api[-_ ]key[-_ ]here|coming soon"),
+            hype: re(r"(?i)revolutionary|cutting-edge|world-class|best-in-class|seamless|supercharge|unleash|game-chang|next-level"),
+        }
+    })
+}
+
+/// Heuristic quality score of a SKILL.md and the files bundled with it
+/// (paths relative to the skill directory), with a `[derived]` rationale
+/// listing each adjustment, in the format of `skills.json`
+pub fn heuristic_score(skill_md: &str, files: &[String]) -> (i64, String) {
+    if skill_md.trim().is_empty() {
+        return (EMPTY_SCORE, "[derived] No skill content available".to_string());
+    }
+    let rubric = rubric();
+    let (frontmatter, body) = split_frontmatter(skill_md);
+    let all_text = format!("{}\n{}", frontmatter.unwrap_or_default(), body);
+    let words = body.split_whitespace().count();
+    let count = |re: &Regex, text: &str| re.find_iter(text).count();
+    let mut adjustments: Vec<(&str, i64)> = Vec::new();
+
+    let workflow_headings = outline(skill_md)
+        .iter()
+        .filter(|h| rubric.workflow_heading.is_match(&h.title))
+        .count();
+    let numbered_steps = count(&rubric.numbered_step, body) >= 3;
+    let workflow = (workflow_headings + numbered_steps as usize).min(3) as i64;
+    if workflow > 0 {
+        adjustments.push(("workflow structure", 3 * workflow));
+    }
+
+    let code_blocks = count(&rubric.fence, body) / 2;
+    match code_blocks {
+        0 => {}
+        1..=2 => adjustments.push(("some code", 4)),
+        3..=5 => adjustments.push(("code examples", 8)),
+        _ => adjustments.push(("extensive code examples", 12)),
+    }
+
+    let bundled = rubric
+        .bundled_kinds
+        .iter().filter(|k| files.iter().any(|f| f != "SKILL.md" && k.is_match(f))).count() as i64;
+    if bundled > 0 {
+        adjustments.push(("bundled scripts/assets", 2 * bundled));
+    }
+
+    let triggers = count(&rubric.trigger, &all_text).min(4) as i64;
+    if triggers > 0 {
+        adjustments.push(("clear triggers", 2 * triggers));
+    }
+    if rubric.error_handling.is_match(body) {
+        adjustments.push(("error handling", 5));
+    }
+    if rubric.best_practices.is_match(body) {
+        adjustments.push(("best practices", 5));
+    }
+    if rubric.references.is_match(body) {
+        adjustments.push(("references", 3));
+    }
+
+    let installs = rubric.install
+        .find_iter(body)
+        .map(|m| m.as_str().to_string())
+        .collect::<HashSet<_>>()
+        .len();
+    if installs >= 4 {
+        adjustments.push(("heavy external deps", -5));
+    }
+    if rubric.placeholder.is_match(body) {
+        adjustments.push(("placeholder content", -15));
+    }
+    if words < 100 {
+        adjustments.push(("very short", -10));
+    } else if words < 250 {
+        adjustments.push(("brief content", -5));
+    }
+    let hype = count(&rubric.hype, body);
+    if hype >= 3 && words < 250 {
+        adjustments.push(("vague claims without substance", -8));
+    }
+
+    let score = (BASE_SCORE + adjustments.iter().map(|(_, d)| d).sum::<i64>()).clamp(0, 100);
+    let rationale = if adjustments.is_empty() {
+        "baseline content".to_string()
+    } else {
+        adjustments.iter().map(|(what, d)| format!("{} {:+}", what, d)).collect::<Vec<_>>().join("; ")
+    };
+    (score, format!("[derived] {}", rationale))
+}
+
+/// A skill to score with `quality batch-score`, one JSON object per line
+#[derive(Debug, Clone, Deserialize)]
+pub struct Candidate {
+    pub name: String,
+    pub registry: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub stars: i64,
+    /// SKILL.md content, when there's no `path`
+    pub skill_md: Option<String>,
+    /// Local skill directory; its bundled files count toward the score
+    pub path: Option<PathBuf>,
+}
+
+/// Score a candidate into a `skills.json` entry
+pub fn score_candidate(candidate: &Candidate) -> Result<QualityEntry> {
+    let (skill_md, files) = match (&candidate.path, &candidate.skill_md) {
+        (Some(dir), _) => {
+            let files = plan_install(dir, dir)?.files.into_iter().map(|f| f.rel_path.to_string_lossy().replace('\\', "/")).collect();
+            (std::fs::read_to_string(dir.join("SKILL.md")).unwrap_or_default(), files)
+        }
+        (None, Some(skill_md)) => (skill_md.clone(), Vec::new()),
+        (None, None) => anyhow::bail!("{}:{} has neither `skill_md` nor `path`", candidate.registry, candidate.name),
+    };
+    let (score, rationale) = heuristic_score(&skill_md, &files);
+    Ok(QualityEntry {
+        name: candidate.name.clone(),
+        registry: candidate.registry.clone(),
+        score,
+        stars: candidate.stars,
+        rationale,
+        url: candidate.url.clone(),
+    })
+}
+
+/// Score every candidate of a `quality batch-score` file, one JSON object
+/// per line
+pub fn score_candidates_file(path: &Path) -> Result<Vec<QualityEntry>> {
+    let content = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Can't read {:?}: {}", path, e))?;
+    let mut entries = Vec::new();
+    for (n, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let candidate: Candidate = serde_json::from_str(line).map_err(|e| anyhow::anyhow!("{}:{}: {}", path.display(), n + 1, e))?;
+        entries.push(score_candidate(&candidate).map_err(|e| anyhow::anyhow!("{}:{}: {}", path.display(), n + 1, e))?);
+    }
+    Ok(entries)
+}
+
 fn normalize_slug(s: &str) -> String {
     s.to_lowercase()
         .chars()
@@ -69,11 +553,102 @@ fn extract_slug_from_url(url: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
 
     #[test]
     fn test_load_quality_scores() {
-        let qs = QualityScores::load();
-        assert!(qs.scores.len() > 0);
+        let dir = tempdir().unwrap();
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+        assert!(QualityScores::load(&db).unwrap().scores.is_empty());
+        assert!(seed_bundled(&mut db).unwrap());
+        assert!(!seed_bundled(&mut db).unwrap());
+        let qs = QualityScores::load(&db).unwrap();
+        assert!(!qs.scores.is_empty());
+        assert_eq!(qs.scores.len(), QualityScores::bundled().scores.len());
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let line = r#"{"name":"PDF","registry":"anthropic","score":88,"url":"https://github.com/anthropics/skills/tree/main/skills/pdf"}"#;
+        let from_lines = parse_entries(&format!("{}\n\n{}\n", line, line.replace("88", "70"))).unwrap();
+        assert_eq!(from_lines.len(), 2);
+        assert_eq!(from_lines[1].score, 70);
+        assert_eq!(from_lines[0].rationale, "");
+        assert_eq!(parse_entries(&format!("[{}]", line)).unwrap(), from_lines[..1]);
+
+        assert!(parse_entries(&line.replace("88", "101")).unwrap_err().to_string().contains("from 0 to 100"));
+        assert!(parse_entries(&format!("{}\nnot json", line)).unwrap_err().to_string().contains("line 2"));
+        assert!(parse_entries(&line.replace("https://github.com/anthropics/skills/tree/main/skills/pdf", "")).is_err());
+    }
+
+    #[test]
+    fn test_heuristic_score() {
+        assert_eq!(heuristic_score("  ", &[]), (30, "[derived] No skill content available".to_string()));
+
+        let prose = "Plain words about nothing in particular. ".repeat(50);
+        assert_eq!(heuristic_score(&prose, &[]), (50, "[derived] baseline content".to_string()));
+
+        let md = format!(
+            "---\ndescription: Fill PDF forms. Use when the user has a form.\n---\n# PDF\n## Workflow\n1. Open\n2. Fill\n3. Save\n```bash\npdftk a.pdf\n```\nOn errors, retry. TODO: more.\n{}",
+            prose
+        );
+        let files = ["SKILL.md".to_string(), "scripts/fill.py".to_string()];
+        let (score, rationale) = heuristic_score(&md, &files);
+        assert_eq!(rationale, "[derived] workflow structure +6; some code +4; bundled scripts/assets +2; clear triggers +2; error handling +5; placeholder content -15");
+        assert_eq!(score, 54);
+    }
+
+    #[test]
+    fn test_score_candidate() {
+        let line = r#"{"name": "pdf", "registry": "clawdhub", "skill_md": "Short."}"#;
+        let candidate: Candidate = serde_json::from_str(line).unwrap();
+        let entry = score_candidate(&candidate).unwrap();
+        assert_eq!((entry.score, entry.rationale.as_str()), (40, "[derived] very short -10"));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("assets")).unwrap();
+        std::fs::write(dir.path().join("SKILL.md"), "Short.").unwrap();
+        std::fs::write(dir.path().join("assets/logo.png"), "png").unwrap();
+        let candidate = Candidate { path: Some(dir.path().to_path_buf()), skill_md: None, ..candidate };
+        assert_eq!(score_candidate(&candidate).unwrap().score, 42);
+
+        let candidate = Candidate { path: None, ..candidate };
+        assert!(score_candidate(&candidate).is_err());
+    }
+
+    #[test]
+    fn test_find_outlier() {
+        let overrated = Signals {
+            score: 95,
+            risk: RiskScore { score: 48, shell: 0, links: 0, injection: 48 },
+            injection_rules: vec!["ignore-previous".to_string()],
+            ..Default::default()
+        };
+        let (direction, reasons) = find_outlier(&overrated).unwrap();
+        assert_eq!(direction, Direction::Overrated);
+        assert_eq!(reasons, ["injection patterns: ignore-previous", "medium risk (48)"]);
+
+        let underrated = Signals {
+            score: 30,
+            stars_top_pct: Some(4.2),
+            rating: Some((4.5, 40)),
+            ..Default::default()
+        };
+        let (direction, reasons) = find_outlier(&underrated).unwrap();
+        assert_eq!(direction, Direction::Underrated);
+        assert_eq!(reasons, ["top 5% by stars", "rated 4.5 by 40 users"]);
+
+        // Too few ratings to disagree with the curator
+        let unsure = Signals { score: 90, rating: Some((1.5, 2)), ..Default::default() };
+        assert!(find_outlier(&unsure).is_none());
+    }
+
+    #[test]
+    fn test_rank_correlation() {
+        assert_eq!(rank_correlation(&[(1.0, 10.0), (2.0, 20.0), (3.0, 40.0)]), Some(1.0));
+        assert_eq!(rank_correlation(&[(1.0, 40.0), (2.0, 20.0), (3.0, 10.0)]), Some(-1.0));
+        assert_eq!(rank_correlation(&[(1.0, 1.0), (2.0, 1.0), (3.0, 1.0)]), None);
+        assert_eq!(ranks(&[5.0, 1.0, 5.0]), [2.5, 1.0, 2.5]);
     }
 
     #[test]
@@ -0,0 +1,144 @@
+use crate::config::QueryConfig;
+
+/// Openings that only say a search is wanted, dropped from the start of a
+/// query. Checked in order, so longer phrases come before their prefixes.
+const LEADING_FILLER: &[&str] = &[
+    "i am looking for",
+    "i'm looking for",
+    "looking for",
+    "search for",
+    "look for",
+    "find me",
+    "get me",
+    "give me",
+    "show me",
+    "help me",
+    "is there",
+    "are there",
+    "do you have",
+    "i need",
+    "i want",
+    "we need",
+    "can you",
+    "could you",
+    "please",
+    "find",
+];
+
+/// Words that carry no meaning in a request for a skill, dropped anywhere
+const FILLER_WORDS: &[&str] = &[
+    "a", "an", "the", "some", "any", "me", "i", "my", "we", "our", "you", "your", "it", "its", "that", "which", "who",
+    "can", "could", "would", "should", "will", "is", "are", "be", "to", "for", "of", "in", "into", "on", "with", "from",
+    "and", "or", "please", "skill", "skills", "tool", "tools", "something", "anything", "able", "lets", "allows", "do",
+    "does", "how", "what", "there", "this", "these", "those", "them", "so", "like", "just",
+];
+
+/// Characters with a meaning in the index's query syntax
+const SYNTAX: &[char] = &['"', ':', '(', ')', '+', '^', '*', '[', ']', '{', '}', '~'];
+
+/// True if the query uses the index's query syntax, which rewriting would
+/// break
+fn uses_syntax(query: &str) -> bool {
+    query.contains(SYNTAX)
+        || query
+            .split_whitespace()
+            .any(|w| w.starts_with('-') || matches!(w, "AND" | "OR" | "NOT"))
+}
+
+/// The words of `query` worth searching for. A request written as a
+/// sentence ("find me a skill that can convert HTML pages into markdown")
+/// comes back as its key terms ("convert html pages markdown"). Queries in
+/// the index's syntax, and ones that would be left empty, come back
+/// unchanged.
+pub fn key_terms(query: &str, config: &QueryConfig) -> String {
+    if uses_syntax(query) {
+        return query.to_string();
+    }
+    let mut words: Vec<&str> = Vec::new();
+    let lowered = query.to_lowercase().replace('\u{2019}', "'");
+    for word in lowered
+        .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '\''))
+        .map(|w| w.trim_matches(|c| c == '-' || c == '\''))
+        .filter(|w| !w.is_empty())
+    {
+        if !words.contains(&word) {
+            words.push(word);
+        }
+    }
+
+    if config.strip_filler {
+        'strip: loop {
+            for phrase in LEADING_FILLER {
+                let phrase: Vec<&str> = phrase.split(' ').collect();
+                if words.len() > phrase.len() && words.iter().zip(&phrase).all(|(w, p)| w == p) {
+                    words.drain(..phrase.len());
+                    continue 'strip;
+                }
+            }
+            break;
+        }
+        words.retain(|w| !FILLER_WORDS.contains(w));
+    }
+    if words.is_empty() {
+        return query.to_string();
+    }
+    words.join(" ")
+}
+
+/// `terms` followed by the configured synonyms of each, for a search that
+/// ranks rather than requires every word
+pub fn expand(terms: &str, config: &QueryConfig) -> String {
+    if config.synonyms.is_empty() || uses_syntax(terms) {
+        return terms.to_string();
+    }
+    let mut expanded: Vec<String> = Vec::new();
+    for word in terms.split_whitespace() {
+        let synonyms = config.synonyms.iter().find(|(k, _)| k.eq_ignore_ascii_case(word)).map(|(_, v)| v.as_slice()).unwrap_or_default();
+        for term in std::iter::once(word.to_lowercase()).chain(synonyms.iter().map(|s| s.to_lowercase())) {
+            if !expanded.contains(&term) {
+                expanded.push(term);
+            }
+        }
+    }
+    expanded.join(" ")
+}
+
+/// [`key_terms`], then [`expand`]
+pub fn rewrite(query: &str, config: &QueryConfig) -> String {
+    expand(&key_terms(query, config), config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_strips_filler() {
+        let config = QueryConfig::default();
+        assert_eq!(rewrite("find me a skill that can convert HTML pages into clean markdown", &config), "convert html pages clean markdown");
+        assert_eq!(rewrite("Can you please find a tool for PDF forms?", &config), "pdf forms");
+        assert_eq!(rewrite("I'm looking for something to manage my calendar", &config), "manage calendar");
+        // Keyword queries pass through, give or take case
+        assert_eq!(rewrite("pdf-to-docx", &config), "pdf-to-docx");
+        assert_eq!(rewrite("Find", &config), "find");
+        // Nothing left to search for, or query syntax to keep
+        assert_eq!(rewrite("is there one?", &config), "one");
+        assert_eq!(rewrite("the", &config), "the");
+        assert_eq!(rewrite("name:pdf -forms", &config), "name:pdf -forms");
+        assert_eq!(rewrite("\"fill in forms\"", &config), "\"fill in forms\"");
+
+        let verbatim = QueryConfig { strip_filler: false, ..config };
+        assert_eq!(rewrite("find a pdf skill", &verbatim), "find a pdf skill");
+    }
+
+    #[test]
+    fn test_rewrite_adds_synonyms() {
+        let config = QueryConfig {
+            synonyms: [("MD".to_string(), vec!["Markdown".to_string(), "commonmark".to_string()])].into_iter().collect(),
+            ..QueryConfig::default()
+        };
+        assert_eq!(rewrite("convert html to md", &config), "convert html md markdown commonmark");
+        assert_eq!(rewrite("markdown md", &config), "markdown md commonmark");
+        assert_eq!(key_terms("convert html to md", &config), "convert html md");
+    }
+}
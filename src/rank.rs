@@ -0,0 +1,180 @@
+use crate::config::RankingConfig;
+use crate::db::Skill;
+use crate::index::SearchResult;
+
+/// Days over which a skill's recency signal halves
+const RECENCY_HALF_LIFE_DAYS: f32 = 90.0;
+
+/// What search results can be ordered by. Every order but `Relevance`
+/// breaks ties with the blended score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sort {
+    /// Text relevance blended with the other signals by the `[ranking]`
+    /// weights
+    #[default]
+    Relevance,
+    Stars,
+    Quality,
+    /// Most recently updated upstream first
+    Recency,
+}
+
+impl Sort {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "relevance" => Some(Sort::Relevance),
+            "stars" => Some(Sort::Stars),
+            "quality" => Some(Sort::Quality),
+            "recency" => Some(Sort::Recency),
+            _ => None,
+        }
+    }
+}
+
+/// What `top` orders skills by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopSort {
+    /// Stars plus installs plus a tenth of downloads
+    #[default]
+    Popularity,
+    Stars,
+    Downloads,
+    Installs,
+    /// Average user rating, weighted by how many ratings back it
+    Rating,
+}
+
+impl TopSort {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "popularity" => Some(TopSort::Popularity),
+            "stars" => Some(TopSort::Stars),
+            "downloads" => Some(TopSort::Downloads),
+            "installs" => Some(TopSort::Installs),
+            "rating" => Some(TopSort::Rating),
+            _ => None,
+        }
+    }
+}
+
+/// What a hit is ranked on besides its text relevance
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Signals {
+    pub stars: i64,
+    /// Quality score, 0-100
+    pub quality: i64,
+    pub trusted: bool,
+    /// Days since the last upstream change, if known
+    pub age_days: Option<i64>,
+}
+
+impl Signals {
+    pub fn of(skill: &Skill, quality: i64, trusted: bool, now: i64) -> Self {
+        Self {
+            stars: skill.stars,
+            quality,
+            trusted,
+            age_days: skill.age_days(now),
+        }
+    }
+
+    /// 1 for a skill updated today, halving every `RECENCY_HALF_LIFE_DAYS`;
+    /// 0 when unknown
+    fn recency(&self) -> f32 {
+        self.age_days.map_or(0.0, |age| 0.5f32.powf(age as f32 / RECENCY_HALF_LIFE_DAYS))
+    }
+}
+
+/// Each hit's blended score: its relevance relative to the best hit's and
+/// its stars relative to the most-starred hit's (on a log scale), its
+/// quality score, trust, and recency, each 0-1, weighted
+pub fn blend(relevance: &[f32], signals: &[Signals], weights: &RankingConfig) -> Vec<f32> {
+    let best = relevance.iter().copied().fold(0.0f32, f32::max);
+    let most_stars = signals.iter().map(|s| s.stars).max().unwrap_or(0).max(0);
+    relevance
+        .iter()
+        .zip(signals)
+        .map(|(&score, s)| {
+            let relevance = if best > 0.0 { score / best } else { 0.0 };
+            let stars = if most_stars > 0 { (s.stars.max(0) as f32).ln_1p() / (most_stars as f32).ln_1p() } else { 0.0 };
+            weights.relevance * relevance
+                + weights.stars * stars
+                + weights.quality * s.quality.clamp(0, 100) as f32 / 100.0
+                + weights.trust * if s.trusted { 1.0 } else { 0.0 }
+                + weights.recency * s.recency()
+        })
+        .collect()
+}
+
+/// Reorder search hits by `sort`. Hits keep their text relevance as
+/// `score`, so confidence still says how well each matched.
+pub fn rank(results: Vec<SearchResult>, sort: Sort, weights: &RankingConfig, signals: impl Fn(&SearchResult) -> Signals) -> Vec<SearchResult> {
+    let signals: Vec<Signals> = results.iter().map(signals).collect();
+    let relevance: Vec<f32> = results.iter().map(|r| r.score).collect();
+    let blended = blend(&relevance, &signals, weights);
+    let mut ranked: Vec<(SearchResult, Signals, f32)> = results.into_iter().zip(signals).zip(blended).map(|((r, s), b)| (r, s, b)).collect();
+    ranked.sort_by(|(_, a, a_score), (_, b, b_score)| {
+        let by_sort = match sort {
+            Sort::Relevance => std::cmp::Ordering::Equal,
+            Sort::Stars => b.stars.cmp(&a.stars),
+            Sort::Quality => b.quality.cmp(&a.quality),
+            Sort::Recency => a.age_days.unwrap_or(i64::MAX).cmp(&b.age_days.unwrap_or(i64::MAX)),
+        };
+        by_sort.then(b_score.total_cmp(a_score))
+    });
+    ranked.into_iter().map(|(r, _, _)| r).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(slug: &str, score: f32) -> SearchResult {
+        SearchResult {
+            slug: slug.to_string(),
+            name: slug.to_string(),
+            description: String::new(),
+            registry: "jo".to_string(),
+            overlay: false,
+            score,
+            fuzzy: false,
+            snippet: None,
+        }
+    }
+
+    fn signals(r: &SearchResult) -> Signals {
+        match r.slug.as_str() {
+            "popular" => Signals { stars: 5000, quality: 60, trusted: false, age_days: Some(400) },
+            "fresh" => Signals { stars: 10, quality: 70, trusted: false, age_days: Some(0) },
+            _ => Signals { stars: 0, quality: 95, trusted: true, age_days: None },
+        }
+    }
+
+    #[test]
+    fn test_relevance_only_keeps_text_order() {
+        let weights = RankingConfig { relevance: 1.0, stars: 0.0, quality: 0.0, trust: 0.0, recency: 0.0 };
+        let ranked = rank(vec![hit("curated", 12.0), hit("popular", 11.0), hit("fresh", 3.0)], Sort::Relevance, &weights, signals);
+        assert_eq!(ranked.iter().map(|r| r.slug.as_str()).collect::<Vec<_>>(), ["curated", "popular", "fresh"]);
+        assert_eq!(ranked[1].score, 11.0);
+    }
+
+    #[test]
+    fn test_signals_reorder_close_matches() {
+        let weights = RankingConfig { relevance: 1.0, stars: 0.5, quality: 0.0, trust: 0.0, recency: 0.0 };
+        let ranked = rank(vec![hit("curated", 12.0), hit("popular", 11.0), hit("fresh", 3.0)], Sort::Relevance, &weights, signals);
+        assert_eq!(ranked[0].slug, "popular");
+        // A weak match stays behind however fresh it is
+        let weights = RankingConfig { stars: 0.0, recency: 0.5, ..weights };
+        assert_eq!(rank(vec![hit("curated", 12.0), hit("fresh", 3.0)], Sort::Relevance, &weights, signals)[0].slug, "curated");
+    }
+
+    #[test]
+    fn test_sorts() {
+        let weights = RankingConfig::default();
+        let results = || vec![hit("curated", 12.0), hit("popular", 11.0), hit("fresh", 3.0)];
+        let order = |sort| rank(results(), sort, &weights, signals).into_iter().map(|r| r.slug).collect::<Vec<_>>();
+        assert_eq!(order(Sort::Stars), ["popular", "fresh", "curated"]);
+        assert_eq!(order(Sort::Quality), ["curated", "fresh", "popular"]);
+        assert_eq!(order(Sort::Recency), ["fresh", "popular", "curated"]);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::db::{Database, Skill};
+use crate::output::SCHEMA_VERSION;
+use anyhow::Result;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+const MAX_CANDIDATES: usize = 10;
+
+// Returned once per lookup, so the unboxed Skill costs nothing
+#[allow(clippy::large_enum_variant)]
+pub enum Resolution {
+    /// Exactly one skill matches the slug (case-insensitive)
+    Exact(Skill),
+    /// The slug matches exactly in more than one registry, and the registry
+    /// priority ranks this one first; the others follow in priority order
+    Preferred(Skill, Vec<Skill>),
+    /// The slug matches exactly in more than one registry, none of them
+    /// ranked above the rest; ordered by priority, then registry
+    Ambiguous(Vec<Skill>),
+    /// No exact match; best prefix/fuzzy matches, most likely first (may be empty)
+    Candidates(Vec<Skill>),
+}
+
+/// A registry's place in `priority`; unlisted registries share the last
+pub fn priority_rank(priority: &[String], registry: &str) -> usize {
+    priority.iter().position(|p| p.eq_ignore_ascii_case(registry)).unwrap_or(priority.len())
+}
+
+/// Resolve a slug, optionally qualified as `registry:slug` to pick a
+/// specific registry without prompting. A bare slug in several registries
+/// resolves to the one `priority` ranks first. Skills that moved resolve
+/// to where they live now.
+pub fn resolve_skill(db: &Database, query: &str, priority: &[String]) -> Result<Resolution> {
+    if let Some((registry, slug)) = query.split_once(':') {
+        if let Some(skill) = db
+            .get_skills_by_slug(slug)?
+            .into_iter()
+            .find(|s| s.registry.eq_ignore_ascii_case(registry))
+        {
+            return Ok(Resolution::Exact(follow_move(db, skill)?));
+        }
+    }
+
+    let mut exact = Vec::new();
+    for skill in db.get_skills_by_slug(query)? {
+        let skill = follow_move(db, skill)?;
+        if !exact.iter().any(|s: &Skill| s.registry == skill.registry && s.slug == skill.slug) {
+            exact.push(skill);
+        }
+    }
+    exact.sort_by_cached_key(|s| (priority_rank(priority, &s.registry), s.registry.clone(), s.slug.clone()));
+    match exact.len() {
+        0 => Ok(Resolution::Candidates(find_candidates(db, query)?)),
+        1 => Ok(Resolution::Exact(exact.remove(0))),
+        _ if priority_rank(priority, &exact[0].registry) < priority_rank(priority, &exact[1].registry) => {
+            let preferred = exact.remove(0);
+            Ok(Resolution::Preferred(preferred, exact))
+        }
+        _ => Ok(Resolution::Ambiguous(exact)),
+    }
+}
+
+/// Machine-readable resolution failure for non-interactive callers
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ResolutionError {
+    pub schema_version: u32,
+    pub error: &'static str,
+    pub query: String,
+    pub candidates: Vec<Candidate>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Candidate {
+    pub id: String,
+    pub registry: String,
+    pub slug: String,
+    pub name: String,
+    pub github_url: String,
+}
+
+impl ResolutionError {
+    pub fn new(error: &'static str, query: &str, skills: &[Skill]) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            error,
+            query: query.to_string(),
+            candidates: skills
+                .iter()
+                .map(|s| Candidate {
+                    id: format!("{}:{}", s.registry, s.slug),
+                    registry: s.registry.clone(),
+                    slug: s.slug.clone(),
+                    name: s.name.clone(),
+                    github_url: s.github_url.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The entry a superseded skill moved to, or the skill itself
+fn follow_move(db: &Database, skill: Skill) -> Result<Skill> {
+    if let Some((registry, slug)) = db.get_successor(&skill.registry, &skill.slug)? {
+        if let Some(moved) = db.get_skill(&registry, &slug)? {
+            return Ok(moved);
+        }
+    }
+    Ok(skill)
+}
+
+pub fn find_candidates(db: &Database, query: &str) -> Result<Vec<Skill>> {
+    let query = query.to_lowercase();
+    let matcher = SkimMatcherV2::default();
+    let superseded = db.get_superseded()?;
+
+    let mut scored: Vec<(i64, String, String)> = Vec::new();
+    for (registry, slug) in db.get_all_slugs()? {
+        if superseded.contains(&(registry.clone(), slug.clone())) {
+            continue;
+        }
+        let slug_lower = slug.to_lowercase();
+        let score = if slug_lower.starts_with(&query) {
+            // Prefix hits always rank above fuzzy hits, shortest slug first
+            i64::MAX - slug.len() as i64
+        } else {
+            match matcher.fuzzy_match(&slug_lower, &query) {
+                Some(s) => s,
+                None => continue,
+            }
+        };
+        scored.push((score, registry, slug));
+    }
+
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.cmp(&b.2)).then_with(|| a.1.cmp(&b.1)));
+
+    let mut candidates = Vec::new();
+    for (_, registry, slug) in scored.into_iter().take(MAX_CANDIDATES) {
+        if let Some(skill) = db.get_skill(&registry, &slug)? {
+            candidates.push(skill);
+        }
+    }
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn create_test_skill(slug: &str, registry: &str) -> Skill {
+        Skill::fixture(registry, slug)
+    }
+
+    fn setup_db(dir: &Path) -> Database {
+        let db = Database::open(&dir.join("test.db")).unwrap();
+        for slug in ["calendar-manager", "calendar", "pdf-reader", "browser-automation"] {
+            db.upsert_skill(&create_test_skill(slug, "clawdhub")).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn test_resolve_exact_case_insensitive() {
+        let dir = tempdir().unwrap();
+        let db = setup_db(dir.path());
+
+        match resolve_skill(&db, "Calendar-Manager", &[]).unwrap() {
+            Resolution::Exact(s) => assert_eq!(s.slug, "calendar-manager"),
+            _ => panic!("expected exact match"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_ambiguous_across_registries() {
+        let dir = tempdir().unwrap();
+        let db = setup_db(dir.path());
+        db.upsert_skill(&create_test_skill("pdf-reader", "anthropic")).unwrap();
+
+        match resolve_skill(&db, "pdf-reader", &[]).unwrap() {
+            Resolution::Ambiguous(skills) => {
+                let registries: Vec<_> = skills.iter().map(|s| s.registry.as_str()).collect();
+                assert_eq!(registries, vec!["anthropic", "clawdhub"]);
+            }
+            _ => panic!("expected ambiguous match"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_by_registry_priority() {
+        let dir = tempdir().unwrap();
+        let mut db = setup_db(dir.path());
+        db.upsert_skill(&create_test_skill("pdf-reader", "anthropic")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf-reader", "jo")).unwrap();
+        let priority: Vec<String> = ["clawdhub", "anthropic"].map(String::from).to_vec();
+
+        match resolve_skill(&db, "pdf-reader", &priority).unwrap() {
+            Resolution::Preferred(s, others) => {
+                assert_eq!(s.registry, "clawdhub");
+                let registries: Vec<_> = others.iter().map(|s| s.registry.as_str()).collect();
+                assert_eq!(registries, vec!["anthropic", "jo"]);
+            }
+            _ => panic!("expected a preferred match"),
+        }
+
+        // Two unlisted registries tie, so the user picks
+        db.delete_skill("clawdhub", "pdf-reader").unwrap();
+        db.delete_skill("anthropic", "pdf-reader").unwrap();
+        db.upsert_skill(&create_test_skill("pdf-reader", "openai")).unwrap();
+        assert!(matches!(resolve_skill(&db, "pdf-reader", &priority).unwrap(), Resolution::Ambiguous(_)));
+        assert_eq!(priority_rank(&priority, "Anthropic"), 1);
+        assert_eq!(priority_rank(&priority, "jo"), 2);
+    }
+
+    #[test]
+    fn test_resolve_registry_qualified() {
+        let dir = tempdir().unwrap();
+        let db = setup_db(dir.path());
+        db.upsert_skill(&create_test_skill("pdf-reader", "anthropic")).unwrap();
+
+        match resolve_skill(&db, "anthropic:pdf-reader", &[]).unwrap() {
+            Resolution::Exact(s) => assert_eq!(s.registry, "anthropic"),
+            _ => panic!("expected exact match"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_follows_moves() {
+        let dir = tempdir().unwrap();
+        let mut db = setup_db(dir.path());
+        db.upsert_skill(&create_test_skill("pdf-reader", "anthropic")).unwrap();
+        db.record_move(("clawdhub", "pdf-reader"), ("anthropic", "pdf-reader"), "sha256:x", 1).unwrap();
+
+        // The superseded entry no longer makes the slug ambiguous
+        match resolve_skill(&db, "pdf-reader", &[]).unwrap() {
+            Resolution::Exact(s) => assert_eq!(s.registry, "anthropic"),
+            _ => panic!("expected exact match"),
+        }
+        match resolve_skill(&db, "clawdhub:pdf-reader", &[]).unwrap() {
+            Resolution::Exact(s) => assert_eq!(s.registry, "anthropic"),
+            _ => panic!("expected exact match"),
+        }
+        match resolve_skill(&db, "pdf", &[]).unwrap() {
+            Resolution::Candidates(c) => assert!(c.iter().all(|s| s.registry == "anthropic")),
+            _ => panic!("expected candidates"),
+        }
+    }
+
+    #[test]
+    fn test_resolution_error_json_shape() {
+        let skills = vec![create_test_skill("pdf", "anthropic"), create_test_skill("pdf", "openai")];
+        let err = ResolutionError::new("ambiguous", "pdf", &skills);
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["error"], "ambiguous");
+        assert_eq!(json["candidates"][1]["id"], "openai:pdf");
+    }
+
+    #[test]
+    fn test_resolve_prefix_candidates() {
+        let dir = tempdir().unwrap();
+        let db = setup_db(dir.path());
+
+        match resolve_skill(&db, "cal", &[]).unwrap() {
+            Resolution::Candidates(c) => {
+                let slugs: Vec<_> = c.iter().map(|s| s.slug.as_str()).collect();
+                assert_eq!(slugs, vec!["calendar", "calendar-manager"]);
+            }
+            _ => panic!("expected candidates"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_fuzzy_candidates() {
+        let dir = tempdir().unwrap();
+        let db = setup_db(dir.path());
+
+        let candidates = find_candidates(&db, "pdfrdr").unwrap();
+        assert_eq!(candidates[0].slug, "pdf-reader");
+    }
+
+    #[test]
+    fn test_resolve_no_candidates() {
+        let dir = tempdir().unwrap();
+        let db = setup_db(dir.path());
+
+        assert!(find_candidates(&db, "zzzzqqq").unwrap().is_empty());
+    }
+}
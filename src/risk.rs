@@ -0,0 +1,125 @@
+use crate::audit::{Finding, Severity};
+use crate::db::{Database, Skill};
+use crate::links::{self, Blocklist, LinkAudit, LinkFlag};
+use anyhow::Result;
+use serde::Serialize;
+
+const MAX_SCORE: i64 = 100;
+
+/// Combined 0-100 risk estimate for a skill; higher is riskier. Each
+/// component is capped on its own so one noisy signal can't dominate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RiskScore {
+    pub score: i64,
+    /// Contribution of shell code block findings
+    pub shell: i64,
+    /// Contribution of flagged links
+    pub links: i64,
+    /// Contribution of the prompt-injection score
+    pub injection: i64,
+}
+
+impl RiskScore {
+    pub fn assess(findings: &[Finding], links: &[LinkAudit], injection_score: i64) -> Self {
+        let shell = findings
+            .iter()
+            .map(|f| match f.severity {
+                Severity::High => 25,
+                Severity::Warning => 10,
+                Severity::Info => 2,
+            })
+            .sum::<i64>()
+            .min(60);
+        let links = links
+            .iter()
+            .flat_map(|l| &l.flags)
+            .map(|flag| match flag {
+                LinkFlag::Blocklisted => 40,
+                LinkFlag::RawIp => 20,
+                LinkFlag::Shortener | LinkFlag::SuspiciousTld => 10,
+                LinkFlag::Insecure => 3,
+            })
+            .sum::<i64>()
+            .min(50);
+        let injection = injection_score.clamp(0, MAX_SCORE) * 60 / MAX_SCORE;
+
+        Self {
+            score: (shell + links + injection).min(MAX_SCORE),
+            shell,
+            links,
+            injection,
+        }
+    }
+
+    pub fn level(&self) -> &'static str {
+        level(self.score)
+    }
+
+    /// Audit a skill's links and combine them with its recorded shell
+    /// findings and injection risk
+    pub fn of_skill(db: &Database, blocklist: &Blocklist, skill: &Skill) -> Result<(Self, Vec<LinkAudit>)> {
+        let findings = db.get_audit_findings(&skill.registry, &skill.slug)?;
+        let link_audits = links::audit_links(&skill.skill_md, blocklist);
+        let (injection_score, _) = db.get_injection_risk(&skill.registry, &skill.slug)?;
+        Ok((Self::assess(&findings, &link_audits, injection_score), link_audits))
+    }
+}
+
+/// Name of the band a 0-100 risk score falls in
+pub fn level(score: i64) -> &'static str {
+    match score {
+        i64::MIN..=0 => "none",
+        1..=24 => "low",
+        25..=59 => "medium",
+        _ => "high",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(severity: Severity) -> Finding {
+        Finding {
+            rule: "test".to_string(),
+            severity,
+            line: 1,
+            snippet: String::new(),
+            message: String::new(),
+        }
+    }
+
+    fn link(flags: Vec<LinkFlag>) -> LinkAudit {
+        LinkAudit {
+            url: "https://example.com".to_string(),
+            host: "example.com".to_string(),
+            flags,
+        }
+    }
+
+    #[test]
+    fn test_clean_skill_scores_zero() {
+        let risk = RiskScore::assess(&[], &[link(vec![])], 0);
+        assert_eq!(risk.score, 0);
+        assert_eq!(risk.level(), "none");
+    }
+
+    #[test]
+    fn test_components_are_capped() {
+        let findings: Vec<_> = (0..5).map(|_| finding(Severity::High)).collect();
+        let links = vec![link(vec![LinkFlag::Blocklisted, LinkFlag::RawIp])];
+        let risk = RiskScore::assess(&findings, &links, 0);
+        assert_eq!(risk.shell, 60);
+        assert_eq!(risk.links, 50);
+        assert_eq!(risk.score, 100);
+        assert_eq!(risk.level(), "high");
+    }
+
+    #[test]
+    fn test_injection_component_is_scaled() {
+        let risk = RiskScore::assess(&[], &[], 50);
+        assert_eq!(risk.injection, 30);
+        assert_eq!(risk.score, 30);
+        assert_eq!(risk.level(), "medium");
+    }
+}
@@ -0,0 +1,116 @@
+use crate::config::SandboxPlatform;
+use crate::db::Skill;
+use crate::install;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// A throwaway directory with one skill laid out where an agent platform
+/// looks for skills. Removed on drop unless kept.
+pub struct Sandbox {
+    pub workdir: PathBuf,
+    pub skill_dir: PathBuf,
+    keep: bool,
+}
+
+impl Sandbox {
+    /// Copy `skill` into a new directory under `root`, at the platform's
+    /// skills directory. Skills with no synced copy are fetched like an install.
+    pub fn create(repos_dir: &Path, root: &Path, skill: &Skill, platform: &SandboxPlatform) -> Result<Self> {
+        let workdir = root.join(format!("skill-search-try-{}-{}-{}", skill.registry, skill.slug, std::process::id()));
+        if workdir.exists() {
+            std::fs::remove_dir_all(&workdir)?;
+        }
+        let skill_dir = workdir.join(&platform.skills_dir).join(&skill.slug);
+        // Constructed first, so a failed copy still cleans up
+        let sandbox = Self { workdir, skill_dir, keep: false };
+        let prepared = install::prepare_install(repos_dir, &sandbox.workdir.join(".staging"), skill, &sandbox.skill_dir, None)?;
+        install::execute_plan(&prepared.plan, false)?;
+        drop(prepared);
+        let _ = std::fs::remove_dir(sandbox.workdir.join(".staging"));
+        Ok(sandbox)
+    }
+
+    /// The platform's command line for this sandbox
+    pub fn invocation(&self, skill: &Skill, platform: &SandboxPlatform) -> String {
+        platform
+            .command
+            .replace("{workdir}", &shell_quote(&self.workdir))
+            .replace("{skill_dir}", &shell_quote(&self.skill_dir))
+            .replace("{slug}", &skill.slug)
+            .replace("{registry}", &skill.registry)
+    }
+
+    /// Leave the directory in place when the sandbox is dropped
+    pub fn keep(&mut self) {
+        self.keep = true;
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        if !self.keep {
+            if let Err(e) = std::fs::remove_dir_all(&self.workdir) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!("Could not remove sandbox {:?}: {}", self.workdir, e);
+                }
+            }
+        }
+    }
+}
+
+fn shell_quote(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path.chars().any(|c| c.is_whitespace() || "'\"$&;|()<>`\\".contains(c)) {
+        format!("'{}'", path.replace('\'', "'\\''"))
+    } else {
+        path.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SandboxConfig;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sandbox_lays_out_skill_and_cleans_up() {
+        let dir = tempdir().unwrap();
+        let repos_dir = dir.path().join("repos");
+        let source = crate::github::repo_dir(&repos_dir, "jo").join("skills").join("pdf");
+        std::fs::create_dir_all(source.join("scripts")).unwrap();
+        std::fs::write(source.join("SKILL.md"), "# PDF").unwrap();
+        std::fs::write(source.join("scripts/fill.py"), "print(1)").unwrap();
+        let skill = Skill {
+            name: "PDF".to_string(),
+            github_url: "https://github.com/jo-inc/skills/tree/main/skills/pdf".to_string(),
+            trusted: true,
+            ..Skill::fixture("jo", "pdf")
+        };
+        let platform = SandboxConfig::default().platform("claude").unwrap();
+
+        let root = dir.path().join("tmp");
+        let sandbox = Sandbox::create(&repos_dir, &root, &skill, &platform).unwrap();
+        assert_eq!(sandbox.skill_dir, sandbox.workdir.join(".claude/skills/pdf"));
+        assert!(sandbox.skill_dir.join("scripts/fill.py").is_file());
+        assert!(!sandbox.workdir.join(".staging").exists());
+        let command = sandbox.invocation(&skill, &platform);
+        assert_eq!(command, format!("cd {} && claude \"Use the pdf skill\"", sandbox.workdir.display()));
+
+        let workdir = sandbox.workdir.clone();
+        drop(sandbox);
+        assert!(!workdir.exists());
+
+        let mut kept = Sandbox::create(&repos_dir, &root, &skill, &platform).unwrap();
+        kept.keep();
+        let workdir = kept.workdir.clone();
+        drop(kept);
+        assert!(workdir.join(".claude/skills/pdf/SKILL.md").is_file());
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote(Path::new("/tmp/x")), "/tmp/x");
+        assert_eq!(shell_quote(Path::new("/tmp/my dir/it's")), "'/tmp/my dir/it'\\''s'");
+    }
+}
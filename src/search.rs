@@ -0,0 +1,420 @@
+use crate::config::{FieldWeights, RankProfile, RankingConfig};
+use crate::db::{Database, Layer, Skill};
+use crate::index::{self, IndexFilters, ScoreScale, SearchIndex};
+use crate::output::{Enricher, SearchFilters, SearchResult};
+use crate::policy::{DecisionSet, Policy};
+use crate::quality::QualityScores;
+use crate::rank::{self, Signals, Sort, TopSort};
+use crate::text;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Index results fetched per result wanted, so enough are usually left once
+/// the filters have had their pick; also how much each further page grows
+pub const OVERFETCH: usize = 4;
+
+/// Most index results [`SearchService::fetch`] asks for before settling for
+/// fewer than `limit` results
+pub const MAX_FETCH: usize = 2000;
+
+/// The indexes a search reads, and the database to search when they fail
+pub struct Indexes<'a> {
+    pub db: &'a Database,
+    pub catalog: &'a SearchIndex,
+    /// The user layer's own index, over a system catalog
+    pub user: Option<&'a SearchIndex>,
+    /// Local copies, searched instead of the catalog (`search --installed`)
+    pub local: Option<&'a SearchIndex>,
+    pub weights: &'a FieldWeights,
+    /// Search the database instead: the index is being rebuilt, or failed
+    pub direct: bool,
+}
+
+impl Indexes<'_> {
+    /// Up to `limit` skills matching `terms`. The database requires every
+    /// word, so it's given the `keywords` without synonyms instead.
+    pub fn search(&mut self, terms: &str, keywords: &str, fuzzy: bool, limit: usize, filters: &IndexFilters) -> Result<Vec<index::SearchResult>> {
+        let run = |index: &SearchIndex| if fuzzy { index.search_fuzzy(terms, limit, filters) } else { index.search(terms, limit, filters) };
+        if let Some(local) = self.local {
+            return run(local);
+        }
+        if self.direct {
+            return index::search_database(self.db, keywords, limit, filters, self.weights);
+        }
+        match run(self.catalog) {
+            Ok(results) => match self.user {
+                Some(user) => {
+                    // The user index also holds skills the system layer shadows
+                    let own = run(user)?
+                        .into_iter()
+                        .filter(|r| self.db.layer_of(&r.registry, &r.slug).is_ok_and(|l| l == Layer::User))
+                        .collect();
+                    Ok(index::merge_layers(results, own))
+                }
+                None => Ok(results),
+            },
+            Err(e) => {
+                tracing::warn!("Search index failed, searching the database directly: {:#}", e);
+                self.direct = true;
+                index::search_database(self.db, keywords, limit, filters, self.weights)
+            }
+        }
+    }
+}
+
+/// Queries of `search --queries-file`, one per line, without blanks or repeats
+pub fn read_queries(path: &Path) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Can't read {:?}: {}", path, e))?;
+    let mut queries: Vec<String> = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if !queries.iter().any(|q| q == line) {
+            queries.push(line.to_string());
+        }
+    }
+    Ok(queries)
+}
+
+/// Scale index scores by the profile's recency boost and re-sort them
+pub fn boost_recent(db: &Database, results: &mut [index::SearchResult], profile: &RankProfile, now: i64) {
+    if profile.recency_boost <= 0.0 {
+        return;
+    }
+    for r in results.iter_mut() {
+        let age_days = db.get_skill(&r.registry, &r.slug).ok().flatten().and_then(|s| s.age_days(now));
+        r.score *= profile.recency_factor(age_days);
+    }
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+/// What a search does once the index has answered: rank, drop what the
+/// filters, policy, and decisions rule out, enrich, and cut to the limit.
+/// `search`, `GET /skills/search`, and the MCP `search_skills` tool each
+/// fill one in from their own parameters, so they keep the same results.
+pub struct SearchService<'a> {
+    pub enricher: Enricher<'a>,
+    pub rules: &'a Policy,
+    pub ranking: &'a RankingConfig,
+    pub sort: Sort,
+    pub filters: SearchFilters,
+    /// A registry asked for by name, which overrides an unenforced allow list
+    pub registry: Option<&'a str>,
+    /// Only skills with a code sample in this language
+    pub has_code: Option<String>,
+    /// From [`Database::get_superseded`](crate::db::Database::get_superseded);
+    /// moved skills show up under their new registry instead
+    pub superseded: HashSet<(String, String)>,
+    /// Local copies to take skills from instead of the catalog (`search --installed`)
+    pub installed: Option<&'a [Skill]>,
+    pub limit: usize,
+}
+
+impl SearchService<'_> {
+    /// How many results to ask the index for first
+    pub fn fetch_limit(&self) -> usize {
+        self.limit * OVERFETCH
+    }
+
+    /// Ask `search` for more and more index results until `limit` of them
+    /// survive the filters, or there are no more to find
+    pub fn fetch<E>(&self, mut search: impl FnMut(usize) -> Result<Vec<index::SearchResult>, E>) -> Result<Vec<index::SearchResult>, E> {
+        let mut wanted = self.fetch_limit();
+        loop {
+            let found = search(wanted)?;
+            if found.len() < wanted || wanted >= MAX_FETCH || self.kept(found.clone()).take(self.limit).count() >= self.limit {
+                return Ok(found);
+            }
+            tracing::debug!("Too few of {} results passed the filters, fetching more", found.len());
+            wanted = (wanted * OVERFETCH).min(MAX_FETCH);
+        }
+    }
+
+    fn skill(&self, registry: &str, slug: &str) -> Option<Skill> {
+        match self.installed {
+            Some(skills) => skills.iter().find(|s| s.registry == registry && s.slug == slug).cloned(),
+            None => self.enricher.db.get_skill(registry, slug).ok().flatten(),
+        }
+    }
+
+    fn has_code(&self, s: &Skill, language: &str) -> bool {
+        let language = text::normalize_language(language);
+        // Local copies can differ from the catalog, so look at their own code
+        let samples = match self.installed {
+            Some(_) => text::code_samples(&s.skill_md),
+            None => self.enricher.db.get_code_samples(&s.registry, &s.slug).unwrap_or_default(),
+        };
+        samples.iter().any(|c| c.language == language)
+    }
+
+    /// The results to show for what the index found, best first. Each is
+    /// enriched as it's taken, so callers can stream them.
+    pub fn results(&self, found: Vec<index::SearchResult>, scale: ScoreScale) -> impl Iterator<Item = SearchResult> + '_ {
+        index::with_confidence(self.kept(found), scale, |r| r.search_score)
+            .map(|(r, confidence)| SearchResult { confidence, ..r })
+            .take(self.limit)
+    }
+
+    /// Ranked, enriched results that pass the filters, policy, and decisions
+    fn kept(&self, found: Vec<index::SearchResult>) -> impl Iterator<Item = SearchResult> + '_ {
+        let ranked = rank::rank(found, self.sort, self.ranking, |r| match self.skill(&r.registry, &r.slug) {
+            Some(s) => Signals::of(&s, self.enricher.quality.score_for(&s), self.enricher.decisions.is_trusted(&s), self.enricher.now),
+            None => Signals::default(),
+        });
+        ranked
+            .into_iter()
+            .filter(|r| !self.superseded.contains(&(r.registry.clone(), r.slug.clone())))
+            .filter(|r| !self.enricher.decisions.is_denied(&r.registry, &r.slug))
+            .filter(|r| self.registry.is_some() || self.rules.allows_registry(&r.registry))
+            .filter_map(|r| {
+                let skill = self.skill(&r.registry, &r.slug)?;
+                if self.has_code.as_deref().is_some_and(|lang| !self.has_code(&skill, lang)) {
+                    return None;
+                }
+                Some(SearchResult { fuzzy: r.fuzzy, snippet: r.snippet, ..self.enricher.result(skill, r.overlay, r.score) })
+            })
+            .filter(|r| self.filters.keep(r))
+    }
+}
+
+/// What `top` lists: the skills that pass the filters, policy, and
+/// decisions, with their quality scores, best first
+pub struct TopList<'a> {
+    pub db: &'a Database,
+    pub quality: &'a QualityScores,
+    pub decisions: &'a DecisionSet,
+    pub rules: &'a Policy,
+    pub sort: TopSort,
+    pub trusted: bool,
+    pub min_score: i64,
+    pub max_injection_risk: Option<i64>,
+    /// Normalized tags a skill must all have
+    pub tags: &'a [String],
+    pub include_unreachable: bool,
+    /// Its minimum stars and maximum age filter too, and its recency boost
+    /// scales the sort
+    pub profile: &'a RankProfile,
+    pub now: i64,
+}
+
+impl TopList<'_> {
+    pub fn skills(&self) -> Result<Vec<(Skill, i64)>> {
+        let (db, now) = (self.db, self.now);
+        let superseded = db.get_superseded()?;
+        let removed: HashSet<_> = db.get_removed()?.into_iter().map(|(key, _)| key).collect();
+        let unreachable = if self.include_unreachable { Default::default() } else { db.get_unreachable()? };
+        let mut skills: Vec<_> = db
+            .get_all_skills()?
+            .into_iter()
+            .filter(|s| !superseded.contains(&(s.registry.clone(), s.slug.clone())))
+            .filter(|s| !removed.contains(&(s.registry.clone(), s.slug.clone())))
+            .filter(|s| !unreachable.contains(&(s.registry.clone(), s.slug.clone())))
+            .filter(|s| !self.decisions.is_denied(&s.registry, &s.slug) && self.rules.allows_registry(&s.registry))
+            .map(|mut s| {
+                s.trusted = self.decisions.is_trusted(&s);
+                s
+            })
+            .filter(|s| !self.trusted || s.trusted)
+            .filter(|s| self.profile.min_stars.is_none_or(|min| s.stars >= min))
+            .filter(|s| self.profile.max_age_days.is_none_or(|max| s.age_days(now).is_none_or(|age| age <= max)))
+            .filter(|s| self.tags.is_empty() || db.get_tags(&s.registry, &s.slug).is_ok_and(|have| self.tags.iter().all(|t| have.contains(t))))
+            .filter(|s| {
+                self.max_injection_risk
+                    .is_none_or(|max| db.get_injection_risk(&s.registry, &s.slug).map(|(score, _)| score <= max).unwrap_or(true))
+            })
+            .filter_map(|s| {
+                let quality_score = self.quality.score_for(&s);
+                (quality_score >= self.min_score).then_some((s, quality_score))
+            })
+            .collect();
+
+        let ratings = if self.sort == TopSort::Rating { db.get_weighted_ratings()? } else { Default::default() };
+        skills.sort_by_cached_key(|(s, _)| {
+            let key = match self.sort {
+                TopSort::Popularity => s.popularity(),
+                TopSort::Stars => s.stars,
+                TopSort::Downloads => s.downloads,
+                TopSort::Installs => s.installs,
+                // Compare on a fixed-point scale; unrated skills sort last
+                TopSort::Rating => ratings.get(&(s.registry.clone(), s.slug.clone())).map_or(-1, |r| (r * 1000.0) as i64),
+            };
+            std::cmp::Reverse((key as f64 * self.profile.recency_factor(s.age_days(now)) as f64) as i64)
+        });
+        Ok(skills)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::policy::{DecisionSet, Decisions};
+    use crate::quality::{QualityEntry, QualityScores};
+    use tempfile::tempdir;
+
+    fn skill(registry: &str, slug: &str, skill_md: &str) -> Skill {
+        Skill {
+            description: format!("{} tools", slug),
+            skill_md: skill_md.to_string(),
+            trusted: registry == "anthropic",
+            updated_at: 1234567890,
+            ..Skill::fixture(registry, slug)
+        }
+    }
+
+    fn found(registry: &str, slug: &str, score: f32) -> index::SearchResult {
+        index::SearchResult {
+            slug: slug.to_string(),
+            name: slug.to_string(),
+            description: String::new(),
+            registry: registry.to_string(),
+            overlay: false,
+            score,
+            fuzzy: false,
+            snippet: None,
+        }
+    }
+
+    fn quality(registry: &str, slug: &str, score: i64) -> QualityEntry {
+        QualityEntry {
+            registry: registry.to_string(),
+            name: slug.to_string(),
+            score,
+            stars: 0,
+            rationale: String::new(),
+            url: format!("https://github.com/test/{}", slug),
+        }
+    }
+
+    #[test]
+    fn test_results_are_filtered_enriched_and_limited() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&skill("anthropic", "pdf", "# PDF\n```python\nimport pypdf\n```\n")).unwrap();
+        db.upsert_skill(&skill("clawdhub", "pdf-tools", "# PDF tools\n```bash\npdftk a.pdf cat output b.pdf\n```\n")).unwrap();
+        db.upsert_skill(&skill("clawdhub", "pdf-spam", "# Spam\n")).unwrap();
+        db.upsert_skill(&skill("clawdhub", "pdf-old", "# Old\n")).unwrap();
+        let quality = QualityScores::from_entries(vec![quality("anthropic", "pdf", 95), quality("clawdhub", "pdf-tools", 85), quality("clawdhub", "pdf-old", 90)]);
+        let decisions = DecisionSet::new(None, Decisions { trust: Vec::new(), deny: vec!["clawdhub:pdf-spam".to_string()] });
+        let (rules, ranking) = (Policy::default(), RankingConfig::default());
+        let mut service = SearchService {
+            enricher: Enricher {
+                db: &db,
+                quality: &quality,
+                decisions: &decisions,
+                overlay_dir: None,
+                unreachable: HashSet::new(),
+                now: 1234567890,
+            },
+            rules: &rules,
+            ranking: &ranking,
+            sort: Sort::Relevance,
+            filters: SearchFilters { min_score: 80, ..Default::default() },
+            registry: None,
+            has_code: None,
+            superseded: HashSet::from([("clawdhub".to_string(), "pdf-old".to_string())]),
+            installed: None,
+            limit: 10,
+        };
+        assert_eq!(service.fetch_limit(), 40);
+        let hits = || vec![found("clawdhub", "pdf-tools", 3.0), found("anthropic", "pdf", 2.0), found("clawdhub", "pdf-spam", 1.5), found("clawdhub", "pdf-old", 1.0), found("clawdhub", "gone", 0.5)];
+        let ids = |service: &SearchService| -> Vec<String> { service.results(hits(), ScoreScale::keyword("pdf")).map(|r| format!("{}:{}", r.registry, r.slug)).collect() };
+
+        // Denied, superseded, and unknown skills never show up
+        assert_eq!(ids(&service), ["clawdhub:pdf-tools", "anthropic:pdf"]);
+        let first = service.results(hits(), ScoreScale::keyword("pdf")).next().unwrap();
+        assert_eq!((first.quality_score, first.trusted), (85, false));
+        assert!(first.confidence > 0.0);
+
+        service.filters.trusted = true;
+        assert_eq!(ids(&service), ["anthropic:pdf"]);
+        service.filters = SearchFilters { min_score: 90, ..Default::default() };
+        assert_eq!(ids(&service), ["anthropic:pdf"]);
+        service.filters.min_score = 0;
+        service.has_code = Some("sh".to_string());
+        assert_eq!(ids(&service), ["clawdhub:pdf-tools"]);
+        service.has_code = None;
+        service.limit = 1;
+        assert_eq!(ids(&service), ["clawdhub:pdf-tools"]);
+    }
+
+    #[test]
+    fn test_installed_copies_replace_the_catalog() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&skill("anthropic", "pdf", "# PDF\n")).unwrap();
+        // The local copy gained a code sample the catalog doesn't have
+        let installed = [skill("anthropic", "pdf", "# PDF\n```py\nimport pypdf\n```\n")];
+        let (quality, decisions) = (QualityScores::from_entries(Vec::new()), DecisionSet::default());
+        let (rules, ranking) = (Policy::default(), RankingConfig::default());
+        let mut service = SearchService {
+            enricher: Enricher {
+                db: &db,
+                quality: &quality,
+                decisions: &decisions,
+                overlay_dir: None,
+                unreachable: HashSet::new(),
+                now: 1234567890,
+            },
+            rules: &rules,
+            ranking: &ranking,
+            sort: Sort::Relevance,
+            filters: SearchFilters::default(),
+            registry: None,
+            has_code: Some("python".to_string()),
+            superseded: HashSet::new(),
+            installed: Some(&installed),
+            limit: 10,
+        };
+        let count = |service: &SearchService| service.results(vec![found("anthropic", "pdf", 1.0)], ScoreScale::keyword("pdf")).count();
+        assert_eq!(count(&service), 1);
+        service.installed = None;
+        assert_eq!(count(&service), 0);
+    }
+
+    #[test]
+    fn test_fetch_pages_until_enough_results_survive() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let spam: Vec<String> = (0..10).map(|i| format!("clawdhub:pdf-spam-{}", i)).collect();
+        for id in &spam {
+            db.upsert_skill(&skill("clawdhub", id.trim_start_matches("clawdhub:"), "# Spam\n")).unwrap();
+        }
+        db.upsert_skill(&skill("anthropic", "pdf", "# PDF\n")).unwrap();
+        db.upsert_skill(&skill("anthropic", "docx", "# DOCX\n")).unwrap();
+        let quality = QualityScores::from_entries(Vec::new());
+        let decisions = DecisionSet::new(None, Decisions { trust: Vec::new(), deny: spam.clone() });
+        let (rules, ranking) = (Policy::default(), RankingConfig::default());
+        let service = SearchService {
+            enricher: Enricher {
+                db: &db,
+                quality: &quality,
+                decisions: &decisions,
+                overlay_dir: None,
+                unreachable: HashSet::new(),
+                now: 1234567890,
+            },
+            rules: &rules,
+            ranking: &ranking,
+            sort: Sort::Relevance,
+            filters: SearchFilters::default(),
+            registry: None,
+            has_code: None,
+            superseded: HashSet::new(),
+            installed: None,
+            limit: 2,
+        };
+        // Denied skills outrank the two that pass
+        let mut hits: Vec<_> = (0..10).map(|i| found("clawdhub", &format!("pdf-spam-{}", i), 10.0 - i as f32)).collect();
+        hits.extend([found("anthropic", "pdf", 0.5), found("anthropic", "docx", 0.4)]);
+        let mut asked = Vec::new();
+        let fetched = service
+            .fetch(|limit| -> Result<_, ()> {
+                asked.push(limit);
+                Ok(hits.iter().take(limit).cloned().collect())
+            })
+            .unwrap();
+        assert_eq!(asked, [8, 32]);
+        let ids: Vec<String> = service.results(fetched, ScoreScale::keyword("pdf")).map(|r| format!("{}:{}", r.registry, r.slug)).collect();
+        assert_eq!(ids, ["anthropic:pdf", "anthropic:docx"]);
+    }
+}
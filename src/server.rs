@@ -0,0 +1,619 @@
+use crate::audit_log::Action;
+use crate::catalog;
+use crate::config::Config;
+use crate::db::Database;
+use crate::github::{self, Channel};
+use crate::index::{IndexFilters, ScoreScale, SearchIndex};
+use crate::output::{BatchSearchOutput, Enricher, SearchFilters, SearchOutput, SearchResult, SkillOutput, SCHEMA_VERSION};
+use crate::policy::{DecisionSet, Policy};
+use crate::quality::QualityScores;
+use crate::query;
+use crate::rank::Sort;
+use crate::search::SearchService;
+use crate::sync::sync_catalog;
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Results per search unless `limit` says otherwise, as for `search`
+const DEFAULT_LIMIT: usize = 10;
+const DEFAULT_MIN_SCORE: i64 = 80;
+/// Most queries one `POST /skills/search/batch` may carry
+const MAX_BATCH_QUERIES: usize = 1000;
+/// Streamed results serialized ahead of a slow client
+#[cfg(feature = "server")]
+const STREAM_BUFFER: usize = 32;
+
+/// What `serve` answers requests from
+pub struct ServerState {
+    pub db: tokio::sync::Mutex<Database>,
+    /// Syncs open their own connection here, so searches aren't held up
+    pub db_path: PathBuf,
+    pub repos_dir: PathBuf,
+    pub index: SearchIndex,
+    /// Where `config` came from; see [`ServerState::reload_config`]
+    pub config_path: PathBuf,
+    pub config: RwLock<Config>,
+    pub quality: QualityScores,
+    pub rules: Policy,
+    pub decisions: RwLock<DecisionSet>,
+    /// Held while a sync runs; a second one is refused
+    pub syncing: tokio::sync::Mutex<()>,
+}
+
+/// Query string of `GET /skills/search`, named like the `search` flags
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchParams {
+    pub q: String,
+    pub limit: Option<usize>,
+    pub registry: Option<String>,
+    pub trusted: bool,
+    pub min_stars: Option<i64>,
+    pub has_version: bool,
+    /// Comma-separated tags a skill must all have
+    pub tag: Option<String>,
+    pub channel: Option<String>,
+    pub min_score: Option<i64>,
+    pub max_injection_risk: Option<i64>,
+    pub max_age_days: Option<i64>,
+    pub include_unreachable: bool,
+    pub include_removed: bool,
+    /// Search for `q` as written, without dropping filler words or adding
+    /// synonyms
+    pub verbatim: bool,
+    /// Match words a typo or two away; a search with no exact matches is
+    /// retried this way regardless
+    pub fuzzy: bool,
+    /// relevance (the default), stars, quality, or recency
+    pub sort: Option<String>,
+    /// Only skills with a code sample in this language
+    pub has_code: Option<String>,
+    /// Stream the results as newline-delimited JSON instead
+    pub ndjson: bool,
+}
+
+/// Body of `POST /skills/search/batch`: the queries, and the parameters of
+/// `GET /skills/search` (but `q` and `ndjson`) to run each with
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BatchSearchParams {
+    pub queries: Vec<String>,
+    #[serde(flatten)]
+    pub params: SearchParams,
+}
+
+/// Response to `POST /sync`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SyncOutput {
+    pub schema_version: u32,
+    /// Skills in the catalog after the sync
+    pub skills: i64,
+    pub seconds: f64,
+}
+
+/// An error response: an HTTP status and a message, sent as `{"error": ...}`
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(500, format!("{:#}", e))
+    }
+}
+
+impl ServerState {
+    /// Re-read config.toml and apply it to requests from now on, returning
+    /// what changed. A config that doesn't load is an error, and the running
+    /// one stays.
+    pub fn reload_config(&self) -> Result<Vec<String>> {
+        let new = Config::load(&self.config_path)?;
+        let mut config = self.config.write().expect("config lock poisoned");
+        let changes = config.changes(&new);
+        if !changes.is_empty() {
+            self.decisions.write().expect("decisions lock poisoned").set_user(new.decisions.clone());
+            *config = new;
+        }
+        Ok(changes)
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+/// Search parameters checked before any results go out, so refusals are
+/// still an error status when results are streamed
+struct CheckedSearch {
+    min_score: i64,
+    channel: Option<Channel>,
+    sort: Sort,
+    limit: usize,
+}
+
+fn check_search(state: &ServerState, params: &SearchParams) -> Result<CheckedSearch, ApiError> {
+    // Refusals of the system policy are the client's to fix
+    state.rules.check_registry_flag(params.registry.as_deref()).map_err(|e| ApiError::new(400, e.to_string()))?;
+    let min_score = state.rules.min_score(params.min_score, DEFAULT_MIN_SCORE).map_err(|e| ApiError::new(400, e.to_string()))?;
+    let channel = params
+        .channel
+        .as_deref()
+        .map(|c| Channel::parse(c).ok_or_else(|| ApiError::new(400, format!("unknown channel '{}' (expected curated, experimental, or community)", c))))
+        .transpose()?;
+    let sort = match params.sort.as_deref() {
+        Some(s) => Sort::parse(s).ok_or_else(|| ApiError::new(400, format!("unknown sort '{}' (expected relevance, stars, quality, or recency)", s)))?,
+        None => Sort::default(),
+    };
+    Ok(CheckedSearch { min_score, channel, sort, limit: params.limit.unwrap_or(DEFAULT_LIMIT) })
+}
+
+/// Hand each result to `emit` as soon as it's enriched, until `limit` are
+/// out or `emit` returns false
+fn search_each(state: &ServerState, db: &Database, params: &SearchParams, checked: &CheckedSearch, emit: &mut dyn FnMut(SearchResult) -> bool) -> Result<(), ApiError> {
+    let registry = params.registry.as_deref();
+    let config = state.config.read().expect("config lock poisoned");
+    let decisions = state.decisions.read().expect("decisions lock poisoned");
+    let tags: Vec<String> = params.tag.as_deref().unwrap_or_default().split(',').filter_map(github::normalize_tag).collect();
+    let index_filters = IndexFilters {
+        registry,
+        min_stars: params.min_stars,
+        trusted: params.trusted && !decisions.adds_trust(),
+        has_version: params.has_version,
+        tags: &tags,
+    };
+    let service = SearchService {
+        enricher: Enricher {
+            db,
+            quality: &state.quality,
+            decisions: &decisions,
+            overlay_dir: config.index.overlay_dir.as_deref(),
+            unreachable: db.get_unreachable()?,
+            now: unix_now(),
+        },
+        rules: &state.rules,
+        ranking: &config.ranking,
+        sort: checked.sort,
+        filters: SearchFilters {
+            trusted: params.trusted,
+            channel: checked.channel,
+            include_unreachable: params.include_unreachable,
+            include_removed: params.include_removed,
+            min_score: checked.min_score,
+            max_injection_risk: params.max_injection_risk,
+            max_age_days: params.max_age_days,
+        },
+        registry,
+        has_code: params.has_code.clone(),
+        superseded: db.get_superseded()?,
+        installed: None,
+        limit: checked.limit,
+    };
+    let terms = if params.verbatim { params.q.clone() } else { query::rewrite(&params.q, &config.query) };
+    let results = if params.fuzzy { Vec::new() } else { service.fetch(|limit| state.index.search(&terms, limit, &index_filters))? };
+    // Finding nothing at all usually means a typo
+    let fuzzy = params.fuzzy || (results.is_empty() && config.index.fuzzy_distance > 0 && !params.q.trim().is_empty());
+    let results = if fuzzy { service.fetch(|limit| state.index.search_fuzzy(&terms, limit, &index_filters))? } else { results };
+    let results = service.results(results, ScoreScale::keyword(&terms));
+    for result in results {
+        if !emit(result) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// `search --json` for a query string
+pub async fn search(state: &ServerState, params: SearchParams) -> Result<SearchOutput, ApiError> {
+    let checked = check_search(state, &params)?;
+    let db = state.db.lock().await;
+    let mut results = Vec::new();
+    search_each(state, &db, &params, &checked, &mut |r| {
+        results.push(r);
+        true
+    })?;
+    Ok(SearchOutput::new(params.q, results))
+}
+
+/// `search --queries-file` for a request body. The parameters are checked
+/// once and the database held for the whole batch.
+pub async fn search_batch(state: &ServerState, batch: BatchSearchParams) -> Result<BatchSearchOutput, ApiError> {
+    if batch.queries.len() > MAX_BATCH_QUERIES {
+        return Err(ApiError::new(400, format!("at most {} queries per batch", MAX_BATCH_QUERIES)));
+    }
+    let mut params = batch.params;
+    let checked = check_search(state, &params)?;
+    let db = state.db.lock().await;
+    let mut results = BTreeMap::new();
+    for query in batch.queries {
+        params.q = query;
+        let mut hits = Vec::new();
+        search_each(state, &db, &params, &checked, &mut |r| {
+            hits.push(r);
+            true
+        })?;
+        results.insert(std::mem::take(&mut params.q), hits);
+    }
+    Ok(BatchSearchOutput {
+        schema_version: SCHEMA_VERSION,
+        results,
+    })
+}
+
+/// One skill's search fields and SKILL.md; moved skills answer from where
+/// they live now
+pub async fn get_skill(state: &ServerState, registry: &str, slug: &str) -> Result<SkillOutput, ApiError> {
+    let db = state.db.lock().await;
+    let (registry, slug) = db.get_successor(registry, slug)?.unwrap_or_else(|| (registry.to_string(), slug.to_string()));
+    let config = state.config.read().expect("config lock poisoned");
+    let decisions = state.decisions.read().expect("decisions lock poisoned");
+    let skill = db
+        .get_skill(&registry, &slug)?
+        .filter(|s| !decisions.is_denied(&s.registry, &s.slug))
+        .ok_or_else(|| ApiError::new(404, format!("No skill {}:{}", registry, slug)))?;
+    let enricher = Enricher {
+        db: &db,
+        quality: &state.quality,
+        decisions: &decisions,
+        overlay_dir: config.index.overlay_dir.as_deref(),
+        unreachable: db.get_unreachable()?,
+        now: unix_now(),
+    };
+    let skill_md = skill.skill_md.clone();
+    Ok(SkillOutput {
+        schema_version: SCHEMA_VERSION,
+        skill: enricher.result(skill, false, 0.0),
+        skill_md,
+    })
+}
+
+/// What `sync` does: sync the registries, rebuild the index, and spot-check
+/// skill URLs. Refused while another sync runs.
+pub async fn sync(state: Arc<ServerState>) -> Result<SyncOutput, ApiError> {
+    let Ok(_running) = state.syncing.try_lock() else {
+        return Err(ApiError::new(409, "A sync is already running"));
+    };
+    let started = Instant::now();
+    let worker = state.clone();
+    // Registry syncs hold the connection across awaits, so they run on a
+    // blocking thread of their own
+    let skills = tokio::task::spawn_blocking(move || -> Result<i64> {
+        let handle = tokio::runtime::Handle::current();
+        let registries = worker.config.read().expect("config lock poisoned").registries.clone();
+        let mut db = Database::open(&worker.db_path)?;
+        db.begin_sync_run(unix_now())?;
+        handle.block_on(sync_catalog(&mut db, &worker.repos_dir, &registries, github::DEFAULT_SYNC_JOBS))?;
+        worker.index.rebuild(&db)?;
+        match handle.block_on(catalog::validate_urls(&db, catalog::SYNC_URL_SAMPLE)) {
+            Ok(0) => {}
+            Ok(gone) => tracing::info!("{} skill URLs are gone upstream and now hidden from search", gone),
+            Err(e) => tracing::warn!("Failed to check skill URLs: {:#}", e),
+        }
+        let skills = db.count_all_skills()?;
+        db.log_action(Action::Sync, "catalog", &format!("{} skills, via serve", skills), unix_now())?;
+        Ok(skills)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Sync task failed: {}", e))??;
+    Ok(SyncOutput {
+        schema_version: SCHEMA_VERSION,
+        skills,
+        seconds: started.elapsed().as_secs_f64(),
+    })
+}
+
+#[cfg(feature = "server")]
+mod http {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::{Path, Query, State};
+    use axum::http::{header, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+
+    /// `search --ndjson`: one result per line, written as it's enriched. An
+    /// error after the first line ends the stream with an `{"error": ...}` line.
+    pub async fn search_ndjson(state: Arc<ServerState>, params: SearchParams) -> Result<Response, ApiError> {
+        let checked = check_search(&state, &params)?;
+        let (tx, rx) = tokio::sync::mpsc::channel::<String>(STREAM_BUFFER);
+        tokio::task::spawn_blocking(move || {
+            let db = state.db.blocking_lock();
+            // A closed channel means the client went away
+            let outcome = search_each(&state, &db, &params, &checked, &mut |r| match serde_json::to_string(&r) {
+                Ok(line) => tx.blocking_send(line + "\n").is_ok(),
+                Err(_) => false,
+            });
+            if let Err(e) = outcome {
+                let _ = tx.blocking_send(serde_json::json!({ "error": e.message }).to_string() + "\n");
+            }
+        });
+        let lines = futures_util::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|line| (Ok::<_, std::convert::Infallible>(line), rx)) });
+        Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], Body::from_stream(lines)).into_response())
+    }
+
+    impl IntoResponse for ApiError {
+        fn into_response(self) -> Response {
+            let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            (status, Json(serde_json::json!({ "error": self.message }))).into_response()
+        }
+    }
+
+    pub fn router(state: Arc<ServerState>) -> Router {
+        Router::new()
+            .route(
+                "/skills/search",
+                get(|State(state): State<Arc<ServerState>>, Query(params): Query<SearchParams>| async move {
+                    if params.ndjson {
+                        search_ndjson(state, params).await
+                    } else {
+                        search(&state, params).await.map(|out| Json(out).into_response())
+                    }
+                }),
+            )
+            .route(
+                "/skills/search/batch",
+                post(|State(state): State<Arc<ServerState>>, Json(batch): Json<BatchSearchParams>| async move { search_batch(&state, batch).await.map(Json) }),
+            )
+            .route("/skills/{registry}/{slug}", get(|State(state): State<Arc<ServerState>>, Path((registry, slug)): Path<(String, String)>| async move { get_skill(&state, &registry, &slug).await.map(Json) }))
+            .route("/sync", post(|State(state): State<Arc<ServerState>>| async move { sync(state).await.map(Json) }))
+            .with_state(state)
+    }
+}
+
+#[cfg(feature = "server")]
+pub use http::router;
+
+/// Reload config.toml whenever it changes, logging what changed, for as
+/// long as the returned watcher lives. The directory is watched rather
+/// than the file, since editors save by replacing it.
+#[cfg(feature = "server")]
+pub fn watch_config(state: Arc<ServerState>) -> Result<notify::RecommendedWatcher> {
+    use notify::Watcher;
+
+    let dir = match state.config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = state.config_path.file_name().map(|n| n.to_os_string());
+    let path = state.config_path.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else { return };
+        if event.kind.is_access() || !event.paths.iter().any(|p| p.file_name() == file_name.as_deref()) {
+            return;
+        }
+        // An editor's save can take several events; only the ones that
+        // change something are logged
+        match state.reload_config() {
+            Ok(changes) => {
+                for change in &changes {
+                    tracing::info!("Reloaded {}: {}", path.display(), change);
+                    if change == "[index] changed" {
+                        tracing::warn!("Index settings take effect after a restart");
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Keeping the running config: {:#}", e),
+        }
+    })?;
+    watcher.watch(&dir, notify::RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+/// Answer HTTP requests on `addr` until the process is stopped, picking up
+/// changes to config.toml as they're saved
+#[cfg(feature = "server")]
+pub async fn serve(state: ServerState, addr: &str) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| anyhow::anyhow!("Can't listen on {}: {}", addr, e))?;
+    tracing::info!("Serving the skill catalog on http://{}", listener.local_addr()?);
+    let state = Arc::new(state);
+    let _watcher = match watch_config(state.clone()) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            tracing::warn!("Not watching {} for changes: {:#}", state.config_path.display(), e);
+            None
+        }
+    };
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+#[cfg(not(feature = "server"))]
+pub async fn serve(_state: ServerState, _addr: &str) -> Result<()> {
+    crate::features::require("server", "serve")
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::config::IndexConfig;
+    use crate::db::Skill;
+    use crate::policy::Decisions;
+    use std::io::{Read, Write};
+    use tempfile::tempdir;
+
+    fn skill(slug: &str, description: &str) -> Skill {
+        Skill {
+            description: description.to_string(),
+            skill_md: format!("# {}\n\n{}\n", slug, description),
+            github_url: format!("https://github.com/anthropics/skills/tree/main/{}", slug),
+            stars: 3,
+            trusted: true,
+            ..Skill::fixture("anthropic", slug)
+        }
+    }
+
+    /// Status line and body of a bare HTTP/1.1 exchange
+    fn request(addr: std::net::SocketAddr, method: &str, path: &str) -> (String, serde_json::Value) {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: 0\r\n\r\n", method, path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), serde_json::from_str(body).unwrap())
+    }
+
+    /// Status line and body lines of a streamed response; HTTP/1.0, so the
+    /// body isn't chunked
+    fn request_lines(addr: std::net::SocketAddr, path: &str) -> (String, Vec<serde_json::Value>) {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.0\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.to_lowercase().contains("content-type: application/x-ndjson"), "{}", head);
+        (head.lines().next().unwrap().to_string(), body.lines().map(|l| serde_json::from_str(l).unwrap()).collect())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_and_get_skill_over_http() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("skills.db");
+        let db = Database::open(&db_path).unwrap();
+        db.upsert_skill(&skill("pdf", "Fill and merge PDF forms")).unwrap();
+        db.upsert_skill(&skill("docx", "Edit Word documents")).unwrap();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+        let state = ServerState {
+            db: tokio::sync::Mutex::new(db),
+            db_path,
+            repos_dir: dir.path().join("repos"),
+            index,
+            config_path: dir.path().join("config.toml"),
+            config: RwLock::new(Config::default()),
+            quality: QualityScores::bundled(),
+            rules: Policy::default(),
+            decisions: RwLock::new(DecisionSet::new(None, Decisions::default())),
+            syncing: tokio::sync::Mutex::new(()),
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(Arc::new(state))).await });
+
+        let (status, body) = tokio::task::spawn_blocking(move || request(addr, "GET", "/skills/search?q=pdf+forms&min_score=0")).await.unwrap();
+        assert!(status.contains("200"), "{}", status);
+        assert_eq!(body["schema_version"], SCHEMA_VERSION);
+        assert_eq!(body["results"][0]["slug"], "pdf");
+        assert_eq!(body["results"].as_array().unwrap().len(), 1);
+
+        let (status, lines) = tokio::task::spawn_blocking(move || request_lines(addr, "/skills/search?q=pdf+word&min_score=0&ndjson=true")).await.unwrap();
+        assert!(status.contains("200"), "{}", status);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["registry"], "anthropic");
+
+        let (status, body) = tokio::task::spawn_blocking(move || request(addr, "GET", "/skills/anthropic/docx")).await.unwrap();
+        assert!(status.contains("200"), "{}", status);
+        assert_eq!(body["skill"]["description"], "Edit Word documents");
+        assert!(body["skill_md"].as_str().unwrap().starts_with("# docx"));
+
+        let (status, body) = tokio::task::spawn_blocking(move || request(addr, "GET", "/skills/anthropic/nope")).await.unwrap();
+        assert!(status.contains("404"), "{}", status);
+        assert_eq!(body["error"], "No skill anthropic:nope");
+
+        let (status, _) = tokio::task::spawn_blocking(move || request(addr, "GET", "/skills/search?q=pdf&channel=bogus")).await.unwrap();
+        assert!(status.contains("400"), "{}", status);
+        let (status, _) = tokio::task::spawn_blocking(move || request(addr, "GET", "/skills/search?q=pdf&channel=bogus&ndjson=true")).await.unwrap();
+        assert!(status.contains("400"), "{}", status);
+    }
+
+    #[tokio::test]
+    async fn test_search_batch() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("skills.db");
+        let db = Database::open(&db_path).unwrap();
+        db.upsert_skill(&skill("pdf", "Fill and merge PDF forms")).unwrap();
+        db.upsert_skill(&skill("docx", "Edit Word documents")).unwrap();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        index.rebuild(&db).unwrap();
+        let state = ServerState {
+            db: tokio::sync::Mutex::new(db),
+            db_path,
+            repos_dir: dir.path().join("repos"),
+            index,
+            config_path: dir.path().join("config.toml"),
+            config: RwLock::new(Config::default()),
+            quality: QualityScores::bundled(),
+            rules: Policy::default(),
+            decisions: RwLock::new(DecisionSet::new(None, Decisions::default())),
+            syncing: tokio::sync::Mutex::new(()),
+        };
+
+        let batch: BatchSearchParams = serde_json::from_str(r#"{"queries": ["pdf forms", "word", "spreadsheets"], "min_score": 0}"#).unwrap();
+        let out = search_batch(&state, batch).await.unwrap();
+        assert_eq!(out.results.len(), 3);
+        assert_eq!(out.results["pdf forms"][0].slug, "pdf");
+        assert_eq!(out.results["word"][0].slug, "docx");
+        assert!(out.results["spreadsheets"].is_empty());
+
+        // Parameters are checked once, for the whole batch
+        let batch: BatchSearchParams = serde_json::from_str(r#"{"queries": ["pdf"], "channel": "bogus"}"#).unwrap();
+        assert_eq!(search_batch(&state, batch).await.unwrap_err().status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_applies_new_denials() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("skills.db");
+        let db = Database::open(&db_path).unwrap();
+        db.upsert_skill(&skill("pdf", "Fill and merge PDF forms")).unwrap();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        let state = ServerState {
+            db: tokio::sync::Mutex::new(db),
+            db_path,
+            repos_dir: dir.path().join("repos"),
+            index,
+            config_path: dir.path().join("config.toml"),
+            config: RwLock::new(Config::default()),
+            quality: QualityScores::bundled(),
+            rules: Policy::default(),
+            decisions: RwLock::new(DecisionSet::new(None, Decisions::default())),
+            syncing: tokio::sync::Mutex::new(()),
+        };
+        assert!(get_skill(&state, "anthropic", "pdf").await.is_ok());
+
+        std::fs::write(&state.config_path, "[decisions]\ndeny = [\"anthropic:pdf\"]\n").unwrap();
+        assert_eq!(state.reload_config().unwrap(), vec!["decisions.deny: added anthropic:pdf"]);
+        assert_eq!(get_skill(&state, "anthropic", "pdf").await.unwrap_err().status, 404);
+
+        // A broken edit leaves the running config in place
+        std::fs::write(&state.config_path, "[decisions\n").unwrap();
+        assert!(state.reload_config().is_err());
+        assert!(state.decisions.read().unwrap().is_denied("anthropic", "pdf"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_applies_new_synonyms() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("skills.db");
+        let db = Database::open(&db_path).unwrap();
+        let index = SearchIndex::open_or_create(&dir.path().join("index"), &IndexConfig::default()).unwrap();
+        let state = ServerState {
+            db: tokio::sync::Mutex::new(db),
+            db_path,
+            repos_dir: dir.path().join("repos"),
+            index,
+            config_path: dir.path().join("config.toml"),
+            config: RwLock::new(Config::default()),
+            quality: QualityScores::bundled(),
+            rules: Policy::default(),
+            decisions: RwLock::new(DecisionSet::new(None, Decisions::default())),
+            syncing: tokio::sync::Mutex::new(()),
+        };
+
+        std::fs::write(&state.config_path, "[query.synonyms]\nmd = [\"markdown\"]\n").unwrap();
+        assert_eq!(state.reload_config().unwrap(), vec!["[query] changed"]);
+        assert_eq!(state.config.read().unwrap().query.synonyms["md"], vec!["markdown"]);
+    }
+}
@@ -3,7 +3,7 @@ use anyhow::Result;
 use serde::Deserialize;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-const API_BASE: &str = "https://skills.sh";
+pub const API_BASE: &str = "https://skills.sh";
 
 #[derive(Debug, Deserialize)]
 struct SearchResponse {
@@ -21,6 +21,8 @@ struct SkillsShSkill {
     top_source: Option<String>,
 }
 
+/// Fetch skills.sh's listings into `db`. Fails only if nothing at all came
+/// back; the caller records that as a sync failure.
 pub async fn sync_skillssh(db: &mut Database) -> Result<()> {
     tracing::info!("Syncing skills.sh registry...");
 
@@ -29,6 +31,7 @@ pub async fn sync_skillssh(db: &mut Database) -> Result<()> {
         .build()?;
 
     let mut total = 0;
+    let mut last_error = None;
     let queries = ["", "a", "e", "i", "o", "u", "s", "t", "n", "r", "code", "docker", "git", "api", "test", "debug", "python", "rust", "javascript", "typescript"];
 
     for query in queries {
@@ -48,13 +51,22 @@ pub async fn sync_skillssh(db: &mut Database) -> Result<()> {
             }
             Ok(resp) => {
                 tracing::debug!("skills.sh API error for query '{}': {}", query, resp.status());
+                last_error = Some(anyhow::anyhow!("skills.sh API error: {}", resp.status()));
             }
             Err(e) => {
                 tracing::debug!("skills.sh request failed for query '{}': {}", query, e);
+                last_error = Some(anyhow::Error::from(e));
             }
         }
     }
 
+    // Partial results still count as a sync; nothing at all is a failure
+    if total == 0 {
+        if let Some(e) = last_error {
+            return Err(e);
+        }
+    }
+
     tracing::info!("Synced {} skills from skills.sh", total);
 
     let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
@@ -67,7 +79,7 @@ fn upsert_skillssh_skill(db: &mut Database, skill: &SkillsShSkill) -> Result<()>
     let source = skill.top_source.as_deref().unwrap_or("");
     
     let (github_url, description) = if !source.is_empty() {
-        let skill_id = skill.id.split('/').last().unwrap_or(&skill.id);
+        let skill_id = skill.id.split('/').next_back().unwrap_or(&skill.id);
         (
             format!("https://github.com/{}/tree/main/skills/{}", source, skill_id),
             format!("From {}", source),
@@ -92,9 +104,12 @@ fn upsert_skillssh_skill(db: &mut Database, skill: &SkillsShSkill) -> Result<()>
         skill_md: String::new(),
         github_url,
         version: None,
-        stars: skill.installs,
+        stars: 0,
         trusted: false,
         updated_at: now,
+        downloads: 0,
+        installs: skill.installs,
+        upstream_updated_at: None,
     };
 
     db.upsert_skill(&db_skill)?;
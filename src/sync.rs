@@ -0,0 +1,340 @@
+use crate::audit_log::Action;
+use crate::catalog;
+use crate::config::{registry_enabled, RegistryConfig};
+use crate::db::Database;
+use crate::github::{self, RegistrySync, SyncSummary, REGISTRIES};
+use crate::index::{self, SearchIndex};
+use crate::{diskspace, skillssh, unicode};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Everything `sync` does to a catalog: pull the git registries, up to
+/// `jobs` at once (and skills.sh, when enabled), into `db`, then recompute
+/// star percentiles and unicode flags. Rebuilding the search index is left
+/// to the caller.
+pub async fn sync_catalog(db: &mut Database, repos_dir: &Path, registries: &HashMap<String, RegistryConfig>, jobs: usize) -> Result<SyncSummary> {
+    let mut summary = sync_registries(db, repos_dir, registries, jobs).await?;
+    // Failures are often a network blip; give each failed registry one more
+    // try once the others are done
+    let failed: Vec<String> = summary.registries.iter().filter(|r| r.error.is_some()).map(|r| r.registry.clone()).collect();
+    if !failed.is_empty() {
+        tracing::info!("Retrying {}", failed.join(", "));
+        let retry = sync_registries(db, repos_dir, &only(registries, &failed), jobs).await?;
+        for result in retry.registries {
+            if let Some(entry) = summary.registries.iter_mut().find(|r| r.registry == result.registry) {
+                *entry = RegistrySync { retried: true, ..result };
+            }
+        }
+    }
+    db.update_percentiles()?;
+    unicode::flag_all(db)?;
+    Ok(summary)
+}
+
+/// What `import` does to a catalog: scan a local tree of skills into `db`
+/// under `registry` (see [`github::import_local`]), then recompute star
+/// percentiles and unicode flags. Returns how many skills the registry has.
+pub fn import_local(db: &mut Database, dir: &Path, registry: &str, exclude: &[String]) -> Result<usize> {
+    let count = github::import_local(db, dir, registry, exclude)?;
+    db.update_percentiles()?;
+    unicode::flag_all(db)?;
+    Ok(count)
+}
+
+/// A catalog a sync writes to: its database, registry clones, and search index
+pub struct SyncTarget<'a> {
+    pub db: &'a mut Database,
+    pub repos_dir: &'a Path,
+    pub index: &'a SearchIndex,
+    pub index_path: &'a Path,
+}
+
+impl SyncTarget<'_> {
+    /// Start a sync run: from scratch with `force`, otherwise once there's
+    /// room for it
+    fn begin(&mut self, force: bool) -> Result<()> {
+        if force {
+            self.db.clear_sync_state()?;
+        } else {
+            check_space(self.db, self.repos_dir, self.index_path)?;
+        }
+        self.db.begin_sync_run(unix_now())
+    }
+}
+
+/// Fail before a sync whose fresh clones or index rebuild would likely fill the disk
+pub fn check_space(db: &Database, repos_dir: &Path, index_path: &Path) -> Result<()> {
+    diskspace::ensure_space(repos_dir, github::clone_space_needed(repos_dir), "cloning registries")?;
+    diskspace::ensure_space(index_path, index::rebuild_space_needed(db)?, "rebuilding the search index")
+}
+
+/// What `sync` does: sync the catalog (from scratch with `force`, which also
+/// skips the disk space check), rebuild its index, and recheck a sample of
+/// skill URLs
+pub async fn sync(mut target: SyncTarget<'_>, registries: &HashMap<String, RegistryConfig>, force: bool, jobs: usize) -> Result<SyncSummary> {
+    target.begin(force)?;
+    let summary = sync_catalog(target.db, target.repos_dir, registries, jobs).await?;
+    target.index.rebuild(target.db)?;
+    recheck_urls(target.db).await;
+    let detail = format!("{}{} skills", if force { "full, " } else { "" }, target.db.count_all_skills()?);
+    target.db.log_action(Action::Sync, "catalog", &detail, unix_now())?;
+    Ok(summary)
+}
+
+/// `sync` under a system catalog: only the user layer is ours to sync, and
+/// only for registries the system catalog doesn't already provide
+pub async fn sync_user_layer(system: &Database, mut target: SyncTarget<'_>, registries: &HashMap<String, RegistryConfig>, force: bool, jobs: usize) -> Result<SyncSummary> {
+    let mut registries = registries.clone();
+    for name in system.system_registries()? {
+        registries.entry(name).or_default().enabled = false;
+    }
+    target.begin(force)?;
+    let summary = sync_catalog(target.db, target.repos_dir, &registries, jobs).await?;
+    target.index.rebuild(target.db)?;
+    let detail = format!("{}{} skills", if force { "full, " } else { "" }, target.db.count_all_skills()?);
+    target.db.log_action(Action::Sync, "user catalog", &detail, unix_now())?;
+    Ok(summary)
+}
+
+/// One sync of `sync --watch`: what [`sync`] does, but re-indexing only the
+/// skills that changed
+pub async fn watch_round(mut target: SyncTarget<'_>, registries: &HashMap<String, RegistryConfig>, force: bool, jobs: usize) -> Result<(SyncSummary, CatalogChanges)> {
+    target.begin(force)?;
+    let before = Snapshot::take(target.db)?;
+    let summary = sync_catalog(target.db, target.repos_dir, registries, jobs).await?;
+    let changes = before.changes_to(&Snapshot::take(target.db)?);
+    target.index.update(target.db, &changes.reindex())?;
+    recheck_urls(target.db).await;
+    let detail = format!("watch, {} skills, {}", target.db.count_all_skills()?, changes.summary());
+    target.db.log_action(Action::Sync, "catalog", &detail, unix_now())?;
+    Ok((summary, changes))
+}
+
+/// A rolling sample, so every skill's URL gets rechecked over a few syncs
+async fn recheck_urls(db: &Database) {
+    match catalog::validate_urls(db, catalog::SYNC_URL_SAMPLE).await {
+        Ok(0) => {}
+        Ok(gone) => tracing::info!("{} skill URLs are gone upstream and now hidden from search", gone),
+        Err(e) => tracing::warn!("Failed to check skill URLs: {:#}", e),
+    }
+}
+
+/// What `import` does: [`import_local`], then reindex the catalog. Returns
+/// how many skills the registry has.
+pub fn import(db: &mut Database, index: &SearchIndex, dir: &Path, registry: &str, exclude: &[String]) -> Result<usize> {
+    let count = import_local(db, dir, registry, exclude)?;
+    index.rebuild(db)?;
+    db.log_action(Action::Sync, registry, &format!("imported {} skills from {}", count, dir.display()), unix_now())?;
+    Ok(count)
+}
+
+/// What `prune` does: delete the skills gone upstream from the catalog and
+/// its index
+pub fn prune(db: &mut Database, index: &SearchIndex, keys: &[(String, String)]) -> Result<()> {
+    for (registry, slug) in keys {
+        db.delete_skill(registry, slug)?;
+    }
+    index.update(db, keys)?;
+    db.log_action(Action::Sync, "catalog", &format!("pruned {} skills gone upstream", keys.len()), unix_now())?;
+    Ok(())
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+
+/// What the index keeps of a skill, to tell what a sync changed
+#[derive(Debug, Clone, PartialEq)]
+struct Fingerprint {
+    content_hash: String,
+    stars: i64,
+    trusted: bool,
+}
+
+/// Every skill in a catalog at one moment, by (registry, slug). Skills
+/// marked gone upstream are left out, so a sync that marks one reports it
+/// as removed.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot(HashMap<(String, String), Fingerprint>);
+
+impl Snapshot {
+    pub fn take(db: &Database) -> Result<Self> {
+        let skills = db.get_all_skills()?;
+        let removed: HashSet<_> = db.get_removed()?.into_iter().map(|(key, _)| key).collect();
+        Ok(Self(
+            skills
+                .into_iter()
+                .filter(|s| !removed.contains(&(s.registry.clone(), s.slug.clone())))
+                .map(|s| {
+                    let fingerprint = Fingerprint { content_hash: catalog::content_hash(&s), stars: s.stars, trusted: s.trusted };
+                    ((s.registry, s.slug), fingerprint)
+                })
+                .collect(),
+        ))
+    }
+
+    /// What changed from this snapshot to `after`
+    pub fn changes_to(&self, after: &Snapshot) -> CatalogChanges {
+        let mut changes = CatalogChanges::default();
+        for (key, now) in &after.0 {
+            match self.0.get(key) {
+                None => changes.added.push(key.clone()),
+                Some(before) if before.content_hash != now.content_hash => changes.updated.push(key.clone()),
+                Some(before) if before != now => changes.restated.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.removed = self.0.keys().filter(|key| !after.0.contains_key(*key)).cloned().collect();
+        for list in [&mut changes.added, &mut changes.updated, &mut changes.removed, &mut changes.restated] {
+            list.sort();
+        }
+        changes
+    }
+}
+
+/// Skills a sync added, changed, or removed, by (registry, slug)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatalogChanges {
+    pub added: Vec<(String, String)>,
+    /// Name, description, or SKILL.md changed
+    pub updated: Vec<(String, String)>,
+    pub removed: Vec<(String, String)>,
+    /// Only stars or trust changed, which the index filters on but nobody
+    /// needs to hear about
+    pub restated: Vec<(String, String)>,
+}
+
+impl CatalogChanges {
+    /// Skills whose index documents are out of date
+    pub fn reindex(&self) -> Vec<(String, String)> {
+        [&self.added, &self.updated, &self.removed, &self.restated].into_iter().flatten().cloned().collect()
+    }
+
+    /// e.g. "3 added, 1 updated, 0 removed"
+    pub fn summary(&self) -> String {
+        format!("{} added, {} updated, {} removed", self.added.len(), self.updated.len(), self.removed.len())
+    }
+}
+
+/// A `sync --watch` interval such as `30m`, `6h`, or `1d`
+pub fn parse_interval(interval: &str) -> Result<Duration> {
+    let invalid = || anyhow::anyhow!("Invalid interval '{}'; use e.g. 30m, 6h, or 1d", interval);
+    let trimmed = interval.trim();
+    let (number, unit) = trimmed.split_at(trimmed.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let unit = match unit {
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        _ => return Err(invalid()),
+    };
+    if number == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(number * unit))
+}
+
+/// Registries tracked for sync failures: every git-backed registry plus skills.sh
+pub fn synced_registries() -> Vec<&'static str> {
+    REGISTRIES.iter().map(|r| r.name).chain(std::iter::once("skillssh")).collect()
+}
+
+/// `registries` with every registry not in `names` disabled
+fn only(registries: &HashMap<String, RegistryConfig>, names: &[String]) -> HashMap<String, RegistryConfig> {
+    let mut only = registries.clone();
+    for name in REGISTRIES.iter().map(|r| r.name).chain(["skillssh"]) {
+        if !names.iter().any(|n| n == name) {
+            only.entry(name.to_string()).or_default().enabled = false;
+        }
+    }
+    only
+}
+
+async fn sync_registries(db: &mut Database, repos_dir: &Path, registries: &HashMap<String, RegistryConfig>, jobs: usize) -> Result<SyncSummary> {
+    let mut summary = github::sync_all_registries(db, repos_dir, registries, jobs).await?;
+    if registry_enabled(registries, "skillssh") {
+        let started = Instant::now();
+        let error = match skillssh::sync_skillssh(db).await {
+            Ok(()) => None,
+            Err(e) => {
+                tracing::warn!("Failed to sync skills.sh: {:#}", e);
+                github::record_failure(db, "skillssh", "fetch", &e);
+                Some(("fetch".to_string(), format!("{:#}", e)))
+            }
+        };
+        summary.registries.push(RegistrySync {
+            registry: "skillssh".to_string(),
+            skills: db.count_skills("skillssh")?,
+            seconds: started.elapsed().as_secs_f64(),
+            skipped: 0,
+            error,
+            retried: false,
+        });
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Skill;
+    use tempfile::tempdir;
+
+    fn skill(registry: &str, slug: &str, description: &str, stars: i64) -> Skill {
+        Skill {
+            description: description.to_string(),
+            skill_md: format!("# {}\n", slug),
+            stars,
+            updated_at: 1234567890,
+            ..Skill::fixture(registry, slug)
+        }
+    }
+
+    #[test]
+    fn test_snapshot_changes() {
+        let dir = tempdir().unwrap();
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+        for s in [skill("clawdhub", "pdf", "Read PDFs", 1), skill("clawdhub", "docx", "Edit documents", 1), skill("clawdhub", "xlsx", "Spreadsheets", 1), skill("clawdhub", "pptx", "Slides", 1)] {
+            db.upsert_skill(&s).unwrap();
+        }
+        let before = Snapshot::take(&db).unwrap();
+        db.upsert_skill(&skill("clawdhub", "pdf", "Read and fill PDFs", 1)).unwrap();
+        db.upsert_skill(&skill("clawdhub", "xlsx", "Spreadsheets", 40)).unwrap();
+        db.delete_skill("clawdhub", "docx").unwrap();
+        db.upsert_skill(&skill("openai", "pdf", "Read PDFs", 1)).unwrap();
+        db.mark_removed("clawdhub", "pptx", 100).unwrap();
+
+        let changes = before.changes_to(&Snapshot::take(&db).unwrap());
+        let key = |r: &str, s: &str| (r.to_string(), s.to_string());
+        assert_eq!(changes.added, [key("openai", "pdf")]);
+        assert_eq!(changes.updated, [key("clawdhub", "pdf")]);
+        // Gone upstream counts as removed, though the row stays until `prune`
+        assert_eq!(changes.removed, [key("clawdhub", "docx"), key("clawdhub", "pptx")]);
+        assert_eq!(changes.restated, [key("clawdhub", "xlsx")]);
+        assert_eq!(changes.summary(), "1 added, 1 updated, 2 removed");
+        assert_eq!(changes.reindex().len(), 5);
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("30m").unwrap(), Duration::from_secs(1_800));
+        assert_eq!(parse_interval("6h").unwrap(), Duration::from_secs(6 * 3_600));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(86_400));
+        for bad in ["6", "h", "0h", "6w", "-1h"] {
+            assert!(parse_interval(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_only_enables_the_named_registries() {
+        let mut registries = HashMap::new();
+        registries.insert("jo".to_string(), RegistryConfig { max_skills: Some(10), ..Default::default() });
+        let retry = only(&registries, &["jo".to_string(), "skillssh".to_string()]);
+        assert!(registry_enabled(&retry, "jo") && registry_enabled(&retry, "skillssh"));
+        assert_eq!(retry["jo"].max_skills, Some(10));
+        assert!(!registry_enabled(&retry, "anthropic"));
+        assert!(!registry_enabled(&retry, "clawdhub"));
+    }
+}
@@ -0,0 +1,394 @@
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// Split a SKILL.md into its YAML frontmatter (without the `---` fences) and body
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    if let Some(rest) = content.strip_prefix("---") {
+        if let Some(end) = rest.find("\n---") {
+            let body = &rest[end + 4..];
+            // Drop the remainder of the closing fence line
+            let body = body.split_once('\n').map(|(_, b)| b).unwrap_or("");
+            return (Some(&rest[..end]), body);
+        }
+    }
+    (None, content)
+}
+
+/// Markdown and HTML syntax replaced before indexing, in order. Link and
+/// image targets are dropped (their URLs tokenize into "https", "github",
+/// "com", ...) and the visible text kept.
+const MARKUP_PATTERNS: &[(&str, &str)] = &[
+    (r"!?\[([^\]]*)\]\([^)]*\)", "$1"),
+    (r"!?\[([^\]]*)\]\[[^\]]*\]", "$1"),
+    (r"(?m)^\s*\[[^\]]+\]:\s*\S+.*$", ""),
+    (r"<https?://[^>\s]+>", ""),
+    (r"\bhttps?://\S+", ""),
+    (r"<!--|-->", " "),
+    (r"</?[a-zA-Z][a-zA-Z0-9-]*(\s[^>]*)?/?>", " "),
+    (r"(?m)^\s{0,3}(#{1,6}|>+|[-*+]|\d+[.)])\s+", ""),
+    (r"(?m)^\s*[-*_=|:\s]{3,}$", ""),
+    (r"[*_`~|]+", " "),
+];
+
+fn markup_patterns() -> &'static [(Regex, &'static str)] {
+    static COMPILED: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        MARKUP_PATTERNS
+            .iter()
+            .map(|(p, rep)| (Regex::new(p).expect("valid markup pattern"), *rep))
+            .collect()
+    })
+}
+
+fn strip_markup(line: &str) -> String {
+    let mut out = line.to_string();
+    for (re, rep) in markup_patterns() {
+        out = re.replace_all(&out, *rep).into_owned();
+    }
+    out
+}
+
+/// Prepare a SKILL.md for the full-text index: drop the frontmatter (name
+/// and description are indexed from their own fields), strip markdown and
+/// HTML syntax, and optionally drop the contents of fenced code blocks so
+/// long examples don't dominate term statistics.
+pub fn index_text(skill_md: &str, collapse_code_blocks: bool) -> String {
+    let (_, body) = split_frontmatter(skill_md);
+    let mut out = String::with_capacity(body.len());
+    let mut fence: Option<&str> = None;
+
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => {
+                fence = Some(m);
+                continue;
+            }
+            (Some(open), Some(m)) if open == m => {
+                fence = None;
+                continue;
+            }
+            (Some(_), _) => {
+                if !collapse_code_blocks {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+                continue;
+            }
+            (None, None) => {}
+        }
+        let cleaned = strip_markup(line);
+        let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !cleaned.is_empty() {
+            out.push_str(&cleaned);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Cut index text down to about `max_bytes`, at the last line break that
+/// fits (or word break, for one very long line). 0 keeps all of it.
+pub fn trim_index_text(text: &str, max_bytes: usize) -> &str {
+    if max_bytes == 0 || text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head = &text[..end];
+    match head.rfind('\n').or_else(|| head.rfind(char::is_whitespace)) {
+        Some(cut) if cut > 0 => &text[..cut + head[cut..].chars().next().map_or(0, char::len_utf8)],
+        _ => head,
+    }
+}
+
+/// Rough token count of `text` for LLM context budgets: about four
+/// characters per token for English prose and code
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// One markdown heading of a SKILL.md
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Heading {
+    /// 1 for `#`, up to 6 for `######`
+    pub level: u8,
+    pub title: String,
+}
+
+/// The heading a markdown line opens, if any
+fn parse_heading(line: &str) -> Option<Heading> {
+    let trimmed = line.trim();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) || !trimmed[level..].starts_with(' ') {
+        return None;
+    }
+    Some(Heading {
+        level: level as u8,
+        title: trimmed[level..].trim().trim_end_matches('#').trim_end().to_string(),
+    })
+}
+
+/// Body lines of a SKILL.md outside fenced code blocks, with `None` where
+/// a code block was
+fn prose_lines(body: &str) -> Vec<Option<&str>> {
+    let mut lines = Vec::new();
+    let mut fence: Option<&str> = None;
+    for line in body.lines() {
+        let trimmed = line.trim();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => {
+                fence = Some(m);
+                lines.push(None);
+            }
+            (Some(open), Some(m)) if open == m => fence = None,
+            (Some(_), _) => {}
+            (None, None) => lines.push(Some(trimmed)),
+        }
+    }
+    lines
+}
+
+/// Every heading in a SKILL.md body, in order, skipping `#` lines inside
+/// code blocks
+pub fn outline(skill_md: &str) -> Vec<Heading> {
+    let (_, body) = split_frontmatter(skill_md);
+    prose_lines(body).into_iter().flatten().filter_map(parse_heading).collect()
+}
+
+/// A fenced code block of a SKILL.md that names its language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct CodeSample {
+    /// Lowercase, with common aliases folded (`py` is `python`, `sh` is `bash`)
+    pub language: String,
+    /// Title of the nearest heading above the block
+    pub heading: Option<String>,
+    pub code: String,
+}
+
+/// The canonical name of a fence's language label
+pub fn normalize_language(label: &str) -> String {
+    let label = label.trim().to_lowercase();
+    match label.as_str() {
+        "py" | "python3" => "python",
+        "sh" | "shell" | "zsh" | "console" => "bash",
+        "js" | "node" => "javascript",
+        "ts" => "typescript",
+        "yml" => "yaml",
+        "rs" => "rust",
+        "rb" => "ruby",
+        "ps1" | "pwsh" => "powershell",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Every fenced code block in a SKILL.md body that has a language label,
+/// in order. Unlabelled blocks are usually output or prose, so they're left out.
+pub fn code_samples(skill_md: &str) -> Vec<CodeSample> {
+    let (_, body) = split_frontmatter(skill_md);
+    let mut samples = Vec::new();
+    let mut heading: Option<String> = None;
+    // The fence that opened the current block and the sample it's filling
+    let mut open: Option<(&str, Option<CodeSample>)> = None;
+    for line in body.lines() {
+        let trimmed = line.trim();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (&mut open, marker) {
+            (None, Some(m)) => {
+                let label = trimmed.trim_start_matches(m.chars().next().unwrap()).split(|c: char| c.is_whitespace() || c == '{').next().unwrap_or("");
+                let sample = (!label.is_empty()).then(|| CodeSample {
+                    language: normalize_language(label),
+                    heading: heading.clone(),
+                    code: String::new(),
+                });
+                open = Some((m, sample));
+            }
+            (Some((fence, _)), Some(m)) if *fence == m => {
+                if let Some((_, Some(sample))) = open.take() {
+                    if !sample.code.trim().is_empty() {
+                        samples.push(sample);
+                    }
+                }
+            }
+            (Some((_, Some(sample))), _) => {
+                sample.code.push_str(line);
+                sample.code.push('\n');
+            }
+            (Some((_, None)), _) => {}
+            (None, None) => {
+                if let Some(h) = parse_heading(line) {
+                    heading = Some(h.title);
+                }
+            }
+        }
+    }
+    samples
+}
+
+/// The parts of a SKILL.md `show` prints instead of the whole file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary<'a> {
+    pub frontmatter: Option<&'a str>,
+    pub headings: Vec<Heading>,
+    /// First block of prose after the frontmatter, joined onto one line
+    pub first_paragraph: Option<String>,
+    pub tokens: usize,
+    pub lines: usize,
+}
+
+pub fn summarize(skill_md: &str) -> Summary<'_> {
+    let (frontmatter, body) = split_frontmatter(skill_md);
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in prose_lines(body) {
+        match line {
+            Some(line) if !line.is_empty() && parse_heading(line).is_none() => paragraph.push(line),
+            _ if !paragraph.is_empty() => break,
+            _ => {}
+        }
+    }
+
+    Summary {
+        frontmatter,
+        headings: outline(skill_md),
+        first_paragraph: (!paragraph.is_empty()).then(|| paragraph.join(" ")),
+        tokens: estimate_tokens(skill_md),
+        lines: skill_md.lines().count(),
+    }
+}
+
+/// Token budget of a skill summary for agent prompts
+pub const SUMMARY_TOKENS: usize = 100;
+
+/// A skill in about [`SUMMARY_TOKENS`] tokens: the first paragraph of its
+/// SKILL.md (or its description, without one) and its section headings
+pub fn compact_summary(skill_md: &str, description: &str) -> String {
+    let summary = summarize(skill_md);
+    let mut text = summary.first_paragraph.unwrap_or_else(|| description.trim().to_string());
+    let sections: Vec<_> = summary.headings.iter().filter(|h| h.level == 2).map(|h| h.title.as_str()).collect();
+    if !sections.is_empty() {
+        if !text.is_empty() {
+            text.push_str(if text.ends_with(['.', '!', '?']) { " " } else { ". " });
+        }
+        text.push_str(&format!("Sections: {}.", sections.join(", ")));
+    }
+    truncate_tokens(&text, SUMMARY_TOKENS)
+}
+
+/// "3 months ago"-style age of something `days` old
+pub fn format_age(days: i64) -> String {
+    match days {
+        0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        2..=59 => format!("{} days ago", days),
+        60..=729 => format!("{} months ago", days / 30),
+        _ => format!("{} years ago", days / 365),
+    }
+}
+
+/// Cut `text` at a word boundary to fit `tokens`, marking the cut with "…"
+fn truncate_tokens(text: &str, tokens: usize) -> String {
+    if estimate_tokens(text) <= tokens {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    for word in text.split_whitespace() {
+        if estimate_tokens(&out) + estimate_tokens(word) + 1 > tokens {
+            break;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out.push('…');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter() {
+        let (fm, body) = split_frontmatter("---\nname: pdf\n---\n# PDF\n");
+        assert_eq!(fm, Some("\nname: pdf"));
+        assert_eq!(body, "# PDF\n");
+        assert_eq!(split_frontmatter("# No frontmatter"), (None, "# No frontmatter"));
+    }
+
+    #[test]
+    fn test_index_text_drops_frontmatter_and_markup() {
+        let md = "---\nname: pdf\ndescription: Read PDFs\nlicense: MIT\n---\n# Reading **PDFs**\n\n- See [the docs](https://example.com/docs) or <https://example.com>\n- <div align=\"center\">Use `pdftotext`</div>\n\n| Tool | Use |\n|------|-----|\n";
+        let text = index_text(md, false);
+        assert_eq!(text, "Reading PDFs\nSee the docs or\nUse pdftotext\nTool Use\n");
+        assert!(!text.contains("license"));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let md = "---\nname: pdf\n---\n# PDF\n\nRead PDFs\nwith pdftotext.\n\nMore prose.\n\n## Usage ##\n```bash\n# not a heading\n```\n### Flags\n";
+        let summary = summarize(md);
+        assert_eq!(summary.frontmatter, Some("\nname: pdf"));
+        let titles: Vec<_> = summary.headings.iter().map(|h| (h.level, h.title.as_str())).collect();
+        assert_eq!(titles, [(1, "PDF"), (2, "Usage"), (3, "Flags")]);
+        assert_eq!(summary.first_paragraph.as_deref(), Some("Read PDFs with pdftotext."));
+        assert_eq!(summary.tokens, md.len().div_ceil(4));
+        assert_eq!(summary.lines, 15);
+        assert_eq!(summarize("# Title only").first_paragraph, None);
+    }
+
+    #[test]
+    fn test_compact_summary() {
+        let md = "---\nname: pdf\n---\n# PDF\n\nRead and fill PDFs.\n\n## Reading\n### Tables\n## Forms\n";
+        assert_eq!(compact_summary(md, "PDF tools"), "Read and fill PDFs. Sections: Reading, Forms.");
+        assert_eq!(compact_summary("# PDF\n", " PDF tools "), "PDF tools");
+
+        let long = "word ".repeat(500);
+        let summary = compact_summary(&long, "");
+        assert!(summary.ends_with('…'));
+        assert!(estimate_tokens(&summary) <= SUMMARY_TOKENS + 1);
+    }
+
+    #[test]
+    fn test_code_samples() {
+        let md = "---\nname: pdf\n---\n# PDF\n```py\nimport pypdf\n```\n## Command line\n~~~Shell\npdftotext in.pdf\n```not a fence close\n~~~\n```\nplain output\n```\n```json\n\n```\n";
+        let samples = code_samples(md);
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0], CodeSample { language: "python".to_string(), heading: Some("PDF".to_string()), code: "import pypdf\n".to_string() });
+        assert_eq!(samples[1].language, "bash");
+        assert_eq!(samples[1].heading.as_deref(), Some("Command line"));
+        assert_eq!(samples[1].code, "pdftotext in.pdf\n```not a fence close\n");
+    }
+
+    #[test]
+    fn test_index_text_code_blocks() {
+        let md = "Intro\n```bash\npdftotext input.pdf\n```\nOutro\n";
+        assert_eq!(index_text(md, false), "Intro\npdftotext input.pdf\nOutro\n");
+        assert_eq!(index_text(md, true), "Intro\nOutro\n");
+    }
+
+    #[test]
+    fn test_trim_index_text() {
+        let text = "Merge PDFs\nSplit PDFs\nRotate pages\n";
+        assert_eq!(trim_index_text(text, 0), text);
+        assert_eq!(trim_index_text(text, 100), text);
+        assert_eq!(trim_index_text(text, 25), "Merge PDFs\nSplit PDFs\n");
+        assert_eq!(trim_index_text("one long line of words", 12), "one long ");
+        assert_eq!(trim_index_text("héllo", 2), "h");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(0), "today");
+        assert_eq!(format_age(45), "45 days ago");
+        assert_eq!(format_age(90), "3 months ago");
+        assert_eq!(format_age(800), "2 years ago");
+    }
+}
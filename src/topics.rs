@@ -0,0 +1,113 @@
+use crate::db::Database;
+use crate::github::{normalize_tag, record_failure, Registry, SourceRepo};
+use crate::github_api::{github_repo, GithubClient};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// In a registry's skills directory, maps skill slugs to their tags. When
+/// present, it replaces the repo's topics: `pdf = ["documents", "forms"]`
+pub const MAPPING_FILE: &str = "skill-topics.toml";
+
+/// Topics set on a GitHub repo, as tags
+pub async fn fetch_topics(client: &GithubClient, repo: &SourceRepo) -> Result<Vec<String>> {
+    let Some(path) = github_repo(repo) else {
+        return Ok(Vec::new());
+    };
+    Ok(tags(&client.topics(path).await?))
+}
+
+fn tags(names: &[String]) -> Vec<String> {
+    let mut tags = Vec::new();
+    for tag in names.iter().filter_map(|n| normalize_tag(n)) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Per-skill tags from a registry's [`MAPPING_FILE`], or `None` if it has
+/// none
+pub fn read_mapping(skills_dir: &Path) -> Result<Option<BTreeMap<String, Vec<String>>>> {
+    let path = skills_dir.join(MAPPING_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)?;
+    let mapping: BTreeMap<String, Vec<String>> = toml::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e))?;
+    Ok(Some(mapping.into_iter().map(|(slug, names)| (slug, tags(&names))).collect()))
+}
+
+/// Attach each registry's repo topics, or its mapping file's tags, to its
+/// skills. A registry whose topics can't be fetched keeps the ones it had.
+pub async fn sync_topics(db: &mut Database, repos_dir: &Path, registries: &[&Registry]) -> Result<()> {
+    let client = GithubClient::new()?;
+    let mut fetched: HashMap<&str, Vec<String>> = HashMap::new();
+    for registry in registries {
+        let skills_dir = crate::github::repo_dir(repos_dir, registry.name).join(registry.skills_path);
+        let result = match read_mapping(&skills_dir) {
+            Ok(Some(mapping)) => db.set_registry_topics(registry.name, &[], &mapping),
+            Ok(None) => {
+                if !fetched.contains_key(registry.repo.name) {
+                    match fetch_topics(&client, registry.repo).await {
+                        Ok(topics) => {
+                            fetched.insert(registry.repo.name, topics);
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to fetch topics of {}: {:#}", registry.repo.name, e);
+                            record_failure(db, registry.name, "topics", &e);
+                            continue;
+                        }
+                    }
+                }
+                db.set_registry_topics(registry.name, &fetched[registry.repo.name], &BTreeMap::new())
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to tag {} skills with topics: {:#}", registry.name, e);
+            record_failure(db, registry.name, "topics", &e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Skill;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_topics_and_mapping_become_tags() {
+        let dir = tempdir().unwrap();
+        let mut db = Database::open(&dir.path().join("test.db")).unwrap();
+        for (slug, skill_md) in [("pdf", "---\ntags: [forms]\n---\n# PDF"), ("xlsx", "# XLSX")] {
+            let skill = Skill {
+                skill_md: skill_md.to_string(),
+                github_url: String::new(),
+                trusted: true,
+                ..Skill::fixture("jo", slug)
+            };
+            db.upsert_skill(&skill).unwrap();
+            db.rescan_skill(&skill).unwrap();
+        }
+
+        db.set_registry_topics("jo", &tags(&["Agent Skills".to_string(), "office".to_string()]), &BTreeMap::new()).unwrap();
+        assert_eq!(db.get_tags("jo", "pdf").unwrap(), ["agent-skills", "forms", "office"]);
+        assert_eq!(db.get_tags("jo", "xlsx").unwrap(), ["agent-skills", "office"]);
+        // A rescan keeps them
+        let pdf = db.get_skills_by_registry("jo").unwrap().into_iter().find(|s| s.slug == "pdf").unwrap();
+        db.rescan_skill(&pdf).unwrap();
+        assert_eq!(db.get_tags("jo", "pdf").unwrap(), ["agent-skills", "forms", "office"]);
+
+        std::fs::write(dir.path().join(MAPPING_FILE), "xlsx = [\"Spreadsheets\"]\n").unwrap();
+        let mapping = read_mapping(dir.path()).unwrap().unwrap();
+        db.set_registry_topics("jo", &[], &mapping).unwrap();
+        assert_eq!(db.get_tags("jo", "pdf").unwrap(), ["forms"]);
+        assert_eq!(db.get_tags("jo", "xlsx").unwrap(), ["spreadsheets"]);
+        assert_eq!(db.topics_by_skill().unwrap().len(), 1);
+        assert!(read_mapping(&dir.path().join("missing")).unwrap().is_none());
+    }
+}
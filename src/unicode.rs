@@ -0,0 +1,247 @@
+use crate::db::Database;
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Characters that render as nothing (zero-width, word joiners, BOM, tag
+/// characters) or reorder surrounding text (bidi controls)
+pub fn is_invisible(c: char) -> bool {
+    matches!(c,
+        '\u{200B}'..='\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2060}'..='\u{2064}'
+        | '\u{2066}'..='\u{2069}'
+        | '\u{FEFF}'
+        | '\u{E0000}'..='\u{E007F}')
+}
+
+pub fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Non-Latin letters commonly swapped in for their Latin lookalikes
+fn confusable(c: char) -> Option<char> {
+    Some(match c {
+        // Cyrillic
+        'а' | 'А' => 'a',
+        'в' | 'В' => 'b',
+        'с' | 'С' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'Е' | 'ё' => 'e',
+        'һ' | 'Н' => 'h',
+        'і' | 'І' | 'ӏ' => 'i',
+        'ј' | 'Ј' => 'j',
+        'к' | 'К' => 'k',
+        'м' | 'М' => 'm',
+        'о' | 'О' => 'o',
+        'р' | 'Р' => 'p',
+        'ԛ' => 'q',
+        'ѕ' | 'Ѕ' => 's',
+        'т' | 'Т' => 't',
+        'у' | 'У' => 'y',
+        'х' | 'Х' => 'x',
+        'ԝ' => 'w',
+        // Greek
+        'α' | 'Α' => 'a',
+        'β' | 'Β' => 'b',
+        'ε' | 'Ε' => 'e',
+        'η' | 'Η' => 'h',
+        'ι' | 'Ι' => 'i',
+        'κ' | 'Κ' => 'k',
+        'Μ' => 'm',
+        'ν' | 'Ν' => 'n',
+        'ο' | 'Ο' => 'o',
+        'ρ' | 'Ρ' => 'p',
+        'τ' | 'Τ' => 't',
+        'υ' | 'Υ' => 'y',
+        'χ' | 'Χ' => 'x',
+        'Ζ' => 'z',
+        // Letterlike and dotless forms
+        'ı' => 'i',
+        'ℓ' => 'l',
+        // Fullwidth ASCII
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0)?.to_ascii_lowercase(),
+        _ => return None,
+    })
+}
+
+/// Strip invisible characters and map lookalikes to lowercase ASCII, leaving
+/// everything else as is. Used to index and query text so a disguised name
+/// is still found by its plain spelling.
+pub fn normalize_for_search(s: &str) -> String {
+    s.chars().filter(|&c| !is_invisible(c)).map(|c| confusable(c).unwrap_or(c)).collect()
+}
+
+/// Lowercase comparison key: two strings with the same skeleton look alike
+pub fn skeleton(s: &str) -> String {
+    normalize_for_search(s).to_lowercase()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnicodeFlag {
+    /// Zero-width or other invisible characters in the slug or name
+    Invisible,
+    /// Bidirectional override/isolate characters
+    Bidi,
+    /// Latin mixed with Cyrillic/Greek lookalikes
+    MixedScript,
+    /// Looks identical to another skill's slug or name
+    Homoglyph,
+}
+
+impl UnicodeFlag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnicodeFlag::Invisible => "invisible",
+            UnicodeFlag::Bidi => "bidi",
+            UnicodeFlag::MixedScript => "mixed-script",
+            UnicodeFlag::Homoglyph => "homoglyph",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "invisible" => Some(UnicodeFlag::Invisible),
+            "bidi" => Some(UnicodeFlag::Bidi),
+            "mixed-script" => Some(UnicodeFlag::MixedScript),
+            "homoglyph" => Some(UnicodeFlag::Homoglyph),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnicodeIssue {
+    pub flag: UnicodeFlag,
+    pub detail: String,
+}
+
+/// Character-level problems in a single slug or name
+pub fn scan_text(field: &str, text: &str) -> Vec<UnicodeIssue> {
+    let mut issues = Vec::new();
+    let bidi: Vec<_> = text.chars().filter(|&c| is_bidi_control(c)).collect();
+    let invisible: Vec<_> = text.chars().filter(|&c| is_invisible(c) && !is_bidi_control(c)).collect();
+    if !invisible.is_empty() {
+        issues.push(UnicodeIssue {
+            flag: UnicodeFlag::Invisible,
+            detail: format!("{} contains {} invisible character(s), first U+{:04X}", field, invisible.len(), invisible[0] as u32),
+        });
+    }
+    if !bidi.is_empty() {
+        issues.push(UnicodeIssue {
+            flag: UnicodeFlag::Bidi,
+            detail: format!("{} contains bidi control U+{:04X}", field, bidi[0] as u32),
+        });
+    }
+    // Per word, so a genuinely non-Latin name next to "PDF" isn't flagged
+    let lookalikes: String = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().any(|c| c.is_ascii_alphabetic()))
+        .flat_map(|word| word.chars().filter(|&c| !c.is_ascii() && confusable(c).is_some_and(|a| a.is_ascii_alphabetic())))
+        .collect();
+    if !lookalikes.is_empty() {
+        issues.push(UnicodeIssue {
+            flag: UnicodeFlag::MixedScript,
+            detail: format!("{} mixes Latin with lookalike letters {:?}", field, lookalikes),
+        });
+    }
+    issues
+}
+
+/// Recompute unicode flags for every skill: character-level issues in slugs
+/// and names, plus homoglyph collisions where a disguised slug or name has
+/// the same skeleton as another skill's plain one.
+pub fn flag_all(db: &Database) -> Result<usize> {
+    let skills = db.get_all_skills()?;
+
+    // Plain (pure ASCII, no invisibles) spellings by skeleton; trusted first
+    let mut plain: HashMap<String, &crate::db::Skill> = HashMap::new();
+    for s in &skills {
+        for text in [&s.slug, &s.name] {
+            if text.is_ascii() {
+                let key = skeleton(text);
+                let keep = plain.get(&key).is_some_and(|existing| existing.trusted || !s.trusted);
+                if !keep {
+                    plain.insert(key, s);
+                }
+            }
+        }
+    }
+
+    let mut flagged = 0;
+    for s in &skills {
+        let mut issues = scan_text("slug", &s.slug);
+        issues.extend(scan_text("name", &s.name));
+        for (field, text) in [("slug", &s.slug), ("name", &s.name)] {
+            if text.is_ascii() {
+                continue;
+            }
+            if let Some(original) = plain.get(&skeleton(text)) {
+                if original.registry != s.registry || original.slug != s.slug {
+                    issues.push(UnicodeIssue {
+                        flag: UnicodeFlag::Homoglyph,
+                        detail: format!("{} {:?} mimics {}:{}", field, text, original.registry, original.slug),
+                    });
+                }
+            }
+        }
+        if !issues.is_empty() {
+            flagged += 1;
+            tracing::warn!("{}:{} has suspicious unicode: {}", s.registry, s.slug.escape_debug(), issues.iter().map(|i| i.detail.as_str()).collect::<Vec<_>>().join("; "));
+        }
+        db.set_unicode_flags(&s.registry, &s.slug, &issues)?;
+    }
+    Ok(flagged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Skill;
+    use tempfile::tempdir;
+
+    fn create_test_skill(slug: &str, registry: &str, trusted: bool) -> Skill {
+        Skill {
+            github_url: String::new(),
+            trusted,
+            ..Skill::fixture(registry, slug)
+        }
+    }
+
+    #[test]
+    fn test_skeleton_maps_lookalikes() {
+        assert_eq!(skeleton("pdf-reаder"), "pdf-reader");
+        assert_eq!(skeleton("ＰＤＦ\u{200B}"), "pdf");
+        assert_eq!(normalize_for_search("Саlendar"), "calendar");
+    }
+
+    #[test]
+    fn test_scan_text() {
+        assert!(scan_text("slug", "pdf-reader").is_empty());
+        assert!(scan_text("name", "Прочитать PDF").is_empty());
+
+        let flags: Vec<_> = scan_text("slug", "pdf\u{200B}-rea\u{202E}der").into_iter().map(|i| i.flag).collect();
+        assert_eq!(flags, vec![UnicodeFlag::Invisible, UnicodeFlag::Bidi]);
+
+        let issues = scan_text("slug", "pdf-reаder");
+        assert_eq!(issues[0].flag, UnicodeFlag::MixedScript);
+    }
+
+    #[test]
+    fn test_flag_all_detects_homoglyph_of_trusted_skill() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        db.upsert_skill(&create_test_skill("pdf-reader", "anthropic", true)).unwrap();
+        db.upsert_skill(&create_test_skill("pdf-reаder", "clawdhub", false)).unwrap();
+        db.upsert_skill(&create_test_skill("calendar", "clawdhub", false)).unwrap();
+
+        assert_eq!(flag_all(&db).unwrap(), 1);
+        let issues = db.get_unicode_flags("clawdhub", "pdf-reаder").unwrap();
+        let flags: Vec<_> = issues.iter().map(|i| i.flag).collect();
+        assert!(flags.contains(&UnicodeFlag::Homoglyph));
+        assert!(issues.iter().any(|i| i.detail.contains("anthropic:pdf-reader")));
+        assert!(db.get_unicode_flags("anthropic", "pdf-reader").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::audit_log::{self, Action};
+use crate::config::{Config, IndexConfig};
+use crate::db::Database;
+use crate::index::{self, SearchIndex};
+use crate::policy::{self, DecisionSet, Policy};
+use crate::quality::{self, QualityScores};
+use crate::{diskspace, github, overlay, sync};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// System catalog directory, when `--catalog-dir` isn't given
+pub const CATALOG_DIR_ENV: &str = "SKILL_SEARCH_CATALOG_DIR";
+
+/// `--data-dir`, or `~/.local/share/skill-search`, created if need be
+pub fn data_dir(cli_path: Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(p) = cli_path {
+        return Ok(p);
+    }
+    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"))?;
+    let data_dir = PathBuf::from(home).join(".local").join("share").join("skill-search");
+    std::fs::create_dir_all(&data_dir)?;
+    Ok(data_dir)
+}
+
+/// The data directory, and the read-only system catalog under it if there
+/// is one
+#[derive(Debug, Clone)]
+pub struct Paths {
+    pub data_dir: PathBuf,
+    pub catalog_dir: Option<PathBuf>,
+}
+
+impl Paths {
+    /// From `--data-dir` and `--catalog-dir`, falling back to the default
+    /// data directory and `SKILL_SEARCH_CATALOG_DIR`
+    pub fn new(data_dir: Option<PathBuf>, catalog_dir: Option<PathBuf>) -> Result<Self> {
+        Ok(Self {
+            data_dir: self::data_dir(data_dir)?,
+            catalog_dir: catalog_dir.or_else(|| std::env::var_os(CATALOG_DIR_ENV).map(PathBuf::from)),
+        })
+    }
+
+    /// Where the catalog being searched lives
+    pub fn catalog_root(&self) -> &Path {
+        self.catalog_dir.as_deref().unwrap_or(&self.data_dir)
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        self.catalog_root().join("skills.db")
+    }
+
+    pub fn index_path(&self) -> PathBuf {
+        self.catalog_root().join("index")
+    }
+
+    pub fn repos_dir(&self) -> PathBuf {
+        self.catalog_root().join("repos")
+    }
+
+    /// The data directory's own database: the whole catalog, or the user
+    /// layer over a system catalog
+    pub fn user_db_path(&self) -> PathBuf {
+        self.data_dir.join("skills.db")
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.data_dir.join("config.toml")
+    }
+
+    pub fn staging_dir(&self) -> PathBuf {
+        self.data_dir.join("staging")
+    }
+}
+
+/// What the command about to run asks of the catalog before it starts
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Needs {
+    /// It writes the catalog, which a system catalog doesn't allow
+    pub writes_catalog: bool,
+    /// It fills the catalog itself (`import`), so a first launch needn't sync
+    pub fills_catalog: bool,
+    /// A catalog search, which may go to the database while the index is empty
+    pub search: bool,
+    /// `sync --force`, which skips the disk space checks
+    pub skip_space_checks: bool,
+}
+
+/// The catalog and everything around it that commands work with
+pub struct Workspace {
+    pub paths: Paths,
+    pub db: Database,
+    pub config: Config,
+    /// The system policy; without a system catalog nothing is restricted
+    pub rules: Policy,
+    pub decisions: DecisionSet,
+    pub search_index: SearchIndex,
+    /// The user layer's own index, alongside the system catalog's
+    pub user_index: Option<SearchIndex>,
+    pub quality_scores: QualityScores,
+    /// Set when search should go to the database; the index is rebuilt
+    /// after the results are out (unless the catalog is read-only)
+    pub rebuild_after: bool,
+}
+
+impl Workspace {
+    /// Open the catalog, its indexes, the config, and the system policy,
+    /// logging a policy change since the last run
+    pub fn open(paths: Paths) -> Result<Self> {
+        let mut db = match &paths.catalog_dir {
+            Some(_) => Database::open_layered(&paths.db_path(), &paths.user_db_path())?,
+            None => Database::open(&paths.db_path())?,
+        };
+        let config = Config::load(&paths.config_path())?;
+        let system_policy = paths.catalog_dir.as_deref().map(Policy::load_verified).transpose()?;
+        let rules = system_policy.clone().unwrap_or_default();
+        let decisions = DecisionSet::new(system_policy, config.decisions.clone());
+        if let Some(dir) = &paths.catalog_dir {
+            let path = dir.join(policy::POLICY_FILE);
+            let fingerprint = audit_log::policy_fingerprint(std::fs::read(&path).ok().as_deref());
+            let last = db.last_logged(Action::PolicyChange)?;
+            if last.as_deref().unwrap_or("none") != fingerprint {
+                db.log_action(Action::PolicyChange, &path.to_string_lossy(), &fingerprint, unix_now())?;
+            }
+        }
+        let index_path = paths.index_path();
+        let search_index = match &paths.catalog_dir {
+            // Nothing here may be rewritten; without an index, search the database
+            Some(_) => SearchIndex::open_read_only(&index_path, &config.index).or_else(|e| {
+                tracing::warn!("{:#}; searching the catalog database directly", e);
+                SearchIndex::in_memory(&config.index)
+            })?,
+            // The index is derived data: if it can't be read, start it over
+            None => match SearchIndex::open_or_create(&index_path, &config.index) {
+                Ok(index) => index,
+                Err(e) => {
+                    tracing::warn!("Search index {:?} is unreadable, recreating it: {:#}", index_path, e);
+                    SearchIndex::recreate(&index_path, &config.index)?
+                }
+            },
+        };
+        let user_index = match &paths.catalog_dir {
+            Some(_) => Some(SearchIndex::open_or_create(&paths.data_dir.join("index"), &config.index)?),
+            None => None,
+        };
+        if quality::seed_bundled(&mut db)? {
+            tracing::info!("Loaded the bundled quality scores into the database");
+        }
+        let quality_scores = QualityScores::load(&db)?;
+        Ok(Self { paths, db, config, rules, decisions, search_index, user_index, quality_scores, rebuild_after: false })
+    }
+
+    /// Get the catalog ready for a command: sync it on first launch, and
+    /// rebuild an empty or outdated index, unless a search can go to the
+    /// database meanwhile
+    pub async fn prepare(&mut self, needs: Needs) -> Result<()> {
+        let index_path = self.paths.index_path();
+        let index_empty = self.search_index.recreated() || self.search_index.is_empty().unwrap_or(true);
+        if let Some(dir) = &self.paths.catalog_dir {
+            if needs.writes_catalog {
+                anyhow::bail!("The system catalog in {:?} is read-only; run this where it is built, without --catalog-dir", dir);
+            }
+            if self.db.needs_initial_sync()? {
+                anyhow::bail!("The system catalog in {:?} has no skills; sync it first without --catalog-dir", dir);
+            }
+            self.rebuild_after = index_empty;
+            if let Some(index) = self.user_index.as_ref().filter(|i| i.recreated() || i.is_empty().unwrap_or(true)) {
+                let user_db = Database::open(&self.paths.user_db_path())?;
+                if user_db.count_all_skills()? > 0 {
+                    index.rebuild(&user_db)?;
+                }
+            }
+        } else if self.db.needs_initial_sync()? && !needs.fills_catalog {
+            // Auto-sync on first launch (importing local skills needs no registries)
+            tracing::info!("First launch detected, syncing skills...");
+            let repos_dir = self.paths.repos_dir();
+            if !needs.skip_space_checks {
+                sync::check_space(&self.db, &repos_dir, &index_path)?;
+            }
+            self.db.begin_sync_run(unix_now())?;
+            sync::sync_catalog(&mut self.db, &repos_dir, &self.config.registries, github::DEFAULT_SYNC_JOBS).await?;
+            self.search_index.rebuild(&self.db)?;
+            let detail = format!("first launch, {} skills", self.db.count_all_skills()?);
+            self.db.log_action(Action::Sync, "catalog", &detail, unix_now())?;
+        } else if index_empty && needs.search {
+            // Agents need an answer more than a well-ranked one; don't make
+            // them wait for the rebuild
+            tracing::warn!("Search index is empty; searching the database directly and rebuilding the index afterwards");
+            self.rebuild_after = true;
+        } else if index_empty || overlay_changed(&self.config.index, &index_path) {
+            if !needs.skip_space_checks {
+                diskspace::ensure_space(&index_path, index::rebuild_space_needed(&self.db)?, "rebuilding the search index")?;
+            }
+            self.search_index.rebuild(&self.db)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rebuild the catalog's search index from scratch after a search fell back
+/// to the database, leaving it for next time if that fails
+pub fn rebuild_index(index_path: &Path, db: &Database, config: &IndexConfig) -> Result<()> {
+    let rebuilt = diskspace::ensure_space(index_path, index::rebuild_space_needed(db)?, "rebuilding the search index")
+        .and_then(|_| SearchIndex::recreate(index_path, config))
+        .and_then(|index| index.rebuild(db));
+    if let Err(e) = rebuilt {
+        tracing::warn!("Could not rebuild the search index, will retry next time: {:#}", e);
+    }
+    Ok(())
+}
+
+/// Whether overlay files changed since the search index was last written
+fn overlay_changed(config: &IndexConfig, index_path: &Path) -> bool {
+    let Some(dir) = &config.overlay_dir else {
+        return false;
+    };
+    let built = index_path.join("meta.json").metadata().and_then(|m| m.modified()).ok();
+    overlay::last_modified(dir) > built
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}